@@ -238,6 +238,57 @@ impl AddressPayload {
         }
     }
 
+    // bootstrap era public key address, spendable by the given extended key
+    pub fn new_pubkey(
+        xpub: pallas_crypto::key::ed25519::PublicKeyExtended,
+        attributes: AddrAttrs,
+    ) -> Self {
+        let spending_data = SpendingData::PubKey(ByteVec::from(Vec::from(xpub.as_ref())));
+
+        Self::new(AddrType::PubKey, spending_data, attributes)
+    }
+
+    /// Checks if the root of this payload was derived from the given extended
+    /// public key, i.e. if the key can provide a bootstrap witness for it.
+    pub fn is_derived_from(&self, xpub: &pallas_crypto::key::ed25519::PublicKeyExtended) -> bool {
+        let spending_data = SpendingData::PubKey(ByteVec::from(Vec::from(xpub.as_ref())));
+
+        self.root == Self::hash_address_id(&self.addrtype, &spending_data, &self.attributes)
+    }
+
+    /// Network magic of the address, only present in non-mainnet addresses
+    pub fn network_magic(&self) -> Option<u32> {
+        self.attributes.iter().find_map(|x| match x {
+            AddrAttrProperty::NetworkTag(x) => minicbor::decode(x).ok(),
+            _ => None,
+        })
+    }
+
+    /// Encrypted derivation path of the address, only present in addresses
+    /// created by legacy (Daedalus random) wallets
+    pub fn hd_payload(&self) -> Option<Vec<u8>> {
+        self.attributes.iter().find_map(|x| match x {
+            AddrAttrProperty::DerivationPath(x) => {
+                minicbor::decode::<ByteVec>(x).ok().map(Vec::from)
+            }
+            _ => None,
+        })
+    }
+
+    /// Stake distribution of the address, `None` meaning the default bootstrap
+    /// era distribution
+    pub fn stake_distribution(&self) -> Option<&AddrDistr> {
+        self.attributes.iter().find_map(|x| match x {
+            AddrAttrProperty::AddrDistr(x) => Some(x),
+            _ => None,
+        })
+    }
+
+    /// CBOR encoding of the attributes, as required by bootstrap witnesses
+    pub fn attributes_cbor(&self) -> Vec<u8> {
+        minicbor::to_vec(&self.attributes).unwrap()
+    }
+
     // bootstrap era + no hdpayload address
     pub fn new_redeem(
         pubkey: pallas_crypto::key::ed25519::PublicKey,
@@ -263,6 +314,92 @@ impl AddressPayload {
     }
 }
 
+const HD_PAYLOAD_SALT: &[u8] = b"address-hashing";
+const HD_PAYLOAD_NONCE: &[u8] = b"serokellfore";
+const HD_PAYLOAD_ITERATIONS: u32 = 500;
+const HD_PAYLOAD_TAG_SIZE: usize = 16;
+
+/// Key used by legacy (Daedalus random) wallets to encrypt the derivation
+/// path of each address into its attributes
+///
+/// The key is derived from the root extended public key of the wallet, which
+/// in turn is derived from the wallet mnemonic and passphrase.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HdPayloadKey([u8; 32]);
+
+impl HdPayloadKey {
+    pub fn from_root_xpub(root: &pallas_crypto::key::ed25519::PublicKeyExtended) -> Self {
+        use cryptoxide::{hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+
+        let mut mac = Hmac::new(Sha512::new(), root.as_ref());
+        let mut key = [0u8; 32];
+
+        pbkdf2(&mut mac, HD_PAYLOAD_SALT, HD_PAYLOAD_ITERATIONS, &mut key);
+
+        HdPayloadKey(key)
+    }
+
+    /// Encrypts a derivation path into the payload stored in the address
+    pub fn encrypt_path(&self, path: &[u32]) -> Vec<u8> {
+        use cryptoxide::chacha20poly1305::ChaCha20Poly1305;
+
+        let mut plaintext = vec![];
+        let mut e = minicbor::Encoder::new(&mut plaintext);
+
+        e.begin_array().unwrap();
+        for index in path {
+            e.u32(*index).unwrap();
+        }
+        e.end().unwrap();
+
+        let mut payload = vec![0u8; plaintext.len() + HD_PAYLOAD_TAG_SIZE];
+        let (ciphertext, tag) = payload.split_at_mut(plaintext.len());
+
+        ChaCha20Poly1305::new(&self.0, HD_PAYLOAD_NONCE, &[]).encrypt(&plaintext, ciphertext, tag);
+
+        payload
+    }
+
+    /// Decrypts the derivation path from the payload stored in the address,
+    /// failing if the payload was not encrypted with this key
+    pub fn decrypt_path(&self, payload: &[u8]) -> Result<Vec<u32>, Error> {
+        use cryptoxide::chacha20poly1305::ChaCha20Poly1305;
+
+        if payload.len() < HD_PAYLOAD_TAG_SIZE {
+            return Err(Error::InvalidHdPayload);
+        }
+
+        let (ciphertext, tag) = payload.split_at(payload.len() - HD_PAYLOAD_TAG_SIZE);
+        let mut plaintext = vec![0u8; ciphertext.len()];
+
+        let valid = ChaCha20Poly1305::new(&self.0, HD_PAYLOAD_NONCE, &[]).decrypt(
+            ciphertext,
+            &mut plaintext,
+            tag,
+        );
+
+        if !valid {
+            return Err(Error::InvalidHdPayload);
+        }
+
+        minicbor::decode(&plaintext).map_err(|_| Error::InvalidHdPayload)
+    }
+}
+
+impl std::fmt::Debug for HdPayloadKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HdPayloadKey(<redacted>)")
+    }
+}
+
+impl AddressPayload {
+    /// Derivation path of an address created by a legacy wallet, `None` if
+    /// the address has no HD payload
+    pub fn derivation_path(&self, key: &HdPayloadKey) -> Result<Option<Vec<u32>>, Error> {
+        self.hd_payload().map(|x| key.decrypt_path(&x)).transpose()
+    }
+}
+
 impl From<AddressPayload> for ByronAddress {
     fn from(value: AddressPayload) -> Self {
         ByronAddress::from_decoded(value)
@@ -271,6 +408,7 @@ impl From<AddressPayload> for ByronAddress {
 
 /// New type wrapping a Byron address primitive
 #[derive(Debug, Encode, Decode, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct ByronAddress {
     #[n(0)]
     pub payload: TagWrap<ByteVec, 24>,
@@ -359,6 +497,125 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pubkey_address_derivation() {
+        use pallas_crypto::key::ed25519::{PublicKeyExtended, SecretKeyExtended};
+
+        let key = unsafe { SecretKeyExtended::from_bytes_unchecked([3; SecretKeyExtended::SIZE]) };
+        let xpub = PublicKeyExtended::new(key.public_key(), [5; 32]);
+        let other = PublicKeyExtended::new(key.public_key(), [6; 32]);
+
+        let attributes = vec![AddrAttrProperty::NetworkTag(
+            minicbor::to_vec(1097911063u32).unwrap().into(),
+        )]
+        .into();
+
+        let payload = AddressPayload::new_pubkey(xpub, attributes);
+
+        assert!(payload.is_derived_from(&xpub));
+        assert!(!payload.is_derived_from(&other));
+
+        let addr = ByronAddress::from_decoded(payload.clone());
+        let decoded = addr.decode().unwrap();
+
+        assert_eq!(decoded, payload);
+        assert_eq!(decoded.attributes_cbor(), payload.attributes_cbor());
+    }
+
+    #[test]
+    fn attributes_extraction() {
+        let testnet = ByronAddress::from_base58(TEST_VECTORS[0])
+            .unwrap()
+            .decode()
+            .unwrap();
+
+        assert_eq!(testnet.network_magic(), Some(1097911063));
+        assert!(testnet.hd_payload().is_some());
+
+        let legacy = ByronAddress::from_base58(TEST_VECTORS[1])
+            .unwrap()
+            .decode()
+            .unwrap();
+
+        assert_eq!(legacy.network_magic(), None);
+        assert_eq!(legacy.hd_payload().map(|x| x.len()), Some(28));
+
+        let icarus = ByronAddress::from_base58(TEST_VECTORS[2])
+            .unwrap()
+            .decode()
+            .unwrap();
+
+        assert_eq!(icarus.network_magic(), None);
+        assert_eq!(icarus.hd_payload(), None);
+        assert_eq!(icarus.stake_distribution(), None);
+    }
+
+    #[test]
+    fn hd_payload_known_answers() {
+        use pallas_crypto::key::ed25519::{PublicKeyExtended, SecretKeyExtended};
+
+        // golden vectors from the rust-cardano hdpayload module
+        const XPRV: &str = "200f5a406b71d084b5c79ec052f677bd50171f5fdbc65e2712a6aeba8bb1f352caafabf1d9d065e5143c5472d6014928198eef16ef92425279ce1678182d7e42d06c72c8dfdb3c624b76023868e644d7e51ff188a547b0e7bd7db3d3a342bad2";
+        const HD_KEY: &str = "600348f1611a35266e6b95698bfacb7d49980cc39e36544563efea7ab1b33bc8";
+
+        let xprv = hex::decode(XPRV).unwrap();
+        let secret = SecretKeyExtended::from_bytes(xprv[..64].try_into().unwrap()).unwrap();
+        let root = PublicKeyExtended::new(secret.public_key(), xprv[64..].try_into().unwrap());
+
+        let hd_key = HdPayloadKey::from_root_xpub(&root);
+        assert_eq!(hex::encode(hd_key.0), HD_KEY);
+
+        for (path, payload) in [
+            (vec![0, 0], "331cd6c3025d59a16a5f829ed7f24cf874f3ab50"),
+            (
+                vec![0x80000000, 0x80000000],
+                "3306563c02d02f381e78df8404c3505676d55e457193e74a34b690ec",
+            ),
+        ] {
+            assert_eq!(hex::encode(hd_key.encrypt_path(&path)), payload);
+            assert_eq!(
+                hd_key.decrypt_path(&hex::decode(payload).unwrap()).unwrap(),
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn hd_payload_roundtrip() {
+        use pallas_crypto::key::ed25519::{PublicKeyExtended, SecretKeyExtended};
+
+        let key = unsafe { SecretKeyExtended::from_bytes_unchecked([3; SecretKeyExtended::SIZE]) };
+        let root = PublicKeyExtended::new(key.public_key(), [5; 32]);
+        let other = PublicKeyExtended::new(key.public_key(), [6; 32]);
+
+        let hd_key = HdPayloadKey::from_root_xpub(&root);
+        let path = vec![0x80000000, 0x80000001];
+
+        let encrypted = hd_key.encrypt_path(&path);
+        assert_eq!(hd_key.decrypt_path(&encrypted).unwrap(), path);
+
+        let other_key = HdPayloadKey::from_root_xpub(&other);
+        assert!(other_key.decrypt_path(&encrypted).is_err());
+
+        let attributes = vec![AddrAttrProperty::DerivationPath(
+            minicbor::to_vec(ByteVec::from(encrypted)).unwrap().into(),
+        )]
+        .into();
+
+        let payload = AddressPayload::new_pubkey(root, attributes);
+        let address = ByronAddress::from_decoded(payload).decode().unwrap();
+
+        assert_eq!(address.derivation_path(&hd_key).unwrap(), Some(path));
+        assert!(address.derivation_path(&other_key).is_err());
+
+        let icarus = ByronAddress::from_base58(TEST_VECTORS[2])
+            .unwrap()
+            .decode()
+            .unwrap();
+
+        assert_eq!(icarus.derivation_path(&hd_key).unwrap(), None);
+    }
+
     #[test]
     fn payload_crc_matches() {
         for vector in TEST_VECTORS {
@@ -10,7 +10,7 @@
 pub mod byron;
 pub mod varuint;
 
-use std::{fmt::Display, io::Cursor, str::FromStr};
+use std::{collections::HashMap, fmt::Display, io::Cursor, str::FromStr};
 
 use pallas_crypto::hash::Hash;
 use thiserror::Error;
@@ -44,9 +44,15 @@ pub enum Error {
     #[error("invalid CBOR for Byron address {0}")]
     InvalidByronCbor(pallas_codec::minicbor::decode::Error),
 
+    #[error("HD payload can't be decrypted with the provided key")]
+    InvalidHdPayload,
+
     #[error("unkown hrp for network {0:08b}")]
     UnknownNetworkHrp(u8),
 
+    #[error("network id {0} can't be registered")]
+    InvalidNetworkId(u8),
+
     #[error("invalid hash size {0}")]
     InvalidHashSize(usize),
 
@@ -118,6 +124,50 @@ impl Pointer {
     }
 }
 
+/// Stake credentials by the pointer to the certificate that registered them,
+/// used to resolve the delegation part of pointer addresses
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PointerIndex(HashMap<Pointer, StakePayload>);
+
+impl PointerIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the credential of the certificate at `pointer`. A pointer
+    /// identifies a single certificate, so it's never overwritten.
+    pub fn insert(&mut self, pointer: Pointer, credential: StakePayload) {
+        self.0.entry(pointer).or_insert(credential);
+    }
+
+    pub fn resolve(&self, pointer: &Pointer) -> Option<&StakePayload> {
+        self.0.get(pointer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Extend<(Pointer, StakePayload)> for PointerIndex {
+    fn extend<T: IntoIterator<Item = (Pointer, StakePayload)>>(&mut self, iter: T) {
+        for (pointer, credential) in iter {
+            self.insert(pointer, credential);
+        }
+    }
+}
+
+fn credential_key(is_script: bool, hash: &Hash<28>) -> [u8; 29] {
+    let mut out = [0u8; 29];
+    out[0] = is_script as u8;
+    out[1..].copy_from_slice(hash.as_ref());
+    out
+}
+
 /// The payment part of a Shelley address
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash)]
 pub enum ShelleyPaymentPart {
@@ -168,6 +218,12 @@ impl ShelleyPaymentPart {
     pub fn is_script(&self) -> bool {
         matches!(self, Self::Script(_))
     }
+
+    /// Encodes the credential as an ordered KV key: a tag byte (`0` key, `1`
+    /// script) followed by the 28-byte hash
+    pub fn to_key_bytes(&self) -> [u8; 29] {
+        credential_key(self.is_script(), self.as_hash())
+    }
 }
 
 /// The delegation part of a Shelley address
@@ -231,6 +287,14 @@ impl ShelleyDelegationPart {
     pub fn is_script(&self) -> bool {
         matches!(self, ShelleyDelegationPart::Script(_))
     }
+
+    /// Encodes the credential as an ordered KV key, same layout as
+    /// [`ShelleyPaymentPart::to_key_bytes`]. Pointers and null parts have no
+    /// credential to encode.
+    pub fn to_key_bytes(&self) -> Option<[u8; 29]> {
+        self.as_hash()
+            .map(|hash| credential_key(self.is_script(), hash))
+    }
 }
 
 impl StakePayload {
@@ -253,6 +317,12 @@ impl StakePayload {
             StakePayload::Script(x) => x,
         }
     }
+
+    /// Encodes the credential as an ordered KV key, same layout as
+    /// [`ShelleyPaymentPart::to_key_bytes`]
+    pub fn to_key_bytes(&self) -> [u8; 29] {
+        credential_key(self.is_script(), self.as_hash())
+    }
 }
 
 /// The network tag of an address
@@ -263,6 +333,49 @@ pub enum Network {
     Other(u8),
 }
 
+/// Bech32 human-readable-parts used for the addresses of a network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetworkHrp {
+    pub address: &'static str,
+    pub stake: &'static str,
+}
+
+/// Bech32 prefixes of custom network ids (2 to 15), e.g. for sidechains or
+/// private testnets. The well-known networks always use their own prefixes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HrpTable(HashMap<u8, NetworkHrp>);
+
+impl HrpTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prefixes of a custom network id, replacing any previous ones
+    pub fn register(&mut self, id: u8, hrp: NetworkHrp) -> Result<(), Error> {
+        if !(2..=15).contains(&id) {
+            return Err(Error::InvalidNetworkId(id));
+        }
+
+        self.0.insert(id, hrp);
+
+        Ok(())
+    }
+
+    pub fn with(mut self, id: u8, hrp: NetworkHrp) -> Result<Self, Error> {
+        self.register(id, hrp)?;
+        Ok(self)
+    }
+
+    /// Bech32 prefixes of the network, either the well-known ones or those
+    /// registered in this table
+    pub fn get(&self, network: &Network) -> Result<NetworkHrp, Error> {
+        match network {
+            Network::Other(x) => self.0.get(x).copied().ok_or(Error::UnknownNetworkHrp(*x)),
+            x => x.hrp(),
+        }
+    }
+}
+
 impl From<u8> for Network {
     fn from(id: u8) -> Self {
         match id {
@@ -298,6 +411,28 @@ pub enum Address {
     Stake(StakeAddress),
 }
 
+/// Whether a credential is a key hash or a script hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CredentialKind {
+    Key,
+    Script,
+}
+
+/// The type of an address as described by CIP-19, with the kind of the
+/// credentials it holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddressKind {
+    Byron,
+    /// Payment and delegation credentials
+    Base(CredentialKind, CredentialKind),
+    /// Payment credential, delegating through a pointer
+    Pointer(CredentialKind),
+    /// Payment credential, without delegation
+    Enterprise(CredentialKind),
+    /// Stake credential of a reward account
+    Reward(CredentialKind),
+}
+
 fn encode_bech32(addr: &[u8], hrp: &str) -> Result<String, Error> {
     let base32 = bech32::ToBase32::to_base32(&addr);
     bech32::encode(hrp, base32, bech32::Variant::Bech32).map_err(Error::BadBech32)
@@ -441,6 +576,22 @@ impl Network {
             Network::Other(x) => *x,
         }
     }
+
+    /// Bech32 prefixes of the well-known networks, those of custom networks
+    /// are looked up in a [`HrpTable`]
+    pub fn hrp(&self) -> Result<NetworkHrp, Error> {
+        match self {
+            Network::Testnet => Ok(NetworkHrp {
+                address: "addr_test",
+                stake: "stake_test",
+            }),
+            Network::Mainnet => Ok(NetworkHrp {
+                address: "addr",
+                stake: "stake",
+            }),
+            Network::Other(x) => Err(Error::UnknownNetworkHrp(*x)),
+        }
+    }
 }
 
 impl ShelleyAddress {
@@ -489,11 +640,7 @@ impl ShelleyAddress {
 
     /// Gets the bech32 human-readable-part for this address
     pub fn hrp(&self) -> Result<&'static str, Error> {
-        match &self.0 {
-            Network::Testnet => Ok("addr_test"),
-            Network::Mainnet => Ok("addr"),
-            Network::Other(x) => Err(Error::UnknownNetworkHrp(*x)),
-        }
+        self.0.hrp().map(|x| x.address)
     }
 
     pub fn to_vec(&self) -> Vec<u8> {
@@ -515,10 +662,31 @@ impl ShelleyAddress {
         encode_bech32(&bytes, hrp)
     }
 
+    /// Like [`Self::to_bech32`], looking up the prefix of custom networks in
+    /// the given table
+    pub fn to_bech32_with(&self, table: &HrpTable) -> Result<String, Error> {
+        let hrp = table.get(&self.0)?.address;
+        let bytes = self.to_vec();
+        encode_bech32(&bytes, hrp)
+    }
+
     /// Indicates if either the payment or delegation part is a script
     pub fn has_script(&self) -> bool {
         self.payment().is_script() || self.delegation().is_script()
     }
+
+    /// Stake credential the funds of this address are delegated with. Pointers
+    /// are resolved through `index`, those that don't point to a registration
+    /// certificate (plenty of them on mainnet) resolve to `None`, as the
+    /// ledger does.
+    pub fn stake_credential(&self, index: &PointerIndex) -> Option<StakePayload> {
+        match self.delegation() {
+            ShelleyDelegationPart::Key(x) => Some(StakePayload::Stake(*x)),
+            ShelleyDelegationPart::Script(x) => Some(StakePayload::Script(*x)),
+            ShelleyDelegationPart::Pointer(x) => index.resolve(x).cloned(),
+            ShelleyDelegationPart::Null => None,
+        }
+    }
 }
 
 impl TryFrom<ShelleyAddress> for StakeAddress {
@@ -574,11 +742,7 @@ impl StakeAddress {
 
     /// Gets the bech32 human-readable-part for this address
     pub fn hrp(&self) -> Result<&'static str, Error> {
-        match &self.0 {
-            Network::Testnet => Ok("stake_test"),
-            Network::Mainnet => Ok("stake"),
-            Network::Other(x) => Err(Error::UnknownNetworkHrp(*x)),
-        }
+        self.0.hrp().map(|x| x.stake)
     }
 
     pub fn to_vec(&self) -> Vec<u8> {
@@ -598,11 +762,102 @@ impl StakeAddress {
         encode_bech32(&bytes, hrp)
     }
 
+    /// Like [`Self::to_bech32`], looking up the prefix of custom networks in
+    /// the given table
+    pub fn to_bech32_with(&self, table: &HrpTable) -> Result<String, Error> {
+        let hrp = table.get(&self.0)?.stake;
+        let bytes = self.to_vec();
+        encode_bech32(&bytes, hrp)
+    }
+
     pub fn is_script(&self) -> bool {
         self.payload().is_script()
     }
 }
 
+impl CredentialKind {
+    fn from_bit(script: bool) -> Self {
+        match script {
+            false => CredentialKind::Key,
+            true => CredentialKind::Script,
+        }
+    }
+}
+
+impl AddressKind {
+    /// Classifies an address from its header byte alone, without decoding the
+    /// rest of it. Returns `None` for headers of unassigned types.
+    pub fn from_header(header: u8) -> Option<Self> {
+        use CredentialKind::*;
+
+        let kind = match header >> 4 {
+            0b0000 => AddressKind::Base(Key, Key),
+            0b0001 => AddressKind::Base(Script, Key),
+            0b0010 => AddressKind::Base(Key, Script),
+            0b0011 => AddressKind::Base(Script, Script),
+            0b0100 => AddressKind::Pointer(Key),
+            0b0101 => AddressKind::Pointer(Script),
+            0b0110 => AddressKind::Enterprise(Key),
+            0b0111 => AddressKind::Enterprise(Script),
+            0b1000 => AddressKind::Byron,
+            0b1110 => AddressKind::Reward(Key),
+            0b1111 => AddressKind::Reward(Script),
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    /// Kind of the payment credential, if the address has one
+    pub fn payment(&self) -> Option<CredentialKind> {
+        match self {
+            AddressKind::Base(x, _) | AddressKind::Pointer(x) | AddressKind::Enterprise(x) => {
+                Some(*x)
+            }
+            AddressKind::Byron | AddressKind::Reward(_) => None,
+        }
+    }
+
+    /// Kind of the stake credential, if the address carries it. Pointers only
+    /// refer to one, so they have none.
+    pub fn stake(&self) -> Option<CredentialKind> {
+        match self {
+            AddressKind::Base(_, x) | AddressKind::Reward(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn is_byron(&self) -> bool {
+        matches!(self, AddressKind::Byron)
+    }
+
+    /// Indicates if funds can be paid to the address, i.e. it is not a reward
+    /// account
+    pub fn is_payment(&self) -> bool {
+        !matches!(self, AddressKind::Reward(_))
+    }
+
+    pub fn is_reward(&self) -> bool {
+        matches!(self, AddressKind::Reward(_))
+    }
+
+    /// Indicates if spending from the address requires running a script
+    pub fn is_payment_script(&self) -> bool {
+        self.payment() == Some(CredentialKind::Script)
+    }
+
+    /// Indicates if the stake of the address is controlled by a script
+    pub fn is_stake_script(&self) -> bool {
+        self.stake() == Some(CredentialKind::Script)
+    }
+
+    /// Indicates if the funds of the address can be delegated, either
+    /// through a stake credential or a pointer to one
+    pub fn is_delegated(&self) -> bool {
+        matches!(self, AddressKind::Base(..) | AddressKind::Pointer(_))
+    }
+}
+
 impl Address {
     /// Tries to encode an Address into a bech32 string
     pub fn to_bech32(&self) -> Result<String, Error> {
@@ -613,6 +868,16 @@ impl Address {
         }
     }
 
+    /// Like [`Self::to_bech32`], looking up the prefix of custom networks in
+    /// the given table
+    pub fn to_bech32_with(&self, table: &HrpTable) -> Result<String, Error> {
+        match self {
+            Address::Byron(_) => Err(Error::InvalidForByron),
+            Address::Shelley(x) => x.to_bech32_with(table),
+            Address::Stake(x) => x.to_bech32_with(table),
+        }
+    }
+
     /// Tries to parse a bech32 value into an Address
     pub fn from_bech32(bech32: &str) -> Result<Self, Error> {
         bech32_to_address(bech32)
@@ -665,6 +930,36 @@ impl Address {
         }
     }
 
+    /// Classifies the address by its type and the kind of its credentials
+    pub fn kind(&self) -> AddressKind {
+        match self {
+            Address::Byron(_) => AddressKind::Byron,
+            Address::Shelley(x) => {
+                let payment = CredentialKind::from_bit(x.payment().is_script());
+
+                match x.delegation() {
+                    ShelleyDelegationPart::Key(_) | ShelleyDelegationPart::Script(_) => {
+                        let stake = CredentialKind::from_bit(x.delegation().is_script());
+                        AddressKind::Base(payment, stake)
+                    }
+                    ShelleyDelegationPart::Pointer(_) => AddressKind::Pointer(payment),
+                    ShelleyDelegationPart::Null => AddressKind::Enterprise(payment),
+                }
+            }
+            Address::Stake(x) => AddressKind::Reward(CredentialKind::from_bit(x.is_script())),
+        }
+    }
+
+    /// Indicates if this address belongs to mainnet. Byron addresses of other
+    /// networks are told apart by the network magic in their attributes.
+    pub fn is_mainnet(&self) -> bool {
+        match self {
+            Address::Byron(x) => x.decode().is_ok_and(|x| x.network_magic().is_none()),
+            Address::Shelley(x) => x.network().is_mainnet(),
+            Address::Stake(x) => x.network().is_mainnet(),
+        }
+    }
+
     /// Indicates if this is an enterpise address
     pub fn is_enterprise(&self) -> bool {
         match self {
@@ -824,6 +1119,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn kind_matches() {
+        use CredentialKind::*;
+
+        let expected = [
+            AddressKind::Base(Key, Key),
+            AddressKind::Base(Script, Key),
+            AddressKind::Base(Key, Script),
+            AddressKind::Base(Script, Script),
+            AddressKind::Pointer(Key),
+            AddressKind::Pointer(Script),
+            AddressKind::Enterprise(Key),
+            AddressKind::Enterprise(Script),
+            AddressKind::Reward(Key),
+            AddressKind::Reward(Script),
+            AddressKind::Byron,
+        ];
+
+        for (vector, expected) in MAINNET_TEST_VECTORS.iter().zip(expected) {
+            let addr = Address::from_str(vector.0).unwrap();
+
+            assert_eq!(addr.kind(), expected);
+            assert_eq!(AddressKind::from_header(addr.to_vec()[0]), Some(expected));
+            // the byron vector carries the network magic of a testnet
+            assert_eq!(addr.is_mainnet(), !expected.is_byron());
+        }
+
+        assert_eq!(AddressKind::from_header(0b1001_0001), None);
+
+        let script = AddressKind::Pointer(Script);
+        assert!(script.is_payment_script() && script.is_delegated());
+        assert!(!script.is_stake_script());
+        assert_eq!(script.stake(), None);
+
+        let reward = AddressKind::Reward(Script);
+        assert!(reward.is_reward() && reward.is_stake_script());
+        assert!(!reward.is_payment() && !reward.is_payment_script());
+
+        let enterprise = AddressKind::Enterprise(Key);
+        assert!(enterprise.is_payment() && !enterprise.is_delegated());
+
+        let legacy = Address::from_str(
+            "DdzFFzCqrht7PQiAhzrn6rNNoADJieTWBt8KeK9BZdUsGyX9ooYD9NpMCTGjQoUKcHN47g8JMXhvKogsGpQHtiQ65fZwiypjrC6d3a4Q",
+        )
+        .unwrap();
+        assert!(legacy.is_mainnet());
+    }
+
     #[test]
     fn payload_matches() {
         for vector in MAINNET_TEST_VECTORS {
@@ -923,4 +1266,81 @@ mod tests {
         let addr = Address::from_hex("015bad085057ac10ecc7060f7ac41edd6f63068d8963ef7d86ca58669e5ecf2d283418a60be5a848a2380eb721000da1e0bbf39733134beca4cb57afb0b35fc89c63061c9914e055001a518c7516");
         assert!(matches!(addr, Ok(Address::Shelley(_))));
     }
+
+    #[test]
+    fn test_pointer_resolution() {
+        let addr = Address::from_bech32(
+            "addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k",
+        )
+        .unwrap();
+
+        let Address::Shelley(addr) = addr else {
+            panic!("expected shelley address");
+        };
+
+        let pointer = Pointer::new(2498243, 27, 3);
+        assert_eq!(
+            addr.delegation(),
+            &ShelleyDelegationPart::Pointer(pointer.clone())
+        );
+
+        let mut index = PointerIndex::new();
+        assert_eq!(addr.stake_credential(&index), None);
+
+        let credential = StakePayload::Stake(hash_vector_key(STAKE_PUBLIC_KEY));
+        index.extend([(pointer, credential.clone())]);
+        assert_eq!(addr.stake_credential(&index), Some(credential));
+    }
+
+    #[test]
+    fn test_custom_network_hrp() {
+        let addr = ShelleyAddress::new(
+            Network::Other(9),
+            ShelleyPaymentPart::key_hash(hash_vector_key(PAYMENT_PUBLIC_KEY)),
+            ShelleyDelegationPart::Null,
+        );
+
+        let hrp = NetworkHrp {
+            address: "addr_side",
+            stake: "stake_side",
+        };
+
+        let table = HrpTable::new().with(9, hrp).unwrap();
+        assert!(HrpTable::new().with(1, hrp).is_err());
+
+        let bech32 = addr.to_bech32_with(&table).unwrap();
+        assert!(bech32.starts_with("addr_side1"));
+
+        let parsed = Address::from_bech32(&bech32).unwrap();
+        assert_eq!(parsed, Address::Shelley(addr.clone()));
+
+        // the default path only knows the well-known networks
+        assert!(matches!(addr.to_bech32(), Err(Error::UnknownNetworkHrp(9))));
+
+        let mainnet = Address::from_bech32(MAINNET_TEST_VECTORS[0].0).unwrap();
+        assert_eq!(
+            mainnet.to_bech32_with(&table).unwrap(),
+            mainnet.to_bech32().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_credential_key_bytes() {
+        let hash = hash_vector_key(PAYMENT_PUBLIC_KEY);
+
+        let key = ShelleyPaymentPart::key_hash(hash).to_key_bytes();
+        let script = ShelleyPaymentPart::script_hash(hash).to_key_bytes();
+
+        assert_eq!(key[0], 0);
+        assert_eq!(script[0], 1);
+        assert_eq!(&key[1..], hash.as_ref());
+        assert!(key < script);
+
+        assert_eq!(StakePayload::Script(hash).to_key_bytes(), script);
+        assert_eq!(
+            ShelleyDelegationPart::key_hash(hash).to_key_bytes(),
+            Some(key)
+        );
+        assert_eq!(ShelleyDelegationPart::Null.to_key_bytes(), None);
+    }
 }
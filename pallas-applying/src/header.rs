@@ -0,0 +1,437 @@
+//! Header-level validation, enough for light clients to follow a chain
+//! without downloading block bodies.
+//!
+//! The epoch nonce and the stake distribution of each epoch are inputs: a
+//! light client gets them from a trusted source and hands them over with
+//! [`HeaderValidator::new_epoch`]. Headers are then checked against the
+//! consensus rules of Praos (Babbage onwards) and TPraos (Shelley to Alonzo),
+//! except for the overlay schedule of the early Shelley epochs.
+
+use std::collections::HashMap;
+
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::{
+        ed25519::{PublicKey, Signature},
+        kes::Sum6KesSignature,
+    },
+    vrf::{VrfProof, VrfPublicKey},
+};
+use pallas_primitives::VrfCert;
+use pallas_traverse::{forge::operational_cert_signable, MultiEraHeader};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeaderError {
+    /// Byron headers are not supported
+    UnsupportedHeader,
+    SlotNotIncreasing,
+    BlockNumberMismatch,
+    PrevHashMismatch,
+    ProtocolVersionTooLow,
+    ProtocolVersionTooHigh,
+    UnknownPool(Hash<28>),
+    VrfKeyMismatch,
+    InvalidVrfProof,
+    VrfOutputMismatch,
+    NotSlotLeader,
+    InvalidOpCertSignature,
+    KesPeriodBeforeOpCert,
+    KesPeriodExpired,
+    InvalidKesSignature,
+    /// The opcert counter is lower than one already seen for the pool, or
+    /// skips ahead more than one
+    InvalidOpCertCounter,
+}
+
+pub type HeaderResult = Result<(), HeaderError>;
+
+/// Consensus parameters from the genesis and the protocol parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsensusParams {
+    pub active_slot_coeff: f64,
+    pub slots_per_kes_period: u64,
+    pub max_kes_evolutions: u64,
+    /// Major protocol version of the current protocol parameters
+    pub protocol_major: u64,
+    /// Highest major protocol version known to the client
+    pub max_protocol_major: u64,
+}
+
+impl ConsensusParams {
+    pub fn mainnet() -> Self {
+        Self {
+            active_slot_coeff: 0.05,
+            slots_per_kes_period: 129_600,
+            max_kes_evolutions: 62,
+            protocol_major: 9,
+            max_protocol_major: 10,
+        }
+    }
+}
+
+/// Stake of a pool and the hash of the VRF key it registered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolStake {
+    pub stake: u64,
+    pub vrf_vkey_hash: Hash<32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StakeDistribution {
+    pub total: u64,
+    pub pools: HashMap<Hash<28>, PoolStake>,
+}
+
+impl StakeDistribution {
+    fn relative_stake(&self, pool: &PoolStake) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => pool.stake as f64 / total as f64,
+        }
+    }
+}
+
+/// Last header accepted by the validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainTip {
+    pub slot: u64,
+    pub number: u64,
+    pub hash: Hash<32>,
+}
+
+/// VRF input of Praos headers: the hash of the slot and the epoch nonce
+pub fn praos_vrf_input(slot: u64, epoch_nonce: &Hash<32>) -> Hash<32> {
+    let mut hasher = Hasher::<256>::new();
+    hasher.input(&slot.to_be_bytes());
+    hasher.input(epoch_nonce.as_ref());
+    hasher.finalize()
+}
+
+/// Seed separating the nonce and leader VRF inputs of TPraos headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TPraosSeed {
+    Nonce = 0,
+    Leader = 1,
+}
+
+/// VRF input of TPraos headers: the Praos input xor-ed with the hash of the
+/// seed number
+pub fn tpraos_vrf_input(slot: u64, epoch_nonce: &Hash<32>, seed: TPraosSeed) -> Hash<32> {
+    let seed = Hasher::<256>::hash(&(seed as u64).to_be_bytes());
+    let mut input = *praos_vrf_input(slot, epoch_nonce);
+
+    for (x, y) in input.iter_mut().zip(seed.iter()) {
+        *x ^= y;
+    }
+
+    Hash::new(input)
+}
+
+/// Praos leader value, the hash of the VRF output tagged with "L"
+pub fn praos_leader_value(vrf_output: &[u8]) -> Hash<32> {
+    Hasher::<256>::hash_tagged(vrf_output, b'L')
+}
+
+/// Whether a leader value, as a big-endian fraction of its whole range,
+/// wins the slot for a pool with the given relative stake. The threshold is
+/// computed with floating point, so values within its precision of the
+/// boundary may be judged differently than by the Haskell node.
+pub fn is_slot_leader(leader_value: &[u8], relative_stake: f64, active_slot_coeff: f64) -> bool {
+    let mut leading = [0; 16];
+    let len = leader_value.len().min(16);
+    leading[..len].copy_from_slice(&leader_value[..len]);
+
+    let p = u128::from_be_bytes(leading) as f64 / 2f64.powi(128);
+    let threshold = 1.0 - (1.0 - active_slot_coeff).powf(relative_stake);
+
+    p < threshold
+}
+
+enum LeaderVrf<'a> {
+    TPraos {
+        nonce: &'a VrfCert,
+        leader: &'a VrfCert,
+    },
+    Praos(&'a VrfCert),
+}
+
+struct OpCert<'a> {
+    hot_vkey: &'a [u8],
+    sequence_number: u64,
+    kes_period: u64,
+    sigma: &'a [u8],
+}
+
+struct HeaderView<'a> {
+    slot: u64,
+    number: u64,
+    prev_hash: Option<Hash<32>>,
+    issuer_vkey: &'a [u8],
+    vrf_vkey: &'a [u8],
+    vrf: LeaderVrf<'a>,
+    opcert: OpCert<'a>,
+    protocol_major: u64,
+    body_cbor: &'a [u8],
+    body_signature: &'a [u8],
+}
+
+impl<'a> HeaderView<'a> {
+    fn new(header: &'a MultiEraHeader<'_>) -> Result<Self, HeaderError> {
+        if let Some(x) = header.as_babbage() {
+            let body = &x.header_body;
+            let opcert = &body.operational_cert;
+
+            return Ok(Self {
+                slot: body.slot,
+                number: body.block_number,
+                prev_hash: body.prev_hash,
+                issuer_vkey: &body.issuer_vkey,
+                vrf_vkey: &body.vrf_vkey,
+                vrf: LeaderVrf::Praos(&body.vrf_result),
+                opcert: OpCert {
+                    hot_vkey: &opcert.operational_cert_hot_vkey,
+                    sequence_number: opcert.operational_cert_sequence_number,
+                    kes_period: opcert.operational_cert_kes_period,
+                    sigma: &opcert.operational_cert_sigma,
+                },
+                protocol_major: body.protocol_version.0,
+                body_cbor: body.raw_cbor(),
+                body_signature: &x.body_signature,
+            });
+        }
+
+        if let Some(x) = header.as_alonzo() {
+            let body = &x.header_body;
+
+            return Ok(Self {
+                slot: body.slot,
+                number: body.block_number,
+                prev_hash: body.prev_hash,
+                issuer_vkey: &body.issuer_vkey,
+                vrf_vkey: &body.vrf_vkey,
+                vrf: LeaderVrf::TPraos {
+                    nonce: &body.nonce_vrf,
+                    leader: &body.leader_vrf,
+                },
+                opcert: OpCert {
+                    hot_vkey: &body.operational_cert_hot_vkey,
+                    sequence_number: body.operational_cert_sequence_number,
+                    kes_period: body.operational_cert_kes_period,
+                    sigma: &body.operational_cert_sigma,
+                },
+                protocol_major: body.protocol_major,
+                body_cbor: body.raw_cbor(),
+                body_signature: &x.body_signature,
+            });
+        }
+
+        Err(HeaderError::UnsupportedHeader)
+    }
+}
+
+fn verify_vrf(
+    vkey: &VrfPublicKey,
+    input: &Hash<32>,
+    cert: &VrfCert,
+) -> Result<[u8; 64], HeaderError> {
+    let proof = VrfProof::try_from(cert.1.as_slice()).map_err(|_| HeaderError::InvalidVrfProof)?;
+
+    let output = vkey
+        .verify(input.as_ref(), &proof)
+        .map_err(|_| HeaderError::InvalidVrfProof)?;
+
+    if output[..] != cert.0[..] {
+        return Err(HeaderError::VrfOutputMismatch);
+    }
+
+    Ok(output)
+}
+
+/// Validates a sequence of headers of a single chain
+#[derive(Debug, Clone)]
+pub struct HeaderValidator {
+    params: ConsensusParams,
+    epoch_nonce: Hash<32>,
+    stake: StakeDistribution,
+    tip: Option<ChainTip>,
+    opcert_counters: HashMap<Hash<28>, u64>,
+}
+
+impl HeaderValidator {
+    pub fn new(params: ConsensusParams, epoch_nonce: Hash<32>, stake: StakeDistribution) -> Self {
+        Self {
+            params,
+            epoch_nonce,
+            stake,
+            tip: None,
+            opcert_counters: HashMap::new(),
+        }
+    }
+
+    /// Header the next one must extend. Without a tip, the first header is
+    /// accepted as is.
+    pub fn with_tip(mut self, tip: ChainTip) -> Self {
+        self.tip = Some(tip);
+        self
+    }
+
+    /// Last opcert counters seen for each pool. Counters of pools missing
+    /// here are not checked until a header of theirs is accepted.
+    pub fn with_opcert_counters(mut self, counters: HashMap<Hash<28>, u64>) -> Self {
+        self.opcert_counters = counters;
+        self
+    }
+
+    /// Switches to the nonce and stake distribution of a new epoch
+    pub fn new_epoch(&mut self, epoch_nonce: Hash<32>, stake: StakeDistribution) {
+        self.epoch_nonce = epoch_nonce;
+        self.stake = stake;
+    }
+
+    pub fn tip(&self) -> Option<&ChainTip> {
+        self.tip.as_ref()
+    }
+
+    pub fn opcert_counters(&self) -> &HashMap<Hash<28>, u64> {
+        &self.opcert_counters
+    }
+
+    /// Validates a header and, if valid, makes it the new tip
+    pub fn validate(&mut self, header: &MultiEraHeader<'_>) -> HeaderResult {
+        let view = HeaderView::new(header)?;
+        let pool = Hasher::<224>::hash(view.issuer_vkey);
+
+        self.check_chain(&view)?;
+        self.check_protocol_version(&view)?;
+        self.check_leader(&view, &pool)?;
+        self.check_opcert(&view, &pool)?;
+
+        self.opcert_counters
+            .insert(pool, view.opcert.sequence_number);
+
+        self.tip = Some(ChainTip {
+            slot: view.slot,
+            number: view.number,
+            hash: header.hash(),
+        });
+
+        Ok(())
+    }
+
+    fn check_chain(&self, view: &HeaderView) -> HeaderResult {
+        let Some(tip) = &self.tip else {
+            return Ok(());
+        };
+
+        if view.slot <= tip.slot {
+            return Err(HeaderError::SlotNotIncreasing);
+        }
+
+        if view.number != tip.number + 1 {
+            return Err(HeaderError::BlockNumberMismatch);
+        }
+
+        if view.prev_hash != Some(tip.hash) {
+            return Err(HeaderError::PrevHashMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn check_protocol_version(&self, view: &HeaderView) -> HeaderResult {
+        if view.protocol_major < self.params.protocol_major {
+            return Err(HeaderError::ProtocolVersionTooLow);
+        }
+
+        if view.protocol_major > self.params.max_protocol_major {
+            return Err(HeaderError::ProtocolVersionTooHigh);
+        }
+
+        Ok(())
+    }
+
+    fn check_leader(&self, view: &HeaderView, pool: &Hash<28>) -> HeaderResult {
+        let stake = self
+            .stake
+            .pools
+            .get(pool)
+            .ok_or(HeaderError::UnknownPool(*pool))?;
+
+        if Hasher::<256>::hash(view.vrf_vkey) != stake.vrf_vkey_hash {
+            return Err(HeaderError::VrfKeyMismatch);
+        }
+
+        let vkey =
+            VrfPublicKey::try_from(view.vrf_vkey).map_err(|_| HeaderError::VrfKeyMismatch)?;
+
+        let leader_value = match view.vrf {
+            LeaderVrf::Praos(cert) => {
+                let input = praos_vrf_input(view.slot, &self.epoch_nonce);
+                let output = verify_vrf(&vkey, &input, cert)?;
+
+                praos_leader_value(&output).to_vec()
+            }
+            LeaderVrf::TPraos { nonce, leader } => {
+                let input = tpraos_vrf_input(view.slot, &self.epoch_nonce, TPraosSeed::Nonce);
+                verify_vrf(&vkey, &input, nonce)?;
+
+                let input = tpraos_vrf_input(view.slot, &self.epoch_nonce, TPraosSeed::Leader);
+                verify_vrf(&vkey, &input, leader)?.to_vec()
+            }
+        };
+
+        let sigma = self.stake.relative_stake(stake);
+
+        if !is_slot_leader(&leader_value, sigma, self.params.active_slot_coeff) {
+            return Err(HeaderError::NotSlotLeader);
+        }
+
+        Ok(())
+    }
+
+    fn check_opcert(&self, view: &HeaderView, pool: &Hash<28>) -> HeaderResult {
+        let opcert = &view.opcert;
+
+        let signable =
+            operational_cert_signable(opcert.hot_vkey, opcert.sequence_number, opcert.kes_period);
+
+        let issuer = PublicKey::try_from(view.issuer_vkey);
+        let sigma = Signature::try_from(opcert.sigma);
+
+        match (issuer, sigma) {
+            (Ok(issuer), Ok(sigma)) if issuer.verify(signable, &sigma) => (),
+            _ => return Err(HeaderError::InvalidOpCertSignature),
+        }
+
+        let current = view.slot / self.params.slots_per_kes_period;
+
+        if current < opcert.kes_period {
+            return Err(HeaderError::KesPeriodBeforeOpCert);
+        }
+
+        if current >= opcert.kes_period + self.params.max_kes_evolutions {
+            return Err(HeaderError::KesPeriodExpired);
+        }
+
+        let valid = <[u8; 32]>::try_from(opcert.hot_vkey)
+            .ok()
+            .zip(Sum6KesSignature::try_from(view.body_signature).ok())
+            .is_some_and(|(vkey, signature)| {
+                let period = (current - opcert.kes_period) as u32;
+                signature.verify(&vkey, period, view.body_cbor)
+            });
+
+        if !valid {
+            return Err(HeaderError::InvalidKesSignature);
+        }
+
+        if let Some(last) = self.opcert_counters.get(pool) {
+            if opcert.sequence_number < *last || opcert.sequence_number > last + 1 {
+                return Err(HeaderError::InvalidOpCertCounter);
+            }
+        }
+
+        Ok(())
+    }
+}
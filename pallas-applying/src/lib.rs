@@ -3,6 +3,10 @@
 pub mod alonzo;
 pub mod babbage;
 pub mod byron;
+pub mod header;
+pub mod lint;
+pub mod mempool;
+pub mod resolve;
 pub mod shelley_ma;
 pub mod utils;
 
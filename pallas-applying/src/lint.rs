@@ -0,0 +1,293 @@
+//! Linting of transactions against the size, budget and collateral limits of
+//! the protocol parameters.
+//!
+//! Unlike validation, which stops at the first broken rule, the linter
+//! reports every violation at once, so that tx builders can fix them all
+//! before submitting.
+
+use std::collections::HashMap;
+
+use pallas_codec::minicbor;
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{conway::TransactionInput, ExUnits};
+use pallas_traverse::{Era, MultiEraOutput, MultiEraTx, OutputRef};
+
+use crate::{resolve::InputResolver, utils::MultiEraProtocolParameters};
+
+/// Limits a tx is checked against. Those left unset are not checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxLimits {
+    pub max_tx_size: u64,
+    pub max_tx_ex_units: Option<ExUnits>,
+    pub max_block_ex_units: Option<ExUnits>,
+    /// Maximum size in bytes of the serialized value of each output
+    pub max_value_size: Option<u64>,
+    pub collateral_percentage: Option<u64>,
+    pub max_collateral_inputs: Option<u64>,
+}
+
+impl TxLimits {
+    pub fn new(max_tx_size: u64) -> Self {
+        Self {
+            max_tx_size,
+            max_tx_ex_units: None,
+            max_block_ex_units: None,
+            max_value_size: None,
+            collateral_percentage: None,
+            max_collateral_inputs: None,
+        }
+    }
+
+    pub fn ex_units(mut self, per_tx: ExUnits, per_block: ExUnits) -> Self {
+        self.max_tx_ex_units = Some(per_tx);
+        self.max_block_ex_units = Some(per_block);
+        self
+    }
+
+    pub fn max_value_size(mut self, max_value_size: u64) -> Self {
+        self.max_value_size = Some(max_value_size);
+        self
+    }
+
+    pub fn collateral(mut self, percentage: u64, max_inputs: u64) -> Self {
+        self.collateral_percentage = Some(percentage);
+        self.max_collateral_inputs = Some(max_inputs);
+        self
+    }
+}
+
+impl From<&MultiEraProtocolParameters> for TxLimits {
+    fn from(params: &MultiEraProtocolParameters) -> Self {
+        macro_rules! plutus_limits {
+            ($x:expr) => {
+                TxLimits::new($x.max_transaction_size as u64)
+                    .ex_units($x.max_tx_ex_units, $x.max_block_ex_units)
+                    .max_value_size($x.max_value_size as u64)
+                    .collateral(
+                        $x.collateral_percentage as u64,
+                        $x.max_collateral_inputs as u64,
+                    )
+            };
+        }
+
+        match params {
+            MultiEraProtocolParameters::Byron(x) => TxLimits::new(x.max_tx_size),
+            MultiEraProtocolParameters::Shelley(x) => TxLimits::new(x.max_transaction_size as u64),
+            MultiEraProtocolParameters::Alonzo(x) => plutus_limits!(x),
+            MultiEraProtocolParameters::Babbage(x) => plutus_limits!(x),
+            MultiEraProtocolParameters::Conway(x) => plutus_limits!(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LintViolation {
+    /// Size of the tx and maximum allowed
+    TxTooBig(u64, u64),
+    /// Units declared by the redeemers and maximum allowed per tx
+    TxExUnitsExceeded(ExUnits, ExUnits),
+    /// Units declared by the redeemers and maximum allowed per block
+    BlockExUnitsExceeded(ExUnits, ExUnits),
+    /// Index of the output, size of its value and maximum allowed
+    ValueTooBig(usize, u64, u64),
+    /// The tx runs scripts but has no collateral inputs
+    CollateralMissing,
+    /// Number of collateral inputs and maximum allowed
+    TooManyCollateralInputs(u64, u64),
+    /// Collateral provided and minimum required for the fee
+    InsufficientCollateral(u64, u64),
+    /// Collateral annotated in the body and actually provided
+    CollateralAnnotation(u64, u64),
+    /// The collateral balance holds assets other than lovelace
+    NonLovelaceCollateral,
+    UnresolvedCollateral(OutputRef),
+}
+
+fn sum_ex_units(tx: &MultiEraTx<'_>) -> ExUnits {
+    tx.redeemers()
+        .iter()
+        .map(|x| x.ex_units())
+        .fold(ExUnits { mem: 0, steps: 0 }, |acc, x| ExUnits {
+            mem: acc.mem + x.mem,
+            steps: acc.steps + x.steps,
+        })
+}
+
+fn exceeds(units: &ExUnits, max: &ExUnits) -> bool {
+    units.mem > max.mem || units.steps > max.steps
+}
+
+fn value_size(output: &MultiEraOutput) -> u64 {
+    minicbor::to_vec(output.value().into_alonzo())
+        .map(|x| x.len() as u64)
+        .unwrap_or_default()
+}
+
+/// Lovelace and assets of a set of outputs, returned ones counting negative
+#[derive(Default)]
+struct Balance {
+    lovelace: i128,
+    assets: HashMap<(Hash<28>, Vec<u8>), i128>,
+}
+
+impl Balance {
+    fn add(&mut self, output: &MultiEraOutput, sign: i128) {
+        let value = output.value();
+
+        self.lovelace += sign * value.coin() as i128;
+
+        for policy in value.assets() {
+            for asset in policy.assets() {
+                *self
+                    .assets
+                    .entry((*asset.policy(), asset.name().to_vec()))
+                    .or_default() += sign * asset.any_coin();
+            }
+        }
+    }
+}
+
+fn lint_collateral(
+    tx: &MultiEraTx<'_>,
+    limits: &TxLimits,
+    resolver: Option<&dyn InputResolver>,
+    violations: &mut Vec<LintViolation>,
+) {
+    let collateral = tx.collateral();
+
+    if collateral.is_empty() {
+        violations.push(LintViolation::CollateralMissing);
+        return;
+    }
+
+    if let Some(max) = limits.max_collateral_inputs {
+        if collateral.len() as u64 > max {
+            violations.push(LintViolation::TooManyCollateralInputs(
+                collateral.len() as u64,
+                max,
+            ));
+        }
+    }
+
+    let provided = match resolver {
+        Some(resolver) => {
+            let inputs: Vec<_> = collateral
+                .iter()
+                .map(|x| TransactionInput {
+                    transaction_id: *x.hash(),
+                    index: x.index(),
+                })
+                .collect();
+
+            let mut balance = Balance::default();
+            let mut resolved = true;
+
+            for (input, output) in collateral.iter().zip(resolver.resolve(&inputs)) {
+                match output
+                    .as_deref()
+                    .map(|x| MultiEraOutput::decode(Era::Conway, x))
+                {
+                    Some(Ok(output)) => balance.add(&output, 1),
+                    _ => {
+                        violations.push(LintViolation::UnresolvedCollateral(input.output_ref()));
+                        resolved = false;
+                    }
+                }
+            }
+
+            if let Some(output) = tx.collateral_return() {
+                balance.add(&output, -1);
+            }
+
+            if balance.assets.values().any(|x| *x != 0) {
+                violations.push(LintViolation::NonLovelaceCollateral);
+            }
+
+            let provided = balance.lovelace.max(0) as u64;
+
+            if let Some(declared) = tx.total_collateral() {
+                if resolved && declared != provided {
+                    violations.push(LintViolation::CollateralAnnotation(declared, provided));
+                }
+            }
+
+            resolved.then_some(provided)
+        }
+        None => tx.total_collateral(),
+    };
+
+    if let (Some(provided), Some(percentage)) = (provided, limits.collateral_percentage) {
+        let fee = tx.fee().unwrap_or_default();
+
+        if provided * 100 < fee * percentage {
+            let required = (fee * percentage).div_ceil(100);
+            violations.push(LintViolation::InsufficientCollateral(provided, required));
+        }
+    }
+}
+
+fn lint_tx(
+    tx: &MultiEraTx<'_>,
+    limits: &TxLimits,
+    resolver: Option<&dyn InputResolver>,
+) -> Vec<LintViolation> {
+    let mut violations = vec![];
+
+    let size = tx.size() as u64;
+
+    if size > limits.max_tx_size {
+        violations.push(LintViolation::TxTooBig(size, limits.max_tx_size));
+    }
+
+    let runs_scripts = !tx.redeemers().is_empty();
+
+    if runs_scripts {
+        let units = sum_ex_units(tx);
+
+        if let Some(max) = limits.max_tx_ex_units {
+            if exceeds(&units, &max) {
+                violations.push(LintViolation::TxExUnitsExceeded(units, max));
+            }
+        }
+
+        if let Some(max) = limits.max_block_ex_units {
+            if exceeds(&units, &max) {
+                violations.push(LintViolation::BlockExUnitsExceeded(units, max));
+            }
+        }
+    }
+
+    if let Some(max) = limits.max_value_size {
+        for (index, output) in tx.outputs().iter().enumerate() {
+            let size = value_size(output);
+
+            if size > max {
+                violations.push(LintViolation::ValueTooBig(index, size, max));
+            }
+        }
+    }
+
+    if runs_scripts {
+        lint_collateral(tx, limits, resolver, &mut violations);
+    }
+
+    violations
+}
+
+/// Checks the tx against the limits. Without access to the collateral
+/// outputs, the collateral amount is taken from the total collateral
+/// annotated in the body, if any.
+pub fn lint(tx: &MultiEraTx<'_>, limits: &TxLimits) -> Vec<LintViolation> {
+    lint_tx(tx, limits, None)
+}
+
+/// Same as [`lint`], resolving the collateral inputs to check the amount
+/// and assets they actually provide
+pub fn lint_with(
+    tx: &MultiEraTx<'_>,
+    limits: &TxLimits,
+    resolver: &impl InputResolver,
+) -> Vec<LintViolation> {
+    lint_tx(tx, limits, Some(resolver))
+}
@@ -0,0 +1,299 @@
+//! In-memory pool of validated transactions waiting to be included in a
+//! block.
+//!
+//! The pool doesn't validate transactions itself, callers are expected to run
+//! phase-1 validation before adding them. What it does enforce is that no two
+//! transactions in the pool spend the same input, that expired transactions
+//! are evicted as the chain moves forward, and that the pool stays within its
+//! size limit.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pallas_crypto::hash::Hash;
+use pallas_traverse::{validity::ValidityEnd, Era, MultiEraBlock, MultiEraTx, OutputRef};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    AlreadyInPool(Hash<32>),
+    Expired(Hash<32>),
+    /// The tx spends inputs already spent by the listed txs in the pool
+    Conflict(Hash<32>, Vec<Hash<32>>),
+    /// The tx conflicts with txs in the pool but doesn't pay enough to
+    /// replace them
+    InsufficientReplacementFee(Hash<32>),
+    PoolFull(Hash<32>),
+}
+
+/// How to handle a tx that spends inputs already spent in the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplacementPolicy {
+    /// The first tx to arrive wins, as in the Haskell node
+    #[default]
+    Reject,
+    /// The new tx evicts the conflicting ones if its fee is greater than the
+    /// sum of theirs
+    HigherFee,
+}
+
+/// A tx held by the pool, along with the data needed to apply the pool rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MempoolTx {
+    pub hash: Hash<32>,
+    pub era: Era,
+    pub cbor: Vec<u8>,
+    pub fee: u64,
+    pub inputs: Vec<OutputRef>,
+    /// Last slot at which the tx can be included in a block
+    pub last_valid_slot: ValidityEnd,
+    broadcast: bool,
+}
+
+impl MempoolTx {
+    fn new(tx: &MultiEraTx) -> Self {
+        Self {
+            hash: tx.hash(),
+            era: tx.era(),
+            cbor: tx.encode(),
+            fee: tx.fee().unwrap_or_default(),
+            inputs: tx.consumes().iter().map(|x| x.output_ref()).collect(),
+            last_valid_slot: tx.last_valid_slot(),
+            broadcast: false,
+        }
+    }
+
+    pub fn decode(&self) -> MultiEraTx<'_> {
+        // the bytes come from a tx that was already decoded
+        MultiEraTx::decode_for_era(self.era, &self.cbor).unwrap()
+    }
+
+    pub fn size(&self) -> usize {
+        self.cbor.len()
+    }
+
+    fn is_expired_at(&self, slot: u64) -> bool {
+        self.last_valid_slot.is_expired_at(slot)
+    }
+}
+
+pub struct Mempool {
+    max_bytes: usize,
+    policy: ReplacementPolicy,
+    size: usize,
+    next_seq: u64,
+    txs: BTreeMap<u64, MempoolTx>,
+    by_hash: HashMap<Hash<32>, u64>,
+    by_input: HashMap<OutputRef, u64>,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self {
+            max_bytes: usize::MAX,
+            policy: ReplacementPolicy::default(),
+            size: 0,
+            next_seq: 0,
+            txs: BTreeMap::new(),
+            by_hash: HashMap::new(),
+            by_input: HashMap::new(),
+        }
+    }
+}
+
+impl Mempool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Max total size in bytes of the txs in the pool, unbounded by default
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn replacement_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Adds a validated tx, returning the hashes of the txs it replaced
+    pub fn add(&mut self, tx: &MultiEraTx, slot: u64) -> Result<Vec<Hash<32>>, MempoolError> {
+        let tx = MempoolTx::new(tx);
+
+        if self.by_hash.contains_key(&tx.hash) {
+            return Err(MempoolError::AlreadyInPool(tx.hash));
+        }
+
+        if tx.is_expired_at(slot) {
+            return Err(MempoolError::Expired(tx.hash));
+        }
+
+        let mut conflicts: Vec<_> = tx
+            .inputs
+            .iter()
+            .filter_map(|x| self.by_input.get(x).copied())
+            .collect();
+
+        conflicts.sort();
+        conflicts.dedup();
+
+        if !conflicts.is_empty() {
+            let hashes = conflicts.iter().map(|x| self.txs[x].hash).collect();
+
+            match self.policy {
+                ReplacementPolicy::Reject => {
+                    return Err(MempoolError::Conflict(tx.hash, hashes));
+                }
+                ReplacementPolicy::HigherFee => {
+                    let replaced_fee: u64 = conflicts.iter().map(|x| self.txs[x].fee).sum();
+
+                    if tx.fee <= replaced_fee {
+                        return Err(MempoolError::InsufficientReplacementFee(tx.hash));
+                    }
+                }
+            }
+        }
+
+        let freed: usize = conflicts.iter().map(|x| self.txs[x].size()).sum();
+
+        if self.size - freed + tx.size() > self.max_bytes {
+            return Err(MempoolError::PoolFull(tx.hash));
+        }
+
+        let replaced = conflicts
+            .into_iter()
+            .filter_map(|x| self.remove_seq(x))
+            .map(|x| x.hash)
+            .collect();
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.size += tx.size();
+        self.by_hash.insert(tx.hash, seq);
+
+        for input in tx.inputs.iter() {
+            self.by_input.insert(input.clone(), seq);
+        }
+
+        self.txs.insert(seq, tx);
+
+        Ok(replaced)
+    }
+
+    fn remove_seq(&mut self, seq: u64) -> Option<MempoolTx> {
+        let tx = self.txs.remove(&seq)?;
+
+        self.size -= tx.size();
+        self.by_hash.remove(&tx.hash);
+
+        for input in tx.inputs.iter() {
+            self.by_input.remove(input);
+        }
+
+        Some(tx)
+    }
+
+    pub fn remove(&mut self, hash: &Hash<32>) -> Option<MempoolTx> {
+        let seq = *self.by_hash.get(hash)?;
+        self.remove_seq(seq)
+    }
+
+    /// Evicts the txs that can no longer be included in a block at `slot`
+    pub fn expire(&mut self, slot: u64) -> Vec<MempoolTx> {
+        let expired: Vec<_> = self
+            .txs
+            .iter()
+            .filter(|(_, tx)| tx.is_expired_at(slot))
+            .map(|(seq, _)| *seq)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|x| self.remove_seq(x))
+            .collect()
+    }
+
+    /// Removes the txs included in a new block, along with the ones that
+    /// became invalid because they spend the same inputs, and those expired
+    /// by the slot of the block
+    pub fn apply_block(&mut self, block: &MultiEraBlock) -> Vec<MempoolTx> {
+        let mut removed = vec![];
+
+        for tx in block.txs() {
+            if let Some(x) = self.remove(&tx.hash()) {
+                removed.push(x);
+            }
+
+            for input in tx.consumes() {
+                if let Some(seq) = self.by_input.get(&input.output_ref()).copied() {
+                    removed.extend(self.remove_seq(seq));
+                }
+            }
+        }
+
+        removed.extend(self.expire(block.slot()));
+
+        removed
+    }
+
+    /// Txs to include in the next block, in arrival order, up to `max_bytes`
+    pub fn ready_for_block(&self, max_bytes: usize) -> Vec<&MempoolTx> {
+        let mut total = 0;
+
+        self.txs
+            .values()
+            .take_while(|tx| {
+                total += tx.size();
+                total <= max_bytes
+            })
+            .collect()
+    }
+
+    /// Txs that haven't been announced to peers yet, marking them as
+    /// announced
+    pub fn pending_broadcast(&mut self) -> Vec<&MempoolTx> {
+        let mut pending = vec![];
+
+        for tx in self.txs.values_mut() {
+            if !tx.broadcast {
+                tx.broadcast = true;
+                pending.push(&*tx);
+            }
+        }
+
+        pending
+    }
+
+    /// Marks every tx as not announced, so that they are all broadcast again
+    pub fn reset_broadcast(&mut self) {
+        for tx in self.txs.values_mut() {
+            tx.broadcast = false;
+        }
+    }
+
+    pub fn contains(&self, hash: &Hash<32>) -> bool {
+        self.by_hash.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &Hash<32>) -> Option<&MempoolTx> {
+        self.by_hash.get(hash).map(|x| &self.txs[x])
+    }
+
+    /// Txs in the pool, in arrival order
+    pub fn iter(&self) -> impl Iterator<Item = &MempoolTx> {
+        self.txs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// Total size in bytes of the txs in the pool
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
@@ -0,0 +1,302 @@
+//! Resolution of the outputs a transaction spends or references, along with
+//! the reference scripts and inline datums they carry (Babbage onwards).
+
+use std::{borrow::Cow, collections::HashMap, convert::Infallible};
+
+use pallas_codec::minicbor;
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::{
+    alonzo,
+    conway::{
+        MintedDatumOption, MintedScriptRef, PlutusData, PseudoScript, ScriptRef, TransactionInput,
+        TransactionOutput,
+    },
+};
+use pallas_traverse::{
+    ComputeHash, Era, MultiEraInput, MultiEraOutput, MultiEraTx, OriginalHash, OutputRef,
+};
+
+use crate::UTxOs;
+
+/// Source of unspent outputs, such as a local UTxO set or a node queried
+/// through LocalStateQuery
+pub trait UtxoStore {
+    type Error;
+
+    /// Era and CBOR of the output at `input`, or `None` if it is not unspent
+    fn fetch_output(&self, input: &OutputRef) -> Result<Option<(Era, Vec<u8>)>, Self::Error>;
+}
+
+impl UtxoStore for HashMap<OutputRef, (Era, Vec<u8>)> {
+    type Error = Infallible;
+
+    fn fetch_output(&self, input: &OutputRef) -> Result<Option<(Era, Vec<u8>)>, Self::Error> {
+        Ok(self.get(input).cloned())
+    }
+}
+
+impl UtxoStore for UTxOs<'_> {
+    type Error = Infallible;
+
+    fn fetch_output(&self, input: &OutputRef) -> Result<Option<(Era, Vec<u8>)>, Self::Error> {
+        let found = self
+            .iter()
+            .find(|(k, _)| k.output_ref() == *input)
+            .map(|(_, v)| (v.era(), v.encode()));
+
+        Ok(found)
+    }
+}
+
+/// Batch lookup of the outputs spent or referenced by transactions
+///
+/// This is the seam between the logic that needs resolved inputs and the
+/// place they come from. Outputs are returned as their original CBOR, in the
+/// same order as the inputs, with `None` for the ones that can't be resolved.
+pub trait InputResolver {
+    fn resolve(&self, inputs: &[TransactionInput]) -> Vec<Option<Vec<u8>>>;
+}
+
+impl<F> InputResolver for F
+where
+    F: Fn(&[TransactionInput]) -> Vec<Option<Vec<u8>>>,
+{
+    fn resolve(&self, inputs: &[TransactionInput]) -> Vec<Option<Vec<u8>>> {
+        self(inputs)
+    }
+}
+
+/// Resolves inputs through a [`UtxoStore`]
+///
+/// Store failures are reported as unresolved inputs, as are Byron outputs,
+/// which have no post-Byron representation.
+pub struct StoreResolver<'a, S>(pub &'a S);
+
+impl<S: UtxoStore> InputResolver for StoreResolver<'_, S> {
+    fn resolve(&self, inputs: &[TransactionInput]) -> Vec<Option<Vec<u8>>> {
+        inputs
+            .iter()
+            .map(|x| {
+                let output_ref = OutputRef::new(x.transaction_id, x.index);
+                let (era, cbor) = self.0.fetch_output(&output_ref).ok()??;
+
+                if era == Era::Byron {
+                    return None;
+                }
+
+                Some(cbor)
+            })
+            .collect()
+    }
+}
+
+/// Fixed set of outputs as CBOR, such as the result of a `GetUTxOByTxIn`
+/// query sent to a node through LocalStateQuery
+#[derive(Debug, Clone, Default)]
+pub struct UtxoSnapshot(pub HashMap<TransactionInput, Vec<u8>>);
+
+impl InputResolver for UtxoSnapshot {
+    fn resolve(&self, inputs: &[TransactionInput]) -> Vec<Option<Vec<u8>>> {
+        inputs.iter().map(|x| self.0.get(x).cloned()).collect()
+    }
+}
+
+impl FromIterator<(TransactionInput, Vec<u8>)> for UtxoSnapshot {
+    fn from_iter<T: IntoIterator<Item = (TransactionInput, Vec<u8>)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[derive(Debug)]
+pub enum ResolveError<E> {
+    Store(E),
+    MissingOutput(OutputRef),
+    MalformedOutput(OutputRef),
+}
+
+/// An output fetched from the store, kept as CBOR so that it can be decoded
+/// with its original bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedOutput {
+    pub input: MultiEraInput<'static>,
+    pub era: Era,
+    pub cbor: Vec<u8>,
+}
+
+impl ResolvedOutput {
+    pub fn output(&self) -> MultiEraOutput<'_> {
+        // bytes were checked to decode when resolving
+        MultiEraOutput::decode(self.era, &self.cbor).unwrap()
+    }
+}
+
+/// The outputs consumed (inputs, collateral) and referenced by a transaction
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedTx {
+    pub inputs: Vec<ResolvedOutput>,
+    pub collateral: Vec<ResolvedOutput>,
+    pub reference_inputs: Vec<ResolvedOutput>,
+}
+
+fn to_owned_input(input: &MultiEraInput) -> MultiEraInput<'static> {
+    match input.as_byron() {
+        Some(x) => MultiEraInput::Byron(Box::new(Cow::Owned(x.clone()))),
+        None => MultiEraInput::AlonzoCompatible(Box::new(Cow::Owned(alonzo::TransactionInput {
+            transaction_id: *input.hash(),
+            index: input.index(),
+        }))),
+    }
+}
+
+fn resolve_all<S: UtxoStore>(
+    inputs: Vec<MultiEraInput>,
+    store: &S,
+) -> Result<Vec<ResolvedOutput>, ResolveError<S::Error>> {
+    inputs
+        .iter()
+        .map(|x| {
+            let output_ref = x.output_ref();
+
+            let (era, cbor) = store
+                .fetch_output(&output_ref)
+                .map_err(ResolveError::Store)?
+                .ok_or_else(|| ResolveError::MissingOutput(output_ref.clone()))?;
+
+            if MultiEraOutput::decode(era, &cbor).is_err() {
+                return Err(ResolveError::MalformedOutput(output_ref));
+            }
+
+            Ok(ResolvedOutput {
+                input: to_owned_input(x),
+                era,
+                cbor,
+            })
+        })
+        .collect()
+}
+
+/// Fetches every output the transaction spends, may spend as collateral or
+/// references
+pub fn resolve<S: UtxoStore>(
+    tx: &MultiEraTx,
+    store: &S,
+) -> Result<ResolvedTx, ResolveError<S::Error>> {
+    Ok(ResolvedTx {
+        inputs: resolve_all(tx.inputs(), store)?,
+        collateral: resolve_all(tx.collateral(), store)?,
+        reference_inputs: resolve_all(tx.reference_inputs(), store)?,
+    })
+}
+
+/// Same as [`resolve`], looking up every input of the transaction in a
+/// single batch
+///
+/// Outputs keep the bytes given by the resolver, and are tagged with the era
+/// that validation expects for their shape: Alonzo for legacy outputs, the
+/// era of the transaction (Babbage at least) for post-Alonzo ones.
+pub fn resolve_with<R: InputResolver>(
+    tx: &MultiEraTx,
+    resolver: &R,
+) -> Result<ResolvedTx, ResolveError<Infallible>> {
+    let groups = [tx.inputs(), tx.collateral(), tx.reference_inputs()];
+
+    let inputs: Vec<_> = groups
+        .iter()
+        .flatten()
+        .map(|x| TransactionInput {
+            transaction_id: *x.hash(),
+            index: x.index(),
+        })
+        .collect();
+
+    let mut outputs = resolver.resolve(&inputs).into_iter();
+
+    let post_alonzo_era = tx.era().max(Era::Babbage);
+
+    let mut resolved = groups.iter().map(|group| {
+        group
+            .iter()
+            .map(|x| {
+                let output_ref = x.output_ref();
+
+                let cbor = outputs
+                    .next()
+                    .flatten()
+                    .ok_or_else(|| ResolveError::MissingOutput(output_ref.clone()))?;
+
+                let era = match minicbor::decode(&cbor) {
+                    Ok(TransactionOutput::Legacy(_)) => Era::Alonzo,
+                    Ok(TransactionOutput::PostAlonzo(_)) => post_alonzo_era,
+                    Err(_) => return Err(ResolveError::MalformedOutput(output_ref)),
+                };
+
+                if MultiEraOutput::decode(era, &cbor).is_err() {
+                    return Err(ResolveError::MalformedOutput(output_ref));
+                }
+
+                Ok(ResolvedOutput {
+                    input: to_owned_input(x),
+                    era,
+                    cbor,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    });
+
+    Ok(ResolvedTx {
+        inputs: resolved.next().unwrap()?,
+        collateral: resolved.next().unwrap()?,
+        reference_inputs: resolved.next().unwrap()?,
+    })
+}
+
+fn script_ref_hash(script: &MintedScriptRef) -> Hash<28> {
+    match script {
+        PseudoScript::NativeScript(x) => x.original_hash(),
+        PseudoScript::PlutusV1Script(x) => x.compute_hash(),
+        PseudoScript::PlutusV2Script(x) => x.compute_hash(),
+        PseudoScript::PlutusV3Script(x) => x.compute_hash(),
+    }
+}
+
+impl ResolvedTx {
+    fn all(&self) -> impl Iterator<Item = &ResolvedOutput> {
+        self.inputs
+            .iter()
+            .chain(self.collateral.iter())
+            .chain(self.reference_inputs.iter())
+    }
+
+    /// Scripts made available by reference, either from reference inputs or
+    /// from the spent outputs themselves
+    pub fn reference_scripts(&self) -> HashMap<Hash<28>, ScriptRef> {
+        self.inputs
+            .iter()
+            .chain(self.reference_inputs.iter())
+            .filter_map(|x| {
+                let output = x.output();
+                let script = output.script_ref()?;
+                Some((script_ref_hash(&script), script.into()))
+            })
+            .collect()
+    }
+
+    /// Inline datums of the resolved outputs, by hash
+    pub fn inline_datums(&self) -> HashMap<Hash<32>, PlutusData> {
+        self.inputs
+            .iter()
+            .chain(self.reference_inputs.iter())
+            .filter_map(|x| match x.output().datum()? {
+                MintedDatumOption::Data(x) => {
+                    Some((Hasher::<256>::hash(x.0.raw_cbor()), x.0.unwrap()))
+                }
+                MintedDatumOption::Hash(_) => None,
+            })
+            .collect()
+    }
+
+    /// The resolved outputs in the shape expected by validation
+    pub fn utxos(&self) -> UTxOs<'_> {
+        self.all().map(|x| (x.input.clone(), x.output())).collect()
+    }
+}
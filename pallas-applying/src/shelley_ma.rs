@@ -26,7 +26,9 @@ use pallas_primitives::{
     byron::TxOut,
 };
 use pallas_traverse::{
-    time::Slot, wellknown::GenesisValues, ComputeHash, Era, MultiEraInput, MultiEraOutput,
+    time::{EpochSchedule, Slot},
+    wellknown::GenesisValues,
+    ComputeHash, Era, MultiEraInput, MultiEraOutput,
 };
 
 use std::{cmp::max, collections::HashMap, ops::Deref};
@@ -58,6 +60,7 @@ pub fn validate_shelley_ma_tx(
     check_ttl(tx_body, block_slot)?;
     check_tx_size(&size, prot_pps)?;
     check_min_lovelace(tx_body, prot_pps, era)?;
+    check_withdrawals(tx_body, &mut cert_state.dstate)?;
     check_certificates(
         &tx_body.certificates,
         txix,
@@ -408,6 +411,15 @@ fn compute_script_hash(script: &NativeScript) -> PolicyId {
     pallas_crypto::hash::Hasher::<224>::hash(&payload)
 }
 
+// Withdrawals are applied before the certificates, so that a credential can
+// withdraw its rewards and deregister in the same transaction.
+fn check_withdrawals(tx_body: &TransactionBody, ds: &mut DState) -> ValidationResult {
+    match &tx_body.withdrawals {
+        Some(withdrawals) => ds.apply_withdrawals(withdrawals),
+        None => Ok(()),
+    }
+}
+
 // Checks all certificates in order, and counts the relevant ones for computing
 // deposits.
 #[allow(clippy::too_many_arguments)]
@@ -424,8 +436,8 @@ fn check_certificates(
     prot_pps: &ShelleyProtParams,
 ) -> ValidationResult {
     if let Some(certs) = cert_opt {
-        let genesis = &GenesisValues::mainnet();
-        let cepoch: Epoch = to_epoch(genesis, slot);
+        let schedule = &EpochSchedule::from(&GenesisValues::mainnet());
+        let cepoch: Epoch = to_epoch(schedule, slot);
         let mpc: Coin = prot_pps.min_pool_cost;
         let mut ptr = CertPointer {
             slot: *slot,
@@ -433,6 +445,7 @@ fn check_certificates(
             cert_ix: 0,
         };
         for (ix, cert) in certs.iter().enumerate() {
+            ptr.cert_ix = ix as u32;
             match cert {
                 StakeRegistration(stc) => {
                     *stk_dep_count += 1;
@@ -494,7 +507,6 @@ fn check_certificates(
                     check_mir(mir, slot, stab_win, &mut cert_state.dstate, acnt)?;
                 }
             }
-            ptr.cert_ix = ix as u32; // FIXME: Careful here, `ix` is `usize`
         }
         Ok(())
     } else {
@@ -647,8 +659,8 @@ fn check_mir(
     ds: &mut DState,
     acnt: &AccountState,
 ) -> ValidationResult {
-    let genesis = &GenesisValues::mainnet();
-    if *slot >= first_slot(genesis, &(to_epoch(genesis, slot) + 1)) - *stab_win {
+    let schedule = &EpochSchedule::from(&GenesisValues::mainnet());
+    if *slot >= first_slot(schedule, &(to_epoch(schedule, slot) + 1)) - *stab_win {
         Err(ShelleyMA(MIRCertificateTooLateinEpoch))
     } else {
         let (ir_reserves, ir_treasury) = ds.inst_rewards.clone();
@@ -679,14 +691,14 @@ fn check_mir(
 
 #[inline]
 // Called just `epoch` in specs
-fn to_epoch(genesis: &GenesisValues, slot: &Slot) -> Epoch {
-    genesis.absolute_slot_to_relative(*slot).0
+fn to_epoch(schedule: &EpochSchedule, slot: &Slot) -> Epoch {
+    schedule.slot_to_epoch(*slot).0
 }
 
 #[inline]
 // CamelCase in specs
-fn first_slot(genesis: &GenesisValues, epoch: &Epoch) -> Slot {
-    genesis.relative_slot_to_absolute(*epoch, 0)
+fn first_slot(schedule: &EpochSchedule, epoch: &Epoch) -> Slot {
+    schedule.epoch_first_slot(*epoch)
 }
 
 fn check_native_scripts(
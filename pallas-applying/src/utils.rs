@@ -4,7 +4,7 @@ pub mod environment;
 pub mod validation;
 
 pub use environment::*;
-use pallas_addresses::{Address, ShelleyAddress, ShelleyPaymentPart};
+use pallas_addresses::{Address, ShelleyAddress, ShelleyPaymentPart, StakePayload};
 use pallas_codec::{
     minicbor::encode,
     utils::{Bytes, KeepRaw, KeyValuePairs, Nullable},
@@ -13,6 +13,7 @@ use pallas_crypto::key::ed25519::{PublicKey, Signature};
 use pallas_primitives::{
     alonzo::{
         AuxiliaryData, MintedTx as AlonzoMintedTx, Multiasset, NativeScript, VKeyWitness, Value,
+        Withdrawals,
     },
     babbage::MintedTx as BabbageMintedTx,
     AddrKeyhash, AssetName, Coin, Epoch, GenesisDelegateHash, Genesishash, NetworkId, PlutusScript,
@@ -376,6 +377,10 @@ pub struct DState {
     pub fut_gen_delegs: FutGenesisDelegation,
     pub gen_delegs: GenesisDelegation,
     pub inst_rewards: InstantaneousRewards,
+    /// Whether `rewards` holds the actual balances of the reward accounts.
+    /// Withdrawals are only checked against them when set, and only for
+    /// Shelley-MA txs, as later eras don't apply certificates yet.
+    pub track_rewards: bool,
 }
 
 // Essentially part of the `PoolRegistration` component of `Certificate` at
@@ -407,3 +412,82 @@ pub struct CertState {
     pub pstate: PState,
     pub dstate: DState,
 }
+
+/// Stake credential of a reward account, which may be a key hash or a script
+/// hash
+pub fn get_reward_account_credential(account: &[u8]) -> Option<StakeCredential> {
+    match Address::from_bytes(account).ok()? {
+        Address::Stake(x) => match x.payload() {
+            StakePayload::Stake(x) => Some(StakeCredential::AddrKeyhash(*x)),
+            StakePayload::Script(x) => Some(StakeCredential::ScriptHash(*x)),
+        },
+        _ => None,
+    }
+}
+
+impl DState {
+    pub fn is_registered(&self, credential: &StakeCredential) -> bool {
+        self.rewards.contains_key(credential)
+    }
+
+    pub fn reward_balance(&self, credential: &StakeCredential) -> Option<Coin> {
+        self.rewards.get(credential).copied()
+    }
+
+    /// Credentials locked by scripts among the registered ones
+    pub fn script_credentials(&self) -> impl Iterator<Item = &StakeCredential> {
+        self.rewards
+            .keys()
+            .filter(|x| matches!(x, StakeCredential::ScriptHash(_)))
+    }
+
+    /// Empties the reward accounts of the withdrawals. As in the ledger, each
+    /// withdrawal must take the whole balance of a registered credential,
+    /// whether a key or a script one.
+    ///
+    /// Does nothing unless `track_rewards` is set, since the balances are
+    /// unknown to callers that don't follow the reward calculation.
+    pub fn apply_withdrawals(&mut self, withdrawals: &Withdrawals) -> ValidationResult {
+        if !self.track_rewards {
+            return Ok(());
+        }
+
+        for (account, amount) in withdrawals.iter() {
+            let credential = get_reward_account_credential(account).ok_or(
+                ValidationError::ShelleyMA(ShelleyMAError::InvalidRewardAccount),
+            )?;
+
+            match self.rewards.get_mut(&credential) {
+                None => return Err(ValidationError::ShelleyMA(ShelleyMAError::KeyNotRegistered)),
+                Some(balance) if balance != amount => {
+                    return Err(ValidationError::ShelleyMA(
+                        ShelleyMAError::WithdrawalNotFull,
+                    ))
+                }
+                Some(balance) => *balance = 0,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Credits the instantaneous rewards accumulated by MIR certificates to
+    /// their registered targets, as done at the epoch boundary, and returns
+    /// the amounts paid from the reserves and the treasury. Rewards for
+    /// unregistered credentials stay in their pot.
+    pub fn pay_instantaneous_rewards(&mut self) -> (Coin, Coin) {
+        let (reserves, treasury) = std::mem::take(&mut self.inst_rewards);
+
+        let mut pay = |pot: HashMap<StakeCredential, Coin>| {
+            pot.into_iter()
+                .filter_map(|(credential, amount)| {
+                    let balance = self.rewards.get_mut(&credential)?;
+                    *balance += amount;
+                    Some(amount)
+                })
+                .sum::<Coin>()
+        };
+
+        (pay(reserves), pay(treasury))
+    }
+}
@@ -64,6 +64,8 @@ pub enum ShelleyMAError {
     InsufficientForInstantaneousRewards,
     MIRCertificateTooLateinEpoch,
     ScriptDenial,
+    InvalidRewardAccount,
+    WithdrawalNotFull,
 }
 
 #[derive(Debug, Clone)]
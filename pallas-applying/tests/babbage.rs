@@ -29,10 +29,22 @@ use std::borrow::Cow;
 
 #[cfg(test)]
 mod babbage_tests {
+    use pallas_applying::resolve::{
+        resolve, resolve_with, ResolveError, StoreResolver, UtxoSnapshot,
+    };
+    use pallas_primitives::conway::TransactionInput;
     use pallas_primitives::MaybeIndefArray;
 
     use super::*;
 
+    /// Address, value, datum and reference script of a made-up output
+    type OutputInfo<'a> = (
+        String,
+        Value,
+        Option<MintedDatumOption<'a>>,
+        Option<CborWrap<MintedScriptRef<'a>>>,
+    );
+
     #[test]
     // Transaction hash:
     // b17d685c42e714238c1fb3abcd40e5c6291ebbb420c9c69b641209607bd00c7d
@@ -313,6 +325,142 @@ mod babbage_tests {
         }
     }
 
+    #[test]
+    // Same as above, with the UTxOs resolved from a store
+    fn successful_preview_tx_with_resolved_utxos() {
+        let cbor_bytes: Vec<u8> = cbor_to_bytes(include_str!("../../test_data/babbage12.tx"));
+        let mtx: MintedTx = babbage_minted_tx_from_cbor(&cbor_bytes);
+        let metx: MultiEraTx = MultiEraTx::from_babbage(&mtx);
+        let tx_outs_info: &[OutputInfo] = &[
+            (
+                String::from("60b5f82aaebdc942bb0c8774dc712338b82e5133fe69ebbc3b6312098e"),
+                Value::Coin(20000000),
+                None,
+                None,
+            ),
+            (
+                String::from("708D73F125395466F1D68570447E4F4B87CD633C6728F3802B2DCFCA20"),
+                Value::Multiasset(
+                    2000000,
+                    KeyValuePairs::from(Vec::from([(
+                        "7F5AC1926607F0D6C000E088CEA67A1EDFDF5CB21F8B7F73412319B0"
+                            .parse()
+                            .unwrap(),
+                        KeyValuePairs::from(Vec::from([(
+                            Bytes::from(
+                                hex::decode(
+                                    "B5F82AAEBDC942BB0C8774DC712338B82E5133FE69EBBC3B6312098E",
+                                )
+                                .unwrap(),
+                            ),
+                            1,
+                        )])),
+                    )])),
+                ),
+                Some(PseudoDatumOption::Hash(
+                    hex::decode("923918E403BF43C34B4EF6B48EB2EE04BABED17320D8D1B9FF9AD086E86F44EC")
+                        .unwrap()
+                        .as_slice()
+                        .into(),
+                )),
+                None,
+            ),
+        ];
+        let mut store: UTxOs = mk_utxo_for_babbage_tx(&mtx.transaction_body, tx_outs_info);
+        let collateral_info: &[OutputInfo] = &[(
+            String::from("60b5f82aaebdc942bb0c8774dc712338b82e5133fe69ebbc3b6312098e"),
+            Value::Coin(20000000),
+            None,
+            None,
+        )];
+        add_collateral_babbage(&mtx.transaction_body, &mut store, collateral_info);
+
+        assert!(matches!(
+            resolve(&metx, &UTxOs::new()),
+            Err(ResolveError::MissingOutput(_))
+        ));
+
+        let resolved = resolve(&metx, &store).unwrap();
+        assert_eq!(resolved.inputs.len(), 2);
+        assert_eq!(resolved.collateral.len(), 1);
+        assert!(resolved.reference_inputs.is_empty());
+        assert!(resolved.reference_scripts().is_empty());
+        assert!(resolved.inline_datums().is_empty());
+
+        let acnt = AccountState {
+            treasury: 261_254_564_000_000,
+            reserves: 0,
+        };
+
+        let env: Environment = Environment {
+            prot_params: MultiEraProtocolParameters::Babbage(mk_preview_params_epoch_30()),
+            prot_magic: 2,
+            block_slot: 2592005,
+            network_id: 0,
+            acnt: Some(acnt),
+        };
+        let mut cert_state: CertState = CertState::default();
+        if let Err(err) = validate_txs(
+            std::slice::from_ref(&metx),
+            &env,
+            &resolved.utxos(),
+            &mut cert_state,
+        ) {
+            panic!("Unexpected error ({:?})", err);
+        }
+
+        // same outputs through the batch resolver, from the store and from a
+        // snapshot of it
+        let batched = resolve_with(&metx, &StoreResolver(&store)).unwrap();
+        assert_eq!(batched.inputs.len(), 2);
+        assert_eq!(batched.collateral.len(), 1);
+
+        let snapshot: UtxoSnapshot = batched
+            .inputs
+            .iter()
+            .chain(batched.collateral.iter())
+            .map(|x| {
+                let input = TransactionInput {
+                    transaction_id: *x.input.hash(),
+                    index: x.input.index(),
+                };
+
+                (input, x.cbor.clone())
+            })
+            .collect();
+
+        let from_snapshot = resolve_with(&metx, &snapshot).unwrap();
+        assert_eq!(from_snapshot.inputs, batched.inputs);
+
+        // the bytes of the store reach validation untouched
+        for (batched, original) in batched.inputs.iter().zip(resolved.inputs.iter()) {
+            assert_eq!(batched.cbor, original.cbor);
+        }
+
+        let mut cert_state: CertState = CertState::default();
+        if let Err(err) = validate_txs(
+            std::slice::from_ref(&metx),
+            &env,
+            &batched.utxos(),
+            &mut cert_state,
+        ) {
+            panic!("Unexpected error ({:?})", err);
+        }
+
+        let nothing = |inputs: &[TransactionInput]| vec![None; inputs.len()];
+        assert!(matches!(
+            resolve_with(&metx, &nothing),
+            Err(ResolveError::MissingOutput(_))
+        ));
+
+        // a legacy output paying 5 lovelace with a non-minimal coin width
+        let wide = hex::decode("8241001a00000005").unwrap();
+        let wide_outputs = |inputs: &[TransactionInput]| vec![Some(wide.clone()); inputs.len()];
+        let resolved = resolve_with(&metx, &wide_outputs).unwrap();
+        assert!(resolved.inputs.iter().all(|x| x.cbor == wide));
+        assert_eq!(resolved.inputs[0].output().value().coin(), 5);
+    }
+
     #[test]
     // Transaction hash:
     // 1825d08e4496cca673fd9e47898b92cf97fdc293a40cf5cff99c5b123b364384
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use pallas_applying::header::{
+    praos_vrf_input, tpraos_vrf_input, ChainTip, ConsensusParams, HeaderError, HeaderValidator,
+    PoolStake, StakeDistribution, TPraosSeed,
+};
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::{ed25519::SecretKey, kes::Sum6Kes},
+    vrf::VrfSecretKey,
+};
+use pallas_primitives::VrfCert;
+use pallas_traverse::{
+    forge::{issue_operational_cert, BlockForger, ForgedBlock, LeaderProof, PoolKeys},
+    Era, MultiEraBlock, MultiEraHeader,
+};
+
+const NONCE: Hash<32> = Hash::new([9; 32]);
+
+struct Pool {
+    cold: SecretKey,
+    vrf: VrfSecretKey,
+    forger: BlockForger,
+}
+
+fn pool(era: Era, seed: u8, opcert_counter: u64) -> Pool {
+    let cold = SecretKey::from([seed; 32]);
+    let vrf = VrfSecretKey::from_seed([seed + 1; 32]);
+    let kes = Sum6Kes::from_seed([seed + 2; 32]);
+
+    let keys = PoolKeys {
+        issuer_vkey: cold.public_key().into(),
+        vrf_vkey: vrf.public_key().as_ref().try_into().unwrap(),
+        operational_cert: issue_operational_cert(&cold, kes.verification_key(), opcert_counter, 1),
+        kes,
+    };
+
+    let forger = BlockForger::new(era, keys)
+        .unwrap()
+        .slots_per_kes_period(100);
+
+    Pool { cold, vrf, forger }
+}
+
+impl Pool {
+    fn id(&self) -> Hash<28> {
+        Hasher::<224>::hash(self.cold.public_key().as_ref())
+    }
+
+    fn stake(&self, stake: u64) -> (Hash<28>, PoolStake) {
+        let stake = PoolStake {
+            stake,
+            vrf_vkey_hash: Hasher::<256>::hash(self.vrf.public_key().as_ref()),
+        };
+
+        (self.id(), stake)
+    }
+
+    fn cert(&self, input: Hash<32>) -> VrfCert {
+        let (proof, output) = self.vrf.prove(input.as_ref()).unwrap();
+        VrfCert(output.to_vec().into(), proof.as_ref().to_vec().into())
+    }
+
+    fn forge(&self, era: Era, number: u64, slot: u64, prev: Option<Hash<32>>) -> ForgedBlock {
+        let proof = match era {
+            Era::Babbage => LeaderProof::Praos(self.cert(praos_vrf_input(slot, &NONCE))),
+            _ => LeaderProof::TPraos {
+                nonce_vrf: self.cert(tpraos_vrf_input(slot, &NONCE, TPraosSeed::Nonce)),
+                leader_vrf: self.cert(tpraos_vrf_input(slot, &NONCE, TPraosSeed::Leader)),
+            },
+        };
+
+        self.forger.forge(number, slot, prev, &proof, &[]).unwrap()
+    }
+}
+
+fn params() -> ConsensusParams {
+    ConsensusParams {
+        // every slot has a leader
+        active_slot_coeff: 1.0,
+        slots_per_kes_period: 100,
+        max_kes_evolutions: 62,
+        protocol_major: 6,
+        max_protocol_major: 8,
+    }
+}
+
+fn distribution(pools: &[(Hash<28>, PoolStake)]) -> StakeDistribution {
+    StakeDistribution {
+        total: pools.iter().map(|(_, x)| x.stake).sum(),
+        pools: pools.iter().cloned().collect(),
+    }
+}
+
+fn validator(pools: &[(Hash<28>, PoolStake)]) -> HeaderValidator {
+    HeaderValidator::new(params(), NONCE, distribution(pools))
+}
+
+fn validate(validator: &mut HeaderValidator, block: &ForgedBlock) -> Result<(), HeaderError> {
+    let block = MultiEraBlock::decode(&block.cbor).unwrap();
+    validator.validate(&block.header())
+}
+
+#[test]
+fn forged_chains_validate() {
+    for era in [Era::Alonzo, Era::Babbage] {
+        let pool = pool(era, 1, 0);
+        let mut validator = validator(&[pool.stake(1)]);
+
+        let mut prev = None;
+
+        for (number, slot) in [(0, 100), (1, 150), (2, 230)] {
+            let block = pool.forge(era, number, slot, prev);
+
+            assert_eq!(validate(&mut validator, &block), Ok(()));
+            prev = Some(block.hash);
+        }
+
+        let tip = validator.tip().unwrap();
+        assert_eq!((tip.number, tip.slot, Some(tip.hash)), (2, 230, prev));
+        assert_eq!(validator.opcert_counters()[&pool.id()], 0);
+    }
+}
+
+#[test]
+fn headers_not_extending_the_tip() {
+    let pool = pool(Era::Babbage, 1, 0);
+
+    let tip = ChainTip {
+        slot: 200,
+        number: 10,
+        hash: Hash::new([1; 32]),
+    };
+
+    let cases = [
+        (
+            pool.forge(Era::Babbage, 11, 200, Some(tip.hash)),
+            HeaderError::SlotNotIncreasing,
+        ),
+        (
+            pool.forge(Era::Babbage, 12, 210, Some(tip.hash)),
+            HeaderError::BlockNumberMismatch,
+        ),
+        (
+            pool.forge(Era::Babbage, 11, 210, None),
+            HeaderError::PrevHashMismatch,
+        ),
+    ];
+
+    for (block, error) in cases {
+        let mut validator = validator(&[pool.stake(1)]).with_tip(tip);
+        assert_eq!(validate(&mut validator, &block), Err(error));
+    }
+}
+
+#[test]
+fn leader_checks() {
+    let pool = pool(Era::Babbage, 1, 0);
+    let other = self::pool(Era::Babbage, 10, 0);
+
+    let block = pool.forge(Era::Babbage, 0, 100, None);
+
+    assert_eq!(
+        validate(&mut validator(&[other.stake(1)]), &block),
+        Err(HeaderError::UnknownPool(pool.id()))
+    );
+
+    let (id, mut stake) = pool.stake(1);
+    stake.vrf_vkey_hash = Hash::new([0; 32]);
+
+    assert_eq!(
+        validate(&mut validator(&[(id, stake)]), &block),
+        Err(HeaderError::VrfKeyMismatch)
+    );
+
+    // a proof for another epoch nonce
+    let mut next_epoch = validator(&[pool.stake(1)]);
+    next_epoch.new_epoch(Hash::new([8; 32]), distribution(&[pool.stake(1)]));
+
+    assert_eq!(
+        validate(&mut next_epoch, &block),
+        Err(HeaderError::InvalidVrfProof)
+    );
+
+    // a negligible share of the stake can't win the slot on mainnet
+    let params = ConsensusParams {
+        active_slot_coeff: 0.05,
+        ..params()
+    };
+
+    let stake = distribution(&[pool.stake(1), other.stake(1_000_000_000_000_000)]);
+    let mut validator = HeaderValidator::new(params, NONCE, stake);
+
+    assert_eq!(
+        validate(&mut validator, &block),
+        Err(HeaderError::NotSlotLeader)
+    );
+}
+
+#[test]
+fn opcert_and_protocol_checks() {
+    let pool = pool(Era::Babbage, 1, 3);
+
+    // the opcert starts at KES period 1 and lasts 62 periods
+    assert_eq!(
+        validate(
+            &mut validator(&[pool.stake(1)]),
+            &pool.forge(Era::Babbage, 0, 6_300, None)
+        ),
+        Err(HeaderError::KesPeriodExpired)
+    );
+
+    let block = pool.forge(Era::Babbage, 0, 100, None);
+
+    let counters = |x| HashMap::from([(pool.id(), x)]);
+
+    for (last, result) in [
+        (3, Ok(())),
+        (2, Ok(())),
+        (4, Err(HeaderError::InvalidOpCertCounter)),
+        (1, Err(HeaderError::InvalidOpCertCounter)),
+    ] {
+        let mut validator = validator(&[pool.stake(1)]).with_opcert_counters(counters(last));
+        assert_eq!(validate(&mut validator, &block), result);
+    }
+
+    // flip a byte of the KES signature, which sits right before the four
+    // parts of the empty body
+    let mut tampered = block.clone();
+    let at = tampered.cbor.len() - 10;
+    tampered.cbor[at] ^= 0xff;
+
+    assert_eq!(
+        validate(&mut validator(&[pool.stake(1)]), &tampered),
+        Err(HeaderError::InvalidKesSignature)
+    );
+
+    // the forger announces protocol version 8
+    for (min, max, result) in [
+        (9, 10, Err(HeaderError::ProtocolVersionTooLow)),
+        (6, 7, Err(HeaderError::ProtocolVersionTooHigh)),
+    ] {
+        let params = ConsensusParams {
+            protocol_major: min,
+            max_protocol_major: max,
+            ..params()
+        };
+
+        let mut validator = HeaderValidator::new(params, NONCE, distribution(&[pool.stake(1)]));
+        assert_eq!(validate(&mut validator, &block), result);
+    }
+}
+
+#[test]
+fn mainnet_conway_header() {
+    // mainnet block 10817298 (slot 134402628, epoch 508), checked against the
+    // epoch nonce and the VRF key registered by its pool
+    let cbor = hex::decode(include_str!("../../test_data/conway1.header")).unwrap();
+    let header = MultiEraHeader::decode(6, None, &cbor).unwrap();
+
+    let nonce: Hash<32> = "c7937fc47fecbe687891b3decd71e904d1e129598aa3852481d295eea3ea3ada"
+        .parse()
+        .unwrap();
+
+    let pool: Hash<28> = "00beef0a9be2f6d897ed24a613cf547bb20cd282a04edfc53d477114"
+        .parse()
+        .unwrap();
+
+    let vrf_vkey_hash: Hash<32> =
+        "c0d1f9b040d2f6fd7fc8775d24753d6db4b697429f11404a6178a0a4a005867b"
+            .parse()
+            .unwrap();
+
+    // the leader value of this header wins the slot with at least 0.02866% of
+    // the active stake, which the pool held that epoch
+    let stake = |stake| StakeDistribution {
+        total: 1_000_000,
+        pools: HashMap::from([(
+            pool,
+            PoolStake {
+                stake,
+                vrf_vkey_hash,
+            },
+        )]),
+    };
+
+    let validator = |nonce, stake| {
+        HeaderValidator::new(ConsensusParams::mainnet(), nonce, stake)
+            .with_opcert_counters(HashMap::from([(pool, 23)]))
+    };
+
+    assert_eq!(validator(nonce, stake(287)).validate(&header), Ok(()));
+
+    assert_eq!(
+        validator(nonce, stake(286)).validate(&header),
+        Err(HeaderError::NotSlotLeader)
+    );
+
+    assert_eq!(
+        validator(NONCE, stake(287)).validate(&header),
+        Err(HeaderError::InvalidVrfProof)
+    );
+}
@@ -0,0 +1,139 @@
+pub mod common;
+
+use common::cbor_to_bytes;
+use pallas_applying::lint::{lint, lint_with, LintViolation, TxLimits};
+use pallas_codec::{minicbor, utils::NonEmptyKeyValuePairs};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{
+    babbage::PseudoPostAlonzoTransactionOutput,
+    conway::{PseudoTransactionOutput, TransactionInput, TransactionOutput, Value},
+    ExUnits, PositiveCoin,
+};
+use pallas_traverse::{Era, MultiEraTx};
+
+// A PlutusV2 tx paying a fee of 300316 with one collateral input, annotating
+// a total collateral of 5 ADA and returning 65884589 lovelace
+const PLUTUS_TX: &str = include_str!("../../test_data/babbage13.tx");
+
+const COLLATERAL_INPUT: u64 = 70_884_589;
+
+fn ex_units(mem: u64, steps: u64) -> ExUnits {
+    ExUnits { mem, steps }
+}
+
+fn generous() -> TxLimits {
+    TxLimits::new(16_384)
+        .ex_units(
+            ex_units(14_000_000, 10_000_000_000),
+            ex_units(62_000_000, 20_000_000_000),
+        )
+        .max_value_size(5_000)
+        .collateral(150, 3)
+}
+
+fn collateral_output(value: Value) -> TransactionOutput {
+    PseudoTransactionOutput::PostAlonzo(PseudoPostAlonzoTransactionOutput {
+        address: hex::decode("61c3b1f2e1a0a1a5d40a9f6a5a4e0d9c7d1f08c1ab0f2cd76a1b2a4e7c")
+            .unwrap()
+            .into(),
+        value,
+        datum_option: None,
+        script_ref: None,
+    })
+}
+
+fn resolving(
+    output: Option<TransactionOutput>,
+) -> impl Fn(&[TransactionInput]) -> Vec<Option<Vec<u8>>> {
+    let cbor = output.map(|x| minicbor::to_vec(x).unwrap());
+    move |inputs| inputs.iter().map(|_| cbor.clone()).collect()
+}
+
+#[test]
+fn lint_reports_every_violation() {
+    let cbor = cbor_to_bytes(PLUTUS_TX);
+    let tx = MultiEraTx::decode_for_era(Era::Babbage, &cbor).unwrap();
+
+    assert_eq!(lint(&tx, &generous()), vec![]);
+
+    let limits = TxLimits::new(1_000)
+        .ex_units(
+            ex_units(200_000, 200_000_000),
+            ex_units(300_000, 100_000_000),
+        )
+        .max_value_size(1)
+        .collateral(10_000, 0);
+
+    let units = ex_units(253_695, 102_079_693);
+    let violations = lint(&tx, &limits);
+
+    assert_eq!(violations[0], LintViolation::TxTooBig(2692, 1_000));
+    assert_eq!(
+        violations[1],
+        LintViolation::TxExUnitsExceeded(units, ex_units(200_000, 200_000_000))
+    );
+    assert_eq!(
+        violations[2],
+        LintViolation::BlockExUnitsExceeded(units, ex_units(300_000, 100_000_000))
+    );
+    assert!(matches!(violations[3], LintViolation::ValueTooBig(0, _, 1)));
+    assert!(matches!(violations[4], LintViolation::ValueTooBig(1, _, 1)));
+    assert_eq!(violations[5], LintViolation::TooManyCollateralInputs(1, 0));
+    assert_eq!(
+        violations[6],
+        LintViolation::InsufficientCollateral(5_000_000, 30_031_600)
+    );
+    assert_eq!(violations.len(), 7);
+
+    // txs without redeemers need no collateral nor budget, only the limits on
+    // their size and values apply
+    let cbor = cbor_to_bytes(include_str!("../../test_data/babbage11.tx"));
+    let tx = MultiEraTx::decode_for_era(Era::Babbage, &cbor).unwrap();
+
+    assert_eq!(
+        lint(&tx, &limits),
+        vec![LintViolation::ValueTooBig(0, 5, 1)]
+    );
+}
+
+#[test]
+fn lint_with_resolved_collateral() {
+    let cbor = cbor_to_bytes(PLUTUS_TX);
+    let tx = MultiEraTx::decode_for_era(Era::Babbage, &cbor).unwrap();
+    let collateral_ref = tx.collateral()[0].output_ref();
+
+    let exact = resolving(Some(collateral_output(Value::Coin(COLLATERAL_INPUT))));
+    assert_eq!(lint_with(&tx, &generous(), &exact), vec![]);
+
+    let short = resolving(Some(collateral_output(Value::Coin(66_000_000))));
+    assert_eq!(
+        lint_with(&tx, &generous(), &short),
+        vec![
+            LintViolation::CollateralAnnotation(5_000_000, 115_411),
+            LintViolation::InsufficientCollateral(115_411, 450_474),
+        ]
+    );
+
+    let assets = NonEmptyKeyValuePairs::Def(vec![(
+        Hash::new([1; 28]),
+        NonEmptyKeyValuePairs::Def(vec![(
+            b"token".to_vec().into(),
+            PositiveCoin::try_from(1).unwrap(),
+        )]),
+    )]);
+
+    let with_assets = resolving(Some(collateral_output(Value::Multiasset(
+        COLLATERAL_INPUT,
+        assets,
+    ))));
+    assert_eq!(
+        lint_with(&tx, &generous(), &with_assets),
+        vec![LintViolation::NonLovelaceCollateral]
+    );
+
+    let unresolved = resolving(None);
+    assert_eq!(
+        lint_with(&tx, &generous(), &unresolved),
+        vec![LintViolation::UnresolvedCollateral(collateral_ref)]
+    );
+}
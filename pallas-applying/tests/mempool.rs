@@ -0,0 +1,119 @@
+pub mod common;
+
+use common::cbor_to_bytes;
+use pallas_applying::mempool::{Mempool, MempoolError, MempoolTx, ReplacementPolicy};
+use pallas_codec::minicbor;
+use pallas_primitives::babbage::Tx;
+use pallas_traverse::{Era, MultiEraBlock, MultiEraTx};
+
+// Re-encodes a babbage tx with a different fee and ttl, keeping its inputs
+fn variant(tx_hex: &str, fee: Option<u64>, ttl: Option<u64>) -> Vec<u8> {
+    let mut tx: Tx = minicbor::decode(&cbor_to_bytes(tx_hex)).unwrap();
+
+    if let Some(fee) = fee {
+        tx.transaction_body.fee = fee;
+    }
+
+    tx.transaction_body.ttl = ttl;
+
+    minicbor::to_vec(&tx).unwrap()
+}
+
+fn decode(cbor: &[u8]) -> MultiEraTx<'_> {
+    MultiEraTx::decode_for_era(Era::Babbage, cbor).unwrap()
+}
+
+#[test]
+fn mempool_rejects_conflicts_and_expires() {
+    let a = variant(
+        include_str!("../../test_data/babbage3.tx"),
+        None,
+        Some(1000),
+    );
+    let conflicting = variant(include_str!("../../test_data/babbage3.tx"), Some(1), None);
+    let b = variant(include_str!("../../test_data/babbage4.tx"), None, None);
+
+    let (a, conflicting, b) = (decode(&a), decode(&conflicting), decode(&b));
+
+    let mut pool = Mempool::new();
+
+    assert_eq!(pool.add(&a, 0), Ok(vec![]));
+    assert_eq!(pool.add(&a, 0), Err(MempoolError::AlreadyInPool(a.hash())));
+    assert_eq!(
+        pool.add(&conflicting, 0),
+        Err(MempoolError::Conflict(conflicting.hash(), vec![a.hash()]))
+    );
+    assert_eq!(pool.add(&b, 0), Ok(vec![]));
+
+    let hashes = |txs: Vec<&MempoolTx>| txs.iter().map(|x| x.hash).collect::<Vec<_>>();
+
+    assert_eq!(
+        hashes(pool.ready_for_block(usize::MAX)),
+        [a.hash(), b.hash()]
+    );
+    assert_eq!(hashes(pool.ready_for_block(pool.size() - 1)), [a.hash()]);
+
+    assert_eq!(pool.pending_broadcast().len(), 2);
+    assert!(pool.pending_broadcast().is_empty());
+
+    // babbage ttls are exclusive
+    assert!(pool.expire(999).is_empty());
+    assert_eq!(pool.expire(1000)[0].hash, a.hash());
+    assert_eq!(pool.len(), 1);
+
+    assert_eq!(pool.add(&a, 1000), Err(MempoolError::Expired(a.hash())));
+
+    // the input spent by the expired tx is available again
+    assert_eq!(pool.add(&conflicting, 1000), Ok(vec![]));
+    assert_eq!(pool.pending_broadcast().len(), 1);
+}
+
+#[test]
+fn mempool_replaces_by_fee() {
+    let tx_hex = include_str!("../../test_data/babbage3.tx");
+    let fee = decode(&cbor_to_bytes(tx_hex)).fee().unwrap();
+
+    let a = variant(tx_hex, None, None);
+    let same_fee = variant(tx_hex, None, Some(5000));
+    let higher_fee = variant(tx_hex, Some(fee + 1), None);
+
+    let (a, same_fee, higher_fee) = (decode(&a), decode(&same_fee), decode(&higher_fee));
+
+    let mut pool = Mempool::new().replacement_policy(ReplacementPolicy::HigherFee);
+
+    pool.add(&a, 0).unwrap();
+
+    assert_eq!(
+        pool.add(&same_fee, 0),
+        Err(MempoolError::InsufficientReplacementFee(same_fee.hash()))
+    );
+    assert_eq!(pool.add(&higher_fee, 0), Ok(vec![a.hash()]));
+    assert!(!pool.contains(&a.hash()));
+    assert_eq!(pool.get(&higher_fee.hash()).unwrap().fee, fee + 1);
+
+    let b = variant(include_str!("../../test_data/babbage4.tx"), None, None);
+    let b = decode(&b);
+
+    let mut full = Mempool::new().max_bytes(pool.size());
+    full.add(&higher_fee, 0).unwrap();
+
+    assert_eq!(full.add(&b, 0), Err(MempoolError::PoolFull(b.hash())));
+}
+
+#[test]
+fn mempool_drops_txs_included_in_blocks() {
+    let block_bytes = cbor_to_bytes(include_str!("../../test_data/babbage1.block"));
+    let block = MultiEraBlock::decode(&block_bytes).unwrap();
+    let txs = block.txs();
+
+    let mut pool = Mempool::new();
+
+    for tx in txs.iter() {
+        pool.add(tx, 0).unwrap();
+    }
+
+    assert_eq!(pool.len(), txs.len());
+    assert_eq!(pool.apply_block(&block).len(), txs.len());
+    assert!(pool.is_empty());
+    assert_eq!(pool.size(), 0);
+}
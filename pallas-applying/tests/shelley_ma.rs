@@ -1928,4 +1928,41 @@ mod shelley_ma_tests {
             },
         }
     }
+
+    #[test]
+    // Rewards of script stake credentials, as held by DAO treasuries, are
+    // credited from MIR certificates and withdrawn like those of keys
+    fn script_credential_rewards() {
+        use pallas_applying::utils::DState;
+        use pallas_codec::utils::KeyValuePairs;
+
+        let script_hash =
+            Hash::from_str("8DA6ECCA8CB33AAD6E4B7BA7A1DBDC1AE8FDE53D56AED80BF8D0EA7F").unwrap();
+        let script = StakeCredential::ScriptHash(script_hash);
+
+        // script reward account on mainnet
+        let account: Bytes = [&[0xf1], script_hash.as_ref()].concat().into();
+        let partial = KeyValuePairs::from(vec![(account.clone(), 1)]);
+
+        // balances are only checked when they are known
+        let mut dstate = DState::default();
+        dstate.apply_withdrawals(&partial).unwrap();
+
+        dstate.track_rewards = true;
+        dstate.rewards.insert(script.clone(), 0);
+        dstate.inst_rewards.1.insert(script.clone(), 1_000_000);
+
+        assert_eq!(dstate.pay_instantaneous_rewards(), (0, 1_000_000));
+        assert_eq!(dstate.reward_balance(&script), Some(1_000_000));
+        assert_eq!(dstate.script_credentials().count(), 1);
+
+        match dstate.apply_withdrawals(&partial) {
+            Err(ShelleyMA(ShelleyMAError::WithdrawalNotFull)) => (),
+            x => panic!("Unexpected result ({:?})", x),
+        }
+
+        let full = KeyValuePairs::from(vec![(account, 1_000_000)]);
+        dstate.apply_withdrawals(&full).unwrap();
+        assert_eq!(dstate.reward_balance(&script), Some(0));
+    }
 }
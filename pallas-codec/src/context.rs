@@ -0,0 +1,201 @@
+use std::fmt;
+
+use minicbor::Decode;
+
+/// Anomaly tolerated while decoding a structure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecWarning {
+    /// A value was skipped over without being decoded
+    SkippedValue { position: usize },
+
+    /// A value used a valid but non-canonical encoding
+    NonCanonical {
+        position: usize,
+        detail: &'static str,
+    },
+
+    /// A tag other than the expected one was found and accepted
+    UnknownTag { position: usize, tag: u64 },
+}
+
+impl fmt::Display for CodecWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecWarning::SkippedValue { position } => {
+                write!(f, "skipped value at byte {position}")
+            }
+            CodecWarning::NonCanonical { position, detail } => {
+                write!(f, "non-canonical encoding at byte {position}: {detail}")
+            }
+            CodecWarning::UnknownTag { position, tag } => {
+                write!(f, "unknown tag {tag} at byte {position}")
+            }
+        }
+    }
+}
+
+/// Warnings gathered while decoding with a [`DecodeContext`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodecWarnings(Vec<CodecWarning>);
+
+impl CodecWarnings {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, CodecWarning> {
+        self.0.iter()
+    }
+
+    /// Removes the gathered warnings, leaving the collector empty
+    pub fn take(&mut self) -> Vec<CodecWarning> {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl<'a> IntoIterator for &'a CodecWarnings {
+    type Item = &'a CodecWarning;
+    type IntoIter = std::slice::Iter<'a, CodecWarning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Behavior of a decoding context, as seen by the Pallas codecs
+///
+/// Codecs require their context to implement this trait. The unit context,
+/// used by plain `minicbor::decode` calls, is lenient and ignores warnings;
+/// [`DecodeContext`] makes every aspect configurable.
+pub trait CodecContext {
+    /// Receives an anomaly tolerated while decoding
+    fn warn(&mut self, _warning: CodecWarning) {}
+
+    /// The era of the structure being decoded, as the tag of the hard-fork
+    /// combinator wrapper (Byron is 1, Conway is 7), if known
+    fn era(&self) -> Option<u16> {
+        None
+    }
+
+    /// Whether anomalies should fail the decoding instead of being tolerated
+    fn is_strict(&self) -> bool {
+        false
+    }
+
+    /// Maximum number of items accepted for a single collection, enforced by
+    /// the `decode_limited` methods of the collection types
+    fn collection_limit(&self) -> Option<u64> {
+        None
+    }
+
+    /// Called by codecs when they find an anomaly, which is an error in
+    /// strict mode and a warning otherwise
+    fn report(&mut self, warning: CodecWarning) -> Result<(), minicbor::decode::Error> {
+        if self.is_strict() {
+            return Err(minicbor::decode::Error::message(warning.to_string()));
+        }
+
+        self.warn(warning);
+
+        Ok(())
+    }
+
+    /// Fails if a collection of `len` items exceeds the limit
+    fn check_collection_len(&self, len: Option<u64>) -> Result<(), minicbor::decode::Error> {
+        check_collection_len(self.collection_limit(), len)
+    }
+}
+
+/// Fails if a collection of `len` items exceeds `limit`
+pub(crate) fn check_collection_len(
+    limit: Option<u64>,
+    len: Option<u64>,
+) -> Result<(), minicbor::decode::Error> {
+    match (limit, len) {
+        (Some(max), Some(len)) if len > max => Err(minicbor::decode::Error::message(format!(
+            "collection of {len} items exceeds the limit of {max}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+impl CodecContext for () {}
+
+impl CodecContext for DecodeContext {
+    fn warn(&mut self, warning: CodecWarning) {
+        self.warnings.0.push(warning);
+    }
+
+    fn era(&self) -> Option<u16> {
+        self.era
+    }
+
+    fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    fn collection_limit(&self) -> Option<u64> {
+        self.max_collection_len
+    }
+}
+
+/// Decoding context threaded through the `Decode` impls of Pallas types
+///
+/// All ledger codecs are generic over a [`CodecContext`] and forward it to
+/// the inner values using `decode_with`, so that the context reaches every
+/// level of a structure. This is the default context provided by Pallas;
+/// third parties can provide their own by implementing [`CodecContext`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeContext {
+    /// The era of the structure being decoded, if known by the caller. See
+    /// [`CodecContext::era`] for the numbering.
+    pub era: Option<u16>,
+
+    /// Whether non-canonical or otherwise suspicious encodings should be
+    /// rejected instead of tolerated
+    pub strict: bool,
+
+    /// Maximum number of items accepted for a single collection, see
+    /// [`CodecContext::collection_limit`]
+    pub max_collection_len: Option<u64>,
+
+    /// Anomalies found by the decode calls made with this context
+    pub warnings: CodecWarnings,
+}
+
+impl DecodeContext {
+    pub fn for_era(era: u16) -> Self {
+        Self {
+            era: Some(era),
+            ..Default::default()
+        }
+    }
+
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn max_collection_len(mut self, len: u64) -> Self {
+        self.max_collection_len = Some(len);
+        self
+    }
+}
+
+/// Decodes a value from the provided bytes, threading the given context
+///
+/// Anomalies found along the way are appended to `ctx.warnings`, unless the
+/// context is strict, in which case they fail the decoding.
+pub fn decode_with_context<'b, T>(
+    bytes: &'b [u8],
+    ctx: &mut DecodeContext,
+) -> Result<T, minicbor::decode::Error>
+where
+    T: Decode<'b, DecodeContext>,
+{
+    minicbor::decode_with(bytes, ctx)
+}
@@ -0,0 +1,44 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Replaces the content of the file at `path` in a crash-safe way
+///
+/// The bytes go to a temp file next to `path`, which is synced to disk
+/// before being renamed over the target. The parent directory is synced
+/// afterwards so the rename itself is durable. Readers see either the old or
+/// the new content, never a partial write.
+pub fn atomic_write(path: impl AsRef<Path>, bytes: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&tmp, path)?;
+
+    sync_parent_dir(path)
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(x) if !x.as_os_str().is_empty() => x,
+        _ => Path::new("."),
+    };
+
+    File::open(parent)?.sync_all()
+}
+
+// directories can't be opened for syncing outside of unix; NTFS journals the
+// rename as part of the metadata update
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -2,11 +2,43 @@
 pub mod flat;
 
 /// Shared re-export of minicbor lib across all Pallas
+///
+/// Downstream crates implementing `Encode` / `Decode` for their own wrappers
+/// around Pallas types should use this re-export instead of depending on
+/// minicbor directly, to avoid trait mismatches.
 pub use minicbor;
 
 /// Round-trip friendly common helper structs
 pub mod utils;
 
+/// Context shared across the decoding of nested structures
+pub mod context;
+
+/// Hex literal helpers for tests and examples
+pub mod literals;
+
+/// Codec for enums encoded as `[variant_id, ..fields]`
+pub mod tagged;
+
+/// Crash-safe persistence of encoded data
+pub mod fs;
+
+/// Failure to encode or decode a CBOR structure
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cbor decoding error: {0}")]
+    Decode(#[from] minicbor::decode::Error),
+
+    #[error("cbor encoding error: {0}")]
+    Encode(String),
+}
+
+impl<E: std::fmt::Display> From<minicbor::encode::Error<E>> for Error {
+    fn from(error: minicbor::encode::Error<E>) -> Self {
+        Error::Encode(error.to_string())
+    }
+}
+
 pub trait Fragment: Sized + for<'b> minicbor::Decode<'b, ()> + minicbor::Encode<()> {}
 
 impl<T> Fragment for T where T: for<'b> minicbor::Decode<'b, ()> + minicbor::Encode<()> + Sized {}
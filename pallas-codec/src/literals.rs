@@ -0,0 +1,44 @@
+//! Helpers to build values out of hex literals, meant for tests and examples
+//! where an invalid literal is a bug and panicking is the right outcome.
+
+use crate::Fragment;
+
+/// Decodes a hex string into bytes, panicking if it isn't valid hex
+#[track_caller]
+pub fn hex_bytes(hex: &str) -> Vec<u8> {
+    hex::decode(hex.trim()).unwrap_or_else(|e| panic!("invalid hex literal: {e}"))
+}
+
+/// Decodes a CBOR fragment from its hex representation, panicking if either
+/// the hex or the CBOR is invalid
+#[track_caller]
+pub fn decode_fragment_hex<T: Fragment>(hex: &str) -> T {
+    let bytes = hex_bytes(hex);
+
+    crate::minicbor::decode(&bytes).unwrap_or_else(|e| {
+        panic!(
+            "can't decode {} from hex literal: {e}",
+            std::any::type_name::<T>()
+        )
+    })
+}
+
+/// Bytes of a hex literal, e.g. `hex_bytes!("a100f6")`
+#[macro_export]
+macro_rules! hex_bytes {
+    ($hex:expr) => {
+        $crate::literals::hex_bytes($hex)
+    };
+}
+
+/// Value decoded from the hex of its CBOR, e.g. `cbor_hex!("a100f6")` where
+/// the type is inferred or `cbor_hex!(MyType, "a100f6")`
+#[macro_export]
+macro_rules! cbor_hex {
+    ($ty:ty, $hex:expr) => {
+        $crate::literals::decode_fragment_hex::<$ty>($hex)
+    };
+    ($hex:expr) => {
+        $crate::literals::decode_fragment_hex($hex)
+    };
+}
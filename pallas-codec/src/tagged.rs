@@ -0,0 +1,132 @@
+//! Codec for enums encoded as `[variant_id, ..fields]`
+//!
+//! Most sum types of the ledger CDDL are arrays whose first element tells
+//! the variant apart. [`tagged_enum!`](crate::tagged_enum) declares such an
+//! enum and generates both codec impls from a single table of variants, so
+//! ids and array lengths can't drift between the encoder and the decoder.
+
+use minicbor::{data::Type, decode::Error, Decoder};
+
+/// Checks the length of a definite array against the fields of the variant
+/// being decoded, for decoders written by hand
+pub fn check_len(len: Option<u64>, fields: u64, name: &str) -> Result<(), Error> {
+    match len {
+        Some(len) if len != fields + 1 => Err(Error::message(format!(
+            "expected {} elements for {name}, found {len}",
+            fields + 1
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Consumes the break that closes an indefinite array, failing if it holds
+/// more elements than the variant being decoded
+pub fn end_array(d: &mut Decoder, len: Option<u64>, name: &str) -> Result<(), Error> {
+    if len.is_some() {
+        return Ok(());
+    }
+
+    match d.datatype()? {
+        Type::Break => {
+            d.set_position(d.position() + 1);
+            Ok(())
+        }
+        _ => Err(Error::message(format!("too many elements for {name}"))),
+    }
+}
+
+/// Declares an enum encoded as an array of its variant id followed by the
+/// fields of the variant, generating its `Encode` and `Decode` impls.
+///
+/// Fields are named so the encoder can bind them, but the variants are
+/// declared as tuples:
+///
+/// ```
+/// pallas_codec::tagged_enum! {
+///     #[derive(Debug, PartialEq)]
+///     pub enum Shape {
+///         0 => Circle(radius: u32),
+///         1 => Rect(width: u32, height: u32),
+///     }
+/// }
+///
+/// let bytes = pallas_codec::minicbor::to_vec(Shape::Rect(2, 3)).unwrap();
+/// assert_eq!(bytes, [0x83, 0x01, 0x02, 0x03]);
+/// ```
+#[macro_export]
+macro_rules! tagged_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $id:literal => $variant:ident ( $($field:ident : $ty:ty),* $(,)? )
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant($($ty),*),
+            )*
+        }
+
+        impl<'b, C> $crate::minicbor::decode::Decode<'b, C> for $name {
+            fn decode(
+                d: &mut $crate::minicbor::Decoder<'b>,
+                ctx: &mut C,
+            ) -> Result<Self, $crate::minicbor::decode::Error> {
+                let len = d.array()?;
+                let id = d.u64()?;
+
+                let value = match id {
+                    $(
+                        $id => {
+                            $crate::tagged::check_len(
+                                len,
+                                $crate::tagged_enum!(@count $($field)*),
+                                stringify!($variant),
+                            )?;
+
+                            $name::$variant($(d.decode_with::<C, $ty>(ctx)?),*)
+                        }
+                    )*
+                    x => {
+                        return Err($crate::minicbor::decode::Error::message(format!(
+                            "unknown variant id {x} for {}",
+                            stringify!($name)
+                        )))
+                    }
+                };
+
+                $crate::tagged::end_array(d, len, stringify!($name))?;
+
+                Ok(value)
+            }
+        }
+
+        impl<C> $crate::minicbor::encode::Encode<C> for $name {
+            fn encode<W: $crate::minicbor::encode::Write>(
+                &self,
+                e: &mut $crate::minicbor::Encoder<W>,
+                ctx: &mut C,
+            ) -> Result<(), $crate::minicbor::encode::Error<W::Error>> {
+                match self {
+                    $(
+                        $name::$variant($($field),*) => {
+                            e.array($crate::tagged_enum!(@count $($field)*) + 1)?;
+                            e.u64($id)?;
+                            $(e.encode_with($field, ctx)?;)*
+                        }
+                    )*
+                }
+
+                Ok(())
+            }
+        }
+    };
+
+    (@count) => { 0u64 };
+    (@count $head:ident $($tail:ident)*) => { 1u64 + $crate::tagged_enum!(@count $($tail)*) };
+}
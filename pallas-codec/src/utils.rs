@@ -7,21 +7,73 @@ use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::{collections::HashMap, fmt, hash::Hash as StdHash, ops::Deref};
 
+use crate::context::{check_collection_len, CodecContext, CodecWarning};
+
 static TAG_SET: u64 = 258;
 
+/// Decodes an array into a vec, failing if it holds more than `limit` items
+fn decode_array<'b, C, T>(
+    d: &mut minicbor::Decoder<'b>,
+    ctx: &mut C,
+    limit: Option<u64>,
+) -> Result<Vec<T>, Error>
+where
+    T: Decode<'b, C>,
+{
+    check_collection_len(limit, d.probe().array()?)?;
+
+    let items: Vec<T> = d.decode_with(ctx)?;
+
+    check_collection_len(limit, Some(items.len() as u64))?;
+
+    Ok(items)
+}
+
+/// Decodes a map into a vec of pairs, failing if it holds more than `limit`
+/// entries
+fn decode_map<'b, C, K, V>(
+    d: &mut minicbor::Decoder<'b>,
+    ctx: &mut C,
+    limit: Option<u64>,
+) -> Result<Vec<(K, V)>, Error>
+where
+    K: Decode<'b, C>,
+    V: Decode<'b, C>,
+{
+    if d.datatype()? == Type::Map {
+        check_collection_len(limit, d.probe().map()?)?;
+    }
+
+    let items: Result<Vec<_>, _> = d.map_iter_with::<C, K, V>(ctx)?.collect();
+    let items = items?;
+
+    check_collection_len(limit, Some(items.len() as u64))?;
+
+    Ok(items)
+}
+
+/// Skips the optional set tag (this will be required in era following Conway)
+fn decode_set_tag(d: &mut minicbor::Decoder<'_>) -> Result<(), Error> {
+    if d.datatype()? == Type::Tag {
+        let found_tag = d.tag()?;
+
+        if found_tag != Tag::new(TAG_SET) {
+            return Err(Error::message(format!("Unrecognised tag: {found_tag:?}")));
+        }
+    }
+
+    Ok(())
+}
+
 /// Utility for skipping parts of the CBOR payload, use only for debugging
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub struct SkipCbor<const N: usize> {}
 
-impl<'b, C, const N: usize> minicbor::Decode<'b, C> for SkipCbor<N> {
-    fn decode(
-        d: &mut minicbor::Decoder<'b>,
-        _ctx: &mut C,
-    ) -> Result<Self, minicbor::decode::Error> {
-        {
-            let probe = d.probe();
-            println!("skipped cbor value {N}: {:?}", probe.datatype()?);
-        }
+impl<'b, C: CodecContext, const N: usize> minicbor::Decode<'b, C> for SkipCbor<N> {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        ctx.report(CodecWarning::SkippedValue {
+            position: d.position(),
+        })?;
 
         d.skip()?;
         Ok(SkipCbor {})
@@ -34,7 +86,9 @@ impl<C, const N: usize> minicbor::Encode<C> for SkipCbor<N> {
         _e: &mut minicbor::Encoder<W>,
         _ctx: &mut C,
     ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        todo!()
+        Err(minicbor::encode::Error::message(
+            "skipped cbor values can't be encoded",
+        ))
     }
 }
 
@@ -135,16 +189,38 @@ where
     }
 }
 
-impl<'b, C, K, V> minicbor::decode::Decode<'b, C> for KeyValuePairs<K, V>
+impl<K, V> KeyValuePairs<K, V>
 where
-    K: Decode<'b, C> + Clone,
-    V: Decode<'b, C> + Clone,
+    K: Clone,
+    V: Clone,
 {
-    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+    /// Decodes the pairs, failing if they exceed the collection limit of the
+    /// context
+    pub fn decode_limited<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        C: CodecContext,
+        K: Decode<'b, C>,
+        V: Decode<'b, C>,
+    {
+        let limit = ctx.collection_limit();
+        Self::decode_bounded(d, ctx, limit)
+    }
+
+    fn decode_bounded<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+        limit: Option<u64>,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        K: Decode<'b, C>,
+        V: Decode<'b, C>,
+    {
         let datatype = d.datatype()?;
 
-        let items: Result<Vec<_>, _> = d.map_iter_with::<C, K, V>(ctx)?.collect();
-        let items = items?;
+        let items = decode_map(d, ctx, limit)?;
 
         match datatype {
             minicbor::data::Type::Map => Ok(KeyValuePairs::Def(items)),
@@ -156,6 +232,16 @@ where
     }
 }
 
+impl<'b, C, K, V> minicbor::decode::Decode<'b, C> for KeyValuePairs<K, V>
+where
+    K: Decode<'b, C> + Clone,
+    V: Decode<'b, C> + Clone,
+{
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        Self::decode_bounded(d, ctx, None)
+    }
+}
+
 impl<C, K, V> minicbor::encode::Encode<C> for KeyValuePairs<K, V>
 where
     K: Encode<C> + Clone,
@@ -313,22 +399,38 @@ where
     }
 }
 
-impl<'b, C, K, V> minicbor::decode::Decode<'b, C> for NonEmptyKeyValuePairs<K, V>
+impl<K, V> NonEmptyKeyValuePairs<K, V>
 where
-    K: Decode<'b, C> + Clone,
-    V: Decode<'b, C> + Clone,
+    K: Clone,
+    V: Clone,
 {
-    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        let datatype = d.datatype()?;
+    /// Decodes the pairs, failing if they exceed the collection limit of the
+    /// context
+    pub fn decode_limited<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        C: CodecContext,
+        K: Decode<'b, C>,
+        V: Decode<'b, C>,
+    {
+        let limit = ctx.collection_limit();
+        Self::decode_bounded(d, ctx, limit)
+    }
 
-        let items: Result<Vec<_>, _> = d.map_iter_with::<C, K, V>(ctx)?.collect();
-        let items = items?;
+    fn decode_bounded<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+        limit: Option<u64>,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        K: Decode<'b, C>,
+        V: Decode<'b, C>,
+    {
+        let datatype = d.datatype()?;
 
-        // if items.is_empty() {
-        //     return Err(Error::message(
-        //         "decoding empty map as NonEmptyKeyValuePairs",
-        //     ));
-        // }
+        let items = decode_map(d, ctx, limit)?;
 
         match datatype {
             minicbor::data::Type::Map => Ok(NonEmptyKeyValuePairs::Def(items)),
@@ -340,6 +442,16 @@ where
     }
 }
 
+impl<'b, C, K, V> minicbor::decode::Decode<'b, C> for NonEmptyKeyValuePairs<K, V>
+where
+    K: Decode<'b, C> + Clone,
+    V: Decode<'b, C> + Clone,
+{
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        Self::decode_bounded(d, ctx, None)
+    }
+}
+
 impl<C, K, V> minicbor::encode::Encode<C> for NonEmptyKeyValuePairs<K, V>
 where
     K: Encode<C> + Clone,
@@ -386,6 +498,37 @@ impl<A> MaybeIndefArray<A> {
     pub fn to_vec(self) -> Vec<A> {
         self.into()
     }
+
+    /// Decodes the array, failing if it exceeds the collection limit of the
+    /// context
+    pub fn decode_limited<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        C: CodecContext,
+        A: Decode<'b, C>,
+    {
+        let limit = ctx.collection_limit();
+        Self::decode_bounded(d, ctx, limit)
+    }
+
+    fn decode_bounded<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+        limit: Option<u64>,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        A: Decode<'b, C>,
+    {
+        match d.datatype()? {
+            minicbor::data::Type::Array => Ok(Self::Def(decode_array(d, ctx, limit)?)),
+            minicbor::data::Type::ArrayIndef => Ok(Self::Indef(decode_array(d, ctx, limit)?)),
+            _ => Err(minicbor::decode::Error::message(
+                "unknown data type of maybe indef array",
+            )),
+        }
+    }
 }
 
 impl<A> Deref for MaybeIndefArray<A> {
@@ -413,15 +556,7 @@ where
     A: minicbor::decode::Decode<'b, C>,
 {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        let datatype = d.datatype()?;
-
-        match datatype {
-            minicbor::data::Type::Array => Ok(Self::Def(d.decode_with(ctx)?)),
-            minicbor::data::Type::ArrayIndef => Ok(Self::Indef(d.decode_with(ctx)?)),
-            _ => Err(minicbor::decode::Error::message(
-                "unknown data type of maybe indef array",
-            )),
-        }
+        Self::decode_bounded(d, ctx, None)
     }
 }
 
@@ -577,10 +712,16 @@ impl<I, const T: u64> TagWrap<I, T> {
 
 impl<'b, C, I, const T: u64> minicbor::Decode<'b, C> for TagWrap<I, T>
 where
+    C: CodecContext,
     I: minicbor::Decode<'b, C>,
 {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.tag()?;
+        let position = d.position();
+        let tag = d.tag()?.as_u64();
+
+        if tag != T {
+            ctx.report(CodecWarning::UnknownTag { position, tag })?;
+        }
 
         Ok(TagWrap(d.decode_with(ctx)?))
     }
@@ -708,6 +849,35 @@ impl<T> Set<T> {
     pub fn to_vec(self) -> Vec<T> {
         self.0
     }
+
+    /// Decodes the set, failing if it exceeds the collection limit of the
+    /// context
+    pub fn decode_limited<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        C: CodecContext,
+        T: Decode<'b, C>,
+    {
+        let limit = ctx.collection_limit();
+        Self::decode_bounded(d, ctx, limit)
+    }
+
+    fn decode_bounded<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+        limit: Option<u64>,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        T: Decode<'b, C>,
+    {
+        decode_set_tag(d)?;
+
+        let inner: Vec<T> = decode_array(d, ctx, limit)?;
+
+        Ok(Self(inner))
+    }
 }
 
 impl<T> Deref for Set<T> {
@@ -745,16 +915,7 @@ where
     T: Decode<'b, C>,
 {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        // decode optional set tag (this will be required in era following Conway)
-        if d.datatype()? == Type::Tag {
-            let found_tag = d.tag()?;
-
-            if found_tag != Tag::new(TAG_SET) {
-                return Err(Error::message(format!("Unrecognised tag: {found_tag:?}")));
-            }
-        }
-
-        Ok(Self(d.decode_with(ctx)?))
+        Self::decode_bounded(d, ctx, None)
     }
 }
 
@@ -793,6 +954,39 @@ impl<T> NonEmptySet<T> {
             Some(Self(x))
         }
     }
+
+    /// Decodes the set, failing if it exceeds the collection limit of the
+    /// context
+    pub fn decode_limited<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        C: CodecContext,
+        T: Decode<'b, C>,
+    {
+        let limit = ctx.collection_limit();
+        Self::decode_bounded(d, ctx, limit)
+    }
+
+    fn decode_bounded<'b, C>(
+        d: &mut minicbor::Decoder<'b>,
+        ctx: &mut C,
+        limit: Option<u64>,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        T: Decode<'b, C>,
+    {
+        decode_set_tag(d)?;
+
+        let inner: Vec<T> = decode_array(d, ctx, limit)?;
+
+        // if inner.is_empty() {
+        //     return Err(Error::message("decoding empty set as NonEmptySet"));
+        // }
+
+        Ok(Self(inner))
+    }
 }
 
 impl<T> Deref for NonEmptySet<T> {
@@ -836,22 +1030,7 @@ where
     T: Decode<'b, C>,
 {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        // decode optional set tag (this will be required in era following Conway)
-        if d.datatype()? == Type::Tag {
-            let found_tag = d.tag()?;
-
-            if found_tag != Tag::new(TAG_SET) {
-                return Err(Error::message(format!("Unrecognised tag: {found_tag:?}")));
-            }
-        }
-
-        let inner: Vec<T> = d.decode_with(ctx)?;
-
-        // if inner.is_empty() {
-        //     return Err(Error::message("decoding empty set as NonEmptySet"));
-        // }
-
-        Ok(Self(inner))
+        Self::decode_bounded(d, ctx, None)
     }
 }
 
@@ -887,9 +1066,11 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for AnyUInt {
         _ctx: &mut C,
     ) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
-            minicbor::data::Type::U8 => match d.u8()? {
-                x @ 0..=0x17 => Ok(AnyUInt::MajorByte(x)),
-                x @ 0x18..=0xff => Ok(AnyUInt::U8(x)),
+            // the initial byte tells a one-byte uint apart from a non-minimal
+            // U8 holding a small value
+            minicbor::data::Type::U8 => match d.input()[d.position()] {
+                0x18 => Ok(AnyUInt::U8(d.u8()?)),
+                _ => Ok(AnyUInt::MajorByte(d.u8()?)),
             },
             minicbor::data::Type::U16 => Ok(AnyUInt::U16(d.u16()?)),
             minicbor::data::Type::U32 => Ok(AnyUInt::U32(d.u32()?)),
@@ -979,6 +1160,19 @@ impl From<&AnyUInt> for u64 {
     }
 }
 
+/// Uses the shortest encoding for the value
+impl From<u64> for AnyUInt {
+    fn from(x: u64) -> Self {
+        match x {
+            0..=0x17 => AnyUInt::MajorByte(x as u8),
+            0x18..=0xff => AnyUInt::U8(x as u8),
+            0x100..=0xffff => AnyUInt::U16(x as u16),
+            0x1_0000..=0xffff_ffff => AnyUInt::U32(x as u32),
+            _ => AnyUInt::U64(x),
+        }
+    }
+}
+
 /// Introduced in Conway
 /// positive_coin = 1 .. 18446744073709551615
 #[derive(Debug, PartialEq, Copy, Clone, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
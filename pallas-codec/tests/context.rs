@@ -0,0 +1,140 @@
+use pallas_codec::{
+    context::{decode_with_context, CodecWarning, DecodeContext},
+    minicbor,
+    utils::{KeyValuePairs, MaybeIndefArray, NonEmptySet, Set, SkipCbor, TagWrap},
+};
+
+#[test]
+fn warnings_are_collected_per_context() {
+    let mut ctx = DecodeContext::default();
+
+    // tag 24 where 258 is expected, followed by a skipped value
+    let (wrapped, _): (TagWrap<u8, 258>, SkipCbor<0>) =
+        decode_with_context(&[0x82, 0xd8, 0x18, 0x01, 0x02], &mut ctx).unwrap();
+
+    assert_eq!(wrapped.0, 1);
+    assert_eq!(
+        ctx.warnings.iter().collect::<Vec<_>>(),
+        vec![
+            &CodecWarning::UnknownTag {
+                position: 1,
+                tag: 24
+            },
+            &CodecWarning::SkippedValue { position: 4 },
+        ]
+    );
+
+    // a clean decode doesn't add anything, and other contexts aren't affected
+    let mut other = DecodeContext::default();
+    let _: TagWrap<u8, 258> = decode_with_context(&[0xd9, 0x01, 0x02, 0x01], &mut other).unwrap();
+    assert!(other.warnings.is_empty());
+
+    assert_eq!(ctx.warnings.take().len(), 2);
+    assert!(ctx.warnings.is_empty());
+}
+
+#[test]
+fn warnings_outside_of_a_context_are_dropped() {
+    let _: SkipCbor<0> = minicbor::decode(&[0x01]).unwrap();
+
+    let mut ctx = DecodeContext::default();
+    let _: u8 = decode_with_context(&[0x01], &mut ctx).unwrap();
+
+    assert!(ctx.warnings.is_empty());
+}
+
+#[test]
+fn warnings_follow_the_context_across_threads() {
+    let mut ctx = DecodeContext::default();
+
+    let ctx = std::thread::spawn(move || {
+        let _: SkipCbor<0> = decode_with_context(&[0x01], &mut ctx).unwrap();
+        ctx
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(
+        ctx.warnings.iter().collect::<Vec<_>>(),
+        vec![&CodecWarning::SkippedValue { position: 0 }]
+    );
+}
+
+#[test]
+fn strict_contexts_reject_anomalies() {
+    // tag 24 where 258 is expected
+    let bytes = [0xd8, 0x18, 0x01];
+
+    let mut lenient = DecodeContext::default();
+    let _: TagWrap<u8, 258> = decode_with_context(&bytes, &mut lenient).unwrap();
+    assert_eq!(lenient.warnings.len(), 1);
+
+    let mut strict = DecodeContext::default().strict();
+    let err = decode_with_context::<TagWrap<u8, 258>>(&bytes, &mut strict).unwrap_err();
+    assert!(err.to_string().contains("unknown tag 24 at byte 0"));
+    assert!(strict.warnings.is_empty());
+}
+
+#[test]
+fn collections_are_limited() {
+    fn check<'b, T>(
+        bytes: &'b [u8],
+        decode: fn(
+            &mut minicbor::Decoder<'b>,
+            &mut DecodeContext,
+        ) -> Result<T, minicbor::decode::Error>,
+    ) where
+        T: minicbor::Decode<'b, DecodeContext>,
+    {
+        let mut ctx = DecodeContext::default().max_collection_len(3);
+        assert!(decode(&mut minicbor::Decoder::new(bytes), &mut ctx).is_ok());
+
+        let mut ctx = DecodeContext::default().max_collection_len(2);
+        let err = decode(&mut minicbor::Decoder::new(bytes), &mut ctx)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("exceeds the limit of 2"));
+
+        // the plain decode impls ignore the limit
+        assert!(decode_with_context::<T>(bytes, &mut ctx).is_ok());
+    }
+
+    check(
+        &[0x83, 0x01, 0x02, 0x03],
+        MaybeIndefArray::<u8>::decode_limited,
+    );
+    check(
+        &[0x9f, 0x01, 0x02, 0x03, 0xff],
+        MaybeIndefArray::<u8>::decode_limited,
+    );
+    check(
+        &[0xd9, 0x01, 0x02, 0x83, 0x01, 0x02, 0x03],
+        Set::<u8>::decode_limited,
+    );
+    check(
+        &[0xd9, 0x01, 0x02, 0x83, 0x01, 0x02, 0x03],
+        NonEmptySet::<u8>::decode_limited,
+    );
+    check(
+        &[0xa3, 0x01, 0x01, 0x02, 0x02, 0x03, 0x03],
+        KeyValuePairs::<u8, u8>::decode_limited,
+    );
+    check(
+        &[0xbf, 0x01, 0x01, 0x02, 0x02, 0x03, 0x03, 0xff],
+        KeyValuePairs::<u8, u8>::decode_limited,
+    );
+}
+
+/// A context defined outside of pallas-codec, without a `CodecContext` impl
+struct ForeignContext;
+
+#[test]
+fn collections_decode_with_any_context() {
+    let _: MaybeIndefArray<u8> =
+        minicbor::decode_with(&[0x83, 0x01, 0x02, 0x03], &mut ForeignContext).unwrap();
+    let _: Set<u8> =
+        minicbor::decode_with(&[0xd9, 0x01, 0x02, 0x81, 0x01], &mut ForeignContext).unwrap();
+    let _: NonEmptySet<u8> = minicbor::decode_with(&[0x81, 0x01], &mut ForeignContext).unwrap();
+    let _: KeyValuePairs<u8, u8> =
+        minicbor::decode_with(&[0xa1, 0x01, 0x01], &mut ForeignContext).unwrap();
+}
@@ -0,0 +1,24 @@
+use pallas_codec::fs::atomic_write;
+
+#[test]
+fn atomic_write_replaces_the_file() {
+    let dir = std::env::temp_dir().join(format!("pallas-codec-fs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("state");
+
+    atomic_write(&path, b"first").unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"first");
+
+    atomic_write(&path, b"second").unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"second");
+
+    // the temp file is renamed away
+    let names: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|x| x.unwrap().file_name())
+        .collect();
+    assert_eq!(names, vec!["state"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,18 @@
+use pallas_codec::{cbor_hex, hex_bytes, utils::KeyValuePairs};
+
+#[test]
+fn literals() {
+    assert_eq!(hex_bytes!("00ff"), vec![0, 255]);
+
+    let map = cbor_hex!(KeyValuePairs<u8, bool>, "a101f5");
+    assert_eq!(map.to_vec(), vec![(1, true)]);
+
+    let list: Vec<u64> = cbor_hex!("820102");
+    assert_eq!(list, vec![1, 2]);
+}
+
+#[test]
+#[should_panic(expected = "can't decode")]
+fn bad_cbor_literal() {
+    let _: Vec<u64> = cbor_hex!("a1");
+}
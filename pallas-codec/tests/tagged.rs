@@ -0,0 +1,39 @@
+use pallas_codec::{cbor_hex, hex_bytes, minicbor, tagged_enum};
+
+tagged_enum! {
+    #[derive(Debug, PartialEq)]
+    enum Shape {
+        0 => Point(),
+        1 => Circle(radius: u32),
+        // ids don't need to follow the declaration order
+        3 => Rect(width: u32, height: u32),
+        2 => Group(shapes: Vec<Shape>),
+    }
+}
+
+#[test]
+fn tagged_enum_roundtrip() {
+    let shape = Shape::Group(vec![Shape::Point(), Shape::Circle(1), Shape::Rect(2, 3)]);
+
+    let bytes = minicbor::to_vec(&shape).unwrap();
+    assert_eq!(bytes, hex_bytes!("820283810082010183030203"));
+
+    let decoded: Shape = minicbor::decode(&bytes).unwrap();
+    assert_eq!(decoded, shape);
+
+    // indefinite arrays are accepted
+    assert_eq!(cbor_hex!(Shape, "9f030506ff"), Shape::Rect(5, 6));
+}
+
+#[test]
+fn tagged_enum_rejects_bad_arrays() {
+    // a rect with a single field
+    let err = minicbor::decode::<Shape>(&hex_bytes!("820305")).unwrap_err();
+    assert!(err.to_string().contains("expected 3 elements for Rect"));
+
+    let err = minicbor::decode::<Shape>(&hex_bytes!("9f01050506ff")).unwrap_err();
+    assert!(err.to_string().contains("too many elements for Shape"));
+
+    let err = minicbor::decode::<Shape>(&hex_bytes!("8109")).unwrap_err();
+    assert!(err.to_string().contains("unknown variant id 9 for Shape"));
+}
@@ -0,0 +1,95 @@
+use pallas_codec::{
+    minicbor::{self, Decode, Encode},
+    utils::{CborWrap, KeepRaw, KeyValuePairs, MaybeIndefArray, Nullable},
+    Fragment,
+};
+
+/// A structure defined outside of Pallas, composed from the shared helpers
+#[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
+struct ThirdParty {
+    #[n(0)]
+    labels: KeyValuePairs<u64, String>,
+    #[n(1)]
+    items: MaybeIndefArray<u32>,
+    #[n(2)]
+    optional: Nullable<u8>,
+    #[n(3)]
+    wrapped: CborWrap<u16>,
+}
+
+fn roundtrip<T: Fragment + PartialEq + std::fmt::Debug>(value: T) {
+    let bytes = minicbor::to_vec(&value).unwrap();
+    let decoded: T = minicbor::decode(&bytes).unwrap();
+
+    assert_eq!(decoded, value);
+    assert_eq!(minicbor::to_vec(&decoded).unwrap(), bytes);
+}
+
+#[test]
+fn third_party_structures_roundtrip() {
+    roundtrip(ThirdParty {
+        labels: KeyValuePairs::Indef(vec![(1, "a".into()), (0, "b".into())]),
+        items: MaybeIndefArray::Indef(vec![1, 2, 3]),
+        optional: Nullable::Null,
+        wrapped: CborWrap(42),
+    });
+
+    roundtrip(ThirdParty {
+        labels: KeyValuePairs::Def(vec![]),
+        items: MaybeIndefArray::Def(vec![]),
+        optional: Nullable::Some(7),
+        wrapped: CborWrap(0),
+    });
+}
+
+#[test]
+fn keep_raw_preserves_original_bytes() {
+    let value = ThirdParty {
+        labels: KeyValuePairs::Indef(vec![(1, "a".into())]),
+        items: MaybeIndefArray::Def(vec![1]),
+        optional: Nullable::Undefined,
+        wrapped: CborWrap(1),
+    };
+
+    let bytes = minicbor::to_vec(&value).unwrap();
+    let raw: KeepRaw<ThirdParty> = minicbor::decode(&bytes).unwrap();
+
+    assert_eq!(raw.raw_cbor(), bytes.as_slice());
+    assert_eq!(*raw, value);
+}
+
+#[test]
+fn skipped_values_fail_to_encode() {
+    use pallas_codec::utils::SkipCbor;
+
+    let skipped: SkipCbor<0> = minicbor::decode(&[0x01]).unwrap();
+    let error = pallas_codec::Error::from(minicbor::to_vec(skipped).unwrap_err());
+
+    assert!(matches!(error, pallas_codec::Error::Encode(_)));
+}
+
+#[test]
+fn any_uint_from_u64_uses_shortest_width() {
+    use pallas_codec::utils::AnyUInt;
+
+    for (value, len) in [(23u64, 1), (24, 2), (256, 3), (65536, 5), (1 << 32, 9)] {
+        let uint = AnyUInt::from(value);
+
+        assert_eq!(u64::from(uint), value);
+        assert_eq!(minicbor::to_vec(uint).unwrap().len(), len);
+    }
+}
+
+#[test]
+fn any_uint_keeps_non_minimal_widths() {
+    use pallas_codec::utils::AnyUInt;
+
+    for hex in ["05", "1805", "190005", "1a00000005", "1b0000000000000005"] {
+        let bytes = hex::decode(hex).unwrap();
+        let uint: AnyUInt = minicbor::decode(&bytes).unwrap();
+
+        assert_eq!(u64::from(uint), 5);
+        assert_eq!(minicbor::to_vec(uint).unwrap(), bytes);
+    }
+}
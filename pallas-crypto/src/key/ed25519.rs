@@ -32,6 +32,14 @@ pub struct SecretKeyExtended([u8; Self::SIZE]);
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PublicKey([u8; Self::SIZE]);
 
+/// Ed25519 Extended Public Key. The [`PublicKey`] followed by the 32 bytes
+/// chain code, as found in HD wallets (`xpub`) and in the cardano-cli
+/// extended verification keys.
+///
+/// Only the [`PublicKey`] part is used to verify a [`Signature`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKeyExtended([u8; Self::SIZE]);
+
 /// Ed25519 Signature. Is created by a [`SecretKey`] and is verified
 /// with a [`PublicKey`].
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -45,6 +53,14 @@ pub enum TryFromPublicKeyError {
     InvalidSize,
 }
 
+/// Error type used when retrieving a [`PublicKeyExtended`] via the [`TryFrom`]
+/// trait.
+#[derive(Debug, Error)]
+pub enum TryFromPublicKeyExtendedError {
+    #[error("Invalid size, expecting {}", PublicKeyExtended::SIZE)]
+    InvalidSize,
+}
+
 /// Error type used when retrieving a [`Signature`] via the [`TryFrom`]
 /// trait.
 #[derive(Debug, Error)]
@@ -60,8 +76,13 @@ pub enum TryFromSignatureError {
 pub enum TryFromSecretKeyExtendedError {
     #[error("Invalid Ed25519 Extended Secret Key format")]
     InvalidBitTweaks,
+    #[error("Embedded public key does not match the Ed25519 Extended Secret Key")]
+    PublicKeyMismatch,
 }
 
+/// size of the chain code appended to the extended keys
+const CHAIN_CODE_LENGTH: usize = 32;
+
 macro_rules! impl_size_zero {
     ($Type:ty, $Size:expr) => {
         impl $Type {
@@ -81,6 +102,7 @@ macro_rules! impl_size_zero {
 impl_size_zero!(SecretKey, PRIVATE_KEY_LENGTH);
 impl_size_zero!(SecretKeyExtended, EXTENDED_KEY_LENGTH);
 impl_size_zero!(PublicKey, PUBLIC_KEY_LENGTH);
+impl_size_zero!(PublicKeyExtended, PUBLIC_KEY_LENGTH + CHAIN_CODE_LENGTH);
 impl_size_zero!(Signature, SIGNATURE_LENGTH);
 
 impl SecretKey {
@@ -256,6 +278,48 @@ impl SecretKeyExtended {
         Self(bytes)
     }
 
+    /// Retrieve a [`SecretKeyExtended`] and its [`PublicKeyExtended`] from
+    /// the 128 bytes signing key form (`secret key || public key || chain
+    /// code`) used by the cardano-cli extended signing keys.
+    ///
+    /// # error
+    ///
+    /// On top of the checks done by [`SecretKeyExtended::from_bytes`], this
+    /// function will check that the embedded public key is the one
+    /// associated to the secret key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use pallas_crypto::key::ed25519::{SecretKeyExtended, TryFromSecretKeyExtendedError};
+    /// #
+    /// # fn test() -> Result<(), TryFromSecretKeyExtendedError> {
+    /// let bytes = // ...
+    /// # [0; 128] ;
+    /// let (key, public_key) = SecretKeyExtended::from_bytes_with_public_key(bytes)?;
+    /// # let _ = (key, public_key); Ok(()) }
+    /// # assert!(matches!(test(), Err(TryFromSecretKeyExtendedError::InvalidBitTweaks)));
+    /// ```
+    ///
+    pub fn from_bytes_with_public_key(
+        bytes: [u8; Self::SIZE + PublicKeyExtended::SIZE],
+    ) -> Result<(Self, PublicKeyExtended), TryFromSecretKeyExtendedError> {
+        let mut secret = [0; Self::SIZE];
+        secret.copy_from_slice(&bytes[..Self::SIZE]);
+        let key = Self::from_bytes(secret);
+        secret.scrub();
+        let key = key?;
+
+        let mut public = PublicKeyExtended::zero();
+        public.0.copy_from_slice(&bytes[Self::SIZE..]);
+
+        if key.public_key() != public.public_key() {
+            return Err(TryFromSecretKeyExtendedError::PublicKeyMismatch);
+        }
+
+        Ok((key, public))
+    }
+
     /// get the [`PublicKey`] associated to this key
     ///
     /// Unlike the [`SecretKeyExtended`], the [`PublicKey`] can be safely
@@ -326,6 +390,41 @@ impl PublicKey {
     }
 }
 
+impl PublicKeyExtended {
+    /// create a [`PublicKeyExtended`] from the given [`PublicKey`] and its
+    /// chain code
+    pub fn new(public_key: PublicKey, chain_code: [u8; CHAIN_CODE_LENGTH]) -> Self {
+        let mut s = Self::zero();
+        s.0[..PublicKey::SIZE].copy_from_slice(public_key.as_ref());
+        s.0[PublicKey::SIZE..].copy_from_slice(&chain_code);
+        s
+    }
+
+    /// get the [`PublicKey`] part of this extended key
+    pub fn public_key(&self) -> PublicKey {
+        let mut pk = PublicKey::zero();
+        pk.0.copy_from_slice(&self.0[..PublicKey::SIZE]);
+        pk
+    }
+
+    /// get the chain code part of this extended key
+    pub fn chain_code(&self) -> [u8; CHAIN_CODE_LENGTH] {
+        let mut cc = [0; CHAIN_CODE_LENGTH];
+        cc.copy_from_slice(&self.0[PublicKey::SIZE..]);
+        cc
+    }
+
+    /// verify the cryptographic [`Signature`] against the `message` and the
+    /// [`PublicKey`] part of `self`.
+    #[inline]
+    pub fn verify<T>(&self, message: T, signature: &Signature) -> bool
+    where
+        T: AsRef<[u8]>,
+    {
+        self.public_key().verify(message, signature)
+    }
+}
+
 /* Drop ******************************************************************** */
 
 impl Drop for SecretKey {
@@ -354,6 +453,12 @@ impl fmt::Display for PublicKey {
     }
 }
 
+impl fmt::Display for PublicKeyExtended {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.as_ref()))
+    }
+}
+
 impl fmt::Debug for Signature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Signature<Ed25519>")
@@ -370,6 +475,14 @@ impl fmt::Debug for PublicKey {
     }
 }
 
+impl fmt::Debug for PublicKeyExtended {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PublicKeyExtended<Ed25519>")
+            .field(&hex::encode(self.as_ref()))
+            .finish()
+    }
+}
+
 macro_rules! impl_secret_fmt {
     ($Type:ty) => {
         /// conveniently provide a proper implementation to debug for the
@@ -412,6 +525,12 @@ impl AsRef<[u8]> for PublicKey {
     }
 }
 
+impl AsRef<[u8]> for PublicKeyExtended {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
 impl AsRef<[u8]> for Signature {
     fn as_ref(&self) -> &[u8] {
         self.0.as_ref()
@@ -444,6 +563,24 @@ impl From<PublicKey> for [u8; PublicKey::SIZE] {
     }
 }
 
+impl From<[u8; Self::SIZE]> for PublicKeyExtended {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<PublicKeyExtended> for [u8; PublicKeyExtended::SIZE] {
+    fn from(pk: PublicKeyExtended) -> Self {
+        pk.0
+    }
+}
+
+impl From<PublicKeyExtended> for PublicKey {
+    fn from(pk: PublicKeyExtended) -> Self {
+        pk.public_key()
+    }
+}
+
 impl From<[u8; Self::SIZE]> for Signature {
     fn from(bytes: [u8; Self::SIZE]) -> Self {
         Self(bytes)
@@ -476,6 +613,19 @@ impl<'a> TryFrom<&'a [u8]> for PublicKey {
     }
 }
 
+impl<'a> TryFrom<&'a [u8]> for PublicKeyExtended {
+    type Error = TryFromPublicKeyExtendedError;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() != Self::SIZE {
+            Err(Self::Error::InvalidSize)
+        } else {
+            let mut s = Self::zero();
+            s.0.copy_from_slice(value);
+            Ok(s)
+        }
+    }
+}
+
 impl<'a> TryFrom<&'a [u8]> for Signature {
     type Error = TryFromSignatureError;
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
@@ -498,6 +648,15 @@ impl FromStr for PublicKey {
     }
 }
 
+impl FromStr for PublicKeyExtended {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = Self::zero();
+        hex::decode_to_slice(s, &mut r.0)?;
+        Ok(r)
+    }
+}
+
 impl FromStr for Signature {
     type Err = hex::FromHexError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -555,6 +714,16 @@ mod tests {
         }
     }
 
+    impl Arbitrary for PublicKeyExtended {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let mut s = Self::zero();
+            s.0.iter_mut().for_each(|byte| {
+                *byte = u8::arbitrary(g);
+            });
+            s
+        }
+    }
+
     impl Arbitrary for Signature {
         fn arbitrary(g: &mut Gen) -> Self {
             let mut s = Self::zero();
@@ -676,4 +845,63 @@ mod tests {
             Err(error) => TestResult::error(error.to_string()),
         }
     }
+
+    #[quickcheck]
+    fn public_key_extended_from_str(public_key: PublicKeyExtended) -> TestResult {
+        let s = public_key.to_string();
+
+        match s.parse::<PublicKeyExtended>() {
+            Ok(decoded) => {
+                if decoded == public_key {
+                    TestResult::passed()
+                } else {
+                    TestResult::error("the decoded key is not equal")
+                }
+            }
+            Err(error) => TestResult::error(error.to_string()),
+        }
+    }
+
+    #[quickcheck]
+    fn public_key_extended_verify_works(
+        signing_key: SecretKeyExtended,
+        chain_code: Vec<u8>,
+        message: Vec<u8>,
+    ) -> bool {
+        let mut cc = [0; CHAIN_CODE_LENGTH];
+        cc.iter_mut().zip(chain_code).for_each(|(b, x)| *b = x);
+
+        let public_key = PublicKeyExtended::new(signing_key.public_key(), cc);
+        let signature = signing_key.sign(&message);
+
+        public_key.chain_code() == cc && public_key.verify(message, &signature)
+    }
+
+    #[quickcheck]
+    fn secret_key_extended_from_bytes_with_public_key(
+        signing_key: SecretKeyExtended,
+        other: SecretKeyExtended,
+    ) -> TestResult {
+        let public_key = PublicKeyExtended::new(signing_key.public_key(), [7; CHAIN_CODE_LENGTH]);
+
+        let mut bytes = [0; SecretKeyExtended::SIZE + PublicKeyExtended::SIZE];
+        bytes[..SecretKeyExtended::SIZE].copy_from_slice(&signing_key.0);
+        bytes[SecretKeyExtended::SIZE..].copy_from_slice(public_key.as_ref());
+
+        match SecretKeyExtended::from_bytes_with_public_key(bytes) {
+            Ok((key, pk)) if key.0 == signing_key.0 && pk == public_key => {}
+            _ => return TestResult::error("was expecting the key to be parsed"),
+        }
+
+        if other.public_key() == signing_key.public_key() {
+            return TestResult::discard();
+        }
+
+        bytes[..SecretKeyExtended::SIZE].copy_from_slice(&other.0);
+
+        match SecretKeyExtended::from_bytes_with_public_key(bytes) {
+            Err(TryFromSecretKeyExtendedError::PublicKeyMismatch) => TestResult::passed(),
+            _ => TestResult::error("was expecting a public key mismatch"),
+        }
+    }
 }
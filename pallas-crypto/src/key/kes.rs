@@ -0,0 +1,198 @@
+//! Key Evolving Signatures, as used by Cardano to sign block headers
+//!
+//! This implements the sum composition of [MMM] instantiated the same way as
+//! the Haskell node: `Sum6KES`, a binary tree of depth 6 over Ed25519 keys,
+//! with Blake2b-256 to derive seeds and to hash pairs of verification keys.
+//! It is compatible with the keys and signatures of `cardano-base`.
+//!
+//! [`Sum6Kes`] keeps the seed the whole tree derives from, so it can sign at
+//! any period. That drops the forward security of the scheme, which makes it
+//! suited for testnets and tooling rather than for production pools.
+//!
+//! [MMM]: https://eprint.iacr.org/2001/034
+
+use thiserror::Error;
+
+use super::ed25519::{PublicKey, SecretKey, Signature};
+use crate::{hash::Hasher, memsec::Scrubbed as _};
+
+/// Depth of the tree of keys
+pub const SUM6_DEPTH: u32 = 6;
+
+/// Number of periods a key can sign for
+pub const SUM6_PERIODS: u32 = 1 << SUM6_DEPTH;
+
+/// Ed25519 signature plus a pair of verification keys for each level
+pub const SUM6_SIGNATURE_SIZE: usize = Signature::SIZE + SUM6_DEPTH as usize * 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KesError {
+    #[error("KES period {0} is out of range")]
+    PeriodOutOfRange(u32),
+
+    #[error("invalid KES signature size {0}")]
+    InvalidSignatureSize(usize),
+}
+
+fn expand_seed(seed: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    (
+        *Hasher::<256>::hash_tagged(seed, 1),
+        *Hasher::<256>::hash_tagged(seed, 2),
+    )
+}
+
+fn hash_pair(vk0: &[u8], vk1: &[u8]) -> [u8; 32] {
+    let mut hasher = Hasher::<256>::new();
+    hasher.input(vk0);
+    hasher.input(vk1);
+    *hasher.finalize()
+}
+
+fn derive_vkey(seed: &[u8; 32], depth: u32) -> [u8; 32] {
+    if depth == 0 {
+        let mut vk = [0; 32];
+        vk.copy_from_slice(SecretKey::from(*seed).public_key().as_ref());
+        return vk;
+    }
+
+    let (mut r0, mut r1) = expand_seed(seed);
+    let vk = hash_pair(&derive_vkey(&r0, depth - 1), &derive_vkey(&r1, depth - 1));
+
+    r0.scrub();
+    r1.scrub();
+
+    vk
+}
+
+fn sign_with(seed: &[u8; 32], depth: u32, period: u32, msg: &[u8], out: &mut Vec<u8>) {
+    if depth == 0 {
+        out.extend_from_slice(SecretKey::from(*seed).sign(msg).as_ref());
+        return;
+    }
+
+    let (mut r0, mut r1) = expand_seed(seed);
+    let half = 1 << (depth - 1);
+
+    match period < half {
+        true => sign_with(&r0, depth - 1, period, msg, out),
+        false => sign_with(&r1, depth - 1, period - half, msg, out),
+    }
+
+    out.extend_from_slice(&derive_vkey(&r0, depth - 1));
+    out.extend_from_slice(&derive_vkey(&r1, depth - 1));
+
+    r0.scrub();
+    r1.scrub();
+}
+
+fn verify_with(vk: &[u8], depth: u32, period: u32, msg: &[u8], sig: &[u8]) -> bool {
+    if depth == 0 {
+        let (Ok(vk), Ok(sig)) = (PublicKey::try_from(vk), Signature::try_from(sig)) else {
+            return false;
+        };
+
+        return vk.verify(msg, &sig);
+    }
+
+    let (inner, pair) = sig.split_at(sig.len() - 64);
+    let (vk0, vk1) = pair.split_at(32);
+
+    if hash_pair(vk0, vk1) != vk {
+        return false;
+    }
+
+    let half = 1 << (depth - 1);
+
+    match period < half {
+        true => verify_with(vk0, depth - 1, period, msg, inner),
+        false => verify_with(vk1, depth - 1, period - half, msg, inner),
+    }
+}
+
+/// Sum6KES signing key, derived from a 32 bytes seed
+pub struct Sum6Kes([u8; 32]);
+
+impl Sum6Kes {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self(seed)
+    }
+
+    /// Verification key, the same for every period
+    pub fn verification_key(&self) -> [u8; 32] {
+        derive_vkey(&self.0, SUM6_DEPTH)
+    }
+
+    /// Signs `msg` at the given period, relative to the start of the key's
+    /// validity
+    pub fn sign(&self, period: u32, msg: &[u8]) -> Result<Sum6KesSignature, KesError> {
+        if period >= SUM6_PERIODS {
+            return Err(KesError::PeriodOutOfRange(period));
+        }
+
+        let mut out = Vec::with_capacity(SUM6_SIGNATURE_SIZE);
+        sign_with(&self.0, SUM6_DEPTH, period, msg, &mut out);
+
+        Ok(Sum6KesSignature(out))
+    }
+}
+
+impl Drop for Sum6Kes {
+    fn drop(&mut self) {
+        self.0.scrub()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sum6KesSignature(Vec<u8>);
+
+impl Sum6KesSignature {
+    pub fn verify(&self, vkey: &[u8; 32], period: u32, msg: &[u8]) -> bool {
+        period < SUM6_PERIODS && verify_with(vkey, SUM6_DEPTH, period, msg, &self.0)
+    }
+}
+
+impl AsRef<[u8]> for Sum6KesSignature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for Sum6KesSignature {
+    type Error = KesError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match value.len() {
+            SUM6_SIGNATURE_SIZE => Ok(Self(value.to_vec())),
+            x => Err(KesError::InvalidSignatureSize(x)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_verify_only_at_their_period() {
+        let key = Sum6Kes::from_seed([7; 32]);
+        let vkey = key.verification_key();
+
+        for period in [0, 1, 31, 32, 63] {
+            let sig = key.sign(period, b"header").unwrap();
+
+            assert_eq!(sig.as_ref().len(), SUM6_SIGNATURE_SIZE);
+            assert!(sig.verify(&vkey, period, b"header"));
+            assert!(!sig.verify(&vkey, period ^ 1, b"header"));
+            assert!(!sig.verify(&vkey, period, b"other"));
+        }
+
+        let other = Sum6Kes::from_seed([8; 32]).verification_key();
+        assert!(!key.sign(0, b"header").unwrap().verify(&other, 0, b"header"));
+
+        assert_eq!(key.sign(64, b"header"), Err(KesError::PeriodOutOfRange(64)));
+        assert_eq!(
+            Sum6KesSignature::try_from(&[0u8; 10][..]),
+            Err(KesError::InvalidSignatureSize(10))
+        );
+    }
+}
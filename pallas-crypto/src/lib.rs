@@ -4,3 +4,4 @@ pub mod hash;
 pub mod key;
 pub mod memsec;
 pub mod nonce;
+pub mod vrf;
@@ -0,0 +1,384 @@
+//! Verifiable Random Functions, as used by Cardano to prove slot leadership
+//!
+//! This is ECVRF-ED25519-SHA512-Elligator2 from version 03 of the IETF
+//! draft, the variant implemented by the libsodium fork of the Haskell node.
+//! Proving runs in variable time, so [`VrfSecretKey`] is meant for testnets
+//! and tooling rather than for production pools.
+
+use cryptoxide::{
+    curve25519::{Fe, Ge, GePartial, Scalar},
+    hashing::sha512,
+};
+use thiserror::Error;
+
+use crate::memsec::Scrubbed as _;
+
+const SUITE: u8 = 0x04;
+
+/// Size of a VRF proof: a point, a 16 bytes challenge and a scalar
+pub const VRF_PROOF_SIZE: usize = 80;
+
+/// Size of a VRF output, a SHA-512 digest
+pub const VRF_OUTPUT_SIZE: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VrfError {
+    #[error("invalid VRF public key")]
+    InvalidPublicKey,
+
+    #[error("invalid VRF proof size {0}")]
+    InvalidProofSize(usize),
+
+    #[error("malformed VRF proof")]
+    MalformedProof,
+
+    #[error("VRF proof doesn't verify")]
+    VerificationFailed,
+}
+
+fn fe_from_u64(x: u64) -> Fe {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&x.to_le_bytes());
+    Fe::from_bytes(&bytes)
+}
+
+fn mul_by_cofactor(point: &Ge) -> Ge {
+    point.double().double().double()
+}
+
+// cryptoxide decodes points negated, as `ge_frombytes_negate_vartime` in
+// ref10, so the sign bit is flipped beforehand to get the point itself
+fn decode_point(bytes: &[u8; 32]) -> Option<Ge> {
+    let mut bytes = *bytes;
+    bytes[31] ^= 0x80;
+    Ge::from_bytes(&bytes)
+}
+
+fn decode_negated_point(bytes: &[u8; 32]) -> Option<Ge> {
+    Ge::from_bytes(bytes)
+}
+
+fn add(a: &GePartial, b: &GePartial) -> Option<Ge> {
+    let a = decode_point(&a.to_bytes())?;
+    let b = decode_point(&b.to_bytes())?;
+
+    Some((&a + &b.to_cached()).to_full())
+}
+
+/// Elligator2 map from a uniform string to a point of the prime subgroup,
+/// as `ge25519_from_uniform` in libsodium
+fn from_uniform(r: &[u8; 32]) -> Option<Ge> {
+    let a = fe_from_u64(486_662);
+
+    let mut bytes = *r;
+    bytes[31] &= 0x7f;
+
+    let r = Fe::from_bytes(&bytes);
+    let rr2 = &(&r.square() + &r.square()) + &Fe::ONE;
+    let mut x = -&(&a * &rr2.invert());
+
+    let x2 = x.square();
+    let e = &(&(&x2 * &x) + &x) + &(&x2 * &a);
+
+    // Legendre symbol, e^((p-1)/2) = e^(2^254-10)
+    let chi = &e.pow25523().square().square() * &e.square();
+
+    if chi == -&Fe::ONE {
+        x = &(-&x) - &a;
+    }
+
+    let y = &(&x - &Fe::ONE) * &(&x + &Fe::ONE).invert();
+
+    decode_point(&y.to_bytes()).map(|x| mul_by_cofactor(&x))
+}
+
+fn hash_to_curve(pk: &[u8; 32], alpha: &[u8]) -> Option<Ge> {
+    let mut buf = vec![SUITE, 0x01];
+    buf.extend_from_slice(pk);
+    buf.extend_from_slice(alpha);
+
+    let mut r = [0; 32];
+    r.copy_from_slice(&sha512(&buf)[..32]);
+
+    from_uniform(&r)
+}
+
+fn hash_points(points: [&[u8; 32]; 4]) -> [u8; 16] {
+    let mut buf = vec![SUITE, 0x02];
+
+    for point in points {
+        buf.extend_from_slice(point);
+    }
+
+    let mut c = [0; 16];
+    c.copy_from_slice(&sha512(&buf)[..16]);
+    c
+}
+
+// (a * b + c) mod L, with `a` and `b` up to 32 bytes, little endian
+fn muladd(a: &[u8], b: &[u8], c: &[u8; 32]) -> Scalar {
+    let mut acc = [0u64; 64];
+
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            acc[i + j] += *x as u64 * *y as u64;
+        }
+    }
+
+    for (i, x) in c.iter().enumerate() {
+        acc[i] += *x as u64;
+    }
+
+    let mut wide = [0u8; 64];
+    let mut carry = 0;
+
+    for (out, x) in wide.iter_mut().zip(acc) {
+        let x = x + carry;
+        *out = x as u8;
+        carry = x >> 8;
+    }
+
+    Scalar::reduce_from_wide_bytes(&wide)
+}
+
+/// VRF signing key, derived from a 32 bytes seed the same way as Ed25519
+/// keys
+pub struct VrfSecretKey {
+    scalar: [u8; 32],
+    prefix: [u8; 32],
+    public: VrfPublicKey,
+}
+
+impl VrfSecretKey {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let hash = sha512(&seed);
+
+        let mut scalar: [u8; 32] = hash[..32].try_into().unwrap();
+        scalar[0] &= 248;
+        scalar[31] &= 63;
+        scalar[31] |= 64;
+
+        let public = Ge::scalarmult_base(&Scalar::from_bytes(&scalar)).to_bytes();
+
+        Self {
+            scalar,
+            prefix: hash[32..].try_into().unwrap(),
+            public: VrfPublicKey(public),
+        }
+    }
+
+    pub fn public_key(&self) -> VrfPublicKey {
+        self.public
+    }
+
+    /// Proves the VRF output for the input `alpha`, returning both
+    pub fn prove(&self, alpha: &[u8]) -> Result<(VrfProof, [u8; 64]), VrfError> {
+        let h = hash_to_curve(&self.public.0, alpha).ok_or(VrfError::InvalidPublicKey)?;
+        let h_bytes = h.to_bytes();
+
+        let zero = Scalar::from_bytes(&[0; 32]);
+        let x = Scalar::from_bytes(&self.scalar);
+
+        let gamma = GePartial::double_scalarmult_vartime(&x, h.clone(), &zero).to_bytes();
+
+        let mut nonce = self.prefix.to_vec();
+        nonce.extend_from_slice(&h_bytes);
+        let k = Scalar::reduce_from_wide_bytes(&sha512(&nonce));
+
+        let kb = Ge::scalarmult_base(&k).to_bytes();
+        let kh = GePartial::double_scalarmult_vartime(&k, h, &zero).to_bytes();
+
+        let c = hash_points([&h_bytes, &gamma, &kb, &kh]);
+        let s = muladd(&c, &self.scalar, &k.to_bytes());
+
+        let mut proof = [0; VRF_PROOF_SIZE];
+        proof[..32].copy_from_slice(&gamma);
+        proof[32..48].copy_from_slice(&c);
+        proof[48..].copy_from_slice(&s.to_bytes());
+
+        let proof = VrfProof(proof);
+        let output = proof.to_output()?;
+
+        Ok((proof, output))
+    }
+}
+
+impl Drop for VrfSecretKey {
+    fn drop(&mut self) {
+        self.scalar.scrub();
+        self.prefix.scrub();
+    }
+}
+
+/// VRF public key of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VrfPublicKey([u8; 32]);
+
+impl VrfPublicKey {
+    /// Verifies `proof` for the input `alpha`, returning the VRF output
+    pub fn verify(&self, alpha: &[u8], proof: &VrfProof) -> Result<[u8; 64], VrfError> {
+        let y = decode_point(&self.0).ok_or(VrfError::InvalidPublicKey)?;
+
+        // reject non-canonical encodings and points of small order
+        let mut y_bytes = Fe::from_bytes(&self.0).to_bytes();
+        y_bytes[31] |= self.0[31] & 0x80;
+
+        if y_bytes != self.0 || mul_by_cofactor(&y).to_bytes() == Ge::ZERO.to_bytes() {
+            return Err(VrfError::InvalidPublicKey);
+        }
+
+        let (gamma, c, s) = proof.decode()?;
+
+        let h = hash_to_curve(&self.0, alpha).ok_or(VrfError::MalformedProof)?;
+        let h_bytes = h.to_bytes();
+
+        let neg_y = decode_negated_point(&self.0).ok_or(VrfError::InvalidPublicKey)?;
+        let neg_gamma = decode_negated_point(&proof.gamma()).ok_or(VrfError::MalformedProof)?;
+
+        // U = s*B - c*Y
+        let u = GePartial::double_scalarmult_vartime(&c, neg_y, &s).to_bytes();
+
+        // V = s*H - c*Gamma
+        let zero = Scalar::from_bytes(&[0; 32]);
+        let sh = GePartial::double_scalarmult_vartime(&s, h, &zero);
+        let cg = GePartial::double_scalarmult_vartime(&c, neg_gamma, &zero);
+
+        let v = add(&sh, &cg).ok_or(VrfError::MalformedProof)?.to_bytes();
+
+        if hash_points([&h_bytes, &gamma.to_bytes(), &u, &v]) != proof.challenge() {
+            return Err(VrfError::VerificationFailed);
+        }
+
+        Ok(proof.output_of(&gamma))
+    }
+}
+
+impl From<[u8; 32]> for VrfPublicKey {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<&[u8]> for VrfPublicKey {
+    type Error = VrfError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        value
+            .try_into()
+            .map(Self)
+            .map_err(|_| VrfError::InvalidPublicKey)
+    }
+}
+
+impl AsRef<[u8]> for VrfPublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VrfProof([u8; VRF_PROOF_SIZE]);
+
+impl VrfProof {
+    fn gamma(&self) -> [u8; 32] {
+        self.0[..32].try_into().unwrap()
+    }
+
+    fn challenge(&self) -> [u8; 16] {
+        self.0[32..48].try_into().unwrap()
+    }
+
+    fn decode(&self) -> Result<(Ge, Scalar, Scalar), VrfError> {
+        let gamma = decode_point(&self.gamma()).ok_or(VrfError::MalformedProof)?;
+
+        let mut c = [0; 32];
+        c[..16].copy_from_slice(&self.challenge());
+
+        let s = Scalar::from_bytes_canonical(self.0[48..].try_into().unwrap())
+            .ok_or(VrfError::MalformedProof)?;
+
+        Ok((gamma, Scalar::from_bytes(&c), s))
+    }
+
+    fn output_of(&self, gamma: &Ge) -> [u8; 64] {
+        let mut buf = vec![SUITE, 0x03];
+        buf.extend_from_slice(&mul_by_cofactor(gamma).to_bytes());
+        sha512(&buf)
+    }
+
+    /// VRF output certified by the proof, without checking it. Only
+    /// meaningful once the proof has been verified.
+    pub fn to_output(&self) -> Result<[u8; 64], VrfError> {
+        let (gamma, _, _) = self.decode()?;
+        Ok(self.output_of(&gamma))
+    }
+}
+
+impl TryFrom<&[u8]> for VrfProof {
+    type Error = VrfError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        value
+            .try_into()
+            .map(Self)
+            .map_err(|_| VrfError::InvalidProofSize(value.len()))
+    }
+}
+
+impl AsRef<[u8]> for VrfProof {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode<const N: usize>(x: &str) -> [u8; N] {
+        hex::decode(x).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn draft03_vectors() {
+        // test vectors from draft-irtf-cfrg-vrf-03, appendix A.4
+        let vectors = [
+            (
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "b6b4699f87d56126c9117a7da55bd0085246f4c56dbc95d20172612e9d38e8d7ca65e573a126ed88d4e30a46f80a666854d675cf3ba81de0de043c3774f061560f55edc256a787afe701677c0f602900",
+                "5b49b554d05c0cd5a5325376b3387de59d924fd1e13ded44648ab33c21349a603f25b84ec5ed887995b33da5e3bfcb87cd2f64521c4c62cf825cffabbe5d31cc",
+            ),
+            (
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "ae5b66bdf04b4c010bfe32b2fc126ead2107b697634f6f7337b9bff8785ee111200095ece87dde4dbe87343f6df3b107d91798c8a7eb1245d3bb9c5aafb093358c13e6ae1111a55717e895fd15f99f07",
+                "94f4487e1b2fec954309ef1289ecb2e15043a2461ecc7b2ae7d4470607ef82eb1cfa97d84991fe4a7bfdfd715606bc27e2967a6c557cfb5875879b671740b7d8",
+            ),
+        ];
+
+        // the secret key of the first vector, from the draft
+        let sk = VrfSecretKey::from_seed(decode(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        ));
+
+        let (proof, output) = sk.prove(&[]).unwrap();
+        assert_eq!(sk.public_key().as_ref(), hex::decode(vectors[0].0).unwrap());
+        assert_eq!(hex::encode(proof), vectors[0].2);
+        assert_eq!(hex::encode(output), vectors[0].3);
+
+        for (pk, alpha, proof, output) in vectors {
+            let pk = VrfPublicKey::from(decode::<32>(pk));
+            let alpha = hex::decode(alpha).unwrap();
+            let proof = VrfProof::try_from(&hex::decode(proof).unwrap()[..]).unwrap();
+
+            assert_eq!(proof.to_output(), Ok(decode::<64>(output)));
+            assert_eq!(pk.verify(&alpha, &proof), Ok(decode::<64>(output)));
+
+            assert_eq!(
+                pk.verify(b"other", &proof),
+                Err(VrfError::VerificationFailed)
+            );
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::net::SocketAddr;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{debug, error};
 
@@ -20,6 +20,7 @@ use crate::miniprotocols::{
 };
 
 use crate::multiplexer::{self, Bearer, RunningPlexer};
+use crate::proxy::Proxy;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -113,6 +114,26 @@ impl PeerClient {
             .await
             .map_err(Error::ConnectFailure)?;
 
+        Self::handshake_bearer(bearer, magic).await
+    }
+
+    /// Connects to `host:port` through a SOCKS5 or HTTP CONNECT proxy
+    pub async fn connect_via_proxy(
+        proxy: &Proxy,
+        host: &str,
+        port: u16,
+        magic: u64,
+    ) -> Result<Self, Error> {
+        let bearer = Bearer::connect_proxy(proxy, host, port)
+            .await
+            .map_err(Error::ConnectFailure)?;
+
+        Self::handshake_bearer(bearer, magic).await
+    }
+
+    /// Performs the handshake over an already connected bearer, such as an
+    /// in-memory one
+    pub async fn handshake_bearer(bearer: Bearer, magic: u64) -> Result<Self, Error> {
         let mut plexer = multiplexer::Plexer::new(bearer);
 
         let channel = plexer.subscribe_client(PROTOCOL_N2N_HANDSHAKE);
@@ -182,6 +203,68 @@ impl PeerClient {
     }
 }
 
+/// Outcome of a handshake-only connection, see [`probe_peer`]
+#[derive(Debug, Clone)]
+pub struct PeerProbe {
+    pub address: SocketAddr,
+    pub version: VersionNumber,
+    pub version_data: n2n::VersionData,
+    pub connect_latency: Duration,
+    pub handshake_latency: Duration,
+}
+
+impl PeerProbe {
+    pub fn network_magic(&self) -> u64 {
+        self.version_data.network_magic()
+    }
+}
+
+/// Connects to a peer, completes the N2N handshake and disconnects
+///
+/// Only the handshake mini-protocol is set up, which makes this a cheap
+/// building block for network scanners. Wrap it in [`tokio::time::timeout`]
+/// to bound the time spent on unresponsive peers.
+pub async fn probe_peer(addr: impl ToSocketAddrs, magic: u64) -> Result<PeerProbe, Error> {
+    let start = Instant::now();
+
+    let stream = tokio::net::TcpStream::connect(addr)
+        .await
+        .map_err(Error::ConnectFailure)?;
+
+    let connect_latency = start.elapsed();
+    let address = stream.peer_addr().map_err(Error::ConnectFailure)?;
+
+    let mut plexer = multiplexer::Plexer::new(Bearer::Tcp(stream));
+    let channel = plexer.subscribe_client(PROTOCOL_N2N_HANDSHAKE);
+    let mut handshake = handshake::N2NClient::new(channel);
+    let plexer = plexer.spawn();
+
+    let start = Instant::now();
+
+    let confirmation = handshake
+        .handshake(n2n::VersionTable::v7_and_above(magic))
+        .await;
+
+    let handshake_latency = start.elapsed();
+
+    plexer.abort().await;
+
+    match confirmation.map_err(Error::HandshakeProtocol)? {
+        Confirmation::Accepted(version, version_data) => Ok(PeerProbe {
+            address,
+            version,
+            version_data,
+            connect_latency,
+            handshake_latency,
+        }),
+        Confirmation::Rejected(reason) => {
+            debug!(?reason, "probe handshake refused");
+            Err(Error::IncompatibleVersion)
+        }
+        Confirmation::QueryReply(_) => Err(Error::IncompatibleVersion),
+    }
+}
+
 /// Server of N2N Ouroboros
 pub struct PeerServer {
     pub plexer: RunningPlexer,
@@ -196,8 +279,13 @@ pub struct PeerServer {
 
 impl PeerServer {
     pub fn new(bearer: Bearer) -> Self {
-        let mut plexer = multiplexer::Plexer::new(bearer);
+        Self::with_plexer(multiplexer::Plexer::new(bearer))
+    }
 
+    /// Server on top of a plexer that's not spawned yet, for plexers that
+    /// need extra configuration such as
+    /// [`multiplexer::Plexer::with_graceful_close`]
+    pub fn with_plexer(mut plexer: multiplexer::Plexer) -> Self {
         let hs_channel = plexer.subscribe_server(PROTOCOL_N2N_HANDSHAKE);
         let cs_channel = plexer.subscribe_server(PROTOCOL_N2N_CHAIN_SYNC);
         let bf_channel = plexer.subscribe_server(PROTOCOL_N2N_BLOCK_FETCH);
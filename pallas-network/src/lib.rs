@@ -1,5 +1,18 @@
 //! Network stack compatible with the Ouroboros protocol
+//!
+//! On `wasm32` targets only the in-memory and websocket bearers are
+//! available, and the plexer runs on the browser event loop. The facades and
+//! proxies, which are built on OS sockets, are left out.
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod facades;
 pub mod miniprotocols;
 pub mod multiplexer;
+pub mod propagation;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod proxy;
+
+mod task;
+
+#[cfg(all(any(test, feature = "sim"), not(target_arch = "wasm32")))]
+pub mod sim;
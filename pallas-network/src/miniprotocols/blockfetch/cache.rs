@@ -0,0 +1,284 @@
+//! Disk-backed LRU cache of raw blocks
+//!
+//! Blocks are stored one per file, named after the hex of their hash, in a
+//! directory that survives restarts. Recency is tracked through the
+//! modification time of the files, so the least recently used blocks are the
+//! first ones evicted once the cache grows beyond its size limit.
+//!
+//! The cache is best-effort: the fetch helpers treat any cache I/O error as a
+//! miss and fall back to the network.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::warn;
+
+use crate::miniprotocols::Point;
+
+use super::{check_bodies, Body, Client, ClientError, FetchScheduler, SchedulerError};
+
+pub struct BlockCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    total_bytes: u64,
+    tick: u64,
+    entries: HashMap<String, (u64, u64)>,
+    recency: BTreeMap<u64, String>,
+}
+
+fn cache_key(point: &Point) -> Option<String> {
+    match point {
+        Point::Specific(_, hash) if !hash.is_empty() => Some(hex::encode(hash)),
+        _ => None,
+    }
+}
+
+impl BlockCache {
+    /// Opens the cache at `dir`, creating the directory if needed and
+    /// indexing the blocks left there by previous runs
+    pub fn open(dir: impl AsRef<Path>, max_bytes: u64) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut existing = vec![];
+
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if name.ends_with(".tmp") {
+                continue;
+            }
+
+            let meta = entry.metadata()?;
+            let used = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            existing.push((used, name, meta.len()));
+        }
+
+        existing.sort();
+
+        let mut cache = Self {
+            dir,
+            max_bytes,
+            total_bytes: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        };
+
+        for (_, key, size) in existing {
+            cache.track(key, size);
+        }
+
+        cache.evict()?;
+
+        Ok(cache)
+    }
+
+    fn track(&mut self, key: String, size: u64) {
+        self.tick += 1;
+
+        if let Some((old_size, old_tick)) = self.entries.insert(key.clone(), (size, self.tick)) {
+            self.recency.remove(&old_tick);
+            self.total_bytes -= old_size;
+        }
+
+        self.recency.insert(self.tick, key);
+        self.total_bytes += size;
+    }
+
+    fn evict(&mut self) -> io::Result<()> {
+        while self.total_bytes > self.max_bytes {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+
+            if let Some((size, _)) = self.entries.remove(&key) {
+                self.total_bytes -= size;
+            }
+
+            match fs::remove_file(self.dir.join(&key)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        cache_key(point).is_some_and(|x| self.entries.contains_key(&x))
+    }
+
+    /// Reads a block, marking it as recently used
+    pub fn get(&mut self, point: &Point) -> io::Result<Option<Body>> {
+        let Some(key) = cache_key(point) else {
+            return Ok(None);
+        };
+
+        let Some(&(size, _)) = self.entries.get(&key) else {
+            return Ok(None);
+        };
+
+        let path = self.dir.join(&key);
+        let body = fs::read(&path)?;
+
+        fs::File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(SystemTime::now())?;
+
+        self.track(key, size);
+
+        Ok(Some(body))
+    }
+
+    /// Stores a block, evicting the least recently used ones if the cache
+    /// exceeds its size limit. Points without a hash are ignored.
+    pub fn put(&mut self, point: &Point, body: &[u8]) -> io::Result<()> {
+        let Some(key) = cache_key(point) else {
+            return Ok(());
+        };
+
+        let path = self.dir.join(&key);
+        let tmp = self.dir.join(format!("{key}.tmp"));
+
+        fs::write(&tmp, body)?;
+        fs::rename(&tmp, &path)?;
+
+        self.track(key, body.len() as u64);
+        self.evict()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Size in bytes of the cached blocks
+    pub fn size(&self) -> u64 {
+        self.total_bytes
+    }
+}
+
+fn cached(cache: &mut BlockCache, point: &Point) -> Option<Body> {
+    cache.get(point).unwrap_or_else(|err| {
+        warn!(%err, "block cache read failed");
+        None
+    })
+}
+
+fn store(cache: &mut BlockCache, point: &Point, body: &[u8]) {
+    if let Err(err) = cache.put(point, body) {
+        warn!(%err, "block cache write failed");
+    }
+}
+
+impl Client {
+    /// Fetch a single block, reading it from the cache when available and
+    /// storing it there otherwise.
+    pub async fn fetch_single_cached(
+        &mut self,
+        cache: &mut BlockCache,
+        point: Point,
+    ) -> Result<Body, ClientError> {
+        if let Some(body) = cached(cache, &point) {
+            return Ok(body);
+        }
+
+        let body = self.fetch_single(point.clone()).await?;
+
+        check_bodies(std::slice::from_ref(&point), std::slice::from_ref(&body))?;
+        store(cache, &point, &body);
+
+        Ok(body)
+    }
+}
+
+impl FetchScheduler {
+    /// Same as [`FetchScheduler::fetch`], but only downloads the blocks
+    /// missing from the cache and stores them there afterwards
+    pub async fn fetch_cached(
+        &self,
+        clients: &mut Vec<Client>,
+        cache: &mut BlockCache,
+        points: &[Point],
+    ) -> Result<Vec<Body>, SchedulerError> {
+        let mut bodies: Vec<_> = points.iter().map(|x| cached(cache, x)).collect();
+
+        // ranges can't skip blocks, so misses are fetched in contiguous runs
+        let mut start = 0;
+
+        while start < points.len() {
+            if bodies[start].is_some() {
+                start += 1;
+                continue;
+            }
+
+            let end = (start..points.len())
+                .find(|i| bodies[*i].is_some())
+                .unwrap_or(points.len());
+
+            let fetched = self.fetch(clients, &points[start..end]).await?;
+
+            // bodies are cached by position, so they must line up exactly
+            check_bodies(&points[start..end], &fetched).map_err(SchedulerError::InvalidBlocks)?;
+
+            for (i, body) in (start..end).zip(fetched) {
+                store(cache, &points[i], &body);
+                bodies[i] = Some(body);
+            }
+
+            start = end;
+        }
+
+        Ok(bodies.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(i: u8) -> Point {
+        Point::Specific(i as u64, vec![i; 32])
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("pallas-block-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut cache = BlockCache::open(&dir, 20).unwrap();
+
+        cache.put(&point(1), &[1; 8]).unwrap();
+        cache.put(&point(2), &[2; 8]).unwrap();
+        assert_eq!(cache.get(&point(1)).unwrap(), Some(vec![1; 8]));
+
+        // block 2 is now the least recently used one
+        cache.put(&point(3), &[3; 8]).unwrap();
+
+        assert!(cache.contains(&point(1)));
+        assert!(!cache.contains(&point(2)));
+        assert!(cache.contains(&point(3)));
+        assert_eq!(cache.size(), 16);
+
+        cache.put(&Point::Origin, &[0; 4]).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // entries survive a reopen
+        drop(cache);
+        let mut cache = BlockCache::open(&dir, 20).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&point(3)).unwrap(), Some(vec![3; 8]));
+        assert_eq!(cache.get(&point(2)).unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -23,6 +23,12 @@ pub enum ClientError {
     #[error("requested range doesn't contain any blocks")]
     NoBlocks,
 
+    #[error("expected {expected} blocks in the range, received {received}")]
+    UnexpectedBlockCount { expected: usize, received: usize },
+
+    #[error("block {0} of the range doesn't match the requested point")]
+    UnexpectedBlock(usize),
+
     #[error("error while sending or receiving data through the multiplexer")]
     Plexer(multiplexer::Error),
 }
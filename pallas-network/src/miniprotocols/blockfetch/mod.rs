@@ -1,10 +1,14 @@
 //! BlockFetch mini-protocol implementation
 
+mod cache;
 mod client;
 mod codec;
 mod protocol;
+mod scheduler;
 mod server;
 
+pub use cache::*;
 pub use client::*;
 pub use protocol::*;
+pub use scheduler::*;
 pub use server::*;
@@ -0,0 +1,393 @@
+//! Bulk download of blocks over one or more peers
+//!
+//! BlockFetch ranges are bounded by points, so the caller needs to know the
+//! points of the blocks to download, typically from the headers received
+//! through ChainSync. The scheduler splits that list into fixed-size batches,
+//! hands them out to the available peers as they become idle, retries failed
+//! batches and returns the bodies in chain order. Every body is checked
+//! against the hash of its point, so a peer can't misalign the result by
+//! skipping or reordering blocks.
+
+use std::collections::VecDeque;
+
+use pallas_codec::minicbor;
+use pallas_crypto::hash::{Hash, Hasher};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+use crate::miniprotocols::Point;
+
+use super::{Body, Client, ClientError, Range, State};
+
+#[derive(Error, Debug)]
+pub enum SchedulerError {
+    #[error("no peers available to fetch from")]
+    NoPeers,
+
+    #[error("batch {range:?} failed after {attempts} attempts")]
+    BatchFailed {
+        range: Range,
+        attempts: usize,
+        #[source]
+        source: ClientError,
+    },
+
+    #[error("every peer failed before the download completed")]
+    PeersExhausted,
+
+    #[error("downloaded blocks don't match the requested points")]
+    InvalidBlocks(#[source] ClientError),
+}
+
+/// Splits an ordered list of points into ranges of at most `batch_size`
+/// blocks each
+pub fn split_range(points: &[Point], batch_size: usize) -> Vec<Range> {
+    points
+        .chunks(batch_size.max(1))
+        .map(|x| (x[0].clone(), x[x.len() - 1].clone()))
+        .collect()
+}
+
+/// Hash of the header of a block as served by BlockFetch, which wraps it
+/// along with its era
+pub fn header_hash(body: &[u8]) -> Option<Hash<32>> {
+    let mut d = minicbor::Decoder::new(body);
+
+    d.array().ok()?;
+    let era = d.u16().ok()?;
+    d.array().ok()?;
+
+    let start = d.position();
+    d.skip().ok()?;
+    let header = &body[start..d.position()];
+
+    match era {
+        // byron headers are hashed along with the type of block
+        0 | 1 => {
+            let mut hasher = Hasher::<256>::new();
+            hasher.input(&[0x82, era as u8]);
+            hasher.input(header);
+            Some(hasher.finalize())
+        }
+        _ => Some(Hasher::<256>::hash(header)),
+    }
+}
+
+/// Checks that the bodies are the blocks at the given points, one per point
+/// and in the same order
+pub fn check_bodies(points: &[Point], bodies: &[Body]) -> Result<(), ClientError> {
+    if points.len() != bodies.len() {
+        return Err(ClientError::UnexpectedBlockCount {
+            expected: points.len(),
+            received: bodies.len(),
+        });
+    }
+
+    for (index, (point, body)) in points.iter().zip(bodies).enumerate() {
+        let matches = match point {
+            Point::Specific(_, hash) => header_hash(body).is_some_and(|x| x.as_ref() == hash),
+            Point::Origin => false,
+        };
+
+        if !matches {
+            return Err(ClientError::UnexpectedBlock(index));
+        }
+    }
+
+    Ok(())
+}
+
+struct Batch {
+    index: usize,
+    range: Range,
+    points: Vec<Point>,
+    attempts: usize,
+}
+
+struct Outcome {
+    worker: usize,
+    batch: Batch,
+    result: Result<Vec<Body>, ClientError>,
+    healthy: bool,
+}
+
+async fn worker(
+    id: usize,
+    mut client: Client,
+    mut jobs: mpsc::UnboundedReceiver<Batch>,
+    outcomes: mpsc::UnboundedSender<Outcome>,
+) -> Client {
+    while let Some(batch) = jobs.recv().await {
+        let result = client
+            .fetch_range(batch.range.clone())
+            .await
+            .and_then(|bodies| check_bodies(&batch.points, &bodies).map(|_| bodies));
+
+        // a failure mid-stream leaves the client out of sync with the peer,
+        // and a peer serving the wrong blocks isn't trusted with more batches
+        let healthy = *client.state() == State::Idle
+            && !matches!(
+                result,
+                Err(ClientError::UnexpectedBlockCount { .. } | ClientError::UnexpectedBlock(_))
+            );
+
+        let outcome = Outcome {
+            worker: id,
+            batch,
+            result,
+            healthy,
+        };
+
+        if outcomes.send(outcome).is_err() || !healthy {
+            break;
+        }
+    }
+
+    client
+}
+
+/// Downloads ranges of blocks distributing the work across peers
+#[derive(Debug, Clone)]
+pub struct FetchScheduler {
+    batch_size: usize,
+    max_attempts: usize,
+}
+
+impl Default for FetchScheduler {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            max_attempts: 3,
+        }
+    }
+}
+
+impl FetchScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Max number of blocks requested in a single range, 100 by default
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Times a batch is attempted before giving up, 3 by default
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Fetches the blocks at the given points, in the same order
+    ///
+    /// Each client downloads one batch at a time. A batch that fails, or whose
+    /// blocks don't match the requested points, is put back at the front of
+    /// the queue for the next idle peer. Peers that failed that way get no
+    /// more batches. Clients left in an inconsistent state by a failure are
+    /// dropped, the remaining ones are handed back through `clients` once
+    /// done.
+    pub async fn fetch(
+        &self,
+        clients: &mut Vec<Client>,
+        points: &[Point],
+    ) -> Result<Vec<Body>, SchedulerError> {
+        if points.is_empty() {
+            return Ok(vec![]);
+        }
+
+        if clients.is_empty() {
+            return Err(SchedulerError::NoPeers);
+        }
+
+        let mut pending: VecDeque<_> = split_range(points, self.batch_size)
+            .into_iter()
+            .zip(points.chunks(self.batch_size))
+            .enumerate()
+            .map(|(index, (range, points))| Batch {
+                index,
+                range,
+                points: points.to_vec(),
+                attempts: 0,
+            })
+            .collect();
+
+        let mut done: Vec<Option<Vec<Body>>> = pending.iter().map(|_| None).collect();
+        let mut remaining = done.len();
+
+        let (outcome_tx, mut outcome_rx) = mpsc::unbounded_channel();
+        let mut workers = JoinSet::new();
+        let mut jobs = vec![];
+        let mut idle = vec![];
+
+        for (id, client) in clients.drain(..).enumerate() {
+            let (job_tx, job_rx) = mpsc::unbounded_channel();
+            workers.spawn(worker(id, client, job_rx, outcome_tx.clone()));
+            jobs.push(Some(job_tx));
+            idle.push(id);
+        }
+
+        drop(outcome_tx);
+
+        let mut busy = 0;
+
+        let result = loop {
+            while !pending.is_empty() && !idle.is_empty() {
+                let worker = idle.remove(0);
+
+                let Some(job) = &jobs[worker] else {
+                    continue;
+                };
+
+                let batch = pending.pop_front().unwrap();
+
+                debug!(worker, batch = batch.index, "dispatching batch");
+
+                match job.send(batch) {
+                    Ok(_) => busy += 1,
+                    Err(mpsc::error::SendError(batch)) => {
+                        pending.push_front(batch);
+                        jobs[worker] = None;
+                    }
+                }
+            }
+
+            if remaining == 0 {
+                break Ok(());
+            }
+
+            if busy == 0 {
+                break Err(SchedulerError::PeersExhausted);
+            }
+
+            let Some(outcome) = outcome_rx.recv().await else {
+                break Err(SchedulerError::PeersExhausted);
+            };
+
+            busy -= 1;
+
+            match outcome.healthy {
+                true => idle.push(outcome.worker),
+                false => jobs[outcome.worker] = None,
+            }
+
+            let mut batch = outcome.batch;
+
+            match outcome.result {
+                Ok(bodies) => {
+                    done[batch.index] = Some(bodies);
+                    remaining -= 1;
+                }
+                Err(source) => {
+                    batch.attempts += 1;
+
+                    warn!(batch = batch.index, attempts = batch.attempts, %source, "batch failed");
+
+                    if batch.attempts >= self.max_attempts {
+                        break Err(SchedulerError::BatchFailed {
+                            range: batch.range,
+                            attempts: batch.attempts,
+                            source,
+                        });
+                    }
+
+                    pending.push_front(batch);
+                }
+            }
+        };
+
+        // closing the job channels lets the workers hand their clients back
+        drop(jobs);
+
+        while let Some(client) = workers.join_next().await {
+            if let Ok(client) = client {
+                if *client.state() == State::Idle {
+                    clients.push(client);
+                }
+            }
+        }
+
+        result?;
+
+        Ok(done.into_iter().flatten().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_are_split_in_batches() {
+        let points: Vec<_> = (0..5).map(|i| Point::Specific(i, vec![i as u8])).collect();
+
+        let ranges = split_range(&points, 2);
+
+        assert_eq!(
+            ranges,
+            vec![
+                (points[0].clone(), points[1].clone()),
+                (points[2].clone(), points[3].clone()),
+                (points[4].clone(), points[4].clone()),
+            ]
+        );
+
+        assert_eq!(split_range(&points, 0).len(), 5);
+        assert!(split_range(&[], 10).is_empty());
+    }
+
+    fn mainnet_block(cbor: &str, slot: u64, hash: &str) -> (Point, Body) {
+        let point = Point::Specific(slot, hex::decode(hash).unwrap());
+        (point, hex::decode(cbor.trim()).unwrap())
+    }
+
+    #[test]
+    fn bodies_are_checked_against_their_points() {
+        let blocks = [
+            mainnet_block(
+                include_str!("../../../../test_data/genesis.block"),
+                0,
+                "89d9b5a5b8ddc8d7e5a6795e9774d97faf1efea59b2caf7eaf9f8c5b32059df4",
+            ),
+            mainnet_block(
+                include_str!("../../../../test_data/byron1.block"),
+                4492794,
+                "5c196e7394ace0449ba5a51c919369699b13896e97432894b4f0354dce8670b6",
+            ),
+            mainnet_block(
+                include_str!("../../../../test_data/alonzo1.block"),
+                43381130,
+                "18362a803c351d5950fa929d87d17c4c34c624d3558f3f96d927221ed6436d23",
+            ),
+            mainnet_block(
+                include_str!("../../../../test_data/conway1.block"),
+                22075282,
+                "9b51ccd4f161c08382a445684ff3eb788923608acbea283081fa5ccf663fef8d",
+            ),
+        ];
+
+        let (points, bodies): (Vec<_>, Vec<_>) = blocks.into_iter().unzip();
+
+        check_bodies(&points, &bodies).unwrap();
+
+        assert!(matches!(
+            check_bodies(&points, &bodies[..3]),
+            Err(ClientError::UnexpectedBlockCount {
+                expected: 4,
+                received: 3
+            })
+        ));
+
+        let mut swapped = bodies.clone();
+        swapped.swap(1, 2);
+
+        assert!(matches!(
+            check_bodies(&points, &swapped),
+            Err(ClientError::UnexpectedBlock(1))
+        ));
+
+        assert!(header_hash(&[0x01]).is_none());
+    }
+}
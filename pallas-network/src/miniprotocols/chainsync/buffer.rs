@@ -18,11 +18,21 @@ use crate::miniprotocols::Point;
 /// Notice that it works by keeping track of points, not blocks. It is meant to
 /// be used as a lightweight index where blocks can then be retrieved from a
 /// more suitable memory structure / persistent storage.
-#[derive(Debug)]
+///
+/// When built with a security parameter `k`, points are handed over as
+/// immutable as soon as `k` points sit on top of them, and the last one
+/// handed over is kept as the immutable tip, which is still a valid
+/// rollback target.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RollbackBuffer {
     points: VecDeque<Point>,
+    k: Option<usize>,
+    immutable_tip: Option<Point>,
 }
 
+/// Security parameter of mainnet and the public testnets
+pub const MAINNET_SECURITY_PARAM: usize = 2160;
+
 impl Default for RollbackBuffer {
     fn default() -> Self {
         Self::new()
@@ -38,20 +48,68 @@ impl RollbackBuffer {
     pub fn new() -> Self {
         Self {
             points: VecDeque::new(),
+            k: None,
+            immutable_tip: None,
+        }
+    }
+
+    /// Buffer that keeps at most `k` points, the ones that may still be
+    /// rolled back
+    pub fn with_security_param(k: usize) -> Self {
+        Self {
+            k: Some(k),
+            ..Self::new()
         }
     }
 
-    /// Adds a new point to the back of the buffer
-    pub fn roll_forward(&mut self, point: Point) {
+    /// Rebuilds a buffer from persisted state: the last point handed over as
+    /// immutable and the points after it, oldest first. Points beyond `k` are
+    /// handed over on the next roll forward.
+    pub fn restore(
+        k: usize,
+        immutable_tip: Option<Point>,
+        points: impl IntoIterator<Item = Point>,
+    ) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+            k: Some(k),
+            immutable_tip,
+        }
+    }
+
+    pub fn security_param(&self) -> Option<usize> {
+        self.k
+    }
+
+    /// The last point handed over as immutable
+    pub fn immutable_tip(&self) -> Option<&Point> {
+        self.immutable_tip.as_ref()
+    }
+
+    /// Adds a new point to the back of the buffer, returning the points that
+    /// became immutable, oldest first. Without a security parameter nothing
+    /// is returned, see [`RollbackBuffer::pop_with_depth`].
+    pub fn roll_forward(&mut self, point: Point) -> Vec<Point> {
         self.points.push_back(point);
+
+        match self.k {
+            Some(k) => self.pop_with_depth(k),
+            None => vec![],
+        }
     }
 
     /// Retrieves all points above or equal a certain depth
     pub fn pop_with_depth(&mut self, min_depth: usize) -> Vec<Point> {
-        match self.points.len().checked_sub(min_depth) {
+        let ready: Vec<_> = match self.points.len().checked_sub(min_depth) {
             Some(ready) => self.points.drain(0..ready).collect(),
             None => vec![],
+        };
+
+        if let Some(last) = ready.last() {
+            self.immutable_tip = Some(last.clone());
         }
+
+        ready
     }
 
     /// Find the position of a point within the buffer
@@ -82,13 +140,17 @@ impl RollbackBuffer {
     /// Unwind the buffer up to a certain point, clearing orphaned items
     ///
     /// If the buffer contains the rollback point, we can safely discard from
-    /// the back and return Ok. If the rollback point is outside the scope of
-    /// the buffer, we clear the whole buffer and notify a failure
-    /// in the rollback process.
+    /// the back and return Ok. Rolling back to the immutable tip empties the
+    /// buffer and is handled too. If the rollback point is outside the scope
+    /// of the buffer, we clear the whole buffer and notify a failure in the
+    /// rollback process.
     pub fn roll_back(&mut self, point: &Point) -> RollbackEffect {
         if let Some(x) = self.position(point) {
             self.points.truncate(x + 1);
             RollbackEffect::Handled
+        } else if self.immutable_tip.as_ref() == Some(point) {
+            self.points.clear();
+            RollbackEffect::Handled
         } else {
             self.points.clear();
             RollbackEffect::OutOfScope
@@ -179,6 +241,63 @@ mod tests {
         assert_eq!(remaining.len(), 3);
     }
 
+    #[test]
+    fn points_become_immutable_past_k() {
+        let mut buffer = RollbackBuffer::with_security_param(3);
+
+        for i in 0..3 {
+            assert!(buffer.roll_forward(dummy_point(i)).is_empty());
+        }
+
+        assert_eq!(buffer.roll_forward(dummy_point(3)), vec![dummy_point(0)]);
+        assert_eq!(buffer.immutable_tip(), Some(&dummy_point(0)));
+        assert_eq!(buffer.size(), 3);
+
+        // deeper than the immutable tip is out of reach
+        assert!(matches!(
+            buffer.clone().roll_back(&Point::Origin),
+            RollbackEffect::OutOfScope
+        ));
+
+        // back to the immutable tip, the whole window is undone
+        assert!(matches!(
+            buffer.roll_back(&dummy_point(0)),
+            RollbackEffect::Handled
+        ));
+        assert_eq!(buffer.size(), 0);
+    }
+
+    #[test]
+    fn roll_back_to_immutable_tip_after_restore() {
+        let mut buffer = RollbackBuffer::with_security_param(2);
+
+        for i in 0..5 {
+            buffer.roll_forward(dummy_point(i));
+        }
+
+        let tip = buffer.immutable_tip().cloned();
+        let points: Vec<_> = buffer.peek().cloned().collect();
+        assert_eq!(tip, Some(dummy_point(2)));
+
+        let mut restored = RollbackBuffer::restore(2, tip, points);
+        assert_eq!(restored, buffer);
+
+        assert!(matches!(
+            restored.roll_back(&dummy_point(2)),
+            RollbackEffect::Handled
+        ));
+        assert_eq!(restored.size(), 0);
+        assert_eq!(restored.immutable_tip(), Some(&dummy_point(2)));
+
+        // a restored window larger than k drains on the next roll forward
+        let mut restored = RollbackBuffer::restore(1, None, (0..3).map(dummy_point));
+        assert_eq!(
+            restored.roll_forward(dummy_point(3)),
+            vec![dummy_point(0), dummy_point(1), dummy_point(2)]
+        );
+        assert_eq!(restored.immutable_tip(), Some(&dummy_point(2)));
+    }
+
     #[test]
     fn roll_back_outside_scope_works() {
         let mut buffer = build_filled_buffer(6);
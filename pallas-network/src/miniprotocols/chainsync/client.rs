@@ -36,7 +36,7 @@ pub enum NextResponse<CONTENT> {
     Await,
 }
 
-pub struct Client<O>(State, multiplexer::ChannelBuffer, PhantomData<O>)
+pub struct Client<O>(State, multiplexer::ChannelBuffer, PhantomData<O>, usize)
 where
     Message<O>: Fragment;
 
@@ -55,6 +55,7 @@ where
             State::Idle,
             multiplexer::ChannelBuffer::new(channel),
             PhantomData {},
+            0,
         )
     }
 
@@ -63,6 +64,11 @@ where
         &self.0
     }
 
+    /// Returns the number of RequestNext messages still waiting for a reply.
+    pub fn in_flight(&self) -> usize {
+        self.3
+    }
+
     /// Checks if the client is done.
     pub fn is_done(&self) -> bool {
         self.0 == State::Done
@@ -220,10 +226,49 @@ where
         let msg = Message::RequestNext;
         self.send_message(&msg).await?;
         self.0 = State::CanAwait;
+        self.3 = 1;
+
+        Ok(())
+    }
+
+    /// Sends a RequestNext message without waiting for the replies of the
+    /// ones already in flight.
+    ///
+    /// The server answers pipelined requests in order, so the state of the
+    /// client tracks the oldest outstanding one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be sent or if the client is
+    /// neither idle nor waiting for a RequestNext reply.
+    pub async fn send_request_next_pipelined(&mut self) -> Result<(), ClientError> {
+        match self.0 {
+            State::Idle => return self.send_request_next().await,
+            State::CanAwait | State::MustReply => (),
+            _ => return Err(ClientError::InvalidOutbound),
+        }
+
+        self.1
+            .send_msg_chunks(&Message::<O>::RequestNext)
+            .await
+            .map_err(ClientError::Plexer)?;
+
+        self.3 += 1;
+
+        debug!(in_flight = self.3, "sent pipelined request next");
 
         Ok(())
     }
 
+    fn on_roll_reply(&mut self) {
+        self.3 = self.3.saturating_sub(1);
+
+        self.0 = match self.3 {
+            0 => State::Idle,
+            _ => State::CanAwait,
+        };
+    }
+
     /// Receives a response while the client is in the CanAwait state.
     ///
     /// # Errors
@@ -236,11 +281,11 @@ where
                 Ok(NextResponse::Await)
             }
             Message::RollForward(a, b) => {
-                self.0 = State::Idle;
+                self.on_roll_reply();
                 Ok(NextResponse::RollForward(a, b))
             }
             Message::RollBackward(a, b) => {
-                self.0 = State::Idle;
+                self.on_roll_reply();
                 Ok(NextResponse::RollBackward(a, b))
             }
             _ => Err(ClientError::InvalidInbound),
@@ -255,11 +300,11 @@ where
     pub async fn recv_while_must_reply(&mut self) -> Result<NextResponse<O>, ClientError> {
         match self.recv_message().await? {
             Message::RollForward(a, b) => {
-                self.0 = State::Idle;
+                self.on_roll_reply();
                 Ok(NextResponse::RollForward(a, b))
             }
             Message::RollBackward(a, b) => {
-                self.0 = State::Idle;
+                self.on_roll_reply();
                 Ok(NextResponse::RollBackward(a, b))
             }
             _ => Err(ClientError::InvalidInbound),
@@ -280,6 +325,31 @@ where
         self.recv_while_can_await().await
     }
 
+    /// Keeps up to `depth` RequestNext messages in flight and receives the
+    /// reply to the oldest one.
+    ///
+    /// Node clients pipeline while they are far behind the tip of the server
+    /// and stop once they catch up; once `Await` shows up it's advisable to
+    /// call this with a depth of 1 until the pipeline drains.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message cannot be sent, or if the inbound
+    /// message is invalid
+    pub async fn request_next_pipelined(
+        &mut self,
+        depth: usize,
+    ) -> Result<NextResponse<O>, ClientError> {
+        while self.3 < depth.max(1) {
+            self.send_request_next_pipelined().await?;
+        }
+
+        match self.0 {
+            State::MustReply => self.recv_while_must_reply().await,
+            _ => self.recv_while_can_await().await,
+        }
+    }
+
     /// Either requests the next block, or waits for one to become available.
     ///
     /// # Errors
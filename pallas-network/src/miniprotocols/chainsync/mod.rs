@@ -1,10 +1,12 @@
 mod buffer;
 mod client;
 mod codec;
+mod points;
 mod protocol;
 mod server;
 
 pub use buffer::*;
 pub use client::*;
+pub use points::*;
 pub use protocol::*;
 pub use server::*;
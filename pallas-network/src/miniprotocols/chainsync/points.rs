@@ -0,0 +1,215 @@
+//! Versioned binary format to persist a list of chain points
+//!
+//! Meant for cursor files holding the breadcrumbs that are later handed to
+//! `find_intersect`, so that different tools can share them. The layout is,
+//! with all integers in network byte order:
+//!
+//! ```text
+//! magic "PPTS" | version: u8 | count: u32 | point* | crc32: u32
+//! point := 0x00                                  (origin)
+//!        | 0x01 | slot: u64 | len: u8 | hash     (specific)
+//! ```
+//!
+//! The CRC (ISO-HDLC, same as Byron addresses) covers every byte before it.
+
+use std::path::Path;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use pallas_codec::fs::atomic_write;
+use thiserror::Error;
+
+use crate::miniprotocols::Point;
+
+pub const POINTS_MAGIC: &[u8; 4] = b"PPTS";
+
+pub const POINTS_FORMAT_VERSION: u8 = 1;
+
+const ORIGIN_TAG: u8 = 0;
+const SPECIFIC_TAG: u8 = 1;
+const HEADER_LEN: usize = 9;
+const CRC_LEN: usize = 4;
+
+const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+#[derive(Debug, Error)]
+pub enum PointsFileError {
+    #[error("not a points file")]
+    BadMagic,
+
+    #[error("unsupported points file version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("points file is truncated")]
+    Truncated,
+
+    #[error("points file checksum mismatch")]
+    ChecksumMismatch,
+
+    #[error("invalid point tag {0}")]
+    InvalidTag(u8),
+
+    #[error("point hash longer than 255 bytes")]
+    HashTooLong,
+
+    #[error("points file I/O error")]
+    Io(#[from] std::io::Error),
+}
+
+pub fn encode_points(points: &[Point]) -> Result<Vec<u8>, PointsFileError> {
+    let mut out = Vec::with_capacity(HEADER_LEN + points.len() * 42 + CRC_LEN);
+
+    out.extend_from_slice(POINTS_MAGIC);
+    out.push(POINTS_FORMAT_VERSION);
+    out.extend_from_slice(&(points.len() as u32).to_be_bytes());
+
+    for point in points {
+        match point {
+            Point::Origin => out.push(ORIGIN_TAG),
+            Point::Specific(slot, hash) => {
+                let len = u8::try_from(hash.len()).map_err(|_| PointsFileError::HashTooLong)?;
+
+                out.push(SPECIFIC_TAG);
+                out.extend_from_slice(&slot.to_be_bytes());
+                out.push(len);
+                out.extend_from_slice(hash);
+            }
+        }
+    }
+
+    let crc = CRC.checksum(&out);
+    out.extend_from_slice(&crc.to_be_bytes());
+
+    Ok(out)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], PointsFileError> {
+    if bytes.len() < len {
+        return Err(PointsFileError::Truncated);
+    }
+
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+
+    Ok(head)
+}
+
+pub fn decode_points(bytes: &[u8]) -> Result<Vec<Point>, PointsFileError> {
+    if bytes.len() < HEADER_LEN + CRC_LEN {
+        return Err(PointsFileError::Truncated);
+    }
+
+    if &bytes[..4] != POINTS_MAGIC {
+        return Err(PointsFileError::BadMagic);
+    }
+
+    if bytes[4] != POINTS_FORMAT_VERSION {
+        return Err(PointsFileError::UnsupportedVersion(bytes[4]));
+    }
+
+    let (body, crc) = bytes.split_at(bytes.len() - CRC_LEN);
+
+    if CRC.checksum(body) != NetworkEndian::read_u32(crc) {
+        return Err(PointsFileError::ChecksumMismatch);
+    }
+
+    let count = NetworkEndian::read_u32(&body[5..HEADER_LEN]) as usize;
+    let mut rest = &body[HEADER_LEN..];
+    let mut points = Vec::with_capacity(count.min(rest.len()));
+
+    for _ in 0..count {
+        match take(&mut rest, 1)?[0] {
+            ORIGIN_TAG => points.push(Point::Origin),
+            SPECIFIC_TAG => {
+                let slot = NetworkEndian::read_u64(take(&mut rest, 8)?);
+                let len = take(&mut rest, 1)?[0] as usize;
+                let hash = take(&mut rest, len)?.to_vec();
+                points.push(Point::Specific(slot, hash));
+            }
+            other => return Err(PointsFileError::InvalidTag(other)),
+        }
+    }
+
+    if !rest.is_empty() {
+        return Err(PointsFileError::Truncated);
+    }
+
+    Ok(points)
+}
+
+/// Writes the points through [`atomic_write`]
+pub fn save_points(path: impl AsRef<Path>, points: &[Point]) -> Result<(), PointsFileError> {
+    atomic_write(path, &encode_points(points)?)?;
+
+    Ok(())
+}
+
+pub fn load_points(path: impl AsRef<Path>) -> Result<Vec<Point>, PointsFileError> {
+    let bytes = std::fs::read(path)?;
+    decode_points(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Point> {
+        vec![
+            Point::Specific(
+                77110778,
+                hex::decode("18e6eeaa592c42113280ba47a0829355e6bac1b4c3ca8eb8b35e2bb0b6a3ac3f")
+                    .unwrap(),
+            ),
+            Point::Specific(1, vec![]),
+            Point::Origin,
+        ]
+    }
+
+    #[test]
+    fn points_roundtrip() {
+        let bytes = encode_points(&sample()).unwrap();
+        assert_eq!(&bytes[..4], POINTS_MAGIC);
+        assert_eq!(decode_points(&bytes).unwrap(), sample());
+
+        let empty = encode_points(&[]).unwrap();
+        assert_eq!(decode_points(&empty).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn corrupted_files_are_rejected() {
+        let bytes = encode_points(&sample()).unwrap();
+
+        let mut flipped = bytes.clone();
+        flipped[12] ^= 0xff;
+        assert!(matches!(
+            decode_points(&flipped),
+            Err(PointsFileError::ChecksumMismatch)
+        ));
+
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert!(matches!(
+            decode_points(&version),
+            Err(PointsFileError::UnsupportedVersion(9))
+        ));
+
+        assert!(matches!(
+            decode_points(&bytes[..6]),
+            Err(PointsFileError::Truncated)
+        ));
+
+        assert!(matches!(
+            encode_points(&[Point::Specific(0, vec![0; 300])]),
+            Err(PointsFileError::HashTooLong)
+        ));
+    }
+
+    #[test]
+    fn points_file_save_and_load() {
+        let path = std::env::temp_dir().join(format!("pallas-points-{}", std::process::id()));
+
+        save_points(&path, &sample()).unwrap();
+        assert_eq!(load_points(&path).unwrap(), sample());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
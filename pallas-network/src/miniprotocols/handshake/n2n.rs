@@ -165,6 +165,22 @@ impl VersionData {
             query,
         }
     }
+
+    pub fn network_magic(&self) -> u64 {
+        self.network_magic
+    }
+
+    pub fn initiator_only_diffusion_mode(&self) -> bool {
+        self.initiator_only_diffusion_mode
+    }
+
+    pub fn peer_sharing(&self) -> Option<u8> {
+        self.peer_sharing
+    }
+
+    pub fn query(&self) -> Option<bool> {
+        self.query
+    }
 }
 
 impl Encode<()> for VersionData {
@@ -1,4 +1,5 @@
 use super::*;
+use pallas_codec::context::CodecContext;
 use pallas_codec::minicbor::{data::Tag, decode, encode, Decode, Decoder, Encode, Encoder};
 
 impl Encode<()> for BlockQuery {
@@ -71,10 +72,10 @@ impl Encode<()> for BlockQuery {
                 e.array(1)?;
                 e.u16(14)?;
             }
-            BlockQuery::GetUTxOByTxIn(_) => {
+            BlockQuery::GetUTxOByTxIn(x) => {
                 e.array(2)?;
                 e.u16(15)?;
-                e.encode(2)?;
+                e.encode(x)?;
             }
             BlockQuery::GetStakePools => {
                 e.array(1)?;
@@ -144,7 +145,7 @@ impl<'b> Decode<'b, ()> for BlockQuery {
             // 12 => Ok(Self::DebugNewEpochState),
             13 => Ok(Self::DebugChainDepState),
             14 => Ok(Self::GetRewardProvenance),
-            // 15 => Ok(Self::GetUTxOByTxIn(())),
+            15 => Ok(Self::GetUTxOByTxIn(d.decode()?)),
             16 => Ok(Self::GetStakePools),
             // 17 => Ok(Self::GetStakePoolParams(())),
             18 => Ok(Self::GetRewardInfoPools),
@@ -271,7 +272,7 @@ impl<'b> Decode<'b, ()> for Request {
     }
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for Value {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for Value {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
             minicbor::data::Type::U8 => Ok(Value::Coin(d.decode_with(ctx)?)),
@@ -339,7 +340,7 @@ impl<C> minicbor::encode::Encode<C> for RationalNumber {
     }
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for TransactionOutput {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for TransactionOutput {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
             minicbor::data::Type::Map => Ok(TransactionOutput::Current(d.decode_with(ctx)?)),
@@ -351,7 +352,7 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for TransactionOutput {
     }
 }
 
-impl<C> minicbor::encode::Encode<C> for TransactionOutput {
+impl<C: CodecContext> minicbor::encode::Encode<C> for TransactionOutput {
     fn encode<W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
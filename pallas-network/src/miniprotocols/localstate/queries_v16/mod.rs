@@ -193,6 +193,7 @@ pub struct ProtocolParam {
 }
 
 #[derive(Debug, Encode, Decode, PartialEq)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct StakeDistribution {
     #[n(0)]
     pub pools: KeyValuePairs<Bytes, Pool>,
@@ -231,11 +232,15 @@ pub type AssetName = Bytes;
 pub type Multiasset<A> = KeyValuePairs<PolicyId, KeyValuePairs<AssetName, A>>;
 
 #[derive(Debug, Encode, Decode, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct UTxOByAddress {
     #[n(0)]
     pub utxo: KeyValuePairs<UTxO, TransactionOutput>,
 }
 
+/// Same shape as [`UTxOByAddress`], returned by `GetUTxOByTxIn`
+pub type UTxOByTxIn = UTxOByAddress;
+
 // Bytes CDDL ->  #6.121([ * #6.121([ *datum ]) ])
 pub type Datum = (Era, TagWrap<Bytes, 24>);
 
@@ -246,6 +251,7 @@ pub enum TransactionOutput {
 }
 
 #[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct PostAlonsoTransactionOutput {
     #[n(0)]
@@ -262,6 +268,7 @@ pub struct PostAlonsoTransactionOutput {
 }
 
 #[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct LegacyTransactionOutput {
     #[n(0)]
     pub address: Bytes,
@@ -283,12 +290,14 @@ pub struct UTxO {
 }
 
 #[derive(Debug, Encode, Decode, PartialEq)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct StakeSnapshot {
     #[n(0)]
     pub snapshots: Snapshots,
 }
 
 #[derive(Debug, Encode, Decode, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Snapshots {
     #[n(0)]
     pub stake_snapshots: KeyValuePairs<Bytes, Stakes>,
@@ -435,6 +444,20 @@ pub async fn get_utxo_by_address(
     Ok(result)
 }
 
+/// Get the unspent outputs at the given inputs, leaving out the spent ones.
+pub async fn get_utxo_by_txin(
+    client: &mut Client,
+    era: u16,
+    txins: Vec<UTxO>,
+) -> Result<UTxOByTxIn, ClientError> {
+    let query = BlockQuery::GetUTxOByTxIn(AnyCbor::from_encode(txins));
+    let query = LedgerQuery::BlockQuery(era, query);
+    let query = Request::LedgerQuery(query);
+    let result = client.query(query).await?;
+
+    Ok(result)
+}
+
 /// Get stake snapshots for the given era and stake pools.
 /// If `pools` are empty, all pools are queried.
 /// Otherwise, only the specified pool is queried.
@@ -6,13 +6,23 @@ use byteorder::{ByteOrder, NetworkEndian};
 use pallas_codec::{minicbor, Fragment};
 use thiserror::Error;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::task::JoinHandle;
-use tokio::time::Instant;
-use tokio::{select, sync::mpsc::error::SendError};
+use tokio::{
+    select,
+    sync::{mpsc::error::SendError, oneshot},
+};
 use tracing::{debug, error, trace, warn};
 
+use crate::task::{self, JoinHandle};
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
 type IOResult<T> = tokio::io::Result<T>;
 
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::net as tcp;
 
 #[cfg(unix)]
@@ -21,8 +31,7 @@ use tokio::net as unix;
 #[cfg(windows)]
 use tokio::net::windows::named_pipe::NamedPipeClient;
 
-#[cfg(windows)]
-use tokio::io::{ReadHalf, WriteHalf};
+use tokio::io::{DuplexStream, ReadHalf, WriteHalf};
 
 const HEADER_LEN: usize = 8;
 
@@ -70,16 +79,30 @@ pub struct Segment {
 }
 
 pub enum Bearer {
+    #[cfg(not(target_arch = "wasm32"))]
     Tcp(tcp::TcpStream),
 
+    /// In-memory pipe, see [`Bearer::memory_pair`]
+    Memory(DuplexStream),
+
     #[cfg(unix)]
     Unix(unix::UnixStream),
 
     #[cfg(windows)]
     NamedPipe(NamedPipeClient),
+
+    /// WebSocket connection, bridged to the plexer through an in-memory pipe
+    /// by a background task, see [`Bearer::connect_websocket`]
+    #[cfg(feature = "websocket")]
+    WebSocket(DuplexStream),
+
+    /// TCP connection wrapped in TLS, see [`Bearer::connect_tls`]
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    Tls(Box<tls::Stream>),
 }
 
 impl Bearer {
+    #[cfg(not(target_arch = "wasm32"))]
     fn configure_tcp(stream: &tcp::TcpStream) -> IOResult<()> {
         let sock_ref = socket2::SockRef::from(&stream);
         let mut tcp_keepalive = socket2::TcpKeepalive::new();
@@ -92,12 +115,14 @@ impl Bearer {
         Ok(())
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn connect_tcp(addr: impl tcp::ToSocketAddrs) -> Result<Self, tokio::io::Error> {
         let stream = tcp::TcpStream::connect(addr).await?;
         Self::configure_tcp(&stream)?;
         Ok(Self::Tcp(stream))
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn connect_tcp_timeout(
         addr: impl tcp::ToSocketAddrs,
         timeout: std::time::Duration,
@@ -108,6 +133,19 @@ impl Bearer {
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Dials `host:port` through a SOCKS5 or HTTP CONNECT proxy
+    pub async fn connect_proxy(
+        proxy: &crate::proxy::Proxy,
+        host: &str,
+        port: u16,
+    ) -> IOResult<Self> {
+        let stream = proxy.connect(host, port).await?;
+        Self::configure_tcp(&stream)?;
+        Ok(Self::Tcp(stream))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn accept_tcp(listener: &tcp::TcpListener) -> IOResult<(Self, std::net::SocketAddr)> {
         let (stream, addr) = listener.accept().await?;
         Self::configure_tcp(&stream)?;
@@ -128,19 +166,104 @@ impl Bearer {
         Ok((Self::Unix(stream), addr))
     }
 
+    /// Two bearers connected to each other in memory, useful to run both ends
+    /// of the protocols in tests and simulations
+    pub fn memory_pair(max_buf_size: usize) -> (Self, Self) {
+        let (a, b) = tokio::io::duplex(max_buf_size);
+        (Self::Memory(a), Self::Memory(b))
+    }
+
     #[cfg(windows)]
     pub fn connect_named_pipe(pipe_name: impl AsRef<std::ffi::OsStr>) -> IOResult<Self> {
         let client = tokio::net::windows::named_pipe::ClientOptions::new().open(&pipe_name)?;
         Ok(Self::NamedPipe(client))
     }
 
+    /// Connects to a websocket endpoint (`ws://` or `wss://`) that forwards
+    /// binary frames to a node socket, such as a websocket-to-TCP bridge
+    #[cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+    pub async fn connect_websocket(url: &str) -> IOResult<Self> {
+        let (stream, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(websocket::to_io_error)?;
+
+        Ok(Self::WebSocket(websocket::bridge_tungstenite(stream)))
+    }
+
+    /// Connects to a websocket endpoint (`ws://` or `wss://`) that forwards
+    /// binary frames to a node socket, using the websocket API of the browser
+    #[cfg(all(feature = "websocket", target_arch = "wasm32"))]
+    pub async fn connect_websocket(url: &str) -> IOResult<Self> {
+        let stream = gloo_net::websocket::futures::WebSocket::open(url)
+            .map_err(|err| tokio::io::Error::other(err.to_string()))?;
+
+        Ok(Self::WebSocket(websocket::bridge_browser(stream)))
+    }
+
+    #[cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+    pub async fn accept_websocket(
+        listener: &tcp::TcpListener,
+    ) -> IOResult<(Self, std::net::SocketAddr)> {
+        let (stream, addr) = listener.accept().await?;
+        Self::configure_tcp(&stream)?;
+
+        let stream = tokio_tungstenite::accept_async(stream)
+            .await
+            .map_err(websocket::to_io_error)?;
+
+        Ok((Self::WebSocket(websocket::bridge_tungstenite(stream)), addr))
+    }
+
+    /// Connects to a node socket exposed behind a TLS-terminating proxy
+    ///
+    /// The `server_name` is the DNS name (or IP) used to verify the
+    /// certificate of the proxy. Use [`tls::default_client_config`] to verify
+    /// it against the Mozilla root certificates.
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    pub async fn connect_tls(
+        addr: impl tcp::ToSocketAddrs,
+        server_name: &str,
+        config: std::sync::Arc<tls::ClientConfig>,
+    ) -> IOResult<Self> {
+        let server_name = tls::ServerName::try_from(server_name.to_owned())
+            .map_err(|err| tokio::io::Error::new(tokio::io::ErrorKind::InvalidInput, err))?;
+
+        let stream = tcp::TcpStream::connect(addr).await?;
+        Self::configure_tcp(&stream)?;
+
+        let stream = tokio_rustls::TlsConnector::from(config)
+            .connect(server_name, stream)
+            .await?;
+
+        Ok(Self::Tls(Box::new(stream.into())))
+    }
+
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    pub async fn accept_tls(
+        listener: &tcp::TcpListener,
+        acceptor: &tokio_rustls::TlsAcceptor,
+    ) -> IOResult<(Self, std::net::SocketAddr)> {
+        let (stream, addr) = listener.accept().await?;
+        Self::configure_tcp(&stream)?;
+
+        let stream = acceptor.accept(stream).await?;
+
+        Ok((Self::Tls(Box::new(stream.into())), addr))
+    }
+
     pub fn into_split(self) -> (BearerReadHalf, BearerWriteHalf) {
         match self {
+            #[cfg(not(target_arch = "wasm32"))]
             Bearer::Tcp(x) => {
                 let (r, w) = x.into_split();
                 (BearerReadHalf::Tcp(r), BearerWriteHalf::Tcp(w))
             }
 
+            Bearer::Memory(x) => {
+                let (r, w) = tokio::io::split(x);
+                (BearerReadHalf::Memory(r), BearerWriteHalf::Memory(w))
+            }
+
             #[cfg(unix)]
             Bearer::Unix(x) => {
                 let (r, w) = x.into_split();
@@ -155,66 +278,270 @@ impl Bearer {
 
                 (reader, writer)
             }
+
+            #[cfg(feature = "websocket")]
+            Bearer::WebSocket(x) => {
+                let (r, w) = tokio::io::split(x);
+                (BearerReadHalf::WebSocket(r), BearerWriteHalf::WebSocket(w))
+            }
+
+            #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+            Bearer::Tls(x) => {
+                let (r, w) = tokio::io::split(*x);
+                (BearerReadHalf::Tls(r), BearerWriteHalf::Tls(w))
+            }
         }
     }
 }
 
+/// Helpers to set up the TLS bearer
+#[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+pub mod tls {
+    use std::sync::Arc;
+
+    pub use tokio_rustls::rustls::{pki_types::ServerName, ClientConfig, RootCertStore};
+
+    use tokio_rustls::rustls::crypto::ring;
+
+    pub type Stream = tokio_rustls::TlsStream<tokio::net::TcpStream>;
+
+    /// Client config with the given trusted roots and no client auth, using
+    /// the `ring` crypto provider regardless of the process default
+    pub fn client_config(roots: RootCertStore) -> Arc<ClientConfig> {
+        let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("ring provider supports the default protocol versions")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        Arc::new(config)
+    }
+
+    /// Client config trusting the Mozilla root certificates
+    pub fn default_client_config() -> Arc<ClientConfig> {
+        let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        client_config(roots)
+    }
+}
+
+#[cfg(feature = "websocket")]
+mod websocket {
+    use std::future::ready;
+
+    use futures_util::{Sink, SinkExt, Stream, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    use super::{IOResult, MAX_SEGMENT_PAYLOAD_LENGTH};
+    use crate::task::{self, MaybeSend};
+
+    const PIPE_BUFFER_SIZE: usize = 1024 * 1024;
+
+    pub fn to_io_error<E>(err: E) -> tokio::io::Error
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        tokio::io::Error::other(err)
+    }
+
+    /// Bridges a websocket of the native `tokio-tungstenite` client or server
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn bridge_tungstenite<S>(stream: tokio_tungstenite::WebSocketStream<S>) -> DuplexStream
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    {
+        use tokio_tungstenite::tungstenite::Message;
+
+        let (ws_tx, ws_rx) = stream.split();
+
+        let ws_rx = ws_rx
+            .take_while(|msg| ready(!matches!(msg, Ok(Message::Close(_)))))
+            .map(|msg| match msg.map_err(to_io_error)? {
+                Message::Binary(data) => Ok(Some(data)),
+                _ => Ok(None),
+            });
+
+        let ws_tx = ws_tx
+            .sink_map_err(to_io_error)
+            .with(|data| ready(Ok(Message::Binary(data))));
+
+        bridge(ws_rx, ws_tx)
+    }
+
+    /// Bridges a websocket of the browser
+    #[cfg(target_arch = "wasm32")]
+    pub fn bridge_browser(stream: gloo_net::websocket::futures::WebSocket) -> DuplexStream {
+        use gloo_net::websocket::{Message, WebSocketError};
+
+        let (ws_tx, ws_rx) = stream.split();
+
+        let ws_rx = ws_rx
+            .take_while(|msg| ready(!matches!(msg, Err(WebSocketError::ConnectionClose(_)))))
+            .map(|msg| match msg.map_err(to_io_error)? {
+                Message::Bytes(data) => Ok(Some(data)),
+                _ => Ok(None),
+            });
+
+        let ws_tx = ws_tx
+            .sink_map_err(to_io_error)
+            .with(|data| ready(Ok(Message::Bytes(data))));
+
+        bridge(ws_rx, ws_tx)
+    }
+
+    /// Spawns a task that pumps bytes between the websocket and one end of an
+    /// in-memory pipe, returning the other end. Each write to the pipe is sent
+    /// as a binary frame, other incoming frames (`None`) are ignored; the task
+    /// ends when either side closes.
+    fn bridge<Rx, Tx>(mut ws_rx: Rx, mut ws_tx: Tx) -> DuplexStream
+    where
+        Rx: Stream<Item = IOResult<Option<Vec<u8>>>> + Unpin + MaybeSend + 'static,
+        Tx: Sink<Vec<u8>, Error = tokio::io::Error> + Unpin + MaybeSend + 'static,
+    {
+        let (local, remote) = tokio::io::duplex(PIPE_BUFFER_SIZE);
+        let (mut pipe_rx, mut pipe_tx) = tokio::io::split(remote);
+
+        let inbound = async move {
+            while let Some(data) = ws_rx.next().await {
+                if let Some(data) = data? {
+                    pipe_tx.write_all(&data).await?;
+                }
+            }
+
+            pipe_tx.shutdown().await
+        };
+
+        let outbound = async move {
+            let mut buf = vec![0u8; MAX_SEGMENT_PAYLOAD_LENGTH];
+
+            loop {
+                let read = pipe_rx.read(&mut buf).await?;
+
+                if read == 0 {
+                    break;
+                }
+
+                ws_tx.send(buf[..read].to_vec()).await?;
+            }
+
+            ws_tx.close().await
+        };
+
+        task::spawn(async move {
+            let result: IOResult<()> = tokio::select! {
+                x = inbound => x,
+                x = outbound => x,
+            };
+
+            if let Err(err) = result {
+                tracing::warn!(?err, "websocket bridge closed");
+            }
+        });
+
+        local
+    }
+}
+
 pub enum BearerReadHalf {
+    #[cfg(not(target_arch = "wasm32"))]
     Tcp(tcp::tcp::OwnedReadHalf),
 
+    Memory(ReadHalf<DuplexStream>),
+
     #[cfg(unix)]
     Unix(unix::unix::OwnedReadHalf),
 
     #[cfg(windows)]
     NamedPipe(ReadHalf<NamedPipeClient>),
+
+    #[cfg(feature = "websocket")]
+    WebSocket(ReadHalf<DuplexStream>),
+
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    Tls(ReadHalf<tls::Stream>),
 }
 
 impl BearerReadHalf {
     async fn read_exact(&mut self, buf: &mut [u8]) -> IOResult<usize> {
         match self {
+            #[cfg(not(target_arch = "wasm32"))]
             BearerReadHalf::Tcp(x) => x.read_exact(buf).await,
 
+            BearerReadHalf::Memory(x) => x.read_exact(buf).await,
+
             #[cfg(unix)]
             BearerReadHalf::Unix(x) => x.read_exact(buf).await,
 
             #[cfg(windows)]
             BearerReadHalf::NamedPipe(x) => x.read_exact(buf).await,
+
+            #[cfg(feature = "websocket")]
+            BearerReadHalf::WebSocket(x) => x.read_exact(buf).await,
+
+            #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+            BearerReadHalf::Tls(x) => x.read_exact(buf).await,
         }
     }
 }
 
 pub enum BearerWriteHalf {
+    #[cfg(not(target_arch = "wasm32"))]
     Tcp(tcp::tcp::OwnedWriteHalf),
 
+    Memory(WriteHalf<DuplexStream>),
+
     #[cfg(unix)]
     Unix(unix::unix::OwnedWriteHalf),
 
     #[cfg(windows)]
     NamedPipe(WriteHalf<NamedPipeClient>),
+
+    #[cfg(feature = "websocket")]
+    WebSocket(WriteHalf<DuplexStream>),
+
+    #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+    Tls(WriteHalf<tls::Stream>),
 }
 
 impl BearerWriteHalf {
     async fn write_all(&mut self, buf: &[u8]) -> IOResult<()> {
         match self {
+            #[cfg(not(target_arch = "wasm32"))]
             Self::Tcp(x) => x.write_all(buf).await,
 
+            Self::Memory(x) => x.write_all(buf).await,
+
             #[cfg(unix)]
             Self::Unix(x) => x.write_all(buf).await,
 
             #[cfg(windows)]
             Self::NamedPipe(x) => x.write_all(buf).await,
+
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(x) => x.write_all(buf).await,
+
+            #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+            Self::Tls(x) => x.write_all(buf).await,
         }
     }
 
     async fn flush(&mut self) -> IOResult<()> {
         match self {
+            #[cfg(not(target_arch = "wasm32"))]
             Self::Tcp(x) => x.flush().await,
 
+            Self::Memory(x) => x.flush().await,
+
             #[cfg(unix)]
             Self::Unix(x) => x.flush().await,
 
             #[cfg(windows)]
             Self::NamedPipe(x) => x.flush().await,
+
+            #[cfg(feature = "websocket")]
+            Self::WebSocket(x) => x.flush().await,
+
+            #[cfg(all(feature = "tls", not(target_arch = "wasm32")))]
+            Self::Tls(x) => x.flush().await,
         }
     }
 }
@@ -388,6 +715,28 @@ impl Muxer {
             }
         }
     }
+
+    /// Writes the messages already queued by the agents, without waiting for
+    /// new ones
+    pub async fn flush_queued(&mut self) -> Result<(), Error> {
+        while let Ok(msg) = self.2 .1.try_recv() {
+            self.mux(msg).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Like [`Muxer::run`], but returns once `closing` fires and every queued
+    /// message has been written to the bearer
+    async fn run_until_closed(&mut self, mut closing: oneshot::Receiver<()>) -> Result<(), Error> {
+        loop {
+            select! {
+                biased;
+                Some(msg) = self.2 .1.recv() => self.mux(msg).await?,
+                Ok(()) = &mut closing => break self.flush_queued().await,
+            }
+        }
+    }
 }
 
 type ToPlexerPort = tokio::sync::mpsc::Sender<(Protocol, Payload)>;
@@ -439,6 +788,7 @@ impl AgentChannel {
 pub struct RunningPlexer {
     demuxer: JoinHandle<Result<(), Error>>,
     muxer: JoinHandle<Result<(), Error>>,
+    close: Option<oneshot::Sender<()>>,
 }
 
 impl RunningPlexer {
@@ -446,11 +796,34 @@ impl RunningPlexer {
         self.demuxer.abort();
         self.muxer.abort();
     }
+
+    /// Writes out the messages already queued by the agents before dropping
+    /// the bearer, so the last responses reach the other end. Only plexers
+    /// spawned with [`Plexer::with_graceful_close`] do so, others are aborted.
+    pub async fn close(self) {
+        let close = match self.close {
+            Some(x) => x,
+            None => {
+                self.muxer.abort();
+                self.demuxer.abort();
+                return;
+            }
+        };
+
+        if close.send(()).is_ok() {
+            if let Ok(Err(err)) = self.muxer.await {
+                debug!(?err, "muxer failed while closing");
+            }
+        }
+
+        self.demuxer.abort();
+    }
 }
 
 pub struct Plexer {
     demuxer: Demuxer,
     muxer: Muxer,
+    graceful_close: bool,
 }
 
 impl Plexer {
@@ -460,9 +833,17 @@ impl Plexer {
         Self {
             demuxer: Demuxer::new(r),
             muxer: Muxer::new(w),
+            graceful_close: false,
         }
     }
 
+    /// Lets [`RunningPlexer::close`] write out the queued messages before
+    /// disconnecting, instead of aborting like [`RunningPlexer::abort`]
+    pub fn with_graceful_close(mut self) -> Self {
+        self.graceful_close = true;
+        self
+    }
+
     pub fn subscribe_client(&mut self, protocol: Protocol) -> AgentChannel {
         let to_plexer = self.muxer.clone_sender();
         let from_plexer = self.demuxer.subscribe(protocol ^ 0x8000);
@@ -479,10 +860,21 @@ impl Plexer {
         let mut demuxer = self.demuxer;
         let mut muxer = self.muxer;
 
-        let demuxer = tokio::spawn(async move { demuxer.run().await });
-        let muxer = tokio::spawn(async move { muxer.run().await });
+        let demuxer = task::spawn(async move { demuxer.run().await });
 
-        RunningPlexer { demuxer, muxer }
+        let (muxer, close) = if self.graceful_close {
+            let (close, closing) = oneshot::channel();
+            let muxer = task::spawn(async move { muxer.run_until_closed(closing).await });
+            (muxer, Some(close))
+        } else {
+            (task::spawn(async move { muxer.run().await }), None)
+        };
+
+        RunningPlexer {
+            demuxer,
+            muxer,
+            close,
+        }
     }
 }
 
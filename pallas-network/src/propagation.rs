@@ -0,0 +1,264 @@
+//! Instrumentation of block propagation: when each peer announced the header
+//! of a block and when its body was fetched, along with percentile summaries
+//! of the delays, in the spirit of pooltool propagation monitoring.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, SystemTime},
+};
+
+use crate::miniprotocols::Point;
+
+/// Timestamps of a block as observed through a single peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Observation {
+    /// When the header was received through chain-sync
+    pub header_seen: Option<SystemTime>,
+    /// When the body was received through block-fetch
+    pub body_fetched: Option<SystemTime>,
+}
+
+impl Observation {
+    /// Time between the header being seen and the body being fetched
+    pub fn fetch_duration(&self) -> Option<Duration> {
+        self.body_fetched?.duration_since(self.header_seen?).ok()
+    }
+}
+
+/// Summary of a set of delays, computed with the nearest-rank method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub count: usize,
+    pub min: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// Summarizes the given delays, `None` if there are none
+    pub fn from_durations(mut values: Vec<Duration>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        values.sort();
+
+        Some(Self {
+            count: values.len(),
+            min: values[0],
+            p50: percentile(&values, 50),
+            p90: percentile(&values, 90),
+            p95: percentile(&values, 95),
+            p99: percentile(&values, 99),
+            max: values[values.len() - 1],
+        })
+    }
+}
+
+/// Value at the given percentile of a sorted, non-empty slice
+pub fn percentile(sorted: &[Duration], pct: u8) -> Duration {
+    let rank = (sorted.len() * pct.min(100) as usize).div_ceil(100);
+    sorted[rank.saturating_sub(1)]
+}
+
+/// Observations of blocks by point, across the peers they were seen through.
+/// `P` identifies a peer, e.g. its socket address.
+#[derive(Debug, Clone)]
+pub struct PropagationLog<P> {
+    points: HashMap<Point, HashMap<P, Observation>>,
+}
+
+impl<P> Default for PropagationLog<P> {
+    fn default() -> Self {
+        Self {
+            points: HashMap::new(),
+        }
+    }
+}
+
+impl<P> PropagationLog<P>
+where
+    P: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entry(&mut self, point: Point, peer: P) -> &mut Observation {
+        self.points
+            .entry(point)
+            .or_default()
+            .entry(peer)
+            .or_default()
+    }
+
+    /// Records the header of `point` being received from `peer` at `at`. Only
+    /// the earliest timestamp per peer is kept.
+    pub fn record_header_at(&mut self, point: Point, peer: P, at: SystemTime) {
+        let entry = self.entry(point, peer);
+        entry.header_seen = Some(entry.header_seen.map_or(at, |x| x.min(at)));
+    }
+
+    pub fn record_header(&mut self, point: Point, peer: P) {
+        self.record_header_at(point, peer, SystemTime::now());
+    }
+
+    /// Records the body of `point` being fetched from `peer` at `at`. Only the
+    /// earliest timestamp per peer is kept.
+    pub fn record_body_at(&mut self, point: Point, peer: P, at: SystemTime) {
+        let entry = self.entry(point, peer);
+        entry.body_fetched = Some(entry.body_fetched.map_or(at, |x| x.min(at)));
+    }
+
+    pub fn record_body(&mut self, point: Point, peer: P) {
+        self.record_body_at(point, peer, SystemTime::now());
+    }
+
+    pub fn observations(&self, point: &Point) -> Option<&HashMap<P, Observation>> {
+        self.points.get(point)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &Point> {
+        self.points.keys()
+    }
+
+    /// Earliest time the header of `point` was seen through any peer
+    pub fn first_header_seen(&self, point: &Point) -> Option<SystemTime> {
+        self.points
+            .get(point)?
+            .values()
+            .filter_map(|x| x.header_seen)
+            .min()
+    }
+
+    /// Earliest time the body of `point` was fetched from any peer
+    pub fn first_body_fetched(&self, point: &Point) -> Option<SystemTime> {
+        self.points
+            .get(point)?
+            .values()
+            .filter_map(|x| x.body_fetched)
+            .min()
+    }
+
+    /// Delay of each peer announcing the header of `point`, relative to the
+    /// first peer that did
+    pub fn header_delays(&self, point: &Point) -> Vec<(P, Duration)> {
+        let Some(first) = self.first_header_seen(point) else {
+            return vec![];
+        };
+
+        self.points[point]
+            .iter()
+            .filter_map(|(peer, x)| {
+                let delay = x.header_seen?.duration_since(first).ok()?;
+                Some((peer.clone(), delay))
+            })
+            .collect()
+    }
+
+    /// Summary of how late peers announced the header of `point`
+    pub fn header_summary(&self, point: &Point) -> Option<Percentiles> {
+        let delays = self.header_delays(point);
+        Percentiles::from_durations(delays.into_iter().map(|(_, x)| x).collect())
+    }
+
+    /// Summary of the time it took to fetch the body of `point` after its
+    /// header was seen, across peers
+    pub fn fetch_summary(&self, point: &Point) -> Option<Percentiles> {
+        let durations = self
+            .points
+            .get(point)?
+            .values()
+            .filter_map(Observation::fetch_duration)
+            .collect();
+
+        Percentiles::from_durations(durations)
+    }
+
+    /// Summary of the delay between the slot of each block and the first time
+    /// its header was seen. `slot_time` maps a slot to its wall-clock start.
+    pub fn slot_delay_summary<F>(&self, slot_time: F) -> Option<Percentiles>
+    where
+        F: Fn(u64) -> SystemTime,
+    {
+        let delays = self
+            .points
+            .keys()
+            .filter_map(|point| {
+                let seen = self.first_header_seen(point)?;
+                seen.duration_since(slot_time(point.slot_or_default())).ok()
+            })
+            .collect();
+
+        Percentiles::from_durations(delays)
+    }
+
+    /// Forgets the blocks before `slot`, to bound the memory of long running
+    /// monitors
+    pub fn prune_before(&mut self, slot: u64) {
+        self.points
+            .retain(|point, _| point.slot_or_default() >= slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(x: u64) -> Duration {
+        Duration::from_millis(x)
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let values = (1..=100).rev().map(ms).collect();
+        let summary = Percentiles::from_durations(values).unwrap();
+
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.min, ms(1));
+        assert_eq!(summary.p50, ms(50));
+        assert_eq!(summary.p95, ms(95));
+        assert_eq!(summary.max, ms(100));
+
+        assert_eq!(percentile(&[ms(7)], 50), ms(7));
+        assert_eq!(Percentiles::from_durations(vec![]), None);
+    }
+
+    #[test]
+    fn delays_across_peers() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let point = Point::Specific(10, vec![1; 32]);
+
+        let mut log = PropagationLog::new();
+
+        log.record_header_at(point.clone(), "a", start + ms(300));
+        log.record_header_at(point.clone(), "b", start + ms(100));
+        log.record_header_at(point.clone(), "b", start + ms(900));
+        log.record_body_at(point.clone(), "b", start + ms(400));
+        log.record_header_at(point.clone(), "c", start + ms(600));
+
+        assert_eq!(log.first_header_seen(&point), Some(start + ms(100)));
+        assert_eq!(log.first_body_fetched(&point), Some(start + ms(400)));
+
+        let header = log.header_summary(&point).unwrap();
+        assert_eq!(header.count, 3);
+        assert_eq!(header.p50, ms(200));
+        assert_eq!(header.max, ms(500));
+
+        let fetch = log.fetch_summary(&point).unwrap();
+        assert_eq!(fetch.count, 1);
+        assert_eq!(fetch.max, ms(300));
+
+        let slots = log
+            .slot_delay_summary(|slot| start - Duration::from_secs(10 - slot))
+            .unwrap();
+        assert_eq!(slots.p50, ms(100));
+
+        log.prune_before(11);
+        assert_eq!(log.points().count(), 0);
+    }
+}
@@ -0,0 +1,389 @@
+//! Dialing peers through SOCKS5 or HTTP CONNECT proxies
+//!
+//! The target host is always resolved by the proxy, which allows reaching
+//! hostnames only known to it (such as Tor onion services).
+
+use std::net::IpAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+type IOResult<T> = tokio::io::Result<T>;
+
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_NO_AUTH: u8 = 0x00;
+const SOCKS_USER_PASS: u8 = 0x02;
+const SOCKS_NO_ACCEPTABLE: u8 = 0xff;
+const SOCKS_CONNECT: u8 = 0x01;
+const SOCKS_IPV4: u8 = 0x01;
+const SOCKS_DOMAIN: u8 = 0x03;
+const SOCKS_IPV6: u8 = 0x04;
+
+const MAX_HTTP_RESPONSE_LEN: usize = 8 * 1024;
+
+fn proxy_error(msg: impl Into<String>) -> tokio::io::Error {
+    tokio::io::Error::new(tokio::io::ErrorKind::ConnectionRefused, msg.into())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyKind {
+    Socks5,
+    HttpConnect,
+}
+
+/// A proxy to dial peers through, see [`crate::multiplexer::Bearer::connect_proxy`]
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    kind: ProxyKind,
+    addr: String,
+    auth: Option<(String, String)>,
+}
+
+impl Proxy {
+    /// SOCKS5 proxy at the given `host:port`, e.g. `127.0.0.1:9050` for Tor
+    pub fn socks5(addr: impl Into<String>) -> Self {
+        Self {
+            kind: ProxyKind::Socks5,
+            addr: addr.into(),
+            auth: None,
+        }
+    }
+
+    /// HTTP proxy at the given `host:port`, tunneling with `CONNECT`
+    pub fn http_connect(addr: impl Into<String>) -> Self {
+        Self {
+            kind: ProxyKind::HttpConnect,
+            addr: addr.into(),
+            auth: None,
+        }
+    }
+
+    /// Authenticate with username and password (SOCKS5 user/pass auth or
+    /// HTTP basic auth)
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    pub fn kind(&self) -> &ProxyKind {
+        &self.kind
+    }
+
+    /// Opens a TCP stream to the proxy and asks it to tunnel to the target
+    pub async fn connect(&self, host: &str, port: u16) -> IOResult<TcpStream> {
+        let mut stream = TcpStream::connect(self.addr.as_str()).await?;
+
+        match self.kind {
+            ProxyKind::Socks5 => socks5_handshake(&mut stream, host, port, &self.auth).await?,
+            ProxyKind::HttpConnect => http_handshake(&mut stream, host, port, &self.auth).await?,
+        }
+
+        Ok(stream)
+    }
+}
+
+async fn socks5_handshake(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: &Option<(String, String)>,
+) -> IOResult<()> {
+    let method = match auth {
+        Some(_) => SOCKS_USER_PASS,
+        None => SOCKS_NO_AUTH,
+    };
+
+    stream.write_all(&[SOCKS_VERSION, 1, method]).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+
+    if reply[0] != SOCKS_VERSION {
+        return Err(proxy_error("unexpected SOCKS version from proxy"));
+    }
+
+    match (reply[1], auth) {
+        (SOCKS_NO_AUTH, _) => (),
+        (SOCKS_USER_PASS, Some((user, pass))) => {
+            if user.len() > 255 || pass.len() > 255 {
+                return Err(proxy_error("SOCKS credentials longer than 255 bytes"));
+            }
+
+            let mut msg = vec![0x01, user.len() as u8];
+            msg.extend_from_slice(user.as_bytes());
+            msg.push(pass.len() as u8);
+            msg.extend_from_slice(pass.as_bytes());
+            stream.write_all(&msg).await?;
+
+            let mut reply = [0u8; 2];
+            stream.read_exact(&mut reply).await?;
+
+            if reply[1] != 0 {
+                return Err(proxy_error("SOCKS authentication failed"));
+            }
+        }
+        (SOCKS_NO_ACCEPTABLE, _) => {
+            return Err(proxy_error("no acceptable SOCKS auth method"));
+        }
+        (other, _) => {
+            return Err(proxy_error(format!(
+                "unsupported SOCKS auth method {other}"
+            )));
+        }
+    }
+
+    let mut msg = vec![SOCKS_VERSION, SOCKS_CONNECT, 0x00];
+
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            msg.push(SOCKS_IPV4);
+            msg.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            msg.push(SOCKS_IPV6);
+            msg.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(proxy_error("target hostname longer than 255 bytes"));
+            }
+
+            msg.push(SOCKS_DOMAIN);
+            msg.push(host.len() as u8);
+            msg.extend_from_slice(host.as_bytes());
+        }
+    }
+
+    msg.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&msg).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+
+    if reply[1] != 0x00 {
+        return Err(proxy_error(format!(
+            "SOCKS proxy refused connection, code {}",
+            reply[1]
+        )));
+    }
+
+    // skip the bound address, which is meaningless for the client
+    let addr_len = match reply[3] {
+        SOCKS_IPV4 => 4,
+        SOCKS_IPV6 => 16,
+        SOCKS_DOMAIN => stream.read_u8().await? as usize,
+        other => return Err(proxy_error(format!("unknown SOCKS address type {other}"))),
+    };
+
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+async fn http_handshake(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: &Option<(String, String)>,
+) -> IOResult<()> {
+    let target = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{ip}]:{port}"),
+        _ => format!("{host}:{port}"),
+    };
+
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+
+    if let Some((user, pass)) = auth {
+        let credentials = base64_encode(format!("{user}:{pass}").as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {credentials}\r\n"));
+    }
+
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // read byte by byte so that nothing past the headers is consumed
+    let mut response = Vec::new();
+
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > MAX_HTTP_RESPONSE_LEN {
+            return Err(proxy_error("HTTP proxy response too long"));
+        }
+
+        response.push(stream.read_u8().await?);
+    }
+
+    let status_line = response
+        .split(|b| *b == b'\n')
+        .next()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+
+    let status = status_line.split_whitespace().nth(1);
+
+    if status != Some("200") {
+        return Err(proxy_error(format!(
+            "HTTP proxy refused connection: {}",
+            status_line.trim()
+        )));
+    }
+
+    Ok(())
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn base64_matches_rfc_vectors() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    async fn echo(mut stream: TcpStream) {
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(&buf).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn socks5_tunnel_with_auth() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, SOCKS_USER_PASS]);
+            stream.write_all(&[5, SOCKS_USER_PASS]).await.unwrap();
+
+            let mut auth = [0u8; 11];
+            stream.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x04pass");
+            stream.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0u8; 5];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, [5, SOCKS_CONNECT, 0, SOCKS_DOMAIN, 11]);
+
+            let mut target = [0u8; 13];
+            stream.read_exact(&mut target).await.unwrap();
+            assert_eq!(&target[..11], b"relay.onion");
+            assert_eq!(&target[11..], &3001u16.to_be_bytes());
+
+            stream
+                .write_all(&[5, 0, 0, SOCKS_IPV4, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            echo(stream).await;
+        });
+
+        let mut stream = Proxy::socks5(addr.to_string())
+            .with_auth("user", "pass")
+            .connect("relay.onion", 3001)
+            .await
+            .unwrap();
+
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn http_connect_tunnel() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(stream.read_u8().await.unwrap());
+            }
+
+            let request = String::from_utf8(request).unwrap();
+            assert!(request.starts_with("CONNECT 10.0.0.1:3001 HTTP/1.1\r\n"));
+
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+
+            echo(stream).await;
+        });
+
+        let mut stream = Proxy::http_connect(addr.to_string())
+            .connect("10.0.0.1", 3001)
+            .await
+            .unwrap();
+
+        stream.write_all(b"pong").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn http_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 256];
+            let _ = stream.read(&mut buf).await.unwrap();
+
+            stream
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let err = Proxy::http_connect(addr.to_string())
+            .connect("relay.example", 3001)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), tokio::io::ErrorKind::ConnectionRefused);
+    }
+}
@@ -0,0 +1,526 @@
+//! Harness to integration-test applications against simulated peers. Peers
+//! serve chain-sync and block-fetch from an in-memory chain through in-memory
+//! bearers, so rollbacks, slow peers and disconnects can be reproduced
+//! deterministically, especially when combined with [`SimClock`].
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    select,
+    sync::{oneshot, watch, Notify},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::debug;
+
+use crate::{
+    facades::{Error, KeepAliveLoop, PeerClient, PeerServer},
+    miniprotocols::{
+        blockfetch::{self, BlockRequest},
+        chainsync::{self, ClientRequest, HeaderContent, Tip},
+        handshake::n2n,
+        Point,
+    },
+    multiplexer::{Bearer, Plexer},
+};
+
+const MEMORY_BEARER_BUFFER: usize = 64 * 1024;
+
+/// Virtual clock on top of the paused tokio time. Timers only fire when the
+/// clock is advanced or when every task is idle, which makes latencies
+/// reproducible. Requires a current-thread runtime, as used by `#[tokio::test]`.
+pub struct SimClock {
+    start: Instant,
+}
+
+impl SimClock {
+    /// Freezes the tokio clock of the current runtime
+    pub fn pause() -> Self {
+        tokio::time::pause();
+
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    /// Virtual time since the clock was paused
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub async fn advance(&self, duration: Duration) {
+        tokio::time::advance(duration).await
+    }
+}
+
+/// A block as served by simulated peers
+#[derive(Debug, Clone)]
+pub struct SimBlock {
+    pub point: Point,
+    pub number: u64,
+    pub header: HeaderContent,
+    pub body: Vec<u8>,
+}
+
+impl SimBlock {
+    pub fn new(point: Point, number: u64, header: HeaderContent, body: Vec<u8>) -> Self {
+        Self {
+            point,
+            number,
+            header,
+            body,
+        }
+    }
+}
+
+/// Chain shared by simulated peers, changes are pushed to the clients that
+/// follow it
+#[derive(Clone)]
+pub struct SimChain {
+    blocks: Arc<Mutex<Vec<SimBlock>>>,
+    changes: Arc<watch::Sender<u64>>,
+}
+
+impl Default for SimChain {
+    fn default() -> Self {
+        Self {
+            blocks: Default::default(),
+            changes: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl SimChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_blocks(blocks: Vec<SimBlock>) -> Self {
+        let chain = Self::new();
+        *chain.blocks.lock().unwrap() = blocks;
+        chain
+    }
+
+    pub fn push(&self, block: SimBlock) {
+        self.blocks.lock().unwrap().push(block);
+        self.changes.send_modify(|x| *x += 1);
+    }
+
+    /// Drops the blocks after `point`, returns false if the point is not on
+    /// the chain
+    pub fn rollback_to(&self, point: &Point) -> bool {
+        let mut blocks = self.blocks.lock().unwrap();
+
+        match prefix_len(&blocks, point) {
+            Some(len) => {
+                blocks.truncate(len);
+                drop(blocks);
+                self.changes.send_modify(|x| *x += 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn blocks(&self) -> Vec<SimBlock> {
+        self.blocks.lock().unwrap().clone()
+    }
+
+    pub fn tip(&self) -> Tip {
+        tip_of(&self.blocks.lock().unwrap())
+    }
+
+    fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+}
+
+/// Number of blocks up to and including `point`
+fn prefix_len(blocks: &[SimBlock], point: &Point) -> Option<usize> {
+    match point {
+        Point::Origin => Some(0),
+        _ => blocks.iter().position(|x| x.point == *point).map(|x| x + 1),
+    }
+}
+
+fn tip_of(blocks: &[SimBlock]) -> Tip {
+    match blocks.last() {
+        Some(x) => Tip(x.point.clone(), x.number),
+        None => Tip(Point::Origin, 0),
+    }
+}
+
+/// How a simulated peer misbehaves
+#[derive(Debug, Clone, Default)]
+pub struct PeerBehavior {
+    latency: Duration,
+    disconnect_after: Option<usize>,
+}
+
+impl PeerBehavior {
+    /// Delay before answering every chain-sync and block-fetch request
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Drops the connection after answering this many requests
+    pub fn disconnect_after(mut self, responses: usize) -> Self {
+        self.disconnect_after = Some(responses);
+        self
+    }
+
+    async fn delay(&self) {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+    }
+}
+
+struct Responses {
+    served: AtomicUsize,
+    max: Option<usize>,
+    exhausted: Notify,
+}
+
+impl Responses {
+    fn tick(&self) {
+        let served = self.served.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if Some(served) == self.max {
+            self.exhausted.notify_one();
+        }
+    }
+}
+
+enum Step {
+    Forward(SimBlock, Tip),
+    Backward(Point, Tip),
+}
+
+/// Next message for a client that holds the `read` points, updating them
+fn next_step(chain: &SimChain, read: &mut Vec<Point>) -> Option<Step> {
+    let blocks = chain.blocks.lock().unwrap();
+    let tip = tip_of(&blocks);
+
+    let common = read
+        .iter()
+        .zip(blocks.iter())
+        .take_while(|(a, b)| **a == b.point)
+        .count();
+
+    if common < read.len() {
+        read.truncate(common);
+        let point = read.last().cloned().unwrap_or(Point::Origin);
+        return Some(Step::Backward(point, tip));
+    }
+
+    let block = blocks.get(read.len())?.clone();
+    read.push(block.point.clone());
+
+    Some(Step::Forward(block, tip))
+}
+
+async fn send_step(
+    server: &mut chainsync::N2NServer,
+    step: Step,
+) -> Result<(), chainsync::ServerError> {
+    match step {
+        Step::Forward(block, tip) => server.send_roll_forward(block.header, tip).await,
+        Step::Backward(point, tip) => server.send_roll_backward(point, tip).await,
+    }
+}
+
+async fn serve_chainsync(
+    server: &mut chainsync::N2NServer,
+    chain: &SimChain,
+    behavior: &PeerBehavior,
+    responses: &Responses,
+) -> Result<(), chainsync::ServerError> {
+    let mut changes = chain.subscribe();
+    let mut read: Vec<Point> = vec![];
+    let mut intersection = None;
+
+    while let Some(request) = server.recv_while_idle().await? {
+        behavior.delay().await;
+
+        match request {
+            ClientRequest::Intersect(points) => {
+                let blocks = chain.blocks();
+                let tip = tip_of(&blocks);
+
+                let found = points
+                    .into_iter()
+                    .find_map(|x| prefix_len(&blocks, &x).map(|len| (x, len)));
+
+                match found {
+                    Some((point, len)) => {
+                        read = blocks[..len].iter().map(|x| x.point.clone()).collect();
+                        intersection = Some(point.clone());
+                        server.send_intersect_found(point, tip).await?;
+                    }
+                    None => server.send_intersect_not_found(tip).await?,
+                }
+            }
+            ClientRequest::RequestNext => {
+                // like the node, the first reply after an intersection is a
+                // rollback to it
+                if let Some(point) = intersection.take() {
+                    server.send_roll_backward(point, chain.tip()).await?;
+                    responses.tick();
+                    continue;
+                }
+
+                changes.borrow_and_update();
+
+                match next_step(chain, &mut read) {
+                    Some(step) => send_step(server, step).await?,
+                    None => {
+                        server.send_await_reply().await?;
+
+                        let step = loop {
+                            if changes.changed().await.is_err() {
+                                return Ok(());
+                            }
+
+                            if let Some(step) = next_step(chain, &mut read) {
+                                break step;
+                            }
+                        };
+
+                        send_step(server, step).await?;
+                    }
+                }
+            }
+        }
+
+        responses.tick();
+    }
+
+    Ok(())
+}
+
+async fn serve_blockfetch(
+    server: &mut blockfetch::Server,
+    chain: &SimChain,
+    behavior: &PeerBehavior,
+    responses: &Responses,
+) -> Result<(), blockfetch::ServerError> {
+    while let Some(BlockRequest((from, to))) = server.recv_while_idle().await? {
+        behavior.delay().await;
+
+        let bodies = {
+            let blocks = chain.blocks.lock().unwrap();
+
+            match (prefix_len(&blocks, &from), prefix_len(&blocks, &to)) {
+                (Some(from), Some(to)) if from > 0 && from <= to => blocks[from - 1..to]
+                    .iter()
+                    .map(|x| x.body.clone())
+                    .collect(),
+                _ => vec![],
+            }
+        };
+
+        server.send_block_range(bodies).await?;
+        responses.tick();
+    }
+
+    Ok(())
+}
+
+async fn serve(
+    mut server: PeerServer,
+    chain: SimChain,
+    behavior: PeerBehavior,
+    magic: u64,
+    stop: oneshot::Receiver<()>,
+) {
+    let accepted = server
+        .handshake()
+        .handshake(n2n::VersionTable::v7_and_above(magic))
+        .await;
+
+    if !matches!(accepted, Ok(Some(_))) {
+        debug!("simulated peer refused handshake");
+        server.abort().await;
+        return;
+    }
+
+    let responses = Responses {
+        served: AtomicUsize::new(0),
+        max: behavior.disconnect_after,
+        exhausted: Notify::new(),
+    };
+
+    let PeerServer {
+        plexer,
+        mut chainsync,
+        mut blockfetch,
+        keepalive,
+        ..
+    } = server;
+
+    let exhausted = select! {
+        _ = serve_chainsync(&mut chainsync, &chain, &behavior, &responses) => false,
+        _ = serve_blockfetch(&mut blockfetch, &chain, &behavior, &responses) => false,
+        _ = KeepAliveLoop::run_server(keepalive) => false,
+        _ = responses.exhausted.notified() => true,
+        _ = stop => false,
+    };
+
+    if exhausted {
+        debug!("simulated peer disconnecting");
+
+        // the last response may still be queued in the muxer
+        plexer.close().await;
+    } else {
+        plexer.abort().await;
+    }
+}
+
+/// A simulated peer serving a [`SimChain`], connected to a [`PeerClient`]
+pub struct SimPeer {
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl SimPeer {
+    /// Spawns a peer and returns it along with a client connected to it
+    pub async fn connect(
+        chain: SimChain,
+        behavior: PeerBehavior,
+        magic: u64,
+    ) -> Result<(Self, PeerClient), Error> {
+        let (client, server) = Bearer::memory_pair(MEMORY_BEARER_BUFFER);
+
+        let (stop, stopped) = oneshot::channel();
+        // lets the peer flush its last response before disconnecting
+        let server = PeerServer::with_plexer(Plexer::new(server).with_graceful_close());
+        let task = tokio::spawn(serve(server, chain, behavior, magic, stopped));
+
+        let client = PeerClient::handshake_bearer(client, magic).await?;
+
+        let peer = Self {
+            stop: Some(stop),
+            task,
+        };
+
+        Ok((peer, client))
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Drops the connection, as if the peer went offline
+    pub async fn disconnect(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+
+        let _ = self.task.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::miniprotocols::chainsync::NextResponse;
+
+    const MAGIC: u64 = 2;
+
+    fn block(slot: u64, fork: u8) -> SimBlock {
+        let hash = vec![fork; 32];
+
+        let header = HeaderContent {
+            variant: 6,
+            byron_prefix: None,
+            cbor: slot.to_be_bytes().to_vec(),
+        };
+
+        SimBlock::new(Point::Specific(slot, hash), slot, header, vec![fork])
+    }
+
+    fn forward_slot(next: NextResponse<HeaderContent>) -> u64 {
+        match next {
+            NextResponse::RollForward(header, _) => {
+                u64::from_be_bytes(header.cbor.try_into().unwrap())
+            }
+            x => panic!("expected roll forward, got {x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_rollbacks() {
+        let chain = SimChain::from_blocks(vec![block(1, 0), block(2, 0), block(3, 0)]);
+        let (peer, mut client) = SimPeer::connect(chain.clone(), Default::default(), MAGIC)
+            .await
+            .unwrap();
+
+        let cs = client.chainsync();
+        let (point, tip) = cs.find_intersect(vec![block(1, 0).point]).await.unwrap();
+        assert_eq!(point, Some(block(1, 0).point));
+        assert_eq!(tip.1, 3);
+
+        assert!(matches!(
+            cs.request_next().await.unwrap(),
+            NextResponse::RollBackward(..)
+        ));
+        assert_eq!(forward_slot(cs.request_next().await.unwrap()), 2);
+        assert_eq!(forward_slot(cs.request_next().await.unwrap()), 3);
+
+        // the client waits at the tip until the chain forks
+        assert!(matches!(
+            cs.request_next().await.unwrap(),
+            NextResponse::Await
+        ));
+
+        assert!(chain.rollback_to(&block(2, 0).point));
+        chain.push(block(3, 1));
+
+        match cs.recv_while_must_reply().await.unwrap() {
+            NextResponse::RollBackward(point, _) => assert_eq!(point, block(2, 0).point),
+            x => panic!("expected roll backward, got {x:?}"),
+        }
+
+        assert_eq!(forward_slot(cs.request_next().await.unwrap()), 3);
+
+        let bodies = client
+            .blockfetch()
+            .fetch_range((block(2, 0).point, block(3, 1).point))
+            .await
+            .unwrap();
+        assert_eq!(bodies, vec![vec![0], vec![1]]);
+
+        peer.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn slow_peers_and_disconnects() {
+        let clock = SimClock::pause();
+
+        let chain = SimChain::from_blocks(vec![block(1, 0)]);
+        let behavior = PeerBehavior::default()
+            .latency(Duration::from_secs(2))
+            .disconnect_after(2);
+
+        let (peer, mut client) = SimPeer::connect(chain, behavior, MAGIC).await.unwrap();
+
+        let cs = client.chainsync();
+        cs.intersect_origin().await.unwrap();
+        assert!(clock.elapsed() >= Duration::from_secs(2));
+
+        cs.request_next().await.unwrap();
+        assert!(clock.elapsed() >= Duration::from_secs(4));
+
+        // the peer hangs up after its second response
+        assert!(cs.request_next().await.is_err());
+        assert!(!peer.is_connected());
+    }
+}
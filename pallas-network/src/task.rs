@@ -0,0 +1,85 @@
+//! Background tasks of the plexer, running on tokio or, on wasm, on the
+//! browser event loop
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use tokio::task::JoinHandle;
+
+/// `Send` on native targets, where tasks may move across threads, and no
+/// bound on wasm, where they all run on the browser event loop
+#[cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+pub trait MaybeSend: Send {}
+
+#[cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+impl<T: Send> MaybeSend for T {}
+
+#[cfg(all(feature = "websocket", target_arch = "wasm32"))]
+pub trait MaybeSend {}
+
+#[cfg(all(feature = "websocket", target_arch = "wasm32"))]
+impl<T> MaybeSend for T {}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use local::{spawn, JoinHandle};
+
+#[cfg(target_arch = "wasm32")]
+mod local {
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use futures_util::future::{AbortHandle, Abortable};
+    use tokio::sync::oneshot;
+
+    /// The task was aborted before it completed
+    #[derive(Debug)]
+    pub struct JoinError;
+
+    /// Handle to a task spawned on the browser event loop, mirroring the
+    /// parts of the tokio handle used by the plexer
+    pub struct JoinHandle<T> {
+        abort: AbortHandle,
+        output: oneshot::Receiver<T>,
+    }
+
+    impl<T> JoinHandle<T> {
+        pub fn abort(&self) {
+            self.abort.abort();
+        }
+    }
+
+    impl<T> Future for JoinHandle<T> {
+        type Output = Result<T, JoinError>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            Pin::new(&mut self.output).poll(cx).map_err(|_| JoinError)
+        }
+    }
+
+    pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (output_tx, output) = oneshot::channel();
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Ok(x) = Abortable::new(future, registration).await {
+                let _ = output_tx.send(x);
+            }
+        });
+
+        JoinHandle { abort, output }
+    }
+}
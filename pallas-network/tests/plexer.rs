@@ -60,3 +60,105 @@ async fn one_way_small_sequence_of_payloads() {
     passive.abort().await;
     active.abort().await;
 }
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn websocket_bearer_roundtrip() {
+    let server = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 50302))
+        .await
+        .unwrap();
+
+    let passive = tokio::task::spawn(async move {
+        let (bearer, _) = Bearer::accept_websocket(&server).await.unwrap();
+        Plexer::new(bearer)
+    });
+
+    let bearer = Bearer::connect_websocket("ws://127.0.0.1:50302")
+        .await
+        .unwrap();
+
+    let mut active = Plexer::new(bearer);
+    let mut passive = passive.await.unwrap();
+
+    let mut sender_channel = active.subscribe_client(3);
+    let mut receiver_channel = passive.subscribe_server(3);
+
+    let passive = passive.spawn();
+    let active = active.spawn();
+
+    for size in [10, 5_000, 65_535] {
+        let payload = random_payload(size);
+        sender_channel.enqueue_chunk(payload.clone()).await.unwrap();
+        let received_payload = receiver_channel.dequeue_chunk().await.unwrap();
+        assert_eq!(payload, received_payload);
+    }
+
+    passive.abort().await;
+    active.abort().await;
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn tls_bearer_roundtrip() {
+    use pallas_network::multiplexer::tls;
+    use std::sync::Arc;
+    use tokio_rustls::rustls::{
+        self,
+        pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+    };
+
+    // self-signed certificate for `localhost`
+    let cert = CertificateDer::from(include_bytes!("../../test_data/tls/cert.der").to_vec());
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+        include_bytes!("../../test_data/tls/key.der").to_vec(),
+    ));
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.clone()], key)
+        .unwrap();
+
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+
+    let server = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 50303))
+        .await
+        .unwrap();
+
+    let passive = tokio::task::spawn(async move {
+        let (bearer, _) = Bearer::accept_tls(&server, &acceptor).await.unwrap();
+        Plexer::new(bearer)
+    });
+
+    let mut roots = tls::RootCertStore::empty();
+    roots.add(cert).unwrap();
+
+    let bearer = Bearer::connect_tls(
+        SocketAddrV4::new(Ipv4Addr::LOCALHOST, 50303),
+        "localhost",
+        tls::client_config(roots),
+    )
+    .await
+    .unwrap();
+
+    let mut active = Plexer::new(bearer);
+    let mut passive = passive.await.unwrap();
+
+    let mut sender_channel = active.subscribe_client(3);
+    let mut receiver_channel = passive.subscribe_server(3);
+
+    let passive = passive.spawn();
+    let active = active.spawn();
+
+    for size in [10, 5_000, 65_535] {
+        let payload = random_payload(size);
+        sender_channel.enqueue_chunk(payload.clone()).await.unwrap();
+        let received_payload = receiver_channel.dequeue_chunk().await.unwrap();
+        assert_eq!(payload, received_payload);
+    }
+
+    passive.abort().await;
+    active.abort().await;
+}
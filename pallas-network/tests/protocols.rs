@@ -3,9 +3,10 @@ use std::fs;
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::time::Duration;
 
+use pallas_codec::minicbor;
 use pallas_codec::utils::{AnyCbor, AnyUInt, Bytes, KeyValuePairs, TagWrap};
-use pallas_crypto::hash::Hash;
-use pallas_network::facades::{NodeClient, PeerClient, PeerServer};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_network::facades::{probe_peer, NodeClient, PeerClient, PeerServer};
 use pallas_network::miniprotocols::blockfetch::BlockRequest;
 use pallas_network::miniprotocols::chainsync::{ClientRequest, HeaderContent, Tip};
 use pallas_network::miniprotocols::handshake::n2n::VersionData;
@@ -167,6 +168,203 @@ pub async fn blockfetch_happy_path() {
     assert!(matches!(client.state(), blockfetch::State::Done));
 }
 
+// a wrapped block whose header is just its slot
+fn fake_block(slot: u64) -> Vec<u8> {
+    minicbor::to_vec((6u16, (slot,))).unwrap()
+}
+
+fn fake_block_point(slot: u64) -> Point {
+    let header = minicbor::to_vec(slot).unwrap();
+    Point::Specific(slot, Hasher::<256>::hash(&header).to_vec())
+}
+
+fn honest(_: &mut Vec<Vec<u8>>) {}
+
+async fn serve_blocks(listener: TcpListener, refuse_first: bool, tamper: fn(&mut Vec<Vec<u8>>)) {
+    let mut peer_server = PeerServer::accept(&listener, 0).await.unwrap();
+    let server_bf = peer_server.blockfetch();
+    let mut refuse = refuse_first;
+
+    while let Some(BlockRequest((from, to))) = server_bf.recv_while_idle().await.unwrap() {
+        if refuse {
+            refuse = false;
+            server_bf.send_block_range(vec![]).await.unwrap();
+            continue;
+        }
+
+        let mut bodies: Vec<_> = (from.slot_or_default()..=to.slot_or_default())
+            .map(fake_block)
+            .collect();
+
+        tamper(&mut bodies);
+
+        server_bf.send_block_range(bodies).await.unwrap();
+    }
+
+    peer_server.abort().await;
+}
+
+#[tokio::test]
+pub async fn blockfetch_scheduler_rejects_short_ranges() {
+    let mut servers = vec![];
+
+    let skip_last: fn(&mut Vec<Vec<u8>>) = |x| drop(x.pop());
+
+    for (port, tamper) in [(30008, honest as fn(&mut _)), (30009, skip_last)] {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))
+            .await
+            .unwrap();
+
+        servers.push(tokio::spawn(serve_blocks(listener, false, tamper)));
+    }
+
+    let good = PeerClient::connect("localhost:30008", 0).await.unwrap();
+    let bad = PeerClient::connect("localhost:30009", 0).await.unwrap();
+
+    let points: Vec<_> = (0..10).map(fake_block_point).collect();
+
+    // a peer skipping blocks gets no more batches once caught
+    let mut clients = vec![bad.blockfetch];
+
+    let result = blockfetch::FetchScheduler::new()
+        .fetch(&mut clients, &points[..2])
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(blockfetch::SchedulerError::PeersExhausted)
+    ));
+
+    // its batches are retried with the other peer
+    clients.push(good.blockfetch);
+
+    let bodies = blockfetch::FetchScheduler::new()
+        .batch_size(3)
+        .fetch(&mut clients, &points)
+        .await
+        .unwrap();
+
+    let expected: Vec<_> = (0..10).map(fake_block).collect();
+    assert_eq!(bodies, expected);
+
+    for client in clients.iter_mut() {
+        client.send_done().await.unwrap();
+    }
+
+    for server in servers {
+        server.await.unwrap();
+    }
+
+    good.plexer.abort().await;
+    bad.plexer.abort().await;
+}
+
+#[tokio::test]
+pub async fn blockfetch_cache_rejects_wrong_blocks() {
+    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 30010))
+        .await
+        .unwrap();
+
+    // serves the block after the requested one
+    let server = tokio::spawn(serve_blocks(listener, false, |x| {
+        for body in x.iter_mut() {
+            let slot: (u16, (u64,)) = minicbor::decode(body).unwrap();
+            *body = fake_block(slot.1 .0 + 1);
+        }
+    }));
+
+    let mut peer = PeerClient::connect("localhost:30010", 0).await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("pallas-wrong-cache-{}", std::process::id()));
+    let mut cache = blockfetch::BlockCache::open(&dir, 1024).unwrap();
+
+    let result = peer
+        .blockfetch()
+        .fetch_single_cached(&mut cache, fake_block_point(3))
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(blockfetch::ClientError::UnexpectedBlock(0))
+    ));
+
+    assert!(cache.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    peer.blockfetch().send_done().await.unwrap();
+    server.await.unwrap();
+    peer.plexer.abort().await;
+}
+
+#[tokio::test]
+pub async fn blockfetch_scheduler_splits_and_retries() {
+    let mut servers = vec![];
+
+    for (port, refuse_first) in [(30006, false), (30007, true)] {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))
+            .await
+            .unwrap();
+
+        servers.push(tokio::spawn(serve_blocks(listener, refuse_first, honest)));
+    }
+
+    let mut plexers = vec![];
+    let mut clients = vec![];
+
+    for port in [30006, 30007] {
+        let peer = PeerClient::connect(format!("localhost:{port}"), 0)
+            .await
+            .unwrap();
+
+        plexers.push(peer.plexer);
+        clients.push(peer.blockfetch);
+    }
+
+    let points: Vec<_> = (0..10).map(fake_block_point).collect();
+
+    let bodies = blockfetch::FetchScheduler::new()
+        .batch_size(3)
+        .fetch(&mut clients, &points)
+        .await
+        .unwrap();
+
+    let expected: Vec<_> = (0..10).map(fake_block).collect();
+    assert_eq!(bodies, expected);
+
+    // both peers are still usable after the refused batch
+    assert_eq!(clients.len(), 2);
+
+    // cached blocks split the download in two runs
+    let dir = std::env::temp_dir().join(format!("pallas-fetch-cache-{}", std::process::id()));
+    let mut cache = blockfetch::BlockCache::open(&dir, 1024).unwrap();
+    cache.put(&points[4], &fake_block(4)).unwrap();
+    cache.put(&points[5], &fake_block(5)).unwrap();
+
+    let cached = blockfetch::FetchScheduler::new()
+        .batch_size(3)
+        .fetch_cached(&mut clients, &mut cache, &points)
+        .await
+        .unwrap();
+
+    assert_eq!(cached, expected);
+    assert_eq!(cache.len(), 10);
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    for client in clients.iter_mut() {
+        client.send_done().await.unwrap();
+    }
+
+    for server in servers {
+        server.await.unwrap();
+    }
+
+    for plexer in plexers {
+        plexer.abort().await;
+    }
+}
+
 #[tokio::test]
 #[ignore]
 pub async fn blockfetch_server_and_client_happy_path() {
@@ -263,6 +461,119 @@ pub async fn blockfetch_server_and_client_happy_path() {
     _ = tokio::join!(client, server);
 }
 
+#[tokio::test]
+pub async fn probe_peer_records_handshake() {
+    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 30004))
+        .await
+        .unwrap();
+
+    let (done, finished) = tokio::sync::oneshot::channel::<()>();
+
+    let server = tokio::spawn(async move {
+        let server = PeerServer::accept(&listener, 0).await.unwrap();
+
+        // keep the plexer alive until the probe has read the reply
+        finished.await.unwrap();
+        server.abort().await;
+    });
+
+    let probe = probe_peer("localhost:30004", 0).await.unwrap();
+    done.send(()).unwrap();
+
+    assert!(probe.version >= 7);
+    assert_eq!(probe.network_magic(), 0);
+    assert_eq!(probe.address.port(), 30004);
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+pub async fn chainsync_pipelined_requests() {
+    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 30005))
+        .await
+        .unwrap();
+
+    let tip = Tip(Point::Specific(3, vec![0x03]), 3);
+
+    let server = tokio::spawn({
+        let tip = tip.clone();
+
+        async move {
+            let mut server = PeerServer::accept(&listener, 0).await.unwrap();
+            let server_cs = server.chainsync();
+
+            for i in 0..3u8 {
+                match server_cs.recv_while_idle().await.unwrap().unwrap() {
+                    ClientRequest::RequestNext => (),
+                    ClientRequest::Intersect(_) => panic!("unexpected message"),
+                };
+
+                if i == 2 {
+                    server_cs.send_await_reply().await.unwrap();
+                }
+
+                let header = HeaderContent {
+                    variant: 1,
+                    byron_prefix: None,
+                    cbor: vec![i],
+                };
+
+                server_cs
+                    .send_roll_forward(header, tip.clone())
+                    .await
+                    .unwrap();
+            }
+
+            assert!(server_cs.recv_while_idle().await.unwrap().is_none());
+
+            server.abort().await;
+        }
+    });
+
+    let mut peer = PeerClient::connect("localhost:30005", 0).await.unwrap();
+    let client_cs = peer.chainsync();
+
+    // first call fills the pipeline and receives the oldest reply
+
+    match client_cs.request_next_pipelined(3).await.unwrap() {
+        NextResponse::RollForward(content, _) => assert_eq!(content.cbor, vec![0]),
+        _ => panic!("unexpected response"),
+    }
+
+    assert_eq!(client_cs.in_flight(), 2);
+    assert_eq!(*client_cs.state(), chainsync::State::CanAwait);
+
+    // draining doesn't send any new request
+
+    match client_cs.request_next_pipelined(1).await.unwrap() {
+        NextResponse::RollForward(content, _) => assert_eq!(content.cbor, vec![1]),
+        _ => panic!("unexpected response"),
+    }
+
+    assert!(matches!(
+        client_cs.request_next_pipelined(1).await.unwrap(),
+        NextResponse::Await
+    ));
+
+    assert_eq!(client_cs.in_flight(), 1);
+    assert_eq!(*client_cs.state(), chainsync::State::MustReply);
+
+    match client_cs.request_next_pipelined(1).await.unwrap() {
+        NextResponse::RollForward(content, tip) => {
+            assert_eq!(content.cbor, vec![2]);
+            assert_eq!(tip.1, 3);
+        }
+        _ => panic!("unexpected response"),
+    }
+
+    assert_eq!(client_cs.in_flight(), 0);
+    assert_eq!(*client_cs.state(), chainsync::State::Idle);
+
+    client_cs.send_done().await.unwrap();
+
+    server.await.unwrap();
+}
+
 #[tokio::test]
 #[ignore]
 pub async fn chainsync_server_and_client_happy_path_n2n() {
@@ -460,6 +771,34 @@ pub async fn chainsync_server_and_client_happy_path_n2n() {
     _ = tokio::join!(client, server);
 }
 
+#[test]
+fn local_state_utxo_by_txin_query_roundtrip() {
+    use localstate::queries_v16::{BlockQuery, LedgerQuery, Request, UTxO};
+    use pallas_codec::minicbor;
+
+    let txins = vec![UTxO {
+        transaction_id: Hash::from([1; 32]),
+        index: AnyUInt::MajorByte(2),
+    }];
+
+    let query = Request::LedgerQuery(LedgerQuery::BlockQuery(
+        6,
+        BlockQuery::GetUTxOByTxIn(AnyCbor::from_encode(txins.clone())),
+    ));
+
+    let bytes = minicbor::to_vec(&query).unwrap();
+    let decoded: Request = minicbor::decode(&bytes).unwrap();
+
+    assert_eq!(decoded, query);
+
+    match decoded {
+        Request::LedgerQuery(LedgerQuery::BlockQuery(_, BlockQuery::GetUTxOByTxIn(x))) => {
+            assert_eq!(x.into_decode::<Vec<UTxO>>().unwrap(), txins)
+        }
+        _ => unreachable!(),
+    }
+}
+
 #[cfg(unix)]
 #[tokio::test]
 pub async fn local_state_query_server_and_client_happy_path() {
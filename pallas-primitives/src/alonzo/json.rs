@@ -54,6 +54,36 @@ impl ToCanonicalJson for super::PlutusData {
     }
 }
 
+// follows the "detailed schema" of cardano-cli tx metadata
+impl ToCanonicalJson for super::Metadatum {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            super::Metadatum::Int(x) => {
+                let x = i128::from(*x);
+
+                match (i64::try_from(x), u64::try_from(x)) {
+                    (Ok(x), _) => json!({ "int": x }),
+                    (_, Ok(x)) => json!({ "int": x }),
+                    _ => json!({ "int": x.to_string() }),
+                }
+            }
+            super::Metadatum::Bytes(x) => json!({ "bytes": hex::encode(x.as_slice()) }),
+            super::Metadatum::Text(x) => json!({ "string": x }),
+            super::Metadatum::Array(x) => {
+                let list: Vec<_> = x.iter().map(|i| i.to_json()).collect();
+                json!({ "list": list })
+            }
+            super::Metadatum::Map(x) => {
+                let map: Vec<_> = x
+                    .iter()
+                    .map(|(k, v)| json!({ "k": k.to_json(), "v": v.to_json() }))
+                    .collect();
+                json!({ "map": map })
+            }
+        }
+    }
+}
+
 impl ToCanonicalJson for super::NativeScript {
     fn to_json(&self) -> serde_json::Value {
         match self {
@@ -82,7 +112,11 @@ impl ToCanonicalJson for super::NativeScript {
 mod tests {
     use pallas_codec::minicbor;
 
-    use crate::{alonzo::Block, ToCanonicalJson};
+    use crate::{
+        alonzo::{Block, Metadatum},
+        canonical_json::ToCanonicalJsonString,
+        KeyValuePairs, ToCanonicalJson,
+    };
 
     type BlockWrapper = (u16, Block);
 
@@ -145,4 +179,26 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_metadata_canonical_json() {
+        let metadatum = Metadatum::Map(KeyValuePairs::from(vec![
+            (
+                Metadatum::Text("name".into()),
+                Metadatum::Text("pallas".into()),
+            ),
+            (
+                Metadatum::Int(1.into()),
+                Metadatum::Array(vec![
+                    Metadatum::Bytes(vec![0xca, 0xfe].into()),
+                    Metadatum::Int((-5).into()),
+                ]),
+            ),
+        ]));
+
+        assert_eq!(
+            metadatum.to_canonical_json_string(),
+            r#"{"map":[{"k":{"string":"name"},"v":{"string":"pallas"}},{"k":{"int":1},"v":{"list":[{"bytes":"cafe"},{"int":-5}]}}]}"#
+        );
+    }
 }
@@ -1,4 +1,5 @@
 mod model;
+mod native_script;
 
 #[cfg(feature = "json")]
 pub mod json;
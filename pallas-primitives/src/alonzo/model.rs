@@ -3,8 +3,14 @@
 //! Handcrafted, idiomatic rust artifacts based on based on the [Alonzo CDDL](https://github.com/input-output-hk/cardano-ledger/blob/master/eras/alonzo/test-suite/cddl-files/alonzo.cddl) file in IOHK repo.
 
 use serde::{Deserialize, Serialize};
+use std::ops::Deref;
 
-use pallas_codec::minicbor::{self, data::Tag, Decode, Encode};
+use pallas_codec::{
+    context::{CodecContext, CodecWarning},
+    minicbor::{self, data::Tag, Decode, Encode},
+    tagged::{check_len, end_array},
+    tagged_enum,
+};
 
 pub use crate::{
     plutus_data::*, AddrKeyhash, AssetName, Bytes, Coin, CostModel, DatumHash, DnsName, Epoch,
@@ -104,13 +110,26 @@ pub enum Value {
     Multiasset(Coin, Multiasset<Coin>),
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for Value {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for Value {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        // historic transactions encode small coins with any uint width
         match d.datatype()? {
-            minicbor::data::Type::U8 => Ok(Value::Coin(d.decode_with(ctx)?)),
-            minicbor::data::Type::U16 => Ok(Value::Coin(d.decode_with(ctx)?)),
-            minicbor::data::Type::U32 => Ok(Value::Coin(d.decode_with(ctx)?)),
-            minicbor::data::Type::U64 => Ok(Value::Coin(d.decode_with(ctx)?)),
+            minicbor::data::Type::U8
+            | minicbor::data::Type::U16
+            | minicbor::data::Type::U32
+            | minicbor::data::Type::U64 => {
+                let position = d.position();
+                let coin: Coin = d.decode_with(ctx)?;
+
+                if d.position() - position > minimal_uint_len(coin) {
+                    ctx.report(CodecWarning::NonCanonical {
+                        position,
+                        detail: "coin with non-minimal width",
+                    })?;
+                }
+
+                Ok(Value::Coin(coin))
+            }
             minicbor::data::Type::Array => {
                 d.array()?;
                 let coin = d.decode_with(ctx)?;
@@ -124,13 +143,24 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for Value {
     }
 }
 
+fn minimal_uint_len(value: u64) -> usize {
+    match value {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
 impl<C> minicbor::encode::Encode<C> for Value {
     fn encode<W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
         ctx: &mut C,
     ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        // TODO: check how to deal with uint variants (u32 vs u64)
+        // coins are re-encoded with the shortest width, the original bytes of
+        // non-minimal encodings are only kept by the `KeepRaw` wrappers
         match self {
             Value::Coin(coin) => {
                 e.encode_with(coin, ctx)?;
@@ -147,6 +177,7 @@ impl<C> minicbor::encode::Encode<C> for Value {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct TransactionOutput {
     #[n(0)]
     pub address: Bytes,
@@ -209,7 +240,7 @@ pub enum InstantaneousRewardTarget {
     OtherAccountingPot(Coin),
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for InstantaneousRewardTarget {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for InstantaneousRewardTarget {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         let datatype = d.datatype()?;
 
@@ -246,6 +277,7 @@ impl<C> minicbor::encode::Encode<C> for InstantaneousRewardTarget {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor()]
 pub struct MoveInstantaneousReward {
     #[n(0)]
@@ -255,6 +287,38 @@ pub struct MoveInstantaneousReward {
     pub target: InstantaneousRewardTarget,
 }
 
+impl MoveInstantaneousReward {
+    /// Moves funds from the source pot into the rewards of stake credentials
+    pub fn to_stake_credentials(
+        source: InstantaneousRewardSource,
+        rewards: Vec<(StakeCredential, i64)>,
+    ) -> Self {
+        MoveInstantaneousReward {
+            source,
+            target: InstantaneousRewardTarget::StakeCredentials(KeyValuePairs::Def(rewards)),
+        }
+    }
+
+    /// Moves funds from the source pot into the other accounting pot
+    pub fn to_other_accounting_pot(source: InstantaneousRewardSource, amount: Coin) -> Self {
+        MoveInstantaneousReward {
+            source,
+            target: InstantaneousRewardTarget::OtherAccountingPot(amount),
+        }
+    }
+
+    /// The reward delta for each credential, empty if the funds are moved to
+    /// the other accounting pot
+    pub fn stake_credential_rewards(&self) -> Vec<(&StakeCredential, i64)> {
+        match &self.target {
+            InstantaneousRewardTarget::StakeCredentials(x) => {
+                x.iter().map(|(cred, delta)| (cred, *delta)).collect()
+            }
+            InstantaneousRewardTarget::OtherAccountingPot(_) => vec![],
+        }
+    }
+}
+
 pub type Withdrawals = KeyValuePairs<RewardAccount, Coin>;
 
 pub type RequiredSigners = Vec<AddrKeyhash>;
@@ -280,26 +344,34 @@ pub enum Certificate {
     MoveInstantaneousRewardsCert(MoveInstantaneousReward),
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for Certificate {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for Certificate {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
+        let len = d.array()?;
         let variant = d.u16()?;
 
-        match variant {
+        let value = match variant {
             0 => {
+                check_len(len, 1, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 Ok(Certificate::StakeRegistration(a))
             }
             1 => {
+                check_len(len, 1, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 Ok(Certificate::StakeDeregistration(a))
             }
             2 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::StakeDelegation(a, b))
             }
             3 => {
+                check_len(len, 9, "certificate")?;
+
                 let operator = d.decode_with(ctx)?;
                 let vrf_keyhash = d.decode_with(ctx)?;
                 let pledge = d.decode_with(ctx)?;
@@ -323,28 +395,38 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for Certificate {
                 })
             }
             4 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::PoolRetirement(a, b))
             }
             5 => {
+                check_len(len, 3, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
                 Ok(Certificate::GenesisKeyDelegation(a, b, c))
             }
             6 => {
+                check_len(len, 1, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 Ok(Certificate::MoveInstantaneousRewardsCert(a))
             }
             _ => Err(minicbor::decode::Error::message(
                 "unknown variant id for certificate",
             )),
-        }
+        }?;
+
+        end_array(d, len, "certificate")?;
+
+        Ok(value)
     }
 }
 
-impl<C> minicbor::encode::Encode<C> for Certificate {
+impl<C: CodecContext> minicbor::encode::Encode<C> for Certificate {
     fn encode<W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
@@ -439,7 +521,8 @@ pub type CostMdls = CostModels;
 
 pub type CostModels = KeyValuePairs<Language, CostModel>;
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, Default)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct ProtocolParamUpdate {
     #[n(0)]
@@ -493,6 +576,7 @@ pub struct ProtocolParamUpdate {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Update {
     #[n(0)]
     pub proposed_protocol_parameter_updates: KeyValuePairs<Genesishash, ProtocolParamUpdate>,
@@ -501,9 +585,31 @@ pub struct Update {
     pub epoch: Epoch,
 }
 
+impl Update {
+    /// An empty update proposal, to be enacted at the given epoch
+    pub fn new(epoch: Epoch) -> Self {
+        Update {
+            proposed_protocol_parameter_updates: KeyValuePairs::Def(vec![]),
+            epoch,
+        }
+    }
+
+    /// Adds (or replaces) the parameter changes proposed by a genesis
+    /// delegate, keeping the proposals sorted by genesis hash
+    pub fn propose(mut self, genesis_hash: Genesishash, update: ProtocolParamUpdate) -> Self {
+        self.proposed_protocol_parameter_updates = crate::propose_update(
+            self.proposed_protocol_parameter_updates,
+            genesis_hash,
+            update,
+        );
+        self
+    }
+}
+
 // Can't derive encode for TransactionBody because it seems to require a very
 // particular order for each key in the map
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct TransactionBody {
     #[n(0)]
@@ -558,75 +664,15 @@ pub struct VKeyWitness {
     pub signature: Bytes,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-pub enum NativeScript {
-    ScriptPubkey(AddrKeyhash),
-    ScriptAll(Vec<NativeScript>),
-    ScriptAny(Vec<NativeScript>),
-    ScriptNOfK(u32, Vec<NativeScript>),
-    InvalidBefore(u64),
-    InvalidHereafter(u64),
-}
-
-impl<'b, C> minicbor::decode::Decode<'b, C> for NativeScript {
-    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
-        let variant = d.u32()?;
-
-        match variant {
-            0 => Ok(NativeScript::ScriptPubkey(d.decode_with(ctx)?)),
-            1 => Ok(NativeScript::ScriptAll(d.decode_with(ctx)?)),
-            2 => Ok(NativeScript::ScriptAny(d.decode_with(ctx)?)),
-            3 => Ok(NativeScript::ScriptNOfK(
-                d.decode_with(ctx)?,
-                d.decode_with(ctx)?,
-            )),
-            4 => Ok(NativeScript::InvalidBefore(d.decode_with(ctx)?)),
-            5 => Ok(NativeScript::InvalidHereafter(d.decode_with(ctx)?)),
-            _ => Err(minicbor::decode::Error::message(
-                "unknown variant id for native script",
-            )),
-        }
-    }
-}
-
-impl<C> minicbor::encode::Encode<C> for NativeScript {
-    fn encode<W: minicbor::encode::Write>(
-        &self,
-        e: &mut minicbor::Encoder<W>,
-        ctx: &mut C,
-    ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        e.array(2)?;
-
-        match self {
-            NativeScript::ScriptPubkey(v) => {
-                e.encode_with(0, ctx)?;
-                e.encode_with(v, ctx)?;
-            }
-            NativeScript::ScriptAll(v) => {
-                e.encode_with(1, ctx)?;
-                e.encode_with(v, ctx)?;
-            }
-            NativeScript::ScriptAny(v) => {
-                e.encode_with(2, ctx)?;
-                e.encode_with(v, ctx)?;
-            }
-            NativeScript::ScriptNOfK(a, b) => {
-                e.encode_with(3, ctx)?;
-                e.encode_with(a, ctx)?;
-                e.encode_with(b, ctx)?;
-            }
-            NativeScript::InvalidBefore(v) => {
-                e.encode_with(4, ctx)?;
-                e.encode_with(v, ctx)?;
-            }
-            NativeScript::InvalidHereafter(v) => {
-                e.encode_with(5, ctx)?;
-                e.encode_with(v, ctx)?;
-            }
-        }
-
-        Ok(())
+tagged_enum! {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    pub enum NativeScript {
+        0 => ScriptPubkey(key: AddrKeyhash),
+        1 => ScriptAll(scripts: Vec<NativeScript>),
+        2 => ScriptAny(scripts: Vec<NativeScript>),
+        3 => ScriptNOfK(n: u32, scripts: Vec<NativeScript>),
+        4 => InvalidBefore(slot: u64),
+        5 => InvalidHereafter(slot: u64),
     }
 }
 
@@ -644,6 +690,7 @@ pub enum RedeemerTag {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Redeemer {
     #[n(0)]
     pub tag: RedeemerTag,
@@ -690,6 +737,7 @@ pub struct BootstrapWitness {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct WitnessSet {
     #[n(0)]
@@ -712,6 +760,7 @@ pub struct WitnessSet {
 }
 
 #[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct MintedWitnessSet<'b> {
     #[n(0)]
@@ -752,6 +801,7 @@ impl<'b> From<MintedWitnessSet<'b>> for WitnessSet {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct PostAlonzoAuxiliaryData {
     #[n(0)]
@@ -761,10 +811,89 @@ pub struct PostAlonzoAuxiliaryData {
     pub native_scripts: Option<Vec<NativeScript>>,
 
     #[n(2)]
-    pub plutus_scripts: Option<Vec<PlutusScript<1>>>,
+    pub plutus_scripts: Option<AuxiliaryPlutusScripts>,
+
+    /// Tag the data was wrapped in when it isn't the standard 259, kept so
+    /// that re-encoding yields the original bytes
+    #[cbor(skip)]
+    #[serde(skip)]
+    pub tag: Option<u64>,
+}
+
+/// Plutus scripts of post-Alonzo auxiliary data. Always a list per the CDDL,
+/// but a bare script is also accepted and kept as such on re-encoding
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum AuxiliaryPlutusScripts {
+    List(Vec<PlutusScript<1>>),
+    Bare(PlutusScript<1>),
+}
+
+impl Deref for AuxiliaryPlutusScripts {
+    type Target = [PlutusScript<1>];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            AuxiliaryPlutusScripts::List(x) => x,
+            AuxiliaryPlutusScripts::Bare(x) => std::slice::from_ref(x),
+        }
+    }
+}
+
+impl From<Vec<PlutusScript<1>>> for AuxiliaryPlutusScripts {
+    fn from(value: Vec<PlutusScript<1>>) -> Self {
+        AuxiliaryPlutusScripts::List(value)
+    }
+}
+
+impl<'b, C: CodecContext> minicbor::Decode<'b, C> for AuxiliaryPlutusScripts {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        match d.datatype()? {
+            minicbor::data::Type::Bytes | minicbor::data::Type::BytesIndef => {
+                Ok(AuxiliaryPlutusScripts::Bare(d.decode_with(ctx)?))
+            }
+            _ => Ok(AuxiliaryPlutusScripts::List(d.decode_with(ctx)?)),
+        }
+    }
+}
+
+impl<C: CodecContext> minicbor::Encode<C> for AuxiliaryPlutusScripts {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        match self {
+            AuxiliaryPlutusScripts::List(x) => e.encode_with(x, ctx)?,
+            AuxiliaryPlutusScripts::Bare(x) => e.encode_with(x, ctx)?,
+        };
+
+        Ok(())
+    }
+}
+
+/// CBOR tag that wraps the post-Alonzo auxiliary data map
+pub const POST_ALONZO_AUXILIARY_DATA_TAG: u64 = 259;
+
+// eras as numbered by the decode context
+const ALLEGRA_ERA: u16 = 3;
+const ALONZO_ERA: u16 = 5;
+
+// fails if the context knows of an era before the one introducing a feature
+fn check_era<C: CodecContext>(
+    ctx: &C,
+    since: u16,
+    feature: &str,
+) -> Result<(), minicbor::decode::Error> {
+    match ctx.era() {
+        Some(era) if era < since => Err(minicbor::decode::Error::message(format!(
+            "{feature} not allowed in era {era}"
+        ))),
+        _ => Ok(()),
+    }
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct ShelleyMaAuxiliaryData {
     #[n(0)]
     pub transaction_metadata: Metadata,
@@ -780,16 +909,29 @@ pub enum AuxiliaryData {
     PostAlonzo(PostAlonzoAuxiliaryData),
 }
 
-impl<'b, C> minicbor::Decode<'b, C> for AuxiliaryData {
+impl<'b, C: CodecContext> minicbor::Decode<'b, C> for AuxiliaryData {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
             minicbor::data::Type::Map | minicbor::data::Type::MapIndef => {
                 Ok(AuxiliaryData::Shelley(d.decode_with(ctx)?))
             }
-            minicbor::data::Type::Array => Ok(AuxiliaryData::ShelleyMa(d.decode_with(ctx)?)),
+            minicbor::data::Type::Array => {
+                check_era(ctx, ALLEGRA_ERA, "auxiliary scripts")?;
+                Ok(AuxiliaryData::ShelleyMa(d.decode_with(ctx)?))
+            }
             minicbor::data::Type::Tag => {
-                d.tag()?;
-                Ok(AuxiliaryData::PostAlonzo(d.decode_with(ctx)?))
+                check_era(ctx, ALONZO_ERA, "tagged auxiliary data")?;
+
+                let position = d.position();
+                let tag = d.tag()?.as_u64();
+                let mut aux: PostAlonzoAuxiliaryData = d.decode_with(ctx)?;
+
+                if tag != POST_ALONZO_AUXILIARY_DATA_TAG {
+                    ctx.report(CodecWarning::UnknownTag { position, tag })?;
+                    aux.tag = Some(tag);
+                }
+
+                Ok(AuxiliaryData::PostAlonzo(aux))
             }
             _ => Err(minicbor::decode::Error::message(
                 "Can't infer variant from data type for AuxiliaryData",
@@ -798,7 +940,7 @@ impl<'b, C> minicbor::Decode<'b, C> for AuxiliaryData {
     }
 }
 
-impl<C> minicbor::Encode<C> for AuxiliaryData {
+impl<C: CodecContext> minicbor::Encode<C> for AuxiliaryData {
     fn encode<W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
@@ -812,8 +954,8 @@ impl<C> minicbor::Encode<C> for AuxiliaryData {
                 e.encode_with(m, ctx)?;
             }
             AuxiliaryData::PostAlonzo(v) => {
-                // TODO: check if this is the correct tag
-                e.tag(Tag::new(259))?;
+                let tag = v.tag.unwrap_or(POST_ALONZO_AUXILIARY_DATA_TAG);
+                e.tag(Tag::new(tag))?;
                 e.encode_with(v, ctx)?;
             }
         };
@@ -823,6 +965,7 @@ impl<C> minicbor::Encode<C> for AuxiliaryData {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Block {
     #[n(0)]
     pub header: Header,
@@ -846,6 +989,7 @@ pub struct Block {
 /// original CBOR bytes for each structure that might require hashing. In this
 /// way, we make sure that the resulting hash matches what exists on-chain.
 #[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct MintedBlock<'b> {
     #[n(0)]
     pub header: KeepRaw<'b, MintedHeader<'b>>,
@@ -893,6 +1037,7 @@ impl<'b> From<MintedBlock<'b>> for Block {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Tx {
     #[n(0)]
     pub transaction_body: TransactionBody,
@@ -908,6 +1053,7 @@ pub struct Tx {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct MintedTx<'b> {
     #[b(0)]
     pub transaction_body: KeepRaw<'b, TransactionBody>,
@@ -926,72 +1072,410 @@ pub struct MintedTx<'b> {
 mod tests {
     use pallas_codec::minicbor::{self, to_vec};
 
-    use crate::{alonzo::PlutusData, Fragment};
+    use crate::{alonzo::PlutusData, golden::Corpus, Fragment, KeepRaw};
 
-    use super::{Header, MintedBlock};
+    use super::{
+        AuxiliaryData, AuxiliaryPlutusScripts, Header, MintedBlock, TransactionBody, WitnessSet,
+    };
 
     type BlockWrapper<'b> = (u16, MintedBlock<'b>);
 
-    #[test]
-    fn block_isomorphic_decoding_encoding() {
-        let test_blocks = vec![
-            include_str!("../../../test_data/alonzo1.block"),
-            include_str!("../../../test_data/alonzo2.block"),
-            include_str!("../../../test_data/alonzo3.block"),
-            include_str!("../../../test_data/alonzo4.block"),
-            include_str!("../../../test_data/alonzo5.block"),
-            include_str!("../../../test_data/alonzo6.block"),
-            include_str!("../../../test_data/alonzo7.block"),
-            include_str!("../../../test_data/alonzo8.block"),
-            include_str!("../../../test_data/alonzo9.block"),
-            // old block without invalid_transactions fields
+    const BLOCKS: &[(&str, &str)] = &[
+        // shelley, allegra and mary blocks share the alonzo model
+        (
+            "shelley1",
+            include_str!("../../../test_data/shelley1.block"),
+        ),
+        (
+            "allegra1",
+            include_str!("../../../test_data/allegra1.block"),
+        ),
+        ("mary1", include_str!("../../../test_data/mary1.block")),
+        ("alonzo1", include_str!("../../../test_data/alonzo1.block")),
+        ("alonzo2", include_str!("../../../test_data/alonzo2.block")),
+        ("alonzo3", include_str!("../../../test_data/alonzo3.block")),
+        ("alonzo4", include_str!("../../../test_data/alonzo4.block")),
+        ("alonzo5", include_str!("../../../test_data/alonzo5.block")),
+        ("alonzo6", include_str!("../../../test_data/alonzo6.block")),
+        ("alonzo7", include_str!("../../../test_data/alonzo7.block")),
+        ("alonzo8", include_str!("../../../test_data/alonzo8.block")),
+        ("alonzo9", include_str!("../../../test_data/alonzo9.block")),
+        // old block without invalid_transactions fields
+        (
+            "alonzo10",
             include_str!("../../../test_data/alonzo10.block"),
-            // peculiar block with protocol update params
+        ),
+        // peculiar block with protocol update params
+        (
+            "alonzo11",
             include_str!("../../../test_data/alonzo11.block"),
-            // peculiar block with decoding issue
-            // https://github.com/txpipe/oura/issues/37
+        ),
+        // peculiar block with decoding issue
+        // https://github.com/txpipe/oura/issues/37
+        (
+            "alonzo12",
             include_str!("../../../test_data/alonzo12.block"),
-            // peculiar block with protocol update params, including nonce
+        ),
+        // peculiar block with protocol update params, including nonce
+        (
+            "alonzo13",
             include_str!("../../../test_data/alonzo13.block"),
-            // peculiar block with overflow crash
-            // https://github.com/txpipe/oura/issues/113
+        ),
+        // peculiar block with overflow crash
+        // https://github.com/txpipe/oura/issues/113
+        (
+            "alonzo14",
             include_str!("../../../test_data/alonzo14.block"),
-            // peculiar block with many move-instantaneous-rewards certs
+        ),
+        // peculiar block with many move-instantaneous-rewards certs
+        (
+            "alonzo15",
             include_str!("../../../test_data/alonzo15.block"),
-            // peculiar block with protocol update values
+        ),
+        // peculiar block with protocol update values
+        (
+            "alonzo16",
             include_str!("../../../test_data/alonzo16.block"),
-            // peculiar block with missing nonce hash
+        ),
+        // peculiar block with missing nonce hash
+        (
+            "alonzo17",
             include_str!("../../../test_data/alonzo17.block"),
-            // peculiar block with strange AuxiliaryData variant
+        ),
+        // peculiar block with strange AuxiliaryData variant
+        (
+            "alonzo18",
             include_str!("../../../test_data/alonzo18.block"),
-            // peculiar block with strange AuxiliaryData variant
+        ),
+        // peculiar block with strange AuxiliaryData variant
+        (
+            "alonzo18",
             include_str!("../../../test_data/alonzo18.block"),
-            // peculiar block with nevative i64 overflow
+        ),
+        // peculiar block with nevative i64 overflow
+        (
+            "alonzo19",
             include_str!("../../../test_data/alonzo19.block"),
-            // peculiar block with very BigInt in plutus code
+        ),
+        // peculiar block with very BigInt in plutus code
+        (
+            "alonzo20",
             include_str!("../../../test_data/alonzo20.block"),
-            // peculiar block with bad tx hash
+        ),
+        // peculiar block with bad tx hash
+        (
+            "alonzo21",
             include_str!("../../../test_data/alonzo21.block"),
-            // peculiar block with bad tx hash
+        ),
+        // peculiar block with bad tx hash
+        (
+            "alonzo22",
             include_str!("../../../test_data/alonzo22.block"),
-            // peculiar block with indef byte array in plutus data
+        ),
+        // peculiar block with indef byte array in plutus data
+        (
+            "alonzo23",
             include_str!("../../../test_data/alonzo23.block"),
-            // peculiar block with invalid address (pointer overflow)
+        ),
+        // peculiar block with invalid address (pointer overflow)
+        (
+            "alonzo27",
             include_str!("../../../test_data/alonzo27.block"),
-        ];
+        ),
+    ];
 
-        for (idx, block_str) in test_blocks.iter().enumerate() {
-            println!("decoding test block {}", idx + 1);
-            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {idx}"));
+    #[test]
+    fn historic_tagged_auxiliary_data_roundtrip() {
+        // metadata only, and a plutus script list, both tagged with 259
+        for (block_str, tx, scripts) in [
+            (include_str!("../../../test_data/alonzo3.block"), 3, 0),
+            (include_str!("../../../test_data/alonzo12.block"), 5, 1),
+        ] {
+            let bytes = hex::decode(block_str).unwrap();
+            let wrapper: BlockWrapper = minicbor::decode(&bytes[..]).unwrap();
+
+            let (_, aux) = wrapper
+                .1
+                .auxiliary_data_set
+                .iter()
+                .find(|(idx, _)| *idx == tx)
+                .unwrap();
+
+            assert!(aux.raw_cbor().starts_with(&[0xd9, 0x01, 0x03]));
+
+            match &**aux {
+                AuxiliaryData::PostAlonzo(x) => {
+                    assert_eq!(x.tag, None);
+                    assert_eq!(x.plutus_scripts.as_ref().map_or(0, |x| x.len()), scripts);
+                }
+                _ => panic!("expected post-alonzo auxiliary data"),
+            }
 
-            let block: BlockWrapper = minicbor::decode(&bytes[..])
-                .unwrap_or_else(|_| panic!("error decoding cbor for file {idx}"));
+            let owned: AuxiliaryData = (**aux).clone();
+            assert_eq!(owned.encode_fragment().unwrap(), aux.raw_cbor());
+        }
+    }
+
+    #[test]
+    fn block_isomorphic_decoding_encoding() {
+        let mut corpus = Corpus::new("alonzo");
+
+        for (name, block_str) in BLOCKS {
+            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {name}"));
+
+            let wrapper: BlockWrapper = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|_| panic!("error decoding cbor for file {name}"));
+            let block = &wrapper.1;
 
-            let bytes2 = to_vec(block)
-                .unwrap_or_else(|_| panic!("error encoding block cbor for file {idx}"));
+            let header = block.header.raw_cbor();
+            if let Some(header) = corpus.roundtrip::<Header>(&format!("{name}/header"), header) {
+                corpus.snapshot_debug(&format!("{name}/header"), &header);
+            }
+
+            // bodies and witness sets are re-encoded with ascending map keys
+            // and definite arrays, only their own encoding needs to be stable
+            for (idx, tx) in block.transaction_bodies.iter().enumerate() {
+                corpus.roundtrip_stable::<TransactionBody>(
+                    &format!("{name}/tx{idx}/body"),
+                    tx.raw_cbor(),
+                );
+            }
+
+            for (idx, wits) in block.transaction_witness_sets.iter().enumerate() {
+                corpus.roundtrip_stable::<WitnessSet>(
+                    &format!("{name}/tx{idx}/witnesses"),
+                    wits.raw_cbor(),
+                );
+            }
+
+            for (idx, aux) in block.auxiliary_data_set.iter() {
+                corpus.roundtrip::<AuxiliaryData>(&format!("{name}/tx{idx}/aux"), aux.raw_cbor());
+            }
+
+            let bytes2 = to_vec(&wrapper)
+                .unwrap_or_else(|_| panic!("error encoding block cbor for file {name}"));
 
             assert!(bytes.eq(&bytes2), "re-encoded bytes didn't match original");
         }
+
+        corpus.finish();
+    }
+
+    #[test]
+    fn value_coin_widths() {
+        use super::Value;
+
+        // coins encoded as U8 and U16 roundtrip untouched
+        for hex in ["1818", "18ff", "190100", "19ffff"] {
+            let bytes = hex::decode(hex).unwrap();
+            let value = Value::decode_fragment(&bytes).unwrap();
+            assert_eq!(value.encode_fragment().unwrap(), bytes);
+        }
+
+        // non-minimal widths decode to the same coin
+        for hex in ["1805", "190005", "1a00000005", "1b0000000000000005"] {
+            let value = Value::decode_fragment(&hex::decode(hex).unwrap()).unwrap();
+            assert_eq!(value, Value::Coin(5));
+        }
+
+        // and keep their width through KeepRaw, as minted bodies do
+        let bytes = hex::decode("8241001a00000005").unwrap();
+        let output: KeepRaw<super::TransactionOutput> = minicbor::decode(&bytes).unwrap();
+
+        assert_eq!(output.amount, Value::Coin(5));
+        assert_eq!(output.raw_cbor(), bytes.as_slice());
+        assert_eq!(to_vec(&output).unwrap(), bytes);
+    }
+
+    #[test]
+    fn native_script_n_of_k_roundtrip() {
+        use super::NativeScript;
+
+        // [3, 1, [[4, 100]]], an array of three elements
+        let bytes = hex::decode("8303018182041864").unwrap();
+
+        let script = NativeScript::decode_fragment(&bytes).unwrap();
+        assert_eq!(
+            script,
+            NativeScript::ScriptNOfK(1, vec![NativeScript::InvalidBefore(100)])
+        );
+        assert_eq!(script.encode_fragment().unwrap(), bytes);
+    }
+
+    #[test]
+    fn mir_certificates_roundtrip() {
+        use super::{
+            Certificate, InstantaneousRewardSource, MoveInstantaneousReward, StakeCredential,
+        };
+
+        let certs = vec![
+            Certificate::MoveInstantaneousRewardsCert(
+                MoveInstantaneousReward::to_stake_credentials(
+                    InstantaneousRewardSource::Reserves,
+                    vec![
+                        (StakeCredential::AddrKeyhash([1; 28].into()), 1_000_000),
+                        (StakeCredential::ScriptHash([2; 28].into()), -500),
+                    ],
+                ),
+            ),
+            Certificate::MoveInstantaneousRewardsCert(
+                MoveInstantaneousReward::to_other_accounting_pot(
+                    InstantaneousRewardSource::Treasury,
+                    42,
+                ),
+            ),
+            Certificate::GenesisKeyDelegation(
+                vec![3; 28].into(),
+                vec![4; 28].into(),
+                [5; 32].into(),
+            ),
+        ];
+
+        for cert in certs {
+            let bytes = cert.encode_fragment().unwrap();
+            let decoded = Certificate::decode_fragment(&bytes).unwrap();
+            assert_eq!(cert, decoded);
+        }
+
+        let mir = MoveInstantaneousReward::to_stake_credentials(
+            InstantaneousRewardSource::Reserves,
+            vec![(StakeCredential::AddrKeyhash([1; 28].into()), 7)],
+        );
+
+        assert_eq!(
+            mir.stake_credential_rewards(),
+            vec![(&StakeCredential::AddrKeyhash([1; 28].into()), 7)]
+        );
+    }
+
+    #[test]
+    fn auxiliary_data_keeps_tag_and_scripts() {
+        use super::{NativeScript, POST_ALONZO_AUXILIARY_DATA_TAG};
+
+        // standard tag 259 and an unusual one, both wrapping {0: {}}
+        for (hex, tag) in [("d90103a100a0", None), ("d90104a100a0", Some(260))] {
+            let bytes = hex::decode(hex).unwrap();
+            let aux = AuxiliaryData::decode_fragment(&bytes).unwrap();
+
+            match &aux {
+                AuxiliaryData::PostAlonzo(x) => {
+                    assert_eq!(x.tag, tag);
+                    assert!(x.metadata.is_some());
+                }
+                _ => panic!("expected post-alonzo auxiliary data"),
+            }
+
+            assert_eq!(aux.encode_fragment().unwrap(), bytes);
+        }
+
+        assert_eq!(POST_ALONZO_AUXILIARY_DATA_TAG, 259);
+
+        // shelley-ma metadata followed by a single sig native script
+        let bytes = hex::decode(format!("82a0818200581c{}", "ab".repeat(28))).unwrap();
+        let aux = AuxiliaryData::decode_fragment(&bytes).unwrap();
+
+        match &aux {
+            AuxiliaryData::ShelleyMa(x) => assert_eq!(
+                x.auxiliary_scripts,
+                Some(vec![NativeScript::ScriptPubkey([0xab; 28].into())])
+            ),
+            _ => panic!("expected shelley-ma auxiliary data"),
+        }
+
+        assert_eq!(aux.encode_fragment().unwrap(), bytes);
+    }
+
+    #[test]
+    fn anomalies_are_reported_as_warnings() {
+        use super::Value;
+        use pallas_codec::context::{decode_with_context, CodecWarning, DecodeContext};
+
+        let mut ctx = DecodeContext::for_era(5);
+
+        let bytes = hex::decode("1a00000005").unwrap();
+        let _: Value = decode_with_context(&bytes, &mut ctx).unwrap();
+
+        let bytes = hex::decode("d90104a100a0").unwrap();
+        let _: AuxiliaryData = decode_with_context(&bytes, &mut ctx).unwrap();
+
+        assert_eq!(
+            ctx.warnings.take(),
+            vec![
+                CodecWarning::NonCanonical {
+                    position: 0,
+                    detail: "coin with non-minimal width"
+                },
+                CodecWarning::UnknownTag {
+                    position: 0,
+                    tag: 260
+                },
+            ]
+        );
+
+        let bytes = hex::decode("1a00010000").unwrap();
+        let _: Value = decode_with_context(&bytes, &mut ctx).unwrap();
+        assert!(ctx.warnings.is_empty());
+    }
+
+    #[test]
+    fn context_rules_are_enforced() {
+        use super::Value;
+        use pallas_codec::context::{decode_with_context, DecodeContext};
+
+        // coin with a non-minimal width
+        let bytes = hex::decode("1a00000005").unwrap();
+        let mut ctx = DecodeContext::default().strict();
+        assert!(decode_with_context::<Value>(&bytes, &mut ctx).is_err());
+
+        // tagged auxiliary data only exists since alonzo
+        let bytes = hex::decode("d90103a0").unwrap();
+
+        let mut ctx = DecodeContext::for_era(5);
+        assert!(decode_with_context::<AuxiliaryData>(&bytes, &mut ctx).is_ok());
+
+        let mut ctx = DecodeContext::for_era(4);
+        let err = decode_with_context::<AuxiliaryData>(&bytes, &mut ctx).unwrap_err();
+        assert!(err.to_string().contains("not allowed in era 4"));
+
+        // and auxiliary scripts since allegra
+        let bytes = hex::decode("82a080").unwrap();
+
+        let mut ctx = DecodeContext::for_era(3);
+        assert!(decode_with_context::<AuxiliaryData>(&bytes, &mut ctx).is_ok());
+
+        let mut ctx = DecodeContext::for_era(2);
+        assert!(decode_with_context::<AuxiliaryData>(&bytes, &mut ctx).is_err());
+    }
+
+    #[test]
+    fn auxiliary_data_plutus_scripts_shapes() {
+        let scripts = |x: &AuxiliaryData| match x {
+            AuxiliaryData::PostAlonzo(x) => x.plutus_scripts.clone().unwrap(),
+            _ => panic!("expected post-alonzo auxiliary data"),
+        };
+
+        // list with two scripts
+        let bytes = hex::decode("d90103a102824301020342aabb").unwrap();
+        let aux = AuxiliaryData::decode_fragment(&bytes).unwrap();
+
+        assert_eq!(scripts(&aux).len(), 2);
+        assert_eq!(scripts(&aux)[1].as_ref(), [0xaa, 0xbb]);
+        assert_eq!(aux.encode_fragment().unwrap(), bytes);
+
+        // a bare script reads as a single script and keeps its shape
+        let bytes = hex::decode("d90103a10243010203").unwrap();
+        let aux = AuxiliaryData::decode_fragment(&bytes).unwrap();
+
+        assert!(matches!(scripts(&aux), AuxiliaryPlutusScripts::Bare(_)));
+        assert_eq!(scripts(&aux).len(), 1);
+        assert_eq!(aux.encode_fragment().unwrap(), bytes);
+
+        // a single-element list stays a list
+        let bytes = hex::decode("d90103a1028143010203").unwrap();
+        let aux = AuxiliaryData::decode_fragment(&bytes).unwrap();
+
+        assert!(matches!(scripts(&aux), AuxiliaryPlutusScripts::List(_)));
+        assert_eq!(aux.encode_fragment().unwrap(), bytes);
     }
 
     #[test]
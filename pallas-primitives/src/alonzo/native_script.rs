@@ -0,0 +1,162 @@
+use std::fmt;
+
+use super::NativeScript;
+
+impl NativeScript {
+    /// Returns an equivalent script with a simpler structure
+    ///
+    /// Nested `all`/`any` nodes are flattened into their parent, duplicated
+    /// children of `all`/`any` are dropped, single-child `all`/`any` nodes are
+    /// replaced by the child and trivial `n of k` nodes become `all`/`any`.
+    ///
+    /// The result has the same semantics but a different hash than the
+    /// original, so it's meant for display and analysis, not for witnesses.
+    pub fn simplify(self) -> Self {
+        match self {
+            NativeScript::ScriptAll(xs) => simplify_group(xs, true),
+            NativeScript::ScriptAny(xs) => simplify_group(xs, false),
+            NativeScript::ScriptNOfK(n, xs) => {
+                let xs: Vec<_> = xs.into_iter().map(NativeScript::simplify).collect();
+
+                match n as usize {
+                    0 => NativeScript::ScriptAll(vec![]),
+                    1 => simplify_group(xs, false),
+                    k if k == xs.len() => simplify_group(xs, true),
+                    _ => NativeScript::ScriptNOfK(n, xs),
+                }
+            }
+            x => x,
+        }
+    }
+}
+
+fn simplify_group(children: Vec<NativeScript>, all: bool) -> NativeScript {
+    let mut out: Vec<NativeScript> = vec![];
+
+    for child in children.into_iter().map(NativeScript::simplify) {
+        let nested = match (child, all) {
+            (NativeScript::ScriptAll(xs), true) => xs,
+            (NativeScript::ScriptAny(xs), false) => xs,
+            (x, _) => vec![x],
+        };
+
+        for x in nested {
+            if !out.contains(&x) {
+                out.push(x);
+            }
+        }
+    }
+
+    if out.len() == 1 {
+        return out.remove(0);
+    }
+
+    match all {
+        true => NativeScript::ScriptAll(out),
+        false => NativeScript::ScriptAny(out),
+    }
+}
+
+fn fmt_list(f: &mut fmt::Formatter<'_>, items: &[NativeScript]) -> fmt::Result {
+    write!(f, "[")?;
+
+    for (i, x) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+
+        fmt::Display::fmt(x, f)?;
+    }
+
+    write!(f, "]")
+}
+
+/// Human-readable form, e.g. `all [sig 1c1b8a0f…, after slot 1000]`
+///
+/// Key hashes are abbreviated unless the alternate flag (`{:#}`) is used.
+impl fmt::Display for NativeScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NativeScript::ScriptPubkey(x) => {
+                let hex = x.to_string();
+
+                match f.alternate() {
+                    true => write!(f, "sig {hex}"),
+                    false => write!(f, "sig {}…", &hex[..8]),
+                }
+            }
+            NativeScript::ScriptAll(xs) => {
+                write!(f, "all ")?;
+                fmt_list(f, xs)
+            }
+            NativeScript::ScriptAny(xs) => {
+                write!(f, "any ")?;
+                fmt_list(f, xs)
+            }
+            NativeScript::ScriptNOfK(n, xs) => {
+                write!(f, "at least {n} of ")?;
+                fmt_list(f, xs)
+            }
+            NativeScript::InvalidBefore(slot) => write!(f, "after slot {slot}"),
+            NativeScript::InvalidHereafter(slot) => write!(f, "before slot {slot}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_crypto::hash::Hash;
+
+    fn sig(x: u8) -> NativeScript {
+        NativeScript::ScriptPubkey(Hash::from([x; 28]))
+    }
+
+    #[test]
+    fn simplify_flattens_and_dedups() {
+        let script = NativeScript::ScriptAll(vec![
+            sig(1),
+            NativeScript::ScriptAll(vec![sig(2), sig(1)]),
+            NativeScript::ScriptAny(vec![NativeScript::ScriptAny(vec![sig(3)])]),
+            NativeScript::ScriptNOfK(2, vec![sig(4), NativeScript::InvalidBefore(10)]),
+        ]);
+
+        let expected = NativeScript::ScriptAll(vec![
+            sig(1),
+            sig(2),
+            sig(3),
+            sig(4),
+            NativeScript::InvalidBefore(10),
+        ]);
+
+        assert_eq!(script.simplify(), expected);
+
+        let one_of = NativeScript::ScriptNOfK(1, vec![sig(1), sig(1), sig(2)]);
+        assert_eq!(
+            one_of.simplify(),
+            NativeScript::ScriptAny(vec![sig(1), sig(2)])
+        );
+
+        let two_of = NativeScript::ScriptNOfK(2, vec![sig(1), sig(1), sig(2)]);
+        assert_eq!(two_of.clone().simplify(), two_of);
+    }
+
+    #[test]
+    fn display_is_readable() {
+        let script = NativeScript::ScriptAll(vec![
+            sig(0xab),
+            NativeScript::ScriptNOfK(1, vec![NativeScript::InvalidHereafter(5)]),
+            NativeScript::InvalidBefore(1000),
+        ]);
+
+        assert_eq!(
+            script.to_string(),
+            "all [sig abababab…, at least 1 of [before slot 5], after slot 1000]"
+        );
+
+        assert_eq!(
+            format!("{:#}", sig(0xab)),
+            format!("sig {}", "ab".repeat(28))
+        );
+    }
+}
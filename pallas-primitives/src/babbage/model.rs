@@ -5,6 +5,7 @@
 use serde::{Deserialize, Serialize};
 
 use pallas_codec::{
+    context::CodecContext,
     minicbor::{self, Decode, Encode},
     utils::{Bytes, CborWrap, KeepRaw, KeyValuePairs, MaybeIndefArray, Nullable},
 };
@@ -146,7 +147,7 @@ pub struct CostModels {
     pub plutus_v2: Option<CostModel>,
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, Default)]
 #[cbor(map)]
 pub struct ProtocolParamUpdate {
     #[n(0)]
@@ -197,6 +198,7 @@ pub struct ProtocolParamUpdate {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Update {
     #[n(0)]
     pub proposed_protocol_parameter_updates: KeyValuePairs<Genesishash, ProtocolParamUpdate>,
@@ -205,7 +207,29 @@ pub struct Update {
     pub epoch: Epoch,
 }
 
+impl Update {
+    /// An empty update proposal, to be enacted at the given epoch
+    pub fn new(epoch: Epoch) -> Self {
+        Update {
+            proposed_protocol_parameter_updates: KeyValuePairs::Def(vec![]),
+            epoch,
+        }
+    }
+
+    /// Adds (or replaces) the parameter changes proposed by a genesis
+    /// delegate, keeping the proposals sorted by genesis hash
+    pub fn propose(mut self, genesis_hash: Genesishash, update: ProtocolParamUpdate) -> Self {
+        self.proposed_protocol_parameter_updates = crate::propose_update(
+            self.proposed_protocol_parameter_updates,
+            genesis_hash,
+            update,
+        );
+        self
+    }
+}
+
 #[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct PseudoTransactionBody<T1> {
     #[n(0)]
@@ -322,7 +346,7 @@ pub enum PseudoTransactionOutput<T> {
     PostAlonzo(T),
 }
 
-impl<'b, C, T> minicbor::Decode<'b, C> for PseudoTransactionOutput<T>
+impl<'b, C: CodecContext, T> minicbor::Decode<'b, C> for PseudoTransactionOutput<T>
 where
     T: minicbor::Decode<'b, C>,
 {
@@ -341,7 +365,7 @@ where
     }
 }
 
-impl<C, T> minicbor::Encode<C> for PseudoTransactionOutput<T>
+impl<C: CodecContext, T> minicbor::Encode<C> for PseudoTransactionOutput<T>
 where
     T: minicbor::Encode<C>,
 {
@@ -415,6 +439,7 @@ pub use crate::alonzo::Redeemer;
 pub use crate::alonzo::BootstrapWitness;
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct WitnessSet {
     #[n(0)]
@@ -440,6 +465,7 @@ pub struct WitnessSet {
 }
 
 #[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct MintedWitnessSet<'b> {
     #[n(0)]
@@ -483,6 +509,7 @@ impl<'b> From<MintedWitnessSet<'b>> for WitnessSet {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct PostAlonzoAuxiliaryData {
     #[n(0)]
@@ -584,18 +611,15 @@ impl<'b> From<MintedScriptRef<'b>> for ScriptRef {
 
 impl<'b, C, T> minicbor::Decode<'b, C> for PseudoScript<T>
 where
-    T: minicbor::Decode<'b, ()>,
+    T: minicbor::Decode<'b, C>,
 {
-    fn decode(
-        d: &mut minicbor::Decoder<'b>,
-        _ctx: &mut C,
-    ) -> Result<Self, minicbor::decode::Error> {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         d.array()?;
 
         match d.u8()? {
-            0 => Ok(Self::NativeScript(d.decode()?)),
-            1 => Ok(Self::PlutusV1Script(d.decode()?)),
-            2 => Ok(Self::PlutusV2Script(d.decode()?)),
+            0 => Ok(Self::NativeScript(d.decode_with(ctx)?)),
+            1 => Ok(Self::PlutusV1Script(d.decode_with(ctx)?)),
+            2 => Ok(Self::PlutusV2Script(d.decode_with(ctx)?)),
             _ => Err(minicbor::decode::Error::message(
                 "invalid variant for script enum",
             )),
@@ -625,6 +649,7 @@ where
 pub use crate::alonzo::AuxiliaryData;
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct PseudoBlock<T1, T2, T3, T4>
 where
     T4: std::clone::Clone,
@@ -732,58 +757,165 @@ impl<'b> From<MintedTx<'b>> for Tx {
 
 #[cfg(test)]
 mod tests {
-    use pallas_codec::minicbor;
+    use pallas_codec::{cbor_hex, minicbor};
 
-    use super::{MintedBlock, TransactionOutput};
-    use crate::Fragment;
+    use super::{
+        AuxiliaryData, Header, MintedBlock, TransactionBody, TransactionOutput, WitnessSet,
+    };
+    use crate::{golden::Corpus, Fragment};
 
     type BlockWrapper<'b> = (u16, MintedBlock<'b>);
 
-    #[test]
-    fn block_isomorphic_decoding_encoding() {
-        let test_blocks = [
+    const BLOCKS: &[(&str, &str)] = &[
+        (
+            "babbage1",
             include_str!("../../../test_data/babbage1.block"),
+        ),
+        (
+            "babbage2",
             include_str!("../../../test_data/babbage2.block"),
+        ),
+        (
+            "babbage3",
             include_str!("../../../test_data/babbage3.block"),
-            // peculiar block with single plutus cost model
+        ),
+        // peculiar block with single plutus cost model
+        (
+            "babbage4",
             include_str!("../../../test_data/babbage4.block"),
-            // peculiar block with i32 overlfow
+        ),
+        // peculiar block with i32 overlfow
+        (
+            "babbage5",
             include_str!("../../../test_data/babbage5.block"),
-            // peculiar block with map undef in plutus data
+        ),
+        // peculiar block with map undef in plutus data
+        (
+            "babbage6",
             include_str!("../../../test_data/babbage6.block"),
-            // block with generic int in cbor
+        ),
+        // block with generic int in cbor
+        (
+            "babbage7",
             include_str!("../../../test_data/babbage7.block"),
-            // block with indef bytes for plutus data bignum
+        ),
+        // block with indef bytes for plutus data bignum
+        (
+            "babbage8",
             include_str!("../../../test_data/babbage8.block"),
-            // block with inline datum that fails hashes
+        ),
+        // block with inline datum that fails hashes
+        (
+            "babbage9",
             include_str!("../../../test_data/babbage9.block"),
-            // block with pool margin numerator greater than i64::MAX
+        ),
+        // block with pool margin numerator greater than i64::MAX
+        (
+            "babbage10",
             include_str!("../../../test_data/babbage10.block"),
-        ];
+        ),
+    ];
+
+    #[test]
+    fn block_isomorphic_decoding_encoding() {
+        let mut corpus = Corpus::new("babbage");
+
+        for (name, block_str) in BLOCKS {
+            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {name}"));
+
+            let wrapper: BlockWrapper = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|e| panic!("error decoding cbor for file {name}: {e:?}"));
+            let block = &wrapper.1;
+
+            let header = block.header.raw_cbor();
+            let header = corpus.roundtrip::<Header>(&format!("{name}/header"), header);
+
+            #[cfg(feature = "json")]
+            if let Some(header) = header {
+                corpus.snapshot_json(&format!("{name}/header"), &header);
+            }
+
+            // bodies and witness sets are re-encoded with ascending map keys
+            // and definite arrays, only their own encoding needs to be stable
+            for (idx, tx) in block.transaction_bodies.iter().enumerate() {
+                corpus.roundtrip_stable::<TransactionBody>(
+                    &format!("{name}/tx{idx}/body"),
+                    tx.raw_cbor(),
+                );
+            }
 
-        for (idx, block_str) in test_blocks.iter().enumerate() {
-            println!("decoding test block {}", idx + 1);
-            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {idx}"));
+            for (idx, wits) in block.transaction_witness_sets.iter().enumerate() {
+                corpus.roundtrip_stable::<WitnessSet>(
+                    &format!("{name}/tx{idx}/witnesses"),
+                    wits.raw_cbor(),
+                );
+            }
 
-            let block: BlockWrapper = minicbor::decode(&bytes[..])
-                .unwrap_or_else(|e| panic!("error decoding cbor for file {idx}: {e:?}"));
+            for (idx, aux) in block.auxiliary_data_set.iter() {
+                corpus.roundtrip::<AuxiliaryData>(&format!("{name}/tx{idx}/aux"), aux.raw_cbor());
+            }
 
-            let bytes2 = minicbor::to_vec(block)
-                .unwrap_or_else(|e| panic!("error encoding block cbor for file {idx}: {e:?}"));
+            let bytes2 = minicbor::to_vec(&wrapper)
+                .unwrap_or_else(|e| panic!("error encoding block cbor for file {name}: {e:?}"));
 
             assert!(bytes.eq(&bytes2), "re-encoded bytes didn't match original");
         }
+
+        corpus.finish();
     }
 
     #[test]
     fn fragments_decoding() {
         // peculiar array of outputs used in an hydra transaction
-        let hex = include_str!("../../../test_data/babbage1.fr");
-        let bytes = hex::decode(hex).unwrap();
-        let outputs = Vec::<TransactionOutput>::decode_fragment(&bytes).unwrap();
+        let outputs = cbor_hex!(
+            Vec<TransactionOutput>,
+            include_str!("../../../test_data/babbage1.fr")
+        );
 
         dbg!(outputs);
 
         // add any loose fragment tests here
     }
+
+    #[test]
+    fn update_proposal_roundtrip() {
+        use super::{ProtocolParamUpdate, Update};
+
+        let update = Update::new(42)
+            .propose(
+                vec![2; 28].into(),
+                ProtocolParamUpdate {
+                    minfee_a: Some(44),
+                    ..Default::default()
+                },
+            )
+            .propose(
+                vec![1; 28].into(),
+                ProtocolParamUpdate {
+                    protocol_version: Some((9, 0)),
+                    ..Default::default()
+                },
+            )
+            .propose(
+                vec![2; 28].into(),
+                ProtocolParamUpdate {
+                    max_tx_ex_units: Some(super::ExUnits {
+                        mem: 14_000_000,
+                        steps: 10_000_000_000,
+                    }),
+                    ..Default::default()
+                },
+            );
+
+        let proposals = update.proposed_protocol_parameter_updates.clone().to_vec();
+
+        assert_eq!(proposals.len(), 2);
+        assert_eq!(proposals[0].0, vec![1; 28].into());
+        assert_eq!(proposals[1].1.minfee_a, None);
+
+        let bytes = update.encode_fragment().unwrap();
+        let decoded = Update::decode_fragment(&bytes).unwrap();
+
+        assert_eq!(update, decoded);
+    }
 }
@@ -10,7 +10,7 @@ use pallas_codec::utils::{
 };
 
 // required for derive attrs to work
-use pallas_codec::minicbor;
+use pallas_codec::{context::CodecContext, minicbor};
 
 use std::hash::Hash as StdHash;
 
@@ -55,6 +55,7 @@ pub type Attributes = EmptyMap;
 
 // address = [ #6.24(bytes .cbor ([addressid, addrattr, addrtype])), u64 ]
 #[derive(Debug, Encode, Decode, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Address {
     #[n(0)]
     pub payload: TagWrap<ByteVec, 24>,
@@ -67,6 +68,7 @@ pub struct Address {
 
 // txout = [address, u64]
 #[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct TxOut {
     #[n(0)]
     pub address: Address,
@@ -124,6 +126,7 @@ impl<C> minicbor::Encode<C> for TxIn {
 
 // tx = [[+ txin], [+ txout], attributes]
 #[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Tx {
     #[n(0)]
     pub inputs: MaybeIndefArray<TxIn>,
@@ -195,14 +198,14 @@ impl<C> minicbor::Encode<C> for Twit {
             Twit::RedeemWitness(x) => {
                 e.array(2)?;
                 e.u8(2)?;
-                e.encode(x)?;
+                e.encode_with(x, ctx)?;
 
                 Ok(())
             }
             Twit::Other(a, b) => {
                 e.array(2)?;
                 e.u8(*a)?;
-                e.encode(b)?;
+                e.encode_with(b, ctx)?;
 
                 Ok(())
             }
@@ -210,6 +213,9 @@ impl<C> minicbor::Encode<C> for Twit {
     }
 }
 
+/// Name of [`Twit`] in the CDDL spec
+pub type TxWitness = Twit;
+
 // Shared Seed Computation
 
 // cddl note:
@@ -269,7 +275,7 @@ pub enum Ssc {
     Variant3(SscCerts),
 }
 
-impl<'b, C> minicbor::Decode<'b, C> for Ssc {
+impl<'b, C: CodecContext> minicbor::Decode<'b, C> for Ssc {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         d.array()?;
 
@@ -530,6 +536,7 @@ pub struct BVerMod {
 pub type UpData = (ByronHash, ByronHash, ByronHash, ByronHash);
 
 #[derive(Debug, Encode, Decode, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct UpProp {
     #[n(0)]
     pub block_version: Option<BVer>,
@@ -571,6 +578,7 @@ pub struct UpVote {
 }
 
 #[derive(Debug, Encode, Decode, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Up {
     #[n(0)]
     pub proposal: ZeroOrOneArray<UpProp>,
@@ -611,27 +619,27 @@ impl<C> minicbor::Encode<C> for BlockSig {
     fn encode<W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
-        _ctx: &mut C,
+        ctx: &mut C,
     ) -> Result<(), minicbor::encode::Error<W::Error>> {
         match self {
             BlockSig::Signature(x) => {
                 e.array(2)?;
                 e.u8(0)?;
-                e.encode(x)?;
+                e.encode_with(x, ctx)?;
 
                 Ok(())
             }
             BlockSig::LwdlgSig(x) => {
                 e.array(2)?;
                 e.u8(1)?;
-                e.encode(x)?;
+                e.encode_with(x, ctx)?;
 
                 Ok(())
             }
             BlockSig::DlgSig(x) => {
                 e.array(2)?;
                 e.u8(2)?;
-                e.encode(x)?;
+                e.encode_with(x, ctx)?;
 
                 Ok(())
             }
@@ -640,6 +648,7 @@ impl<C> minicbor::Encode<C> for BlockSig {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct BlockCons(
     #[n(0)] pub SlotId,
     #[n(1)] pub PubKey,
@@ -678,6 +687,7 @@ pub struct BlockProof {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct BlockHead {
     #[n(0)]
     pub protocol_magic: u32,
@@ -697,7 +707,9 @@ pub struct BlockHead {
 
 pub type Witnesses = MaybeIndefArray<Twit>;
 
+// txaux = [tx, [* twit]]
 #[derive(Debug, Encode, Decode)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct TxPayload {
     #[n(0)]
     pub transaction: Tx,
@@ -706,7 +718,11 @@ pub struct TxPayload {
     pub witness: Witnesses,
 }
 
+/// Name of [`TxPayload`] in the CDDL spec
+pub type TxAux = TxPayload;
+
 #[derive(Debug, Encode, Decode, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct MintedTxPayload<'b> {
     #[b(0)]
     pub transaction: KeepRaw<'b, Tx>,
@@ -716,6 +732,7 @@ pub struct MintedTxPayload<'b> {
 }
 
 #[derive(Encode, Decode, Debug)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct BlockBody {
     #[n(0)]
     pub tx_payload: MaybeIndefArray<TxPayload>,
@@ -731,6 +748,7 @@ pub struct BlockBody {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct MintedBlockBody<'b> {
     #[b(0)]
     pub tx_payload: MaybeIndefArray<MintedTxPayload<'b>>,
@@ -748,6 +766,7 @@ pub struct MintedBlockBody<'b> {
 // Epoch Boundary Blocks
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct EbbCons {
     #[n(0)]
     pub epoch_id: EpochId,
@@ -757,6 +776,7 @@ pub struct EbbCons {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct EbbHead {
     #[n(0)]
     pub protocol_magic: u32,
@@ -775,6 +795,7 @@ pub struct EbbHead {
 }
 
 #[derive(Encode, Decode, Debug)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Block {
     #[n(0)]
     pub header: BlockHead,
@@ -787,6 +808,7 @@ pub struct Block {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct MintedBlock<'b> {
     #[b(0)]
     pub header: KeepRaw<'b, BlockHead>,
@@ -799,6 +821,7 @@ pub struct MintedBlock<'b> {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct EbBlock {
     #[n(0)]
     pub header: EbbHead,
@@ -811,6 +834,7 @@ pub struct EbBlock {
 }
 
 #[derive(Encode, Decode, Debug, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct MintedEbBlock<'b> {
     #[b(0)]
     pub header: KeepRaw<'b, EbbHead>,
@@ -824,7 +848,7 @@ pub struct MintedEbBlock<'b> {
 
 #[cfg(test)]
 mod tests {
-    use super::{BlockHead, EbBlock, MintedBlock};
+    use super::{BlockHead, EbBlock, MintedBlock, Twit, TxAux, TxIn};
     use pallas_codec::minicbor::{self, to_vec};
 
     #[test]
@@ -894,4 +918,28 @@ mod tests {
             assert_eq!(bytes, bytes2);
         }
     }
+
+    #[test]
+    fn tx_isomorphic_decoding_encoding() {
+        let subjects = [
+            include_str!("../../../test_data/byron1.tx"),
+            include_str!("../../../test_data/byron2.tx"),
+        ];
+
+        for (idx, str) in subjects.iter().enumerate() {
+            println!("decoding test tx {}", idx + 1);
+            let bytes = hex::decode(str).unwrap_or_else(|_| panic!("bad tx file {idx}"));
+
+            let tx: TxAux = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|_| panic!("error decoding cbor for file {idx}"));
+
+            assert!(matches!(tx.transaction.inputs[0], TxIn::Variant0(_)));
+            assert!(!matches!(tx.witness[0], Twit::Other(..)));
+
+            let bytes2 =
+                to_vec(tx).unwrap_or_else(|_| panic!("error encoding tx cbor for file {idx}"));
+
+            assert_eq!(bytes, bytes2);
+        }
+    }
 }
@@ -0,0 +1,253 @@
+//! JSON Canonicalization Scheme (RFC 8785)
+//!
+//! Serializes a JSON value into a unique string: object members are sorted by
+//! the UTF-16 code units of their names, there's no whitespace, strings use
+//! the minimal escaping and numbers are written like ECMAScript does. Two
+//! services producing the same JSON projection of a datum or metadata will
+//! get byte-for-byte identical output, which can then be hashed or signed.
+//!
+//! Numbers are treated as IEEE-754 doubles, as mandated by the RFC. The
+//! amounts and labels found in ledger data may not fit in that range, so
+//! integers beyond ±(2^53 - 1) are written as strings holding their exact
+//! decimal text, as I-JSON (RFC 7493) recommends, instead of being rounded.
+
+use serde_json::Value;
+
+use crate::ToCanonicalJson;
+
+/// Returns the RFC 8785 canonical form of a JSON value
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(x) => out.push_str(if *x { "true" } else { "false" }),
+        Value::Number(x) if x.is_i64() || x.is_u64() => write_integer(out, x),
+        Value::Number(x) => match x.as_f64() {
+            Some(x) => write_number(out, x),
+            None => out.push_str("null"),
+        },
+        Value::String(x) => write_string(out, x),
+        Value::Array(xs) => {
+            out.push('[');
+
+            for (i, x) in xs.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_value(out, x);
+            }
+
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_string(out, k);
+                out.push(':');
+                write_value(out, v);
+            }
+
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    // serde_json already applies the escaping required by the RFC: only `"`,
+    // `\` and control characters, using the short forms when available and
+    // lowercase `\u00xx` otherwise
+    out.push_str(&serde_json::to_string(value).expect("strings always serialize"));
+}
+
+/// Largest integer a double holds exactly, `Number.MAX_SAFE_INTEGER`
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+fn write_integer(out: &mut String, value: &serde_json::Number) {
+    let safe = match (value.as_i64(), value.as_u64()) {
+        (Some(x), _) => x.unsigned_abs() <= MAX_SAFE_INTEGER,
+        (_, Some(x)) => x <= MAX_SAFE_INTEGER,
+        _ => false,
+    };
+
+    if safe {
+        // same digits ECMAScript prints for integral doubles in this range
+        out.push_str(&value.to_string());
+    } else {
+        write_string(out, &value.to_string());
+    }
+}
+
+/// Formats a double following ECMAScript's `Number.prototype.toString`
+fn write_number(out: &mut String, value: f64) {
+    if value == 0.0 {
+        out.push('0');
+        return;
+    }
+
+    if !value.is_finite() {
+        // not representable in JSON, serde_json never produces these
+        out.push_str("null");
+        return;
+    }
+
+    if value < 0.0 {
+        out.push('-');
+    }
+
+    // Rust's `{:e}` gives the shortest digits that round-trip, which is the
+    // same digit selection ECMAScript uses
+    let sci = format!("{:e}", value.abs());
+    let (mantissa, exp) = sci.split_once('e').expect("scientific notation");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exp: i32 = exp.parse().expect("valid exponent");
+
+    let k = digits.len() as i32;
+    let n = exp + 1;
+
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-n as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+}
+
+/// Canonical string form of any JSON projection
+pub trait ToCanonicalJsonString: ToCanonicalJson {
+    fn to_canonical_json_string(&self) -> String {
+        canonicalize(&self.to_json())
+    }
+}
+
+impl<T: ToCanonicalJson> ToCanonicalJsonString for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn number(x: f64) -> String {
+        let mut out = String::new();
+        write_number(&mut out, x);
+        out
+    }
+
+    #[test]
+    fn numbers_follow_ecmascript() {
+        assert_eq!(number(0.0), "0");
+        assert_eq!(number(-0.0), "0");
+        assert_eq!(number(1.0), "1");
+        assert_eq!(number(-1.5), "-1.5");
+        assert_eq!(number(100.0), "100");
+        assert_eq!(number(1e21), "1e+21");
+        assert_eq!(number(1e20), "100000000000000000000");
+        assert_eq!(number(0.000001), "0.000001");
+        assert_eq!(number(1e-7), "1e-7");
+        assert_eq!(number(4.5e-7), "4.5e-7");
+        assert_eq!(number(123.456), "123.456");
+        assert_eq!(number(9007199254740991.0), "9007199254740991");
+        assert_eq!(number(333333333.3333333), "333333333.3333333");
+        assert_eq!(number(f64::MAX), "1.7976931348623157e+308");
+    }
+
+    #[test]
+    fn objects_are_sorted_and_compact() {
+        let value = json!({
+            "numbers": [333333333.3333333, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u{20ac}$\u{000F}\u{000a}A'\u{0042}\u{0022}\u{005c}\\\"/",
+            "literals": [null, true, false],
+        });
+
+        assert_eq!(
+            canonicalize(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn large_integers_are_exact_strings() {
+        let value = json!([
+            9007199254740991u64,
+            -9007199254740991i64,
+            9007199254740992u64,
+            9007199254740993u64,
+            u64::MAX,
+            -9007199254740993i64,
+            i64::MIN
+        ]);
+
+        assert_eq!(
+            canonicalize(&value),
+            r#"[9007199254740991,-9007199254740991,"9007199254740992","9007199254740993","18446744073709551615","-9007199254740993","-9223372036854775808"]"#
+        );
+
+        let metadatum = crate::Metadatum::Int(crate::Int::try_from(u64::MAX as i128).unwrap());
+        assert_eq!(
+            metadatum.to_canonical_json_string(),
+            r#"{"int":"18446744073709551615"}"#
+        );
+    }
+
+    #[test]
+    fn keys_sort_by_utf16_units() {
+        // from RFC 8785 section 3.2.3
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{0080}": "Control",
+            "\u{00f6}": "Latin Small Letter O With Diaeresis",
+        });
+
+        let expected = [
+            "Carriage Return",
+            "One",
+            "Control",
+            "Latin Small Letter O With Diaeresis",
+            "Euro Sign",
+            "Emoji: Grinning Face",
+            "Hebrew Letter Dalet With Dagesh",
+        ];
+
+        let canonical = canonicalize(&value);
+        let positions: Vec<_> = expected
+            .iter()
+            .map(|x| canonical.find(x).unwrap())
+            .collect();
+
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+}
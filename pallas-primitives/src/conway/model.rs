@@ -4,7 +4,10 @@
 
 use serde::{Deserialize, Serialize};
 
+use pallas_codec::context::CodecContext;
 use pallas_codec::minicbor::{self, Decode, Encode};
+use pallas_codec::tagged::{check_len, end_array};
+use pallas_codec::tagged_enum;
 use pallas_codec::utils::CborWrap;
 
 pub use crate::{
@@ -35,13 +38,14 @@ pub enum Value {
     Multiasset(Coin, Multiasset<PositiveCoin>),
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for Value {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for Value {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        // historic transactions encode small coins with any uint width
         match d.datatype()? {
-            minicbor::data::Type::U8 => Ok(Value::Coin(d.decode_with(ctx)?)),
-            minicbor::data::Type::U16 => Ok(Value::Coin(d.decode_with(ctx)?)),
-            minicbor::data::Type::U32 => Ok(Value::Coin(d.decode_with(ctx)?)),
-            minicbor::data::Type::U64 => Ok(Value::Coin(d.decode_with(ctx)?)),
+            minicbor::data::Type::U8
+            | minicbor::data::Type::U16
+            | minicbor::data::Type::U32
+            | minicbor::data::Type::U64 => Ok(Value::Coin(d.decode_with(ctx)?)),
             minicbor::data::Type::Array => {
                 d.array()?;
                 let coin = d.decode_with(ctx)?;
@@ -61,7 +65,8 @@ impl<C> minicbor::encode::Encode<C> for Value {
         e: &mut minicbor::Encoder<W>,
         ctx: &mut C,
     ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        // TODO: check how to deal with uint variants (u32 vs u64)
+        // coins are re-encoded with the shortest width, the original bytes of
+        // non-minimal encodings are only kept by the `KeepRaw` wrappers
         match self {
             Value::Coin(coin) => {
                 e.encode_with(coin, ctx)?;
@@ -116,26 +121,34 @@ pub enum Certificate {
     UpdateDRepCert(DRepCredential, Nullable<Anchor>),
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for Certificate {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for Certificate {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
+        let len = d.array()?;
         let variant = d.u16()?;
 
-        match variant {
+        let value = match variant {
             0 => {
+                check_len(len, 1, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 Ok(Certificate::StakeRegistration(a))
             }
             1 => {
+                check_len(len, 1, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 Ok(Certificate::StakeDeregistration(a))
             }
             2 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::StakeDelegation(a, b))
             }
             3 => {
+                check_len(len, 9, "certificate")?;
+
                 let operator = d.decode_with(ctx)?;
                 let vrf_keyhash = d.decode_with(ctx)?;
                 let pledge = d.decode_with(ctx)?;
@@ -159,45 +172,61 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for Certificate {
                 })
             }
             4 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::PoolRetirement(a, b))
             }
 
             7 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::Reg(a, b))
             }
             8 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::UnReg(a, b))
             }
             9 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::VoteDeleg(a, b))
             }
             10 => {
+                check_len(len, 3, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
                 Ok(Certificate::StakeVoteDeleg(a, b, c))
             }
             11 => {
+                check_len(len, 3, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
                 Ok(Certificate::StakeRegDeleg(a, b, c))
             }
             12 => {
+                check_len(len, 3, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
                 Ok(Certificate::VoteRegDeleg(a, b, c))
             }
             13 => {
+                check_len(len, 4, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
@@ -205,27 +234,37 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for Certificate {
                 Ok(Certificate::StakeVoteRegDeleg(a, b, c, d))
             }
             14 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::AuthCommitteeHot(a, b))
             }
             15 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::ResignCommitteeCold(a, b))
             }
             16 => {
+                check_len(len, 3, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
                 Ok(Certificate::RegDRepCert(a, b, c))
             }
             17 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::UnRegDRepCert(a, b))
             }
             18 => {
+                check_len(len, 2, "certificate")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(Certificate::UpdateDRepCert(a, b))
@@ -233,7 +272,11 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for Certificate {
             _ => Err(minicbor::decode::Error::message(
                 "unknown variant id for certificate",
             )),
-        }
+        }?;
+
+        end_array(d, len, "certificate")?;
+
+        Ok(value)
     }
 }
 
@@ -385,18 +428,34 @@ pub enum DRep {
 
 impl<'b, C> minicbor::decode::Decode<'b, C> for DRep {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
+        let len = d.array()?;
         let variant = d.u16()?;
 
-        match variant {
-            0 => Ok(DRep::Key(d.decode_with(ctx)?)),
-            1 => Ok(DRep::Script(d.decode_with(ctx)?)),
-            2 => Ok(DRep::Abstain),
-            3 => Ok(DRep::NoConfidence),
+        let value = match variant {
+            0 => {
+                check_len(len, 1, "DRep")?;
+                Ok(DRep::Key(d.decode_with(ctx)?))
+            }
+            1 => {
+                check_len(len, 1, "DRep")?;
+                Ok(DRep::Script(d.decode_with(ctx)?))
+            }
+            2 => {
+                check_len(len, 0, "DRep")?;
+                Ok(DRep::Abstain)
+            }
+            3 => {
+                check_len(len, 0, "DRep")?;
+                Ok(DRep::NoConfidence)
+            }
             _ => Err(minicbor::decode::Error::message(
                 "invalid variant id for DRep",
             )),
-        }
+        }?;
+
+        end_array(d, len, "DRep")?;
+
+        Ok(value)
     }
 }
 
@@ -472,7 +531,17 @@ pub struct CostModels {
     pub plutus_v3: Option<CostModel>,
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+impl CostModels {
+    pub fn for_language(&self, language: &Language) -> Option<&CostModel> {
+        match language {
+            Language::PlutusV1 => self.plutus_v1.as_ref(),
+            Language::PlutusV2 => self.plutus_v2.as_ref(),
+            Language::PlutusV3 => self.plutus_v3.as_ref(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, Default)]
 #[cbor(map)]
 pub struct ProtocolParamUpdate {
     #[n(0)]
@@ -539,7 +608,10 @@ pub struct ProtocolParamUpdate {
     pub minfee_refscript_cost_per_byte: Option<UnitInterval>,
 }
 
+/// Pre-Conway update proposal, which Conway bodies can no longer carry.
+/// Parameter changes are proposed through [`GovAction::ParameterChange`].
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Update {
     #[n(0)]
     pub proposed_protocol_parameter_updates: KeyValuePairs<Genesishash, ProtocolParamUpdate>,
@@ -646,6 +718,7 @@ impl<C> minicbor::Encode<C> for DRepVotingThresholds {
 }
 
 #[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct PseudoTransactionBody<T1> {
     #[n(0)]
@@ -823,7 +896,7 @@ pub struct ProposalProcedure {
     pub anchor: Anchor,
 }
 
-impl<'b, C> minicbor::Decode<'b, C> for ProposalProcedure {
+impl<'b, C: CodecContext> minicbor::Decode<'b, C> for ProposalProcedure {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         d.array()?;
 
@@ -873,33 +946,43 @@ pub enum GovAction {
     Information,
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for GovAction {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for GovAction {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
+        let len = d.array()?;
         let variant = d.u16()?;
 
-        match variant {
+        let value = match variant {
             0 => {
+                check_len(len, 3, "gov action")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
                 Ok(GovAction::ParameterChange(a, b, c))
             }
             1 => {
+                check_len(len, 2, "gov action")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(GovAction::HardForkInitiation(a, b))
             }
             2 => {
+                check_len(len, 2, "gov action")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(GovAction::TreasuryWithdrawals(a, b))
             }
             3 => {
+                check_len(len, 1, "gov action")?;
+
                 let a = d.decode_with(ctx)?;
                 Ok(GovAction::NoConfidence(a))
             }
             4 => {
+                check_len(len, 4, "gov action")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 let c = d.decode_with(ctx)?;
@@ -907,15 +990,24 @@ impl<'b, C> minicbor::decode::Decode<'b, C> for GovAction {
                 Ok(GovAction::UpdateCommittee(a, b, c, d))
             }
             5 => {
+                check_len(len, 2, "gov action")?;
+
                 let a = d.decode_with(ctx)?;
                 let b = d.decode_with(ctx)?;
                 Ok(GovAction::NewConstitution(a, b))
             }
-            6 => Ok(GovAction::Information),
+            6 => {
+                check_len(len, 0, "gov action")?;
+                Ok(GovAction::Information)
+            }
             _ => Err(minicbor::decode::Error::message(
-                "unknown variant id for certificate",
+                "unknown variant id for gov action",
             )),
-        }
+        }?;
+
+        end_array(d, len, "gov action")?;
+
+        Ok(value)
     }
 }
 
@@ -1007,74 +1099,38 @@ impl<C> minicbor::Encode<C> for Constitution {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
-pub enum Voter {
-    ConstitutionalCommitteeKey(AddrKeyhash),
-    ConstitutionalCommitteeScript(ScriptHash),
-    DRepKey(AddrKeyhash),
-    DRepScript(ScriptHash),
-    StakePoolKey(AddrKeyhash),
+tagged_enum! {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
+    pub enum Voter {
+        0 => ConstitutionalCommitteeKey(hash: AddrKeyhash),
+        1 => ConstitutionalCommitteeScript(hash: ScriptHash),
+        2 => DRepKey(hash: AddrKeyhash),
+        3 => DRepScript(hash: ScriptHash),
+        4 => StakePoolKey(hash: AddrKeyhash),
+    }
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for Voter {
-    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
-        let variant = d.u16()?;
-
-        match variant {
-            0 => Ok(Voter::ConstitutionalCommitteeKey(d.decode_with(ctx)?)),
-            1 => Ok(Voter::ConstitutionalCommitteeScript(d.decode_with(ctx)?)),
-            2 => Ok(Voter::DRepKey(d.decode_with(ctx)?)),
-            3 => Ok(Voter::DRepScript(d.decode_with(ctx)?)),
-            4 => Ok(Voter::StakePoolKey(d.decode_with(ctx)?)),
-            _ => Err(minicbor::decode::Error::message(
-                "invalid variant id for DRep",
-            )),
+impl Voter {
+    /// A DRep voting with the given credential
+    pub fn drep(credential: StakeCredential) -> Self {
+        match credential {
+            StakeCredential::AddrKeyhash(x) => Voter::DRepKey(x),
+            StakeCredential::ScriptHash(x) => Voter::DRepScript(x),
         }
     }
-}
-
-impl<C> minicbor::encode::Encode<C> for Voter {
-    fn encode<W: minicbor::encode::Write>(
-        &self,
-        e: &mut minicbor::Encoder<W>,
-        ctx: &mut C,
-    ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        e.array(2)?;
-
-        match self {
-            Voter::ConstitutionalCommitteeKey(h) => {
-                e.encode_with(0, ctx)?;
-                e.encode_with(h, ctx)?;
 
-                Ok(())
-            }
-            Voter::ConstitutionalCommitteeScript(h) => {
-                e.encode_with(1, ctx)?;
-                e.encode_with(h, ctx)?;
-
-                Ok(())
-            }
-            Voter::DRepKey(h) => {
-                e.encode_with(2, ctx)?;
-                e.encode_with(h, ctx)?;
-
-                Ok(())
-            }
-            Voter::DRepScript(h) => {
-                e.encode_with(3, ctx)?;
-                e.encode_with(h, ctx)?;
-
-                Ok(())
-            }
-            Voter::StakePoolKey(h) => {
-                e.encode_with(4, ctx)?;
-                e.encode_with(h, ctx)?;
-
-                Ok(())
-            }
+    /// A constitutional committee member voting with its hot credential
+    pub fn constitutional_committee(hot_credential: StakeCredential) -> Self {
+        match hot_credential {
+            StakeCredential::AddrKeyhash(x) => Voter::ConstitutionalCommitteeKey(x),
+            StakeCredential::ScriptHash(x) => Voter::ConstitutionalCommitteeScript(x),
         }
     }
+
+    /// A stake pool operator voting with its pool key
+    pub fn stake_pool(operator: PoolKeyhash) -> Self {
+        Voter::StakePoolKey(operator)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
@@ -1109,7 +1165,7 @@ impl<C> minicbor::Encode<C> for Anchor {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
 pub struct GovActionId {
     pub transaction_id: Hash<32>,
     pub action_index: u32,
@@ -1147,7 +1203,7 @@ pub enum PseudoTransactionOutput<T> {
     PostAlonzo(T),
 }
 
-impl<'b, C, T> minicbor::Decode<'b, C> for PseudoTransactionOutput<T>
+impl<'b, C: CodecContext, T> minicbor::Decode<'b, C> for PseudoTransactionOutput<T>
 where
     T: minicbor::Decode<'b, C>,
 {
@@ -1166,7 +1222,7 @@ where
     }
 }
 
-impl<C, T> minicbor::Encode<C> for PseudoTransactionOutput<T>
+impl<C: CodecContext, T> minicbor::Encode<C> for PseudoTransactionOutput<T>
 where
     T: minicbor::Encode<C>,
 {
@@ -1216,6 +1272,36 @@ impl<'b> From<MintedPostAlonzoTransactionOutput<'b>> for PostAlonzoTransactionOu
     }
 }
 
+/// An output along with the input that references it, the way wallets and
+/// UTxO stores exchange unspent outputs (e.g. CIP-30 `getUtxos`)
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
+pub struct TransactionUnspentOutput {
+    #[n(0)]
+    pub input: TransactionInput,
+
+    #[n(1)]
+    pub output: TransactionOutput,
+}
+
+impl TransactionUnspentOutput {
+    pub fn new(input: TransactionInput, output: TransactionOutput) -> Self {
+        Self { input, output }
+    }
+}
+
+impl From<(TransactionInput, TransactionOutput)> for TransactionUnspentOutput {
+    fn from((input, output): (TransactionInput, TransactionOutput)) -> Self {
+        Self { input, output }
+    }
+}
+
+impl From<TransactionUnspentOutput> for (TransactionInput, TransactionOutput) {
+    fn from(value: TransactionUnspentOutput) -> Self {
+        (value.input, value.output)
+    }
+}
+
 pub use crate::alonzo::VKeyWitness;
 
 pub use crate::alonzo::NativeScript;
@@ -1247,6 +1333,7 @@ pub enum RedeemerTag {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct Redeemer {
     #[n(0)]
     pub tag: RedeemerTag,
@@ -1270,6 +1357,7 @@ pub struct RedeemersKey {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct RedeemersValue {
     #[n(0)]
     pub data: PlutusData,
@@ -1289,7 +1377,7 @@ impl From<NonEmptyKeyValuePairs<RedeemersKey, RedeemersValue>> for Redeemers {
     }
 }
 
-impl<'b, C> minicbor::Decode<'b, C> for Redeemers {
+impl<'b, C: CodecContext> minicbor::Decode<'b, C> for Redeemers {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
             minicbor::data::Type::Array | minicbor::data::Type::ArrayIndef => {
@@ -1305,7 +1393,7 @@ impl<'b, C> minicbor::Decode<'b, C> for Redeemers {
     }
 }
 
-impl<C> minicbor::Encode<C> for Redeemers {
+impl<C: CodecContext> minicbor::Encode<C> for Redeemers {
     fn encode<W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
@@ -1320,9 +1408,83 @@ impl<C> minicbor::Encode<C> for Redeemers {
     }
 }
 
+/// CBOR shape of the redeemers of a witness set. Conway accepts both the
+/// legacy array of redeemers and the map keyed by tag and index.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedeemersEncoding {
+    #[default]
+    List,
+    Map,
+}
+
+impl Redeemers {
+    pub fn encoding(&self) -> RedeemersEncoding {
+        match self {
+            Self::List(_) => RedeemersEncoding::List,
+            Self::Map(_) => RedeemersEncoding::Map,
+        }
+    }
+
+    /// Redeemers as entries keyed by tag and index, whatever the encoding
+    pub fn to_pairs(&self) -> Vec<(RedeemersKey, RedeemersValue)> {
+        match self {
+            Self::List(x) => x
+                .iter()
+                .map(|r| {
+                    (
+                        RedeemersKey {
+                            tag: r.tag,
+                            index: r.index,
+                        },
+                        RedeemersValue {
+                            data: r.data.clone(),
+                            ex_units: r.ex_units,
+                        },
+                    )
+                })
+                .collect(),
+            Self::Map(x) => x.clone().to_vec(),
+        }
+    }
+
+    /// Redeemers in the legacy list shape, whatever the encoding
+    pub fn to_list(&self) -> Vec<Redeemer> {
+        match self {
+            Self::List(x) => x.clone().to_vec(),
+            Self::Map(x) => x
+                .iter()
+                .map(|(k, v)| Redeemer {
+                    tag: k.tag,
+                    index: k.index,
+                    data: v.data.clone(),
+                    ex_units: v.ex_units,
+                })
+                .collect(),
+        }
+    }
+
+    /// Re-encodes the redeemers in the given shape. Maps can't be empty, so
+    /// an empty list stays a list.
+    pub fn with_encoding(self, encoding: RedeemersEncoding) -> Self {
+        if self.encoding() == encoding {
+            return self;
+        }
+
+        match encoding {
+            RedeemersEncoding::List => Self::List(MaybeIndefArray::Def(self.to_list())),
+            RedeemersEncoding::Map => match NonEmptyKeyValuePairs::from_vec(self.to_pairs()) {
+                Some(x) => Self::Map(x),
+                None => self,
+            },
+        }
+    }
+}
+
 pub use crate::alonzo::BootstrapWitness;
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct WitnessSet {
     #[n(0)]
@@ -1351,6 +1513,7 @@ pub struct WitnessSet {
 }
 
 #[derive(Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct MintedWitnessSet<'b> {
     #[n(0)]
@@ -1394,6 +1557,7 @@ impl<'b> From<MintedWitnessSet<'b>> for WitnessSet {
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 #[cbor(map)]
 pub struct PostAlonzoAuxiliaryData {
     #[n(0)]
@@ -1467,19 +1631,16 @@ impl<'b> From<babbage::MintedScriptRef<'b>> for MintedScriptRef<'b> {
 
 impl<'b, C, T> minicbor::Decode<'b, C> for PseudoScript<T>
 where
-    T: minicbor::Decode<'b, ()>,
+    T: minicbor::Decode<'b, C>,
 {
-    fn decode(
-        d: &mut minicbor::Decoder<'b>,
-        _ctx: &mut C,
-    ) -> Result<Self, minicbor::decode::Error> {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         d.array()?;
 
         match d.u8()? {
-            0 => Ok(Self::NativeScript(d.decode()?)),
-            1 => Ok(Self::PlutusV1Script(d.decode()?)),
-            2 => Ok(Self::PlutusV2Script(d.decode()?)),
-            3 => Ok(Self::PlutusV3Script(d.decode()?)),
+            0 => Ok(Self::NativeScript(d.decode_with(ctx)?)),
+            1 => Ok(Self::PlutusV1Script(d.decode_with(ctx)?)),
+            2 => Ok(Self::PlutusV2Script(d.decode_with(ctx)?)),
+            3 => Ok(Self::PlutusV3Script(d.decode_with(ctx)?)),
             x => Err(minicbor::decode::Error::message(format!(
                 "invalid variant for script enum: {}",
                 x
@@ -1513,6 +1674,7 @@ pub use crate::alonzo::AuxiliaryData;
 use crate::babbage::MintedHeader;
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+#[cbor(context_bound = "pallas_codec::context::CodecContext")]
 pub struct PseudoBlock<T1, T2, T3, T4>
 where
     T4: std::clone::Clone,
@@ -1620,35 +1782,219 @@ impl<'b> From<MintedTx<'b>> for Tx {
 
 #[cfg(test)]
 mod tests {
-    use pallas_codec::minicbor;
+    use pallas_codec::{hex_bytes, minicbor};
 
-    use super::MintedBlock;
+    use super::{AuxiliaryData, Header, MintedBlock, TransactionBody, WitnessSet};
+    use crate::golden::Corpus;
 
     type BlockWrapper<'b> = (u16, MintedBlock<'b>);
 
+    const BLOCKS: &[(&str, &str)] = &[
+        ("conway1", include_str!("../../../test_data/conway1.block")),
+        ("conway2", include_str!("../../../test_data/conway2.block")),
+        // interesting block with extreme values
+        ("conway3", include_str!("../../../test_data/conway3.block")),
+        // interesting block with extreme values
+        ("conway4", include_str!("../../../test_data/conway4.block")),
+    ];
+
     #[test]
     fn block_isomorphic_decoding_encoding() {
-        let test_blocks = [
-            include_str!("../../../test_data/conway1.block"),
-            include_str!("../../../test_data/conway2.block"),
-            // interesting block with extreme values
-            include_str!("../../../test_data/conway3.block"),
-            // interesting block with extreme values
-            include_str!("../../../test_data/conway4.block"),
-        ];
+        let mut corpus = Corpus::new("conway");
 
-        for (idx, block_str) in test_blocks.iter().enumerate() {
-            println!("decoding test block {}", idx + 1);
-            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {idx}"));
+        for (name, block_str) in BLOCKS {
+            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {name}"));
 
-            let block: BlockWrapper = minicbor::decode(&bytes)
-                .unwrap_or_else(|e| panic!("error decoding cbor for file {idx}: {e:?}"));
+            let wrapper: BlockWrapper = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|e| panic!("error decoding cbor for file {name}: {e:?}"));
+            let block = &wrapper.1;
 
-            let bytes2 = minicbor::to_vec(block)
-                .unwrap_or_else(|e| panic!("error encoding block cbor for file {idx}: {e:?}"));
+            let header = block.header.raw_cbor();
+            let header = corpus.roundtrip::<Header>(&format!("{name}/header"), header);
+
+            #[cfg(feature = "json")]
+            if let Some(header) = header {
+                corpus.snapshot_json(&format!("{name}/header"), &header);
+            }
+
+            // bodies and witness sets are re-encoded with ascending map keys
+            // and definite arrays, only their own encoding needs to be stable
+            for (idx, tx) in block.transaction_bodies.iter().enumerate() {
+                corpus.roundtrip_stable::<TransactionBody>(
+                    &format!("{name}/tx{idx}/body"),
+                    tx.raw_cbor(),
+                );
+            }
+
+            for (idx, wits) in block.transaction_witness_sets.iter().enumerate() {
+                corpus.roundtrip_stable::<WitnessSet>(
+                    &format!("{name}/tx{idx}/witnesses"),
+                    wits.raw_cbor(),
+                );
+            }
+
+            for (idx, aux) in block.auxiliary_data_set.iter() {
+                corpus.roundtrip::<AuxiliaryData>(&format!("{name}/tx{idx}/aux"), aux.raw_cbor());
+            }
+
+            let bytes2 = minicbor::to_vec(&wrapper)
+                .unwrap_or_else(|e| panic!("error encoding block cbor for file {name}: {e:?}"));
 
             assert!(bytes.eq(&bytes2), "re-encoded bytes didn't match original");
         }
+
+        corpus.finish();
+    }
+
+    #[test]
+    fn block_decoding_with_context() {
+        use pallas_codec::context::{decode_with_context, DecodeContext};
+
+        let bytes = hex_bytes!(include_str!("../../../test_data/conway1.block"));
+
+        let mut ctx = DecodeContext::for_era(7).strict();
+        let (_, block): BlockWrapper = decode_with_context(&bytes, &mut ctx).unwrap();
+
+        let (_, expected): BlockWrapper = minicbor::decode(&bytes).unwrap();
+
+        assert_eq!(block, expected);
+    }
+
+    #[test]
+    fn unspent_output_roundtrip() {
+        use super::{TransactionOutput, TransactionUnspentOutput};
+
+        // [input, legacy output] as returned by wallets
+        let bytes = hex_bytes!(
+            "82825820010101010101010101010101010101010101010101010101010101010101010102\
+             82581d61020202020202020202020202020202020202020202020202020202021a004c4b40"
+        );
+
+        let utxo: TransactionUnspentOutput = minicbor::decode(&bytes).unwrap();
+
+        assert_eq!(utxo.input.index, 2);
+
+        match &utxo.output {
+            TransactionOutput::Legacy(x) => {
+                assert_eq!(x.amount, crate::alonzo::Value::Coin(5_000_000))
+            }
+            _ => panic!("expected legacy output"),
+        }
+
+        let (input, output) = utxo.clone().into();
+        assert_eq!(TransactionUnspentOutput::new(input, output), utxo);
+        assert_eq!(minicbor::to_vec(&utxo).unwrap(), bytes);
+    }
+
+    #[test]
+    fn redeemers_keep_their_encoding() {
+        use super::{Redeemers, RedeemersEncoding};
+
+        // a single spend redeemer at index 0, as a list and as a map
+        let list = hex_bytes!("8184000000820102");
+        let map = hex_bytes!("a18200008200820102");
+
+        let from_list: Redeemers = minicbor::decode(&list).unwrap();
+        let from_map: Redeemers = minicbor::decode(&map).unwrap();
+
+        assert_eq!(from_list.encoding(), RedeemersEncoding::List);
+        assert_eq!(from_map.encoding(), RedeemersEncoding::Map);
+        assert_eq!(minicbor::to_vec(&from_list).unwrap(), list);
+        assert_eq!(minicbor::to_vec(&from_map).unwrap(), map);
+
+        assert_eq!(from_list.to_pairs(), from_map.to_pairs());
+        assert_eq!(from_list.to_list(), from_map.to_list());
+
+        let converted = from_list.clone().with_encoding(RedeemersEncoding::Map);
+        assert_eq!(minicbor::to_vec(&converted).unwrap(), map);
+
+        let converted = from_map.with_encoding(RedeemersEncoding::List);
+        assert_eq!(converted, from_list);
+    }
+
+    #[test]
+    fn conway_certificates_roundtrip() {
+        use super::{Anchor, Certificate, DRep};
+        use crate::{Nullable, StakeCredential};
+        use pallas_crypto::hash::Hash;
+
+        let cred = StakeCredential::AddrKeyhash(Hash::new([1; 28]));
+        let hot = StakeCredential::ScriptHash(Hash::new([2; 28]));
+        let pool = Hash::new([3; 28]);
+        let drep = DRep::Key(Hash::new([4; 28]));
+        let anchor = Nullable::Some(Anchor {
+            url: "https://example.com/drep.json".into(),
+            content_hash: Hash::new([5; 32]),
+        });
+
+        let certs = [
+            Certificate::Reg(cred.clone(), 2_000_000),
+            Certificate::UnReg(cred.clone(), 2_000_000),
+            Certificate::VoteDeleg(cred.clone(), DRep::Abstain),
+            Certificate::StakeVoteDeleg(cred.clone(), pool, drep.clone()),
+            Certificate::StakeRegDeleg(cred.clone(), pool, 2_000_000),
+            Certificate::VoteRegDeleg(cred.clone(), DRep::NoConfidence, 2_000_000),
+            Certificate::StakeVoteRegDeleg(cred.clone(), pool, drep, 2_000_000),
+            Certificate::AuthCommitteeHot(cred.clone(), hot),
+            Certificate::ResignCommitteeCold(cred.clone(), Nullable::Null),
+            Certificate::RegDRepCert(cred.clone(), 500_000_000, anchor.clone()),
+            Certificate::UnRegDRepCert(cred.clone(), 500_000_000),
+            Certificate::UpdateDRepCert(cred, anchor),
+        ];
+
+        for (variant, cert) in (7u8..).zip(certs) {
+            let bytes = minicbor::to_vec(&cert).unwrap();
+            assert_eq!(bytes[1], variant);
+
+            let decoded: Certificate = minicbor::decode(&bytes).unwrap();
+            assert_eq!(decoded, cert);
+        }
+
+        let unknown = hex_bytes!("821301");
+        assert!(minicbor::decode::<Certificate>(&unknown).is_err());
+    }
+
+    #[test]
+    fn variant_array_lengths_are_checked() {
+        use super::{Certificate, DRep, GovAction, Voter};
+        use crate::Relay;
+
+        let cred = "8200581c01010101010101010101010101010101010101010101010101010101";
+
+        // a reg cert without its deposit
+        let short = hex_bytes!(&format!("8207{cred}"));
+        let err = minicbor::decode::<Certificate>(&short).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expected 3 elements for certificate, found 2"));
+
+        // a stake registration followed by a stray field
+        let long = hex_bytes!(&format!("8300{cred}00"));
+        assert!(minicbor::decode::<Certificate>(&long).is_err());
+
+        // indefinite arrays are accepted, as long as they hold the right fields
+        let indef = hex_bytes!(&format!("9f09{cred}8102ff"));
+        let cert: Certificate = minicbor::decode(&indef).unwrap();
+        assert!(matches!(cert, Certificate::VoteDeleg(_, DRep::Abstain)));
+
+        let indef = hex_bytes!(&format!("9f09{cred}810200ff"));
+        let err = minicbor::decode::<Certificate>(&indef).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("too many elements for certificate"));
+
+        assert!(minicbor::decode::<DRep>(&hex_bytes!("820200")).is_err());
+        assert!(minicbor::decode::<Voter>(&hex_bytes!("8104")).is_err());
+        assert!(minicbor::decode::<Relay>(&hex_bytes!("8302f6")).is_err());
+
+        // unknown variants are errors too
+        let err = minicbor::decode::<Certificate>(&hex_bytes!("811813")).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unknown variant id for certificate"));
+        assert!(minicbor::decode::<DRep>(&hex_bytes!("8104")).is_err());
+        assert!(minicbor::decode::<Voter>(&hex_bytes!("820500")).is_err());
+        assert!(minicbor::decode::<GovAction>(&hex_bytes!("8107")).is_err());
     }
 
     // #[test]
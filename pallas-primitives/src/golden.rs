@@ -0,0 +1,184 @@
+//! Test framework for the golden CBOR corpus. Samples are registered per
+//! type, decoded, checked to re-encode to the exact same bytes and rendered
+//! into snapshots stored under `test_data/golden`. Snapshots are only written
+//! when `PALLAS_UPDATE_GOLDEN` is set, otherwise they must exist and match.
+
+use std::{fmt::Debug, fs, path::PathBuf};
+
+use pallas_codec::minicbor::{self, Decode, Encode};
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+const UPDATE_ENV: &str = "PALLAS_UPDATE_GOLDEN";
+
+fn snapshot_path(kind: &str, name: &str, ext: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../test_data/golden")
+        .join(kind)
+        .join(format!("{}.{ext}", name.replace('/', ".")))
+}
+
+/// Offset of the first difference between two byte strings
+fn first_difference(a: &[u8], b: &[u8]) -> usize {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .unwrap_or(a.len().min(b.len()))
+}
+
+/// Golden samples of one kind (usually an era), collecting every failure so
+/// that a single run reports all the offending samples
+pub struct Corpus {
+    kind: &'static str,
+    checked: usize,
+    failures: Vec<String>,
+}
+
+impl Corpus {
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            checked: 0,
+            failures: vec![],
+        }
+    }
+
+    /// Decodes a `T` from `cbor` and asserts that encoding it yields the
+    /// exact same bytes
+    pub fn roundtrip<'b, T>(&mut self, name: &str, cbor: &'b [u8]) -> Option<T>
+    where
+        T: Decode<'b, ()> + Encode<()>,
+    {
+        self.checked += 1;
+
+        let value: T = match minicbor::decode(cbor) {
+            Ok(x) => x,
+            Err(e) => {
+                self.failures.push(format!("{name}: can't decode: {e}"));
+                return None;
+            }
+        };
+
+        let encoded = match minicbor::to_vec(&value) {
+            Ok(x) => x,
+            Err(e) => {
+                self.failures.push(format!("{name}: can't encode: {e}"));
+                return None;
+            }
+        };
+
+        if encoded != cbor {
+            let offset = first_difference(cbor, &encoded);
+            let context = |x: &[u8]| hex::encode(&x[offset..x.len().min(offset + 16)]);
+
+            self.failures.push(format!(
+                "{name}: re-encoding differs at byte {offset}, expected {}.. got {}..",
+                context(cbor),
+                context(&encoded),
+            ));
+        }
+
+        Some(value)
+    }
+
+    /// Like `roundtrip`, for types that don't preserve the original encoding
+    /// (e.g. map key order or indefinite arrays). Asserts instead that the
+    /// re-encoded bytes are a fixed point of decoding and encoding again.
+    pub fn roundtrip_stable<T>(&mut self, name: &str, cbor: &[u8]) -> bool
+    where
+        T: for<'a> Decode<'a, ()> + Encode<()>,
+    {
+        self.checked += 1;
+
+        let reencode = |bytes: &[u8]| -> Result<Vec<u8>, String> {
+            let value: T = minicbor::decode(bytes).map_err(|e| format!("can't decode: {e}"))?;
+            minicbor::to_vec(&value).map_err(|e| format!("can't encode: {e}"))
+        };
+
+        let result = reencode(cbor).and_then(|once| Ok((reencode(&once)?, once)));
+
+        match result {
+            Ok((twice, once)) if twice == once => true,
+            Ok((twice, once)) => {
+                let offset = first_difference(&once, &twice);
+                self.failures.push(format!(
+                    "{name}: re-encoding is not stable, differs at byte {offset}"
+                ));
+                false
+            }
+            Err(e) => {
+                self.failures.push(format!("{name}: {e}"));
+                false
+            }
+        }
+    }
+
+    fn snapshot(&mut self, name: &str, ext: &str, rendered: String) {
+        let path = snapshot_path(self.kind, name, ext);
+
+        if std::env::var_os(UPDATE_ENV).is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, rendered).unwrap();
+            return;
+        }
+
+        let Ok(expected) = fs::read_to_string(&path) else {
+            self.failures.push(format!(
+                "{name}: missing {ext} snapshot {}, run with {UPDATE_ENV}=1 to create it",
+                path.display()
+            ));
+            return;
+        };
+
+        if expected != rendered {
+            self.failures.push(format!(
+                "{name}: {ext} snapshot differs from {}, run with {UPDATE_ENV}=1 to accept",
+                path.display()
+            ));
+        }
+    }
+
+    /// Compares the debug representation of `value` with its snapshot
+    pub fn snapshot_debug<T: Debug>(&mut self, name: &str, value: &T) {
+        self.snapshot(name, "debug", format!("{value:?}\n"));
+    }
+
+    /// Compares the JSON representation of `value` with its snapshot
+    #[cfg(feature = "json")]
+    pub fn snapshot_json<T: Serialize>(&mut self, name: &str, value: &T) {
+        let json = serde_json::to_string_pretty(value).unwrap();
+        self.snapshot(name, "json", json + "\n");
+    }
+
+    /// Panics listing every failed sample, if any
+    pub fn finish(self) {
+        assert!(self.checked > 0, "no golden samples for {}", self.kind);
+
+        assert!(
+            self.failures.is_empty(),
+            "{} of {} golden {} samples failed:\n{}",
+            self.failures.len(),
+            self.checked,
+            self.kind,
+            self.failures.join("\n")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Corpus;
+
+    #[test]
+    fn missing_snapshots_fail() {
+        if std::env::var_os(super::UPDATE_ENV).is_some() {
+            return;
+        }
+
+        let mut corpus = Corpus::new("missing");
+        corpus.snapshot_debug("nothing", &0u8);
+
+        assert_eq!(corpus.failures.len(), 1);
+        assert!(!super::snapshot_path("missing", "nothing", "debug").exists());
+    }
+}
@@ -0,0 +1,118 @@
+//! Orderings for the keys of transaction body maps. Pallas encodes bodies
+//! with ascending keys, while the Haskell node follows the field order of the
+//! ledger encoders of each era, which some downstream verifiers rely on.
+
+use pallas_codec::minicbor::{self, data::Type, Decoder, Encoder};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "snake_case"))]
+pub enum BodyKeyOrder {
+    /// Ascending keys, as encoded by pallas
+    #[default]
+    Ascending,
+    /// Order of the node for Shelley, Allegra and Mary bodies (ascending)
+    ShelleyMa,
+    /// Order of the node for Alonzo bodies
+    Alonzo,
+    /// Order of the node for Babbage bodies
+    Babbage,
+    /// Order of the node for Conway bodies
+    Conway,
+}
+
+impl BodyKeyOrder {
+    /// Keys in the order they are encoded, unknown keys go last
+    pub fn keys(&self) -> &'static [u64] {
+        match self {
+            BodyKeyOrder::Ascending => &[],
+            BodyKeyOrder::ShelleyMa => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+            BodyKeyOrder::Alonzo => &[0, 13, 1, 2, 3, 4, 5, 6, 8, 14, 9, 11, 7, 15],
+            BodyKeyOrder::Babbage => &[0, 13, 18, 1, 16, 17, 2, 3, 4, 5, 6, 8, 14, 9, 11, 7, 15],
+            BodyKeyOrder::Conway => &[
+                0, 13, 18, 1, 16, 17, 2, 3, 4, 5, 8, 14, 9, 11, 7, 15, 19, 20, 21, 22,
+            ],
+        }
+    }
+
+    /// Re-encodes the entries of a CBOR map with unsigned keys in this order,
+    /// keeping the bytes of every entry untouched
+    pub fn apply(&self, map: &[u8]) -> Result<Vec<u8>, minicbor::decode::Error> {
+        let mut d = Decoder::new(map);
+
+        let len = d.map()?;
+        let mut entries = vec![];
+
+        loop {
+            match len {
+                Some(len) if entries.len() as u64 == len => break,
+                None if d.datatype()? == Type::Break => break,
+                _ => (),
+            }
+
+            let start = d.position();
+            let key = d.u64()?;
+            d.skip()?;
+
+            entries.push((key, &map[start..d.position()]));
+        }
+
+        let rank = |key: u64| {
+            self.keys()
+                .iter()
+                .position(|x| *x == key)
+                .unwrap_or(usize::MAX)
+        };
+
+        match self {
+            BodyKeyOrder::Ascending => entries.sort_by_key(|(key, _)| *key),
+            _ => entries.sort_by_key(|(key, _)| (rank(*key), *key)),
+        }
+
+        let mut buf = vec![];
+        let mut e = Encoder::new(&mut buf);
+
+        match len {
+            Some(len) => e.map(len).unwrap(),
+            None => e.begin_map().unwrap(),
+        };
+
+        for (_, entry) in entries {
+            buf.extend_from_slice(entry);
+        }
+
+        if len.is_none() {
+            Encoder::new(&mut buf).end().unwrap();
+        }
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::hex_bytes;
+
+    use super::*;
+
+    #[test]
+    fn conway_node_order() {
+        // {0: [], 1: [], 2: 1, 13: [], 18: []}
+        let body = hex_bytes!("a50080018002010d801280");
+
+        let node = BodyKeyOrder::Conway.apply(&body).unwrap();
+        assert_eq!(hex::encode(&node), "a500800d80128001800201");
+
+        let ascending = BodyKeyOrder::Ascending.apply(&node).unwrap();
+        assert_eq!(ascending, body);
+    }
+
+    #[test]
+    fn indefinite_maps_and_unknown_keys() {
+        // {_ 99: 0, 1: [], 0: []}
+        let body = hex_bytes!("bf18630001800080ff");
+
+        let node = BodyKeyOrder::Babbage.apply(&body).unwrap();
+        assert_eq!(hex::encode(node), "bf00800180186300ff");
+    }
+}
@@ -0,0 +1,153 @@
+//! Compact, ordered byte encodings meant to be used as keys in KV stores
+//!
+//! All encodings are fixed-width or prefixed by a fixed-width component, so
+//! that lexicographic order of the bytes (what RocksDB, sled and friends use)
+//! matches a well-defined order of the values:
+//!
+//! - [`TransactionInput`]: `tx_hash (32) ++ index (u64 big-endian)`, 40
+//!   bytes. Orders by tx hash and then by index, same as its `Ord` impl.
+//! - [`StakeCredential`]: `tag (1) ++ hash (28)`, 29 bytes, where the tag is
+//!   `0` for key hashes and `1` for script hashes (same as the CDDL). Orders
+//!   key credentials before script credentials, which differs from the
+//!   derived `Ord` of the enum.
+//! - asset ids: `policy (28) ++ asset name (0..=32)`. Orders by policy and
+//!   then by name; all assets of a policy share a 28-byte prefix, so they can
+//!   be found with a prefix scan.
+
+use crate::{AssetName, Hash, PolicyId, StakeCredential, TransactionInput};
+
+pub const TX_INPUT_KEY_LEN: usize = 40;
+pub const CREDENTIAL_KEY_LEN: usize = 29;
+pub const POLICY_KEY_LEN: usize = 28;
+
+const KEY_CREDENTIAL_TAG: u8 = 0;
+const SCRIPT_CREDENTIAL_TAG: u8 = 1;
+
+impl TransactionInput {
+    pub fn to_key_bytes(&self) -> [u8; TX_INPUT_KEY_LEN] {
+        let mut out = [0u8; TX_INPUT_KEY_LEN];
+        out[..32].copy_from_slice(self.transaction_id.as_ref());
+        out[32..].copy_from_slice(&self.index.to_be_bytes());
+        out
+    }
+
+    pub fn from_key_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != TX_INPUT_KEY_LEN {
+            return None;
+        }
+
+        let (hash, index) = bytes.split_at(32);
+
+        Some(Self {
+            transaction_id: Hash::from(hash),
+            index: u64::from_be_bytes(index.try_into().ok()?),
+        })
+    }
+}
+
+impl StakeCredential {
+    pub fn to_key_bytes(&self) -> [u8; CREDENTIAL_KEY_LEN] {
+        let (tag, hash) = match self {
+            StakeCredential::AddrKeyhash(x) => (KEY_CREDENTIAL_TAG, x),
+            StakeCredential::ScriptHash(x) => (SCRIPT_CREDENTIAL_TAG, x),
+        };
+
+        let mut out = [0u8; CREDENTIAL_KEY_LEN];
+        out[0] = tag;
+        out[1..].copy_from_slice(hash.as_ref());
+        out
+    }
+
+    pub fn from_key_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != CREDENTIAL_KEY_LEN {
+            return None;
+        }
+
+        let hash = Hash::from(&bytes[1..]);
+
+        match bytes[0] {
+            KEY_CREDENTIAL_TAG => Some(StakeCredential::AddrKeyhash(hash)),
+            SCRIPT_CREDENTIAL_TAG => Some(StakeCredential::ScriptHash(hash)),
+            _ => None,
+        }
+    }
+}
+
+/// Builds the key of an asset id, see the module docs for the layout
+pub fn asset_key_bytes(policy: &PolicyId, name: &AssetName) -> Vec<u8> {
+    let mut out = Vec::with_capacity(POLICY_KEY_LEN + name.len());
+    out.extend_from_slice(policy.as_ref());
+    out.extend_from_slice(name);
+    out
+}
+
+/// Splits an asset id key back into its policy and asset name
+pub fn split_asset_key(bytes: &[u8]) -> Option<(PolicyId, AssetName)> {
+    if bytes.len() < POLICY_KEY_LEN {
+        return None;
+    }
+
+    let (policy, name) = bytes.split_at(POLICY_KEY_LEN);
+
+    Some((Hash::from(policy), name.to_vec().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_input_keys_preserve_order() {
+        let hash_a = Hash::from([1u8; 32]);
+        let hash_b = Hash::from([2u8; 32]);
+
+        let mut inputs = vec![
+            TransactionInput {
+                transaction_id: hash_b,
+                index: 0,
+            },
+            TransactionInput {
+                transaction_id: hash_a,
+                index: 256,
+            },
+            TransactionInput {
+                transaction_id: hash_a,
+                index: 1,
+            },
+        ];
+
+        let mut keys: Vec<_> = inputs.iter().map(|x| x.to_key_bytes()).collect();
+
+        inputs.sort();
+        keys.sort();
+
+        let decoded: Vec<_> = keys
+            .iter()
+            .map(|x| TransactionInput::from_key_bytes(x).unwrap())
+            .collect();
+
+        assert_eq!(inputs, decoded);
+    }
+
+    #[test]
+    fn credential_and_asset_keys_roundtrip() {
+        let hash = Hash::from([7u8; 28]);
+
+        let key = StakeCredential::AddrKeyhash(hash).to_key_bytes();
+        let script = StakeCredential::ScriptHash(hash).to_key_bytes();
+
+        assert!(key < script);
+        assert_eq!(
+            StakeCredential::from_key_bytes(&script),
+            Some(StakeCredential::ScriptHash(hash))
+        );
+        assert_eq!(StakeCredential::from_key_bytes(&[9u8; 29]), None);
+
+        let name: AssetName = b"token".to_vec().into();
+        let asset = asset_key_bytes(&hash, &name);
+
+        assert_eq!(asset.len(), 33);
+        assert_eq!(split_asset_key(&asset), Some((hash, name)));
+        assert_eq!(split_asset_key(&asset[..10]), None);
+    }
+}
@@ -1,23 +1,50 @@
 //! Ledger primitives and cbor codec for the Cardano eras
 
+#[cfg(feature = "json")]
+pub mod canonical_json;
 mod framework;
+#[cfg(test)]
+mod golden;
+mod key_order;
+pub mod keys;
 mod plutus_data;
+#[cfg(feature = "plutus-data-cache")]
+pub mod plutus_data_cache;
+mod plutus_script;
+mod rational;
 
+pub mod visit;
+
+#[cfg(feature = "alonzo")]
 pub mod alonzo;
+
+#[cfg(feature = "babbage")]
 pub mod babbage;
+
+#[cfg(feature = "byron")]
 pub mod byron;
+
+#[cfg(feature = "conway")]
 pub mod conway;
 pub use plutus_data::*;
+pub use plutus_script::{detect_wrapping, ScriptWrapping};
 
 pub use framework::*;
+pub use key_order::BodyKeyOrder;
+
+pub use pallas_codec::minicbor;
 
 pub use pallas_codec::utils::{
-    Bytes, Int, KeepRaw, KeyValuePairs, MaybeIndefArray, NonEmptyKeyValuePairs, NonEmptySet,
-    NonZeroInt, Nullable, PositiveCoin, Set,
+    AnyCbor, AnyUInt, Bytes, CborWrap, EmptyMap, Int, KeepRaw, KeyValuePairs, MaybeIndefArray,
+    NonEmptyKeyValuePairs, NonEmptySet, NonZeroInt, Nullable, PositiveCoin, Set, SkipCbor, TagWrap,
 };
 pub use pallas_crypto::hash::Hash;
 
-use pallas_codec::minicbor::{self, data::Tag, Decode, Encode};
+use pallas_codec::{
+    context::CodecContext,
+    minicbor::{data::Tag, Decode, Encode},
+    tagged_enum,
+};
 use serde::{Deserialize, Serialize};
 
 // ----- Common type definitions
@@ -55,6 +82,23 @@ pub struct ExUnitPrices {
 
 pub type Genesishash = Bytes;
 
+/// Adds (or replaces) the parameter changes proposed by a genesis delegate,
+/// keeping the proposals sorted by genesis hash. Backs the `Update` builders of
+/// the pre-Conway eras.
+pub(crate) fn propose_update<T: Clone>(
+    proposals: KeyValuePairs<Genesishash, T>,
+    genesis_hash: Genesishash,
+    update: T,
+) -> KeyValuePairs<Genesishash, T> {
+    let mut proposals = proposals.to_vec();
+
+    proposals.retain(|(k, _)| *k != genesis_hash);
+    proposals.push((genesis_hash, update));
+    proposals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    KeyValuePairs::Def(proposals)
+}
+
 pub type GenesisDelegateHash = Bytes;
 
 pub type IPv4 = Bytes;
@@ -72,7 +116,7 @@ pub enum Metadatum {
     Map(KeyValuePairs<Metadatum, Metadatum>),
 }
 
-impl<'b, C> minicbor::Decode<'b, C> for Metadatum {
+impl<'b, C: CodecContext> minicbor::Decode<'b, C> for Metadatum {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
             minicbor::data::Type::U8
@@ -84,7 +128,7 @@ impl<'b, C> minicbor::Decode<'b, C> for Metadatum {
             | minicbor::data::Type::I32
             | minicbor::data::Type::I64
             | minicbor::data::Type::Int => {
-                let i = d.decode()?;
+                let i = d.decode_with(ctx)?;
                 Ok(Metadatum::Int(i))
             }
             minicbor::data::Type::Bytes => Ok(Metadatum::Bytes(d.decode_with(ctx)?)),
@@ -222,9 +266,20 @@ pub struct RationalNumber {
 
 impl<'b, C> minicbor::decode::Decode<'b, C> for RationalNumber {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        // TODO: Enforce tag == 30 & array of size 2
-        d.tag()?;
-        d.array()?;
+        let tag = d.tag()?;
+
+        if tag != Tag::new(30) {
+            return Err(minicbor::decode::Error::message(
+                "rational number must be tagged with 30",
+            ));
+        }
+
+        if d.array()? != Some(2) {
+            return Err(minicbor::decode::Error::message(
+                "rational number must be a definite array of 2 elements",
+            ));
+        }
+
         Ok(RationalNumber {
             numerator: d.decode_with(ctx)?,
             denominator: d.decode_with(ctx)?,
@@ -246,68 +301,12 @@ impl<C> minicbor::encode::Encode<C> for RationalNumber {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-pub enum Relay {
-    SingleHostAddr(Nullable<Port>, Nullable<IPv4>, Nullable<IPv6>),
-    SingleHostName(Nullable<Port>, DnsName),
-    MultiHostName(DnsName),
-}
-
-impl<'b, C> minicbor::decode::Decode<'b, C> for Relay {
-    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
-        let variant = d.u16()?;
-
-        match variant {
-            0 => Ok(Relay::SingleHostAddr(
-                d.decode_with(ctx)?,
-                d.decode_with(ctx)?,
-                d.decode_with(ctx)?,
-            )),
-            1 => Ok(Relay::SingleHostName(
-                d.decode_with(ctx)?,
-                d.decode_with(ctx)?,
-            )),
-            2 => Ok(Relay::MultiHostName(d.decode_with(ctx)?)),
-            _ => Err(minicbor::decode::Error::message(
-                "invalid variant id for Relay",
-            )),
-        }
-    }
-}
-
-impl<C> minicbor::encode::Encode<C> for Relay {
-    fn encode<W: minicbor::encode::Write>(
-        &self,
-        e: &mut minicbor::Encoder<W>,
-        ctx: &mut C,
-    ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        match self {
-            Relay::SingleHostAddr(a, b, c) => {
-                e.array(4)?;
-                e.encode_with(0, ctx)?;
-                e.encode_with(a, ctx)?;
-                e.encode_with(b, ctx)?;
-                e.encode_with(c, ctx)?;
-
-                Ok(())
-            }
-            Relay::SingleHostName(a, b) => {
-                e.array(3)?;
-                e.encode_with(1, ctx)?;
-                e.encode_with(a, ctx)?;
-                e.encode_with(b, ctx)?;
-
-                Ok(())
-            }
-            Relay::MultiHostName(a) => {
-                e.array(2)?;
-                e.encode_with(2, ctx)?;
-                e.encode_with(a, ctx)?;
-
-                Ok(())
-            }
-        }
+tagged_enum! {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+    pub enum Relay {
+        0 => SingleHostAddr(port: Nullable<Port>, ipv4: Nullable<IPv4>, ipv6: Nullable<IPv6>),
+        1 => SingleHostName(port: Nullable<Port>, name: DnsName),
+        2 => MultiHostName(name: DnsName),
     }
 }
 
@@ -315,59 +314,21 @@ pub type RewardAccount = Bytes;
 
 pub type ScriptHash = Hash<28>;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Hash)]
-// !! NOTE / IMPORTANT !!
-// It is tempting to swap the order of the two constructors so that AddrKeyHash
-// comes first. This indeed nicely maps the binary representation which
-// associates 0 to AddrKeyHash and 1 to ScriptHash.
-//
-// However, for historical reasons, the ScriptHash variant comes first in the
-// Haskell reference codebase. From this ordering is derived the `PartialOrd`
-// and `Ord` instances; which impacts how Maps/Dictionnaries indexed by
-// StakeCredential will be ordered. So, it is crucial to preserve this quirks to
-// avoid hard to troubleshoot issues down the line.
-pub enum StakeCredential {
-    ScriptHash(ScriptHash),
-    AddrKeyhash(AddrKeyhash),
-}
-
-impl<'b, C> minicbor::decode::Decode<'b, C> for StakeCredential {
-    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
-        d.array()?;
-        let variant = d.u16()?;
-
-        match variant {
-            0 => Ok(StakeCredential::AddrKeyhash(d.decode_with(ctx)?)),
-            1 => Ok(StakeCredential::ScriptHash(d.decode_with(ctx)?)),
-            _ => Err(minicbor::decode::Error::message(
-                "invalid variant id for StakeCredential",
-            )),
-        }
-    }
-}
-
-impl<C> minicbor::encode::Encode<C> for StakeCredential {
-    fn encode<W: minicbor::encode::Write>(
-        &self,
-        e: &mut minicbor::Encoder<W>,
-        ctx: &mut C,
-    ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        match self {
-            StakeCredential::AddrKeyhash(a) => {
-                e.array(2)?;
-                e.encode_with(0, ctx)?;
-                e.encode_with(a, ctx)?;
-
-                Ok(())
-            }
-            StakeCredential::ScriptHash(a) => {
-                e.array(2)?;
-                e.encode_with(1, ctx)?;
-                e.encode_with(a, ctx)?;
-
-                Ok(())
-            }
-        }
+tagged_enum! {
+    #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Hash)]
+    // !! NOTE / IMPORTANT !!
+    // It is tempting to swap the order of the two constructors so that AddrKeyHash
+    // comes first. This indeed nicely maps the binary representation which
+    // associates 0 to AddrKeyHash and 1 to ScriptHash.
+    //
+    // However, for historical reasons, the ScriptHash variant comes first in the
+    // Haskell reference codebase. From this ordering is derived the `PartialOrd`
+    // and `Ord` instances; which impacts how Maps/Dictionnaries indexed by
+    // StakeCredential will be ordered. So, it is crucial to preserve this quirks to
+    // avoid hard to troubleshoot issues down the line.
+    pub enum StakeCredential {
+        1 => ScriptHash(hash: ScriptHash),
+        0 => AddrKeyhash(hash: AddrKeyhash),
     }
 }
 
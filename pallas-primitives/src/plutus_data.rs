@@ -1,6 +1,7 @@
 use crate::KeyValuePairs;
 use pallas_codec::utils::Int;
 use pallas_codec::{
+    context::CodecContext,
     minicbor::{
         self,
         data::{IanaTag, Tag},
@@ -20,7 +21,7 @@ pub enum PlutusData {
     Array(MaybeIndefArray<PlutusData>),
 }
 
-impl<'b, C> minicbor::decode::Decode<'b, C> for PlutusData {
+impl<'b, C: CodecContext> minicbor::decode::Decode<'b, C> for PlutusData {
     fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
         let type_ = d.datatype()?;
 
@@ -184,7 +185,7 @@ pub struct Constr<A> {
     pub fields: MaybeIndefArray<A>,
 }
 
-impl<'b, C, A> minicbor::decode::Decode<'b, C> for Constr<A>
+impl<'b, C: CodecContext, A> minicbor::decode::Decode<'b, C> for Constr<A>
 where
     A: minicbor::decode::Decode<'b, C>,
 {
@@ -0,0 +1,218 @@
+//! Shared, reusable PlutusData values
+//!
+//! Servers that build lots of datums tend to allocate the same small values
+//! over and over: units, booleans, `Nothing`, small integers. This module
+//! keeps a single copy of those constants behind an [`Arc`], so that handing
+//! one out is a reference count increment instead of a fresh allocation.
+//!
+//! [`PlutusDataInterner`] extends the same idea to arbitrary values that an
+//! application knows it will reuse, such as the datum of a script it serves.
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, OnceLock},
+};
+
+use pallas_codec::utils::{Int, KeyValuePairs, MaybeIndefArray};
+
+use crate::{BigInt, BoundedBytes, Constr, PlutusData};
+
+pub type SharedPlutusData = Arc<PlutusData>;
+
+/// Small integers kept in the constant cache, `0..=SMALL_INT_MAX`
+pub const SMALL_INT_MAX: i64 = 255;
+
+/// Builds a constr using the compact tag encoding for its index
+///
+/// Fields follow the encoding of the Haskell node: an indefinite array,
+/// unless it's empty. Since `PlutusData` owns its fields, they are moved out
+/// of their `Arc` when it isn't shared and copied otherwise, which for the
+/// constants of this module doesn't allocate.
+pub fn constr(index: u64, fields: Vec<SharedPlutusData>) -> SharedPlutusData {
+    let (tag, any_constructor) = match index {
+        0..=6 => (121 + index, None),
+        7..=127 => (1280 + index - 7, None),
+        _ => (102, Some(index)),
+    };
+
+    let fields: Vec<_> = fields.into_iter().map(Arc::unwrap_or_clone).collect();
+
+    Arc::new(PlutusData::Constr(Constr {
+        tag,
+        any_constructor,
+        fields: match fields.is_empty() {
+            true => MaybeIndefArray::Def(fields),
+            false => MaybeIndefArray::Indef(fields),
+        },
+    }))
+}
+
+struct Constants {
+    false_: SharedPlutusData,
+    true_: SharedPlutusData,
+    empty_bytes: SharedPlutusData,
+    empty_list: SharedPlutusData,
+    empty_map: SharedPlutusData,
+    ints: Vec<SharedPlutusData>,
+}
+
+fn constants() -> &'static Constants {
+    static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+
+    CONSTANTS.get_or_init(|| Constants {
+        false_: constr(0, vec![]),
+        true_: constr(1, vec![]),
+        empty_bytes: Arc::new(PlutusData::BoundedBytes(BoundedBytes::from(vec![]))),
+        empty_list: Arc::new(PlutusData::Array(MaybeIndefArray::Def(vec![]))),
+        empty_map: Arc::new(PlutusData::Map(KeyValuePairs::Def(vec![]))),
+        ints: (0..=SMALL_INT_MAX)
+            .map(|i| Arc::new(PlutusData::BigInt(BigInt::Int(Int::from(i)))))
+            .collect(),
+    })
+}
+
+/// `()`, encoded as `Constr 0 []`
+pub fn unit() -> SharedPlutusData {
+    constants().false_.clone()
+}
+
+pub fn boolean(value: bool) -> SharedPlutusData {
+    match value {
+        true => constants().true_.clone(),
+        false => constants().false_.clone(),
+    }
+}
+
+/// `Nothing` of the Plutus `Maybe` type, encoded as `Constr 1 []`
+pub fn nothing() -> SharedPlutusData {
+    constants().true_.clone()
+}
+
+pub fn empty_bytes() -> SharedPlutusData {
+    constants().empty_bytes.clone()
+}
+
+pub fn empty_list() -> SharedPlutusData {
+    constants().empty_list.clone()
+}
+
+pub fn empty_map() -> SharedPlutusData {
+    constants().empty_map.clone()
+}
+
+/// Integer value, shared when it's in `0..=SMALL_INT_MAX`
+pub fn int(value: i64) -> SharedPlutusData {
+    match usize::try_from(value) {
+        Ok(i) if value <= SMALL_INT_MAX => constants().ints[i].clone(),
+        _ => Arc::new(PlutusData::BigInt(BigInt::Int(Int::from(value)))),
+    }
+}
+
+/// Deduplicates values so that equal datums share a single allocation
+#[derive(Debug, Default)]
+pub struct PlutusDataInterner {
+    entries: BTreeSet<SharedPlutusData>,
+}
+
+impl PlutusDataInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of the value, storing it if it's new
+    pub fn intern(&mut self, value: impl Into<SharedPlutusData>) -> SharedPlutusData {
+        let value = value.into();
+
+        if let Some(x) = self.entries.get(&value) {
+            return x.clone();
+        }
+
+        self.entries.insert(value.clone());
+        value
+    }
+
+    /// Drops the values that aren't referenced outside of the interner
+    pub fn purge(&mut self) {
+        self.entries.retain(|x| Arc::strong_count(x) > 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_codec::minicbor;
+
+    #[test]
+    fn constants_are_shared() {
+        assert!(Arc::ptr_eq(&unit(), &unit()));
+        assert!(Arc::ptr_eq(&boolean(false), &unit()));
+        assert!(Arc::ptr_eq(&boolean(true), &nothing()));
+        assert!(Arc::ptr_eq(&int(42), &int(42)));
+        assert!(!Arc::ptr_eq(&int(-1), &int(-1)));
+        assert!(!Arc::ptr_eq(&int(256), &int(256)));
+
+        assert_eq!(
+            minicbor::to_vec(&*unit()).unwrap(),
+            hex::decode("d87980").unwrap()
+        );
+        assert_eq!(
+            minicbor::to_vec(&*boolean(true)).unwrap(),
+            hex::decode("d87a80").unwrap()
+        );
+        assert_eq!(minicbor::to_vec(&*int(7)).unwrap(), [0x07]);
+    }
+
+    #[test]
+    fn constr_tags() {
+        let tag = |i| match &*constr(i, vec![]) {
+            PlutusData::Constr(x) => (x.tag, x.any_constructor),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(tag(0), (121, None));
+        assert_eq!(tag(6), (127, None));
+        assert_eq!(tag(7), (1280, None));
+        assert_eq!(tag(127), (1400, None));
+        assert_eq!(tag(128), (102, Some(128)));
+    }
+
+    #[test]
+    fn constr_of_shared_fields() {
+        let inner = constr(0, vec![int(300)]);
+        let outer = constr(1, vec![unit(), inner, int(7)]);
+
+        assert_eq!(
+            minicbor::to_vec(&*outer).unwrap(),
+            hex::decode("d87a9fd87980d8799f19012cff07ff").unwrap()
+        );
+
+        // the cached constants are left untouched
+        assert!(Arc::ptr_eq(&unit(), &boolean(false)));
+        assert_eq!(*int(7), PlutusData::BigInt(BigInt::Int(Int::from(7))));
+    }
+
+    #[test]
+    fn interner_dedups() {
+        let mut interner = PlutusDataInterner::new();
+
+        let a = interner.intern(constr(0, vec![int(1)]));
+        let b = interner.intern(constr(0, vec![int(1)]));
+        let c = interner.intern(constr(1, vec![]));
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(interner.len(), 2);
+
+        drop(c);
+        interner.purge();
+        assert_eq!(interner.len(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+//! Helpers to deal with the different CBOR wrappings plutus scripts are
+//! shipped with. The ledger expects the flat-encoded program wrapped once in
+//! a CBOR byte string, but tools usually export it wrapped twice (e.g. the
+//! `cborHex` of cardano-cli text envelopes), and mixing them up produces the
+//! wrong script hash.
+
+use pallas_codec::minicbor;
+
+use crate::{Bytes, PlutusScript};
+
+/// Number of CBOR byte string layers around a flat-encoded program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptWrapping {
+    /// The raw flat-encoded program
+    Unwrapped,
+    /// The ledger representation, a byte string holding the program
+    Single,
+    /// A byte string holding the ledger representation
+    Double,
+}
+
+/// Returns the contents of `bytes` if they are exactly one definite CBOR
+/// byte string
+fn strip_bytes_layer(bytes: &[u8]) -> Option<&[u8]> {
+    let (&head, rest) = bytes.split_first()?;
+
+    if head >> 5 != 2 {
+        return None;
+    }
+
+    let (len, rest) = match head & 0x1f {
+        n @ 0..=23 => (n as usize, rest),
+        24 => (*rest.first()? as usize, &rest[1..]),
+        25 => (
+            u16::from_be_bytes(rest.get(..2)?.try_into().ok()?) as usize,
+            &rest[2..],
+        ),
+        26 => (
+            u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize,
+            &rest[4..],
+        ),
+        27 => (
+            usize::try_from(u64::from_be_bytes(rest.get(..8)?.try_into().ok()?)).ok()?,
+            &rest[8..],
+        ),
+        _ => return None,
+    };
+
+    (rest.len() == len).then_some(rest)
+}
+
+/// Tells how many byte string layers wrap a flat-encoded program. Flat
+/// programs start with their version, so they never look like a byte string.
+pub fn detect_wrapping(bytes: &[u8]) -> ScriptWrapping {
+    match strip_bytes_layer(bytes) {
+        None => ScriptWrapping::Unwrapped,
+        Some(inner) if strip_bytes_layer(inner).is_some() => ScriptWrapping::Double,
+        Some(_) => ScriptWrapping::Single,
+    }
+}
+
+fn wrap_bytes_layer(bytes: &[u8]) -> Vec<u8> {
+    let mut buf = vec![];
+    minicbor::Encoder::new(&mut buf).bytes(bytes).unwrap();
+    buf
+}
+
+impl<const VERSION: usize> PlutusScript<VERSION> {
+    /// Builds a script from bytes with any wrapping, normalizing them to the
+    /// ledger representation
+    pub fn from_any_wrapping(bytes: &[u8]) -> Self {
+        let bytes = match detect_wrapping(bytes) {
+            ScriptWrapping::Unwrapped => wrap_bytes_layer(bytes),
+            ScriptWrapping::Single => bytes.to_vec(),
+            ScriptWrapping::Double => strip_bytes_layer(bytes).unwrap().to_vec(),
+        };
+
+        Self(Bytes::from(bytes))
+    }
+
+    /// Builds a script from the hex of a text envelope (`cborHex`) or of a
+    /// blueprint (`compiledCode`), whichever its wrapping
+    pub fn from_hex_envelope(hex: &str) -> Result<Self, hex::FromHexError> {
+        Ok(Self::from_any_wrapping(&hex::decode(hex)?))
+    }
+
+    /// Hex of the script wrapped twice, as found in text envelopes
+    pub fn to_hex_envelope(&self) -> String {
+        hex::encode(wrap_bytes_layer(self.as_ref()))
+    }
+
+    /// Wrapping of the contained bytes, anything but `Single` means the
+    /// script was not built from the ledger representation
+    pub fn wrapping(&self) -> ScriptWrapping {
+        detect_wrapping(self.as_ref())
+    }
+
+    /// The flat-encoded program, if the script holds the ledger
+    /// representation
+    pub fn unwrap_inner(&self) -> Option<&[u8]> {
+        match self.wrapping() {
+            ScriptWrapping::Single => strip_bytes_layer(self.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping_normalization() {
+        // flat-encoded programs start with their version (1.0.0)
+        let flat = hex::decode("01000022222222222226").unwrap();
+        let single = wrap_bytes_layer(&flat);
+        let double = wrap_bytes_layer(&single);
+
+        assert_eq!(detect_wrapping(&flat), ScriptWrapping::Unwrapped);
+        assert_eq!(detect_wrapping(&single), ScriptWrapping::Single);
+        assert_eq!(detect_wrapping(&double), ScriptWrapping::Double);
+
+        for bytes in [&flat, &single, &double] {
+            let script = PlutusScript::<2>::from_hex_envelope(&hex::encode(bytes)).unwrap();
+
+            assert_eq!(script.as_ref(), single.as_slice());
+            assert_eq!(script.unwrap_inner(), Some(flat.as_slice()));
+            assert_eq!(script.to_hex_envelope(), hex::encode(&double));
+        }
+
+        let wrong = PlutusScript::<2>(double.into());
+        assert_eq!(wrong.wrapping(), ScriptWrapping::Double);
+        assert_eq!(wrong.unwrap_inner(), None);
+    }
+
+    #[test]
+    fn long_byte_string_headers() {
+        let flat = vec![0x01; 300];
+        let single = wrap_bytes_layer(&flat);
+
+        assert_eq!(&single[..3], &[0x59, 0x01, 0x2c]);
+        assert_eq!(strip_bytes_layer(&single), Some(flat.as_slice()));
+        assert_eq!(strip_bytes_layer(&single[..200]), None);
+    }
+}
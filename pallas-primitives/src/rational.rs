@@ -0,0 +1,253 @@
+//! Exact arithmetic for the rational protocol parameters (pool margins,
+//! ex-unit prices, decentralization), mirroring the `Rational` math of the
+//! ledger so that fees match the node to the lovelace.
+
+use crate::{Coin, ExUnitPrices, ExUnits, RationalNumber};
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Reduces `num / den` and fits it back into a `RationalNumber`
+fn reduced(num: u128, den: u128) -> Option<RationalNumber> {
+    if den == 0 {
+        return None;
+    }
+
+    let d = gcd(num, den).max(1);
+
+    Some(RationalNumber {
+        numerator: (num / d).try_into().ok()?,
+        denominator: (den / d).try_into().ok()?,
+    })
+}
+
+impl RationalNumber {
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Equivalent rational with coprime numerator and denominator. `None` on
+    /// a zero denominator.
+    pub fn normalized(&self) -> Option<RationalNumber> {
+        reduced(self.numerator as u128, self.denominator as u128)
+    }
+
+    /// Whether the value lies in `[0, 1]`, as required for unit intervals
+    pub fn is_unit_interval(&self) -> bool {
+        self.denominator != 0 && self.numerator <= self.denominator
+    }
+
+    /// Compares the values of two rationals, unlike `==` which compares the
+    /// encoded numerator and denominator. `None` on a zero denominator.
+    pub fn value_cmp(&self, other: &RationalNumber) -> Option<std::cmp::Ordering> {
+        if self.denominator == 0 || other.denominator == 0 {
+            return None;
+        }
+
+        let a = self.numerator as u128 * other.denominator as u128;
+        let b = other.numerator as u128 * self.denominator as u128;
+
+        Some(a.cmp(&b))
+    }
+
+    pub fn checked_add(&self, other: &RationalNumber) -> Option<RationalNumber> {
+        let (an, ad) = (self.numerator as u128, self.denominator as u128);
+        let (bn, bd) = (other.numerator as u128, other.denominator as u128);
+
+        reduced((an * bd).checked_add(bn * ad)?, ad * bd)
+    }
+
+    /// Difference of the rationals, `None` if it would be negative
+    pub fn checked_sub(&self, other: &RationalNumber) -> Option<RationalNumber> {
+        let (an, ad) = (self.numerator as u128, self.denominator as u128);
+        let (bn, bd) = (other.numerator as u128, other.denominator as u128);
+
+        reduced((an * bd).checked_sub(bn * ad)?, ad * bd)
+    }
+
+    pub fn checked_mul(&self, other: &RationalNumber) -> Option<RationalNumber> {
+        reduced(
+            self.numerator as u128 * other.numerator as u128,
+            self.denominator as u128 * other.denominator as u128,
+        )
+    }
+
+    pub fn checked_div(&self, other: &RationalNumber) -> Option<RationalNumber> {
+        if self.denominator == 0 {
+            return None;
+        }
+
+        reduced(
+            self.numerator as u128 * other.denominator as u128,
+            self.denominator as u128 * other.numerator as u128,
+        )
+    }
+
+    /// Product of the rational by an integer, rounded down. `None` on
+    /// overflow or on a zero denominator.
+    pub fn mul_floor(&self, value: u64) -> Option<u64> {
+        if self.denominator == 0 {
+            return None;
+        }
+
+        let num = (self.numerator as u128).checked_mul(value as u128)?;
+        (num / self.denominator as u128).try_into().ok()
+    }
+
+    /// Product of the rational by an integer, rounded up. `None` on overflow
+    /// or on a zero denominator.
+    pub fn mul_ceil(&self, value: u64) -> Option<u64> {
+        if self.denominator == 0 {
+            return None;
+        }
+
+        let num = (self.numerator as u128).checked_mul(value as u128)?;
+        num.div_ceil(self.denominator as u128).try_into().ok()
+    }
+}
+
+impl ExUnits {
+    pub fn checked_add(&self, other: &ExUnits) -> Option<ExUnits> {
+        Some(ExUnits {
+            mem: self.mem.checked_add(other.mem)?,
+            steps: self.steps.checked_add(other.steps)?,
+        })
+    }
+
+    /// Whether these units fit within the `max` budget
+    pub fn fits(&self, max: &ExUnits) -> bool {
+        self.mem <= max.mem && self.steps <= max.steps
+    }
+}
+
+impl std::iter::Sum for ExUnits {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(ExUnits { mem: 0, steps: 0 }, |acc, x| ExUnits {
+            mem: acc.mem.saturating_add(x.mem),
+            steps: acc.steps.saturating_add(x.steps),
+        })
+    }
+}
+
+impl ExUnitPrices {
+    /// Fee charged for executing scripts with the given budget, i.e.
+    /// `ceiling (mem_price * mem + step_price * steps)`. The sum is computed
+    /// exactly before rounding, rounding each term apart would overcharge.
+    /// `None` on overflow or on a zero denominator.
+    pub fn script_fee(&self, units: &ExUnits) -> Option<Coin> {
+        let (mn, md) = (
+            self.mem_price.numerator as u128,
+            self.mem_price.denominator as u128,
+        );
+        let (sn, sd) = (
+            self.step_price.numerator as u128,
+            self.step_price.denominator as u128,
+        );
+
+        if md == 0 || sd == 0 {
+            return None;
+        }
+
+        // mn * mem / md + sn * steps / sd over the common denominator md * sd
+        let mem = mn.checked_mul(units.mem as u128)?.checked_mul(sd)?;
+        let steps = sn.checked_mul(units.steps as u128)?.checked_mul(md)?;
+        let num = mem.checked_add(steps)?;
+
+        num.div_ceil(md.checked_mul(sd)?).try_into().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    fn ratio(numerator: u64, denominator: u64) -> RationalNumber {
+        RationalNumber::new(numerator, denominator)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let half = ratio(1, 2);
+        let third = ratio(2, 6);
+
+        assert_eq!(third.normalized(), Some(ratio(1, 3)));
+        assert_eq!(half.checked_add(&third), Some(ratio(5, 6)));
+        assert_eq!(half.checked_sub(&third), Some(ratio(1, 6)));
+        assert_eq!(third.checked_sub(&half), None);
+        assert_eq!(half.checked_mul(&third), Some(ratio(1, 6)));
+        assert_eq!(third.checked_div(&half), Some(ratio(2, 3)));
+        assert_eq!(half.checked_div(&ratio(0, 1)), None);
+
+        assert_eq!(half.value_cmp(&ratio(2, 4)), Some(Ordering::Equal));
+        assert_eq!(third.value_cmp(&half), Some(Ordering::Less));
+        assert_eq!(half.value_cmp(&ratio(1, 0)), None);
+
+        assert_eq!(ratio(2, 3).mul_floor(10), Some(6));
+        assert_eq!(ratio(2, 3).mul_ceil(10), Some(7));
+
+        assert!(half.is_unit_interval());
+        assert!(!ratio(3, 2).is_unit_interval());
+    }
+
+    #[test]
+    fn tag_30_encoding() {
+        use pallas_codec::minicbor;
+
+        let bytes = minicbor::to_vec(ratio(1, 2)).unwrap();
+        assert_eq!(hex::encode(&bytes), "d81e820102");
+        assert_eq!(
+            minicbor::decode::<RationalNumber>(&bytes).unwrap(),
+            ratio(1, 2)
+        );
+
+        // untagged or with extra elements
+        assert!(minicbor::decode::<RationalNumber>(&hex::decode("c1820102").unwrap()).is_err());
+        assert!(minicbor::decode::<RationalNumber>(&hex::decode("d81e83010203").unwrap()).is_err());
+    }
+
+    #[test]
+    fn mainnet_script_fee() {
+        let prices = ExUnitPrices {
+            mem_price: ratio(577, 10_000),
+            step_price: ratio(721, 10_000_000),
+        };
+
+        let units = ExUnits {
+            mem: 1_234_567,
+            steps: 987_654_321,
+        };
+
+        // 71234.51590 + 71209.87654 = 142444.39244, rounded up once
+        assert_eq!(prices.script_fee(&units), Some(142_445));
+
+        assert_eq!(prices.mem_price.mul_ceil(units.mem), Some(71_235));
+        assert_eq!(prices.step_price.mul_ceil(units.steps), Some(71_210));
+    }
+
+    #[test]
+    fn budgets() {
+        let a = ExUnits { mem: 1, steps: 10 };
+        let b = ExUnits { mem: 2, steps: 20 };
+
+        let total: ExUnits = [a, b].into_iter().sum();
+        assert_eq!(total, ExUnits { mem: 3, steps: 30 });
+        assert_eq!(a.checked_add(&b), Some(total));
+        assert!(a.fits(&total) && !total.fits(&a));
+
+        let zero = ExUnitPrices {
+            mem_price: ratio(1, 0),
+            step_price: ratio(1, 1),
+        };
+        assert_eq!(zero.script_fee(&a), None);
+    }
+}
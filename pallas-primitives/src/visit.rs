@@ -0,0 +1,171 @@
+//! Borrowed traversal of the strings and bytes embedded in metadata and
+//! plutus data, meant for indexing on-chain payloads without cloning them.
+
+use crate::{Metadatum, PlutusData};
+
+/// A step in the path from the root of a structure to one of its leaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Position of an item in an array (or of a field in a constructor)
+    Index(usize),
+    /// Key of the n-th entry of a map
+    Key(usize),
+    /// Value of the n-th entry of a map
+    Value(usize),
+}
+
+/// A string or byte sequence found inside a structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leaf<'a> {
+    Text(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// Visits every string or byte sequence embedded in a structure, along with
+/// the path leading to it. The path slice is only valid during the call.
+pub trait VisitLeaves {
+    fn visit_leaves<'a, F>(&'a self, visitor: &mut F)
+    where
+        F: FnMut(&[PathSegment], Leaf<'a>),
+    {
+        let mut path = Vec::new();
+        self.visit_leaves_at(&mut path, visitor);
+    }
+
+    fn visit_leaves_at<'a, F>(&'a self, path: &mut Vec<PathSegment>, visitor: &mut F)
+    where
+        F: FnMut(&[PathSegment], Leaf<'a>);
+}
+
+fn visit_child<'a, T, F>(
+    child: &'a T,
+    segment: PathSegment,
+    path: &mut Vec<PathSegment>,
+    visitor: &mut F,
+) where
+    T: VisitLeaves,
+    F: FnMut(&[PathSegment], Leaf<'a>),
+{
+    path.push(segment);
+    child.visit_leaves_at(path, visitor);
+    path.pop();
+}
+
+impl VisitLeaves for Metadatum {
+    fn visit_leaves_at<'a, F>(&'a self, path: &mut Vec<PathSegment>, visitor: &mut F)
+    where
+        F: FnMut(&[PathSegment], Leaf<'a>),
+    {
+        match self {
+            Metadatum::Int(_) => (),
+            Metadatum::Bytes(x) => visitor(path, Leaf::Bytes(x)),
+            Metadatum::Text(x) => visitor(path, Leaf::Text(x)),
+            Metadatum::Array(x) => {
+                for (i, item) in x.iter().enumerate() {
+                    visit_child(item, PathSegment::Index(i), path, visitor);
+                }
+            }
+            Metadatum::Map(x) => {
+                for (i, (k, v)) in x.iter().enumerate() {
+                    visit_child(k, PathSegment::Key(i), path, visitor);
+                    visit_child(v, PathSegment::Value(i), path, visitor);
+                }
+            }
+        }
+    }
+}
+
+impl VisitLeaves for PlutusData {
+    fn visit_leaves_at<'a, F>(&'a self, path: &mut Vec<PathSegment>, visitor: &mut F)
+    where
+        F: FnMut(&[PathSegment], Leaf<'a>),
+    {
+        match self {
+            // the bytes of big integers are not meaningful as byte sequences
+            PlutusData::BigInt(_) => (),
+            PlutusData::BoundedBytes(x) => visitor(path, Leaf::Bytes(x)),
+            PlutusData::Array(x) => {
+                for (i, item) in x.iter().enumerate() {
+                    visit_child(item, PathSegment::Index(i), path, visitor);
+                }
+            }
+            PlutusData::Constr(x) => {
+                for (i, item) in x.fields.iter().enumerate() {
+                    visit_child(item, PathSegment::Index(i), path, visitor);
+                }
+            }
+            PlutusData::Map(x) => {
+                for (i, (k, v)) in x.iter().enumerate() {
+                    visit_child(k, PathSegment::Key(i), path, visitor);
+                    visit_child(v, PathSegment::Value(i), path, visitor);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BigInt, Constr, KeyValuePairs, MaybeIndefArray};
+
+    #[test]
+    fn metadatum_leaves_with_paths() {
+        let metadatum = Metadatum::Map(KeyValuePairs::Def(vec![
+            (
+                Metadatum::Text("msg".into()),
+                Metadatum::Array(vec![
+                    Metadatum::Text("hello".into()),
+                    Metadatum::Int(1.into()),
+                    Metadatum::Bytes(vec![0xca, 0xfe].into()),
+                ]),
+            ),
+            (Metadatum::Int(2.into()), Metadatum::Text("world".into())),
+        ]));
+
+        let mut leaves = vec![];
+
+        metadatum.visit_leaves(&mut |path, leaf| leaves.push((path.to_vec(), leaf)));
+
+        use PathSegment::*;
+
+        assert_eq!(
+            leaves,
+            vec![
+                (vec![Key(0)], Leaf::Text("msg")),
+                (vec![Value(0), Index(0)], Leaf::Text("hello")),
+                (vec![Value(0), Index(2)], Leaf::Bytes(&[0xca, 0xfe])),
+                (vec![Value(1)], Leaf::Text("world")),
+            ]
+        );
+    }
+
+    #[test]
+    fn plutus_data_leaves_with_paths() {
+        let data = PlutusData::Constr(Constr {
+            tag: 121,
+            any_constructor: None,
+            fields: MaybeIndefArray::Indef(vec![
+                PlutusData::BoundedBytes(vec![1, 2].into()),
+                PlutusData::Array(MaybeIndefArray::Def(vec![
+                    PlutusData::BigInt(BigInt::Int(5.into())),
+                    PlutusData::BoundedBytes(vec![3].into()),
+                ])),
+            ]),
+        });
+
+        let mut leaves = vec![];
+
+        data.visit_leaves(&mut |path, leaf| leaves.push((path.to_vec(), leaf)));
+
+        use PathSegment::*;
+
+        assert_eq!(
+            leaves,
+            vec![
+                (vec![Index(0)], Leaf::Bytes(&[1, 2])),
+                (vec![Index(1), Index(1)], Leaf::Bytes(&[3])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Per-epoch aggregation over a stream of blocks
+
+use std::collections::BTreeMap;
+
+use pallas_crypto::hash::Hash;
+
+use crate::{time::Epoch, wellknown::GenesisValues, MultiEraBlock, MultiEraTx};
+
+/// Aggregated figures for all the blocks seen within a single epoch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EpochSummary {
+    pub epoch: Epoch,
+    pub blocks: u64,
+    pub txs: u64,
+    pub invalid_txs: u64,
+    pub script_txs: u64,
+    /// Fees collected by the ledger. For phase-2 invalid txs this is the
+    /// declared total collateral, since resolving collateral inputs would
+    /// require access to the UTxO set.
+    pub fees: u64,
+    /// Net minted quantity per (policy, asset name); burns are negative
+    pub minted: BTreeMap<(Hash<28>, Vec<u8>), i128>,
+}
+
+impl EpochSummary {
+    pub fn new(epoch: Epoch) -> Self {
+        Self {
+            epoch,
+            ..Default::default()
+        }
+    }
+
+    fn add_tx(&mut self, tx: &MultiEraTx) {
+        self.txs += 1;
+
+        if is_script_tx(tx) {
+            self.script_txs += 1;
+        }
+
+        if !tx.is_valid() {
+            self.invalid_txs += 1;
+            self.fees += tx.total_collateral().unwrap_or_default();
+            return;
+        }
+
+        self.fees += tx.fee().unwrap_or_default();
+
+        for policy in tx.mints() {
+            for asset in policy.assets() {
+                if let Some(qty) = asset.mint_coin() {
+                    *self
+                        .minted
+                        .entry((*asset.policy(), asset.name().to_vec()))
+                        .or_default() += qty as i128;
+                }
+            }
+        }
+    }
+
+    /// Adds the figures of a block to this summary, regardless of its epoch
+    pub fn add_block(&mut self, block: &MultiEraBlock) {
+        self.blocks += 1;
+
+        for tx in block.txs() {
+            self.add_tx(&tx);
+        }
+    }
+}
+
+/// A tx is considered a script tx if it carries redeemers or any script
+/// witness (native or plutus)
+fn is_script_tx(tx: &MultiEraTx) -> bool {
+    !tx.redeemers().is_empty()
+        || !tx.native_scripts().is_empty()
+        || !tx.plutus_v1_scripts().is_empty()
+        || !tx.plutus_v2_scripts().is_empty()
+        || !tx.plutus_v3_scripts().is_empty()
+}
+
+/// Folds blocks into per-epoch summaries
+///
+/// Blocks don't need to arrive in order; summaries are keyed by epoch and
+/// returned sorted.
+pub struct EpochAggregator {
+    genesis: GenesisValues,
+    epochs: BTreeMap<Epoch, EpochSummary>,
+}
+
+impl EpochAggregator {
+    pub fn new(genesis: GenesisValues) -> Self {
+        Self {
+            genesis,
+            epochs: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_block(&mut self, block: &MultiEraBlock) {
+        let (epoch, _) = block.epoch(&self.genesis);
+
+        self.epochs
+            .entry(epoch)
+            .or_insert_with(|| EpochSummary::new(epoch))
+            .add_block(block);
+    }
+
+    pub fn get(&self, epoch: Epoch) -> Option<&EpochSummary> {
+        self.epochs.get(&epoch)
+    }
+
+    pub fn finish(self) -> Vec<EpochSummary> {
+        self.epochs.into_values().collect()
+    }
+}
+
+impl<'b> Extend<MultiEraBlock<'b>> for EpochAggregator {
+    fn extend<T: IntoIterator<Item = MultiEraBlock<'b>>>(&mut self, iter: T) {
+        for block in iter {
+            self.add_block(&block);
+        }
+    }
+}
+
+/// Consumes a block stream and returns the per-epoch summaries
+pub fn aggregate_epochs<'b>(
+    genesis: GenesisValues,
+    blocks: impl IntoIterator<Item = MultiEraBlock<'b>>,
+) -> Vec<EpochSummary> {
+    let mut aggregator = EpochAggregator::new(genesis);
+    aggregator.extend(blocks);
+    aggregator.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(block_str: &str) -> Vec<u8> {
+        hex::decode(block_str).unwrap()
+    }
+
+    #[test]
+    fn aggregates_by_epoch() {
+        let raw = [
+            decode(include_str!("../../test_data/mary1.block")),
+            decode(include_str!("../../test_data/alonzo1.block")),
+            decode(include_str!("../../test_data/alonzo27.block")),
+        ];
+
+        let blocks: Vec<_> = raw
+            .iter()
+            .map(|x| MultiEraBlock::decode(x).unwrap())
+            .collect();
+
+        let genesis = GenesisValues::mainnet();
+        let summaries = aggregate_epochs(genesis.clone(), blocks.iter().cloned());
+
+        let total_blocks: u64 = summaries.iter().map(|x| x.blocks).sum();
+        assert_eq!(total_blocks, 3);
+
+        let total_txs: usize = blocks.iter().map(|x| x.tx_count()).sum();
+        assert_eq!(
+            summaries.iter().map(|x| x.txs).sum::<u64>(),
+            total_txs as u64
+        );
+
+        for block in blocks.iter() {
+            let (epoch, _) = block.epoch(&genesis);
+            let summary = summaries.iter().find(|x| x.epoch == epoch).unwrap();
+
+            let fees: u64 = block
+                .txs()
+                .iter()
+                .filter(|tx| tx.is_valid())
+                .filter_map(|tx| tx.fee())
+                .sum();
+
+            assert_eq!(summary.fees, fees);
+        }
+
+        let figures: Vec<_> = summaries
+            .iter()
+            .map(|x| (x.epoch, x.blocks, x.txs, x.fees))
+            .collect();
+
+        assert_eq!(
+            figures,
+            vec![
+                (260, 1, 14, 3_091_185),
+                (298, 1, 5, 872_957),
+                (310, 1, 21, 6_048_914),
+            ]
+        );
+
+        assert!(summaries.windows(2).all(|x| x[0].epoch < x[1].epoch));
+    }
+}
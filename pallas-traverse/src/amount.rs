@@ -0,0 +1,187 @@
+//! Human-readable asset quantities
+//!
+//! On-chain quantities are integers; decimals are only a presentation
+//! convention defined off-chain. [`AssetAmount`] renders a quantity with a
+//! given number of decimals using integer math only, so that no precision is
+//! lost on large amounts.
+
+use std::fmt;
+
+use crate::{
+    asset_registry::{AssetMetadata, AssetMetadataProvider},
+    MultiEraAsset,
+};
+
+/// Decimals of ada when expressed in lovelace
+pub const ADA_DECIMALS: u8 = 6;
+
+/// Quantity of an asset to be displayed, e.g. `1,234.56 TOKEN`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetAmount {
+    quantity: i128,
+    decimals: u8,
+    ticker: Option<String>,
+    grouping: bool,
+}
+
+impl AssetAmount {
+    pub fn new(quantity: i128, decimals: u8) -> Self {
+        Self {
+            quantity,
+            decimals,
+            ticker: None,
+            grouping: true,
+        }
+    }
+
+    pub fn lovelace(quantity: u64) -> Self {
+        Self::new(quantity as i128, ADA_DECIMALS).with_ticker("ADA")
+    }
+
+    /// Takes decimals and ticker from registry metadata, defaulting to zero
+    /// decimals when the registry doesn't define them
+    pub fn from_metadata(quantity: i128, metadata: &AssetMetadata) -> Self {
+        let amount = Self::new(quantity, metadata.decimals.unwrap_or_default());
+
+        match &metadata.ticker {
+            Some(ticker) => amount.with_ticker(ticker),
+            None => amount,
+        }
+    }
+
+    pub fn with_ticker(mut self, ticker: impl Into<String>) -> Self {
+        self.ticker = Some(ticker.into());
+        self
+    }
+
+    /// Enables or disables the `,` thousands separator, enabled by default
+    pub fn with_grouping(mut self, grouping: bool) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    pub fn quantity(&self) -> i128 {
+        self.quantity
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Integer and fractional parts of the amount, as digit strings
+    fn parts(&self) -> (String, String) {
+        let digits = self.quantity.unsigned_abs().to_string();
+        let decimals = self.decimals as usize;
+
+        if decimals == 0 {
+            return (digits, String::new());
+        }
+
+        let padded = format!("{digits:0>width$}", width = decimals + 1);
+        let (int, frac) = padded.split_at(padded.len() - decimals);
+
+        (int.to_owned(), frac.to_owned())
+    }
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    let lead = digits.len() % 3;
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && i % 3 == lead {
+            out.push(',');
+        }
+
+        out.push(c);
+    }
+
+    out
+}
+
+impl fmt::Display for AssetAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (int, frac) = self.parts();
+
+        if self.quantity < 0 {
+            write!(f, "-")?;
+        }
+
+        match self.grouping {
+            true => write!(f, "{}", group_thousands(&int))?,
+            false => write!(f, "{int}")?,
+        }
+
+        if !frac.is_empty() {
+            write!(f, ".{frac}")?;
+        }
+
+        if let Some(ticker) = &self.ticker {
+            write!(f, " {ticker}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'b> MultiEraAsset<'b> {
+    /// Amount of the asset decorated with registry metadata; assets unknown to
+    /// the provider are shown as plain integers
+    pub fn amount(&self, provider: &impl AssetMetadataProvider) -> AssetAmount {
+        match self.metadata(provider) {
+            Some(metadata) => AssetAmount::from_metadata(self.any_coin(), &metadata),
+            None => AssetAmount::new(self.any_coin(), 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_use_exact_decimals() {
+        assert_eq!(
+            AssetAmount::new(123456, 2).with_ticker("TOKEN").to_string(),
+            "1,234.56 TOKEN"
+        );
+        assert_eq!(AssetAmount::new(5, 3).to_string(), "0.005");
+        assert_eq!(AssetAmount::new(-1000, 0).to_string(), "-1,000");
+        assert_eq!(AssetAmount::new(-50, 2).to_string(), "-0.50");
+        assert_eq!(AssetAmount::new(0, 6).to_string(), "0.000000");
+        assert_eq!(
+            AssetAmount::lovelace(45_000_000_000_000_000).to_string(),
+            "45,000,000,000.000000 ADA"
+        );
+        assert_eq!(
+            AssetAmount::new(i128::MAX, 18)
+                .with_grouping(false)
+                .to_string(),
+            "170141183460469231731.687303715884105727"
+        );
+        assert_eq!(
+            AssetAmount::new(i128::MIN, 0).to_string(),
+            "-170,141,183,460,469,231,731,687,303,715,884,105,728"
+        );
+    }
+
+    #[test]
+    fn amounts_from_metadata() {
+        let metadata = AssetMetadata {
+            ticker: Some("HOSKY".into()),
+            decimals: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            AssetAmount::from_metadata(100, &metadata).to_string(),
+            "1.00 HOSKY"
+        );
+
+        let metadata = AssetMetadata::default();
+        assert_eq!(
+            AssetAmount::from_metadata(100, &metadata).to_string(),
+            "100"
+        );
+    }
+}
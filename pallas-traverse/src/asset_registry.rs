@@ -0,0 +1,222 @@
+//! Off-chain metadata of native assets
+//!
+//! Ticker, decimals and the like aren't stored on-chain; they're published by
+//! registries such as the [Cardano token
+//! registry](https://github.com/cardano-foundation/cardano-token-registry).
+//! The [`AssetMetadataProvider`] trait abstracts the lookup so that callers
+//! can back it with whatever storage they use, while [`TokenRegistry`] is an
+//! in-memory implementation that understands the token registry JSON files.
+
+use std::{collections::HashMap, path::Path};
+
+use pallas_crypto::hash::Hash;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::MultiEraAsset;
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("invalid registry entry: {0}")]
+    InvalidEntry(#[from] serde_json::Error),
+
+    #[error("invalid asset subject {0}")]
+    InvalidSubject(String),
+
+    #[error("registry I/O error")]
+    Io(#[from] std::io::Error),
+}
+
+/// Metadata of an asset as published by a registry
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AssetMetadata {
+    /// Hex of the policy id followed by the asset name
+    pub subject: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub ticker: Option<String>,
+    pub decimals: Option<u8>,
+    pub url: Option<String>,
+    /// Base64-encoded PNG
+    pub logo: Option<String>,
+}
+
+/// Registry subject of an asset: hex of the policy id followed by the name
+pub fn asset_subject(policy: &Hash<28>, name: &[u8]) -> String {
+    format!("{}{}", hex::encode(policy), hex::encode(name))
+}
+
+/// Source of off-chain asset metadata
+pub trait AssetMetadataProvider {
+    fn lookup_subject(&self, subject: &str) -> Option<AssetMetadata>;
+
+    fn lookup(&self, policy: &Hash<28>, name: &[u8]) -> Option<AssetMetadata> {
+        self.lookup_subject(&asset_subject(policy, name))
+    }
+}
+
+impl<'b> MultiEraAsset<'b> {
+    pub fn metadata(&self, provider: &impl AssetMetadataProvider) -> Option<AssetMetadata> {
+        provider.lookup(self.policy(), self.name())
+    }
+}
+
+#[derive(Deserialize)]
+struct Property<T> {
+    value: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistryEntry {
+    subject: String,
+    name: Option<Property<String>>,
+    description: Option<Property<String>>,
+    ticker: Option<Property<String>>,
+    decimals: Option<Property<u8>>,
+    url: Option<Property<String>>,
+    logo: Option<Property<String>>,
+}
+
+impl TryFrom<RegistryEntry> for AssetMetadata {
+    type Error = RegistryError;
+
+    fn try_from(entry: RegistryEntry) -> Result<Self, Self::Error> {
+        // 28 bytes of policy id plus at most 32 bytes of name
+        let valid = entry.subject.len() >= 56
+            && entry.subject.len() <= 120
+            && hex::decode(&entry.subject).is_ok();
+
+        if !valid {
+            return Err(RegistryError::InvalidSubject(entry.subject));
+        }
+
+        Ok(Self {
+            subject: entry.subject.to_lowercase(),
+            name: entry.name.map(|x| x.value),
+            description: entry.description.map(|x| x.value),
+            ticker: entry.ticker.map(|x| x.value),
+            decimals: entry.decimals.map(|x| x.value),
+            url: entry.url.map(|x| x.value),
+            logo: entry.logo.map(|x| x.value),
+        })
+    }
+}
+
+impl AssetMetadata {
+    /// Parses a single entry in the token registry format
+    ///
+    /// Signatures and sequence numbers are ignored; entries are expected to
+    /// come from a registry that already verified them.
+    pub fn from_registry_json(json: &str) -> Result<Self, RegistryError> {
+        let entry: RegistryEntry = serde_json::from_str(json)?;
+        entry.try_into()
+    }
+}
+
+/// In-memory registry keyed by subject
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    entries: HashMap<String, AssetMetadata>,
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry, replacing any previous one with the same subject
+    pub fn insert(&mut self, metadata: AssetMetadata) {
+        self.entries.insert(metadata.subject.clone(), metadata);
+    }
+
+    pub fn insert_json(&mut self, json: &str) -> Result<(), RegistryError> {
+        self.insert(AssetMetadata::from_registry_json(json)?);
+        Ok(())
+    }
+
+    /// Loads every `.json` file of a directory, such as the `mappings` folder
+    /// of a token registry checkout
+    pub fn load_dir(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let mut registry = Self::new();
+
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|x| x == "json") {
+                registry.insert_json(&std::fs::read_to_string(path)?)?;
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl AssetMetadataProvider for TokenRegistry {
+    fn lookup_subject(&self, subject: &str) -> Option<AssetMetadata> {
+        self.entries.get(&subject.to_lowercase()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSKY: &str = r#"{
+        "subject": "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235484f534b59",
+        "policy": "8201828200581c4bdb4c5017cdcb50c001af21d2488ed2e741df55b252dd3ab2482050",
+        "name": {
+            "sequenceNumber": 0,
+            "value": "HOSKY Token",
+            "signatures": []
+        },
+        "ticker": {
+            "sequenceNumber": 0,
+            "value": "HOSKY",
+            "signatures": []
+        },
+        "decimals": {
+            "sequenceNumber": 0,
+            "value": 0,
+            "signatures": []
+        }
+    }"#;
+
+    #[test]
+    fn registry_lookup() {
+        let mut registry = TokenRegistry::new();
+        registry.insert_json(HOSKY).unwrap();
+
+        let policy: Hash<28> = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235"
+            .parse()
+            .unwrap();
+
+        let found = registry.lookup(&policy, b"HOSKY").unwrap();
+        assert_eq!(found.ticker.as_deref(), Some("HOSKY"));
+        assert_eq!(found.name.as_deref(), Some("HOSKY Token"));
+        assert_eq!(found.decimals, Some(0));
+        assert_eq!(found.description, None);
+
+        assert!(registry.lookup(&policy, b"OTHER").is_none());
+        assert!(registry
+            .lookup_subject(&found.subject.to_uppercase())
+            .is_some());
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        let err = AssetMetadata::from_registry_json(r#"{"subject": "zz"}"#);
+        assert!(matches!(err, Err(RegistryError::InvalidSubject(_))));
+
+        let err = AssetMetadata::from_registry_json(r#"{"ticker": "x"}"#);
+        assert!(matches!(err, Err(RegistryError::InvalidEntry(_))));
+    }
+}
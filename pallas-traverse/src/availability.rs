@@ -0,0 +1,189 @@
+//! Checks that the scripts and datums of a tx are available under a
+//! protocol version
+//!
+//! Txs using a language or output feature introduced by a later hard fork
+//! than the one the node runs are rejected on submission. These checks catch
+//! them beforehand, reporting everything the target version lacks.
+
+use pallas_primitives::conway::{Language, PseudoDatumOption, PseudoScript};
+use thiserror::Error;
+
+use crate::{era::ProtocolVersion, Era, Feature, MultiEraOutput, MultiEraTx};
+
+const ERAS: [Era; 7] = [
+    Era::Byron,
+    Era::Shelley,
+    Era::Allegra,
+    Era::Mary,
+    Era::Alonzo,
+    Era::Babbage,
+    Era::Conway,
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AvailabilityError {
+    #[error("protocol version {0} is not known")]
+    UnknownProtocolVersion(ProtocolVersion),
+
+    #[error("{0:?} requires protocol version {1} or later")]
+    Feature(Feature, ProtocolVersion),
+
+    #[error("{0:?} scripts require protocol version {1} or later")]
+    Language(Language, ProtocolVersion),
+}
+
+/// Era in which a Plutus language was introduced
+pub fn language_era(language: &Language) -> Era {
+    match language {
+        Language::PlutusV1 => Era::Alonzo,
+        Language::PlutusV2 => Era::Babbage,
+        Language::PlutusV3 => Era::Conway,
+    }
+}
+
+/// Era in which a feature was introduced
+pub fn feature_era(feature: Feature) -> Era {
+    ERAS.into_iter()
+        .find(|x| x.has_feature(feature))
+        .unwrap_or(Era::Conway)
+}
+
+fn script_language<T>(script: &PseudoScript<T>) -> Option<Language> {
+    match script {
+        PseudoScript::NativeScript(_) => None,
+        PseudoScript::PlutusV1Script(_) => Some(Language::PlutusV1),
+        PseudoScript::PlutusV2Script(_) => Some(Language::PlutusV2),
+        PseudoScript::PlutusV3Script(_) => Some(Language::PlutusV3),
+    }
+}
+
+/// Plutus languages of the scripts a tx carries, either as witnesses or as
+/// reference scripts of its outputs
+pub fn tx_languages(tx: &MultiEraTx<'_>) -> Vec<Language> {
+    let witnessed = [
+        (!tx.plutus_v1_scripts().is_empty()).then_some(Language::PlutusV1),
+        (!tx.plutus_v2_scripts().is_empty()).then_some(Language::PlutusV2),
+        (!tx.plutus_v3_scripts().is_empty()).then_some(Language::PlutusV3),
+    ];
+
+    let referenced = outputs(tx)
+        .into_iter()
+        .filter_map(|x| x.script_ref().and_then(|x| script_language(&x)));
+
+    let mut languages = vec![];
+
+    for language in witnessed.into_iter().flatten().chain(referenced) {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+
+    languages
+}
+
+fn outputs<'a>(tx: &'a MultiEraTx<'_>) -> Vec<MultiEraOutput<'a>> {
+    tx.outputs()
+        .into_iter()
+        .chain(tx.collateral_return())
+        .collect()
+}
+
+/// Checks that everything the tx uses is available under the given
+/// protocol version, reporting every missing language and feature
+pub fn check_availability(
+    tx: &MultiEraTx<'_>,
+    version: ProtocolVersion,
+) -> Result<(), Vec<AvailabilityError>> {
+    let era = Era::from_protocol_version(version)
+        .ok_or_else(|| vec![AvailabilityError::UnknownProtocolVersion(version)])?;
+
+    let mut errors = vec![];
+
+    for language in tx_languages(tx) {
+        let required = language_era(&language);
+
+        if required > era {
+            errors.push(AvailabilityError::Language(
+                language,
+                required.first_protocol_version(),
+            ));
+        }
+    }
+
+    let outputs = outputs(tx);
+
+    let used = [
+        (Feature::CIP31, !tx.reference_inputs().is_empty()),
+        (
+            Feature::CIP32,
+            outputs
+                .iter()
+                .any(|x| matches!(x.datum(), Some(PseudoDatumOption::Data(_)))),
+        ),
+        (
+            Feature::CIP33,
+            outputs.iter().any(|x| x.script_ref().is_some()),
+        ),
+    ];
+
+    for (feature, _) in used.into_iter().filter(|(_, used)| *used) {
+        if !era.has_feature(feature) {
+            errors.push(AvailabilityError::Feature(
+                feature,
+                feature_era(feature).first_protocol_version(),
+            ));
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(era: Era, tx_hex: &str, version: (u64, u64)) -> Result<(), Vec<AvailabilityError>> {
+        let cbor = hex::decode(tx_hex).unwrap();
+        let tx = MultiEraTx::decode_for_era(era, &cbor).unwrap();
+
+        check_availability(&tx, version.into())
+    }
+
+    #[test]
+    fn languages_and_features_by_version() {
+        let plutus_v1 = include_str!("../../test_data/alonzo2.tx");
+
+        assert_eq!(check(Era::Alonzo, plutus_v1, (5, 0)), Ok(()));
+        assert_eq!(
+            check(Era::Alonzo, plutus_v1, (4, 0)),
+            Err(vec![AvailabilityError::Language(
+                Language::PlutusV1,
+                ProtocolVersion::new(5, 0)
+            )])
+        );
+
+        // a PlutusV2 witness and an output with an inline datum
+        let plutus_v2 = include_str!("../../test_data/babbage13.tx");
+
+        assert_eq!(check(Era::Babbage, plutus_v2, (7, 0)), Ok(()));
+        assert_eq!(
+            check(Era::Babbage, plutus_v2, (6, 0)),
+            Err(vec![
+                AvailabilityError::Language(Language::PlutusV2, ProtocolVersion::new(7, 0)),
+                AvailabilityError::Feature(Feature::CIP32, ProtocolVersion::new(7, 0)),
+            ])
+        );
+
+        let conway = include_str!("../../test_data/conway1.tx");
+
+        assert_eq!(
+            check(Era::Conway, conway, (11, 0)),
+            Err(vec![AvailabilityError::UnknownProtocolVersion(
+                ProtocolVersion::new(11, 0)
+            )])
+        );
+    }
+}
@@ -1,72 +1,127 @@
 use std::{borrow::Cow, ops::Deref};
 
+use pallas_addresses::{Pointer, StakePayload};
 use pallas_codec::minicbor;
 use pallas_crypto::hash::Hash;
 use pallas_primitives::{alonzo, babbage, byron, conway};
 
 use crate::{
-    probe, support, Era, Error, MultiEraBlock, MultiEraHeader, MultiEraTx, MultiEraUpdate,
+    era::EraTag, probe, support, Era, Error, MultiEraBlock, MultiEraHeader, MultiEraTx,
+    MultiEraUpdate,
 };
 
-type BlockWrapper<T> = (u16, T);
+/// Opens a `[tag, block]` wrapper, returning the tag and a decoder positioned
+/// at the block
+fn open_wrapper(cbor: &[u8]) -> Result<(u16, minicbor::Decoder<'_>), Error> {
+    let mut d = minicbor::Decoder::new(cbor);
+
+    if d.array().map_err(Error::invalid_cbor)? != Some(2) {
+        return Err(Error::invalid_cbor(
+            "block wrapper is not a 2-element array",
+        ));
+    }
+
+    let tag = d.u16().map_err(Error::invalid_cbor)?;
+
+    Ok((tag, d))
+}
+
+/// Decodes a `[tag, block]` wrapper, failing if the tag is unknown or belongs
+/// to an era other than the expected one
+fn decode_wrapped<'b, T>(cbor: &'b [u8], expected: EraTag) -> Result<T, Error>
+where
+    T: minicbor::Decode<'b, ()>,
+{
+    let (tag, mut d) = open_wrapper(cbor)?;
+    let found = EraTag::try_from(tag)?;
+
+    if found != expected {
+        return Err(Error::UnexpectedEraTag { expected, found });
+    }
+
+    d.decode().map_err(Error::invalid_cbor)
+}
 
 impl<'b> MultiEraBlock<'b> {
     pub fn decode_epoch_boundary(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<byron::MintedEbBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: byron::MintedEbBlock = decode_wrapped(cbor, EraTag::EpochBoundary)?;
 
         Ok(Self::EpochBoundary(Box::new(block)))
     }
 
     pub fn decode_byron(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<byron::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: byron::MintedBlock = decode_wrapped(cbor, EraTag::Byron)?;
 
         Ok(Self::Byron(Box::new(block)))
     }
 
     pub fn decode_shelley(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<alonzo::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: alonzo::MintedBlock = decode_wrapped(cbor, EraTag::Shelley)?;
 
         Ok(Self::AlonzoCompatible(Box::new(block), Era::Shelley))
     }
 
     pub fn decode_allegra(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<alonzo::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: alonzo::MintedBlock = decode_wrapped(cbor, EraTag::Allegra)?;
 
         Ok(Self::AlonzoCompatible(Box::new(block), Era::Allegra))
     }
 
     pub fn decode_mary(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<alonzo::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: alonzo::MintedBlock = decode_wrapped(cbor, EraTag::Mary)?;
 
         Ok(Self::AlonzoCompatible(Box::new(block), Era::Mary))
     }
 
     pub fn decode_alonzo(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<alonzo::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: alonzo::MintedBlock = decode_wrapped(cbor, EraTag::Alonzo)?;
 
         Ok(Self::AlonzoCompatible(Box::new(block), Era::Alonzo))
     }
 
     pub fn decode_babbage(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<babbage::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: babbage::MintedBlock = decode_wrapped(cbor, EraTag::Babbage)?;
 
         Ok(Self::Babbage(Box::new(block)))
     }
 
     pub fn decode_conway(cbor: &'b [u8]) -> Result<Self, Error> {
-        let (_, block): BlockWrapper<conway::MintedBlock> =
-            minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+        let block: conway::MintedBlock = decode_wrapped(cbor, EraTag::Conway)?;
 
         Ok(Self::Conway(Box::new(block)))
     }
 
+    /// Decodes the block of a custom era, i.e. one wrapped with a tag outside
+    /// of the Cardano range, sharing the structure of the era of `layout`.
+    /// Blocks wrapped with a Cardano tag must belong to `layout`.
+    pub fn decode_custom(cbor: &'b [u8], layout: EraTag) -> Result<Self, Error> {
+        let (tag, mut d) = open_wrapper(cbor)?;
+
+        if let Ok(found) = EraTag::try_from(tag) {
+            if found != layout {
+                return Err(Error::UnexpectedEraTag {
+                    expected: layout,
+                    found,
+                });
+            }
+        }
+
+        let block = match layout {
+            EraTag::EpochBoundary => {
+                Self::EpochBoundary(Box::new(d.decode().map_err(Error::invalid_cbor)?))
+            }
+            EraTag::Byron => Self::Byron(Box::new(d.decode().map_err(Error::invalid_cbor)?)),
+            EraTag::Shelley | EraTag::Allegra | EraTag::Mary | EraTag::Alonzo => {
+                let block = d.decode().map_err(Error::invalid_cbor)?;
+                Self::AlonzoCompatible(Box::new(block), layout.era())
+            }
+            EraTag::Babbage => Self::Babbage(Box::new(d.decode().map_err(Error::invalid_cbor)?)),
+            EraTag::Conway => Self::Conway(Box::new(d.decode().map_err(Error::invalid_cbor)?)),
+        };
+
+        Ok(block)
+    }
+
     pub fn decode(cbor: &'b [u8]) -> Result<MultiEraBlock<'b>, Error> {
         match probe::block_era(cbor) {
             probe::Outcome::EpochBoundary => Self::decode_epoch_boundary(cbor),
@@ -178,6 +233,29 @@ impl<'b> MultiEraBlock<'b> {
         }
     }
 
+    /// Stake registrations of the block by the pointer that refers to them,
+    /// to be fed into a `PointerIndex` for resolving pointer addresses
+    pub fn stake_pointers(&self) -> Vec<(Pointer, StakePayload)> {
+        let slot = self.slot();
+
+        self.txs()
+            .iter()
+            .enumerate()
+            .filter(|(_, tx)| tx.is_valid())
+            .flat_map(|(tx_idx, tx)| {
+                tx.certs()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(cert_idx, cert)| {
+                        let credential = cert.stake_registration()?;
+                        let pointer = Pointer::new(slot, tx_idx as u64, cert_idx as u64);
+                        Some((pointer, credential))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
     /// Returns any block-level param update proposals (byron-specific)
     pub fn update(&self) -> Option<MultiEraUpdate> {
         match self {
@@ -258,4 +336,181 @@ mod tests {
             assert_eq!(block.txs().len(), tx_count);
         }
     }
+
+    #[test]
+    fn decoders_check_era_tag() {
+        // one block for each tag, in the same order as `EraTag::ALL`
+        let blocks = [
+            include_str!("../../test_data/genesis.block"),
+            include_str!("../../test_data/byron1.block"),
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/allegra1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/conway1.block"),
+        ];
+
+        for (block_str, tag) in blocks.into_iter().zip(EraTag::ALL) {
+            let cbor = hex::decode(block_str).unwrap();
+
+            let decoders = [
+                MultiEraBlock::decode_epoch_boundary,
+                MultiEraBlock::decode_byron,
+                MultiEraBlock::decode_shelley,
+                MultiEraBlock::decode_allegra,
+                MultiEraBlock::decode_mary,
+                MultiEraBlock::decode_alonzo,
+                MultiEraBlock::decode_babbage,
+                MultiEraBlock::decode_conway,
+            ];
+
+            for (decoder, expected) in decoders.iter().zip(EraTag::ALL) {
+                match decoder(&cbor) {
+                    Ok(block) => {
+                        assert_eq!(expected, tag);
+                        assert_eq!(block.era(), tag.era());
+                    }
+                    Err(Error::UnexpectedEraTag { found, .. }) => {
+                        assert_ne!(expected, tag);
+                        assert_eq!(found, tag);
+                    }
+                    Err(err) => panic!("unexpected error {err}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decoders_reject_malformed_wrappers() {
+        let cbor = hex::decode(include_str!("../../test_data/conway1.block")).unwrap();
+
+        // [7, block] -> [8, block]
+        let mut unknown = cbor.clone();
+        unknown[1] = 0x08;
+
+        assert!(matches!(
+            MultiEraBlock::decode_conway(&unknown),
+            Err(Error::UnknownEra(8))
+        ));
+
+        // custom eras opt into unknown tags explicitly
+        let block = MultiEraBlock::decode_custom(&unknown, EraTag::Conway).unwrap();
+        assert_eq!(block.era(), Era::Conway);
+
+        assert!(matches!(
+            MultiEraBlock::decode_custom(&cbor, EraTag::Babbage),
+            Err(Error::UnexpectedEraTag { .. })
+        ));
+
+        // [7, block] -> [7, block, null]
+        let mut longer = cbor.clone();
+        longer[0] = 0x83;
+        longer.push(0xf6);
+
+        assert!(matches!(
+            MultiEraBlock::decode_conway(&longer),
+            Err(Error::InvalidCbor(_))
+        ));
+    }
+
+    // epoch in which each era started on mainnet
+    const MAINNET_HARD_FORKS: [(Era, u64); 7] = [
+        (Era::Byron, 0),
+        (Era::Shelley, 208),
+        (Era::Allegra, 236),
+        (Era::Mary, 251),
+        (Era::Alonzo, 290),
+        (Era::Babbage, 365),
+        (Era::Conway, 507),
+    ];
+
+    #[test]
+    fn mainnet_blocks_across_hard_forks() {
+        // mainnet fixtures with the epoch they belong to, the closest to each
+        // hard fork first
+        let blocks = [
+            // first block of the chain, an epoch boundary block
+            (include_str!("../../test_data/genesis.block"), 0),
+            // six slots before the Shelley hard fork
+            (include_str!("../../test_data/byron1.block"), 207),
+            (include_str!("../../test_data/alonzo15.block"), 208),
+            // last epoch before the Allegra hard fork
+            (include_str!("../../test_data/alonzo16.block"), 235),
+            (include_str!("../../test_data/allegra1.block"), 240),
+            (include_str!("../../test_data/alonzo11.block"), 241),
+            (include_str!("../../test_data/alonzo13.block"), 258),
+            (include_str!("../../test_data/mary1.block"), 260),
+            (include_str!("../../test_data/alonzo21.block"), 304),
+            (include_str!("../../test_data/alonzo9.block"), 308),
+            (include_str!("../../test_data/babbage9.block"), 391),
+            (include_str!("../../test_data/u5c1.block"), 432),
+        ];
+
+        let genesis = crate::wellknown::GenesisValues::mainnet();
+
+        for (block_str, epoch) in blocks {
+            let cbor = hex::decode(block_str).unwrap();
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+
+            assert_eq!(block.epoch(&genesis).0, epoch);
+
+            let (era, _) = MAINNET_HARD_FORKS
+                .iter()
+                .rev()
+                .find(|(_, start)| *start <= epoch)
+                .unwrap();
+
+            assert_eq!(block.era(), *era, "block at slot {}", block.slot());
+
+            for tx in block.txs() {
+                assert_eq!(tx.era(), *era);
+            }
+        }
+    }
+
+    #[test]
+    fn header_protocol_era() {
+        let blocks = vec![
+            include_str!("../../test_data/byron2.block"),
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/conway1.block"),
+        ];
+
+        for block_str in blocks {
+            let cbor = hex::decode(block_str).expect("invalid hex");
+            let block = MultiEraBlock::decode(&cbor).expect("invalid cbor");
+            let header = block.header();
+
+            let version = header.protocol_version().unwrap();
+            assert!(version >= block.era().first_protocol_version());
+            assert!(header.protocol_era().unwrap() >= block.era());
+        }
+
+        let version = crate::era::ProtocolVersion::new(11, 0);
+        assert_eq!(Era::from_protocol_version(version), None);
+    }
+
+    #[test]
+    fn stake_pointers() {
+        let cbor = hex::decode(include_str!("../../test_data/mary1.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        let mut index = pallas_addresses::PointerIndex::new();
+        index.extend(block.stake_pointers());
+        assert_eq!(index.len(), 2);
+
+        let credential = index.resolve(&Pointer::new(27388606, 0, 0)).unwrap();
+        assert_eq!(
+            credential,
+            &StakePayload::Stake(
+                "f2971be702006ff49954fb5c064347451746ffb7e619bd2774caf59c"
+                    .parse()
+                    .unwrap()
+            )
+        );
+    }
 }
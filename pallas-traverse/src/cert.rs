@@ -1,7 +1,15 @@
-use pallas_primitives::{alonzo, conway};
+use pallas_addresses::StakePayload;
+use pallas_primitives::{alonzo, conway, Nullable, StakeCredential};
 
 use crate::MultiEraCert;
 
+fn stake_payload(credential: &StakeCredential) -> StakePayload {
+    match credential {
+        StakeCredential::AddrKeyhash(x) => StakePayload::Stake(*x),
+        StakeCredential::ScriptHash(x) => StakePayload::Script(*x),
+    }
+}
+
 impl<'b> MultiEraCert<'b> {
     pub fn as_alonzo(&self) -> Option<&alonzo::Certificate> {
         match self {
@@ -16,4 +24,175 @@ impl<'b> MultiEraCert<'b> {
             _ => None,
         }
     }
+
+    /// The move-instantaneous-rewards payload, if this is a MIR certificate
+    pub fn as_mir(&self) -> Option<&alonzo::MoveInstantaneousReward> {
+        match self.as_alonzo()? {
+            alonzo::Certificate::MoveInstantaneousRewardsCert(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// The credential registered by this certificate, if it's a stake
+    /// registration. Pointer addresses refer to these certificates.
+    pub fn stake_registration(&self) -> Option<StakePayload> {
+        match self {
+            MultiEraCert::AlonzoCompatible(x) => match x.as_ref().as_ref() {
+                alonzo::Certificate::StakeRegistration(x) => Some(stake_payload(x)),
+                _ => None,
+            },
+            MultiEraCert::Conway(x) => match x.as_ref().as_ref() {
+                conway::Certificate::StakeRegistration(x)
+                | conway::Certificate::Reg(x, _)
+                | conway::Certificate::StakeRegDeleg(x, _, _)
+                | conway::Certificate::VoteRegDeleg(x, _, _)
+                | conway::Certificate::StakeVoteRegDeleg(x, _, _, _) => Some(stake_payload(x)),
+                _ => None,
+            },
+            MultiEraCert::NotApplicable => None,
+        }
+    }
+
+    /// The delegator and the DRep it delegates its votes to, if this
+    /// certificate delegates voting power
+    pub fn as_vote_delegation(&self) -> Option<(StakePayload, &conway::DRep)> {
+        match self.as_conway()? {
+            conway::Certificate::VoteDeleg(x, drep)
+            | conway::Certificate::StakeVoteDeleg(x, _, drep)
+            | conway::Certificate::VoteRegDeleg(x, drep, _)
+            | conway::Certificate::StakeVoteRegDeleg(x, _, drep, _) => {
+                Some((stake_payload(x), drep))
+            }
+            _ => None,
+        }
+    }
+
+    /// The cold credential of a committee member and the hot credential it
+    /// authorizes to vote on its behalf
+    pub fn as_committee_hot_auth(
+        &self,
+    ) -> Option<(
+        &conway::CommitteeColdCredential,
+        &conway::CommitteeHotCredential,
+    )> {
+        match self.as_conway()? {
+            conway::Certificate::AuthCommitteeHot(a, b) => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    /// The cold credential of a resigning committee member and the anchor
+    /// of its resignation, if any
+    pub fn as_committee_resignation(
+        &self,
+    ) -> Option<(&conway::CommitteeColdCredential, Option<&conway::Anchor>)> {
+        match self.as_conway()? {
+            conway::Certificate::ResignCommitteeCold(a, b) => {
+                let anchor = match b {
+                    Nullable::Some(x) => Some(x),
+                    _ => None,
+                };
+
+                Some((a, anchor))
+            }
+            _ => None,
+        }
+    }
+
+    /// The genesis hash, genesis delegate hash and VRF key hash, if this is a
+    /// genesis key delegation certificate
+    pub fn as_genesis_key_delegation(
+        &self,
+    ) -> Option<(
+        &alonzo::Genesishash,
+        &alonzo::GenesisDelegateHash,
+        &alonzo::VrfKeyhash,
+    )> {
+        match self.as_alonzo()? {
+            alonzo::Certificate::GenesisKeyDelegation(a, b, c) => Some((a, b, c)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use pallas_crypto::hash::Hash;
+
+    use super::*;
+    use crate::MultiEraBlock;
+
+    #[test]
+    fn mir_rewards_are_typed() {
+        // block with many move-instantaneous-rewards certs
+        let cbor = hex::decode(include_str!("../../test_data/alonzo15.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        let mirs = block
+            .txs()
+            .iter()
+            .flat_map(|tx| tx.certs())
+            .filter_map(|cert| cert.as_mir().cloned())
+            .collect::<Vec<_>>();
+
+        assert!(!mirs.is_empty());
+
+        for mir in mirs {
+            for (_, delta) in mir.stake_credential_rewards() {
+                assert!(delta != 0);
+            }
+        }
+    }
+
+    #[test]
+    fn vote_delegations_name_their_drep() {
+        let delegator = StakeCredential::AddrKeyhash(Hash::new([1; 28]));
+        let drep = conway::DRep::Script(Hash::new([2; 28]));
+
+        let wrap = |cert| MultiEraCert::Conway(Box::new(Cow::Owned(cert)));
+
+        let cert = wrap(conway::Certificate::VoteRegDeleg(
+            delegator.clone(),
+            drep.clone(),
+            2_000_000,
+        ));
+
+        assert_eq!(
+            cert.as_vote_delegation(),
+            Some((StakePayload::Stake(Hash::new([1; 28])), &drep))
+        );
+
+        let cert = wrap(conway::Certificate::StakeDelegation(
+            delegator,
+            Hash::new([3; 28]),
+        ));
+
+        assert_eq!(cert.as_vote_delegation(), None);
+    }
+
+    #[test]
+    fn committee_certs_are_typed() {
+        let cold = StakeCredential::AddrKeyhash(Hash::new([1; 28]));
+        let hot = StakeCredential::ScriptHash(Hash::new([2; 28]));
+
+        let wrap = |cert| MultiEraCert::Conway(Box::new(Cow::Owned(cert)));
+
+        let auth = wrap(conway::Certificate::AuthCommitteeHot(
+            cold.clone(),
+            hot.clone(),
+        ));
+
+        assert_eq!(auth.as_committee_hot_auth(), Some((&cold, &hot)));
+        assert_eq!(auth.as_committee_resignation(), None);
+
+        let resign = wrap(conway::Certificate::ResignCommitteeCold(
+            cold.clone(),
+            Nullable::Null,
+        ));
+
+        assert_eq!(resign.as_committee_resignation(), Some((&cold, None)));
+        assert_eq!(resign.as_committee_hot_auth(), None);
+    }
 }
@@ -0,0 +1,274 @@
+//! Index of the blocks of a chain by height, slot and hash
+//!
+//! Tools that need to answer "which block is at height N" or "what was the
+//! last block before slot S" can build a [`ChainIndex`] once from the
+//! headers of the chain and keep it on disk, instead of querying a node or a
+//! database.
+//!
+//! The file layout is `"PCIX" | version: u8 | cbor`, where the CBOR part is
+//! an array of `[number, slot, hash]` entries in chain order.
+
+use std::{collections::HashMap, path::Path};
+
+use pallas_codec::{fs::atomic_write, minicbor};
+use pallas_crypto::hash::Hash;
+use thiserror::Error;
+
+use crate::MultiEraHeader;
+
+pub const CHAIN_INDEX_MAGIC: &[u8; 4] = b"PCIX";
+
+pub const CHAIN_INDEX_FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum ChainIndexError {
+    #[error("not a chain index file")]
+    BadMagic,
+
+    #[error("unsupported chain index version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("malformed chain index: {0}")]
+    Malformed(String),
+
+    #[error("block {0} at slot {1} doesn't extend the index tip")]
+    NotExtendingTip(u64, u64),
+
+    #[error("chain index I/O error")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<minicbor::decode::Error> for ChainIndexError {
+    fn from(value: minicbor::decode::Error) -> Self {
+        ChainIndexError::Malformed(value.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub number: u64,
+    pub slot: u64,
+    pub hash: Hash<32>,
+}
+
+/// Blocks of a chain sorted by height and slot, both strictly increasing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainIndex {
+    entries: Vec<IndexEntry>,
+    by_hash: HashMap<Hash<32>, usize>,
+}
+
+impl ChainIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a block, which must be higher and at a later slot than the
+    /// current tip
+    pub fn push(&mut self, entry: IndexEntry) -> Result<(), ChainIndexError> {
+        if let Some(tip) = self.tip() {
+            if entry.number <= tip.number || entry.slot <= tip.slot {
+                return Err(ChainIndexError::NotExtendingTip(entry.number, entry.slot));
+            }
+        }
+
+        self.by_hash.insert(entry.hash, self.entries.len());
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Appends the block of a header. Byron epoch boundary blocks share
+    /// their height with the previous block and their slot with the next
+    /// one, so they are skipped.
+    pub fn add_header(&mut self, header: &MultiEraHeader) -> Result<(), ChainIndexError> {
+        if header.as_eb().is_some() {
+            return Ok(());
+        }
+
+        self.push(IndexEntry {
+            number: header.number(),
+            slot: header.slot(),
+            hash: header.hash(),
+        })
+    }
+
+    /// Drops the blocks after the given slot, returning how many were
+    /// removed
+    pub fn rollback(&mut self, slot: u64) -> usize {
+        let keep = self.entries.partition_point(|x| x.slot <= slot);
+        let removed = self.entries.len() - keep;
+
+        for entry in self.entries.drain(keep..) {
+            self.by_hash.remove(&entry.hash);
+        }
+
+        removed
+    }
+
+    pub fn by_number(&self, number: u64) -> Option<&IndexEntry> {
+        let index = self.entries.binary_search_by_key(&number, |x| x.number);
+        index.ok().map(|x| &self.entries[x])
+    }
+
+    pub fn by_slot(&self, slot: u64) -> Option<&IndexEntry> {
+        let index = self.entries.binary_search_by_key(&slot, |x| x.slot);
+        index.ok().map(|x| &self.entries[x])
+    }
+
+    /// Block at the given slot or, if the slot is empty, the last one before
+    /// it
+    pub fn at_or_before_slot(&self, slot: u64) -> Option<&IndexEntry> {
+        let after = self.entries.partition_point(|x| x.slot <= slot);
+        after.checked_sub(1).map(|x| &self.entries[x])
+    }
+
+    pub fn by_hash(&self, hash: &Hash<32>) -> Option<&IndexEntry> {
+        self.by_hash.get(hash).map(|x| &self.entries[*x])
+    }
+
+    pub fn tip(&self) -> Option<&IndexEntry> {
+        self.entries.last()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .map(|x| (x.number, x.slot, x.hash))
+            .collect();
+
+        let mut out = CHAIN_INDEX_MAGIC.to_vec();
+        out.push(CHAIN_INDEX_FORMAT_VERSION);
+        out.extend(minicbor::to_vec(&entries).expect("encoding to a vec can't fail"));
+
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ChainIndexError> {
+        if bytes.len() < 5 || &bytes[..4] != CHAIN_INDEX_MAGIC {
+            return Err(ChainIndexError::BadMagic);
+        }
+
+        if bytes[4] != CHAIN_INDEX_FORMAT_VERSION {
+            return Err(ChainIndexError::UnsupportedVersion(bytes[4]));
+        }
+
+        let entries: Vec<(u64, u64, Hash<32>)> = minicbor::decode(&bytes[5..])?;
+
+        let mut index = Self::new();
+
+        for (number, slot, hash) in entries {
+            index
+                .push(IndexEntry { number, slot, hash })
+                .map_err(|e| ChainIndexError::Malformed(e.to_string()))?;
+        }
+
+        Ok(index)
+    }
+
+    /// Writes the index through [`atomic_write`]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ChainIndexError> {
+        atomic_write(path, &self.encode())?;
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ChainIndexError> {
+        let bytes = std::fs::read(path)?;
+        Self::decode(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiEraBlock;
+
+    fn entry(number: u64, slot: u64) -> IndexEntry {
+        IndexEntry {
+            number,
+            slot,
+            hash: Hash::from([number as u8; 32]),
+        }
+    }
+
+    fn sample() -> ChainIndex {
+        let mut index = ChainIndex::new();
+
+        for (number, slot) in [(1, 10), (2, 15), (3, 40), (4, 41)] {
+            index.push(entry(number, slot)).unwrap();
+        }
+
+        index
+    }
+
+    #[test]
+    fn index_lookups() {
+        let mut index = sample();
+
+        assert_eq!(index.by_number(3), Some(&entry(3, 40)));
+        assert_eq!(index.by_number(5), None);
+        assert_eq!(index.by_slot(15), Some(&entry(2, 15)));
+        assert_eq!(index.by_slot(16), None);
+        assert_eq!(index.at_or_before_slot(39), Some(&entry(2, 15)));
+        assert_eq!(index.at_or_before_slot(9), None);
+        assert_eq!(index.by_hash(&Hash::from([4; 32])), Some(&entry(4, 41)));
+
+        assert!(matches!(
+            index.push(entry(5, 41)),
+            Err(ChainIndexError::NotExtendingTip(5, 41))
+        ));
+
+        assert_eq!(index.rollback(20), 2);
+        assert_eq!(index.tip(), Some(&entry(2, 15)));
+        assert_eq!(index.by_hash(&Hash::from([4; 32])), None);
+
+        let block = hex::decode(include_str!("../../test_data/babbage1.block")).unwrap();
+        let block = MultiEraBlock::decode(&block).unwrap();
+        let header = block.header();
+
+        index.add_header(&header).unwrap();
+        assert_eq!(index.by_hash(&header.hash()).unwrap().slot, header.slot());
+
+        let ebb = hex::decode(include_str!("../../test_data/genesis.block")).unwrap();
+        let ebb = MultiEraBlock::decode(&ebb).unwrap();
+
+        index.add_header(&ebb.header()).unwrap();
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn index_save_and_load() {
+        let path = std::env::temp_dir().join(format!("pallas-chain-index-{}", std::process::id()));
+
+        sample().save(&path).unwrap();
+        assert_eq!(ChainIndex::load(&path).unwrap(), sample());
+
+        std::fs::remove_file(&path).unwrap();
+
+        let bytes = sample().encode();
+
+        assert!(matches!(
+            ChainIndex::decode(&bytes[..bytes.len() - 10]),
+            Err(ChainIndexError::Malformed(_))
+        ));
+
+        assert!(matches!(
+            ChainIndex::decode(b"nope"),
+            Err(ChainIndexError::BadMagic)
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+//! Columnar export of chain data for analytics
+//!
+//! Full-chain analytics are best served by columnar formats such as Arrow or
+//! Parquet, where each field is stored and compressed as a contiguous array.
+//! A [`ColumnarBatch`] accumulates blocks as one table per kind of record,
+//! each a struct of plain column vectors that map directly onto Arrow arrays,
+//! without this crate depending on any of those formats.
+//!
+//! Inputs and outputs follow the ledger effect of each tx: phase-2 invalid
+//! txs contribute their collateral and collateral return instead.
+
+use pallas_codec::minicbor;
+
+use crate::{MultiEraBlock, MultiEraTx};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderColumns {
+    pub slot: Vec<u64>,
+    pub number: Vec<u64>,
+    pub hash: Vec<Vec<u8>>,
+    pub era: Vec<u16>,
+    pub size: Vec<u64>,
+    pub tx_count: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputColumns {
+    pub slot: Vec<u64>,
+    pub tx_hash: Vec<Vec<u8>>,
+    /// Hash of the tx that produced the spent output
+    pub spent_tx_hash: Vec<Vec<u8>>,
+    pub spent_index: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputColumns {
+    pub slot: Vec<u64>,
+    pub tx_hash: Vec<Vec<u8>>,
+    pub index: Vec<u64>,
+    /// Raw address bytes, empty if they can't be parsed
+    pub address: Vec<Vec<u8>>,
+    pub lovelace: Vec<u64>,
+    /// Number of distinct native assets held
+    pub asset_count: Vec<u32>,
+    pub has_datum: Vec<bool>,
+    pub has_script_ref: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataColumns {
+    pub slot: Vec<u64>,
+    pub tx_hash: Vec<Vec<u8>>,
+    pub label: Vec<u64>,
+    /// CBOR encoding of the metadatum
+    pub cbor: Vec<Vec<u8>>,
+}
+
+/// Blocks laid out as one table of columns per kind of record
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnarBatch {
+    pub headers: HeaderColumns,
+    pub inputs: InputColumns,
+    pub outputs: OutputColumns,
+    pub metadata: MetadataColumns,
+}
+
+impl ColumnarBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_block(&mut self, block: &MultiEraBlock) {
+        let slot = block.slot();
+
+        let headers = &mut self.headers;
+        headers.slot.push(slot);
+        headers.number.push(block.number());
+        headers.hash.push(block.hash().to_vec());
+        headers.era.push(block.era().into());
+        headers.size.push(block.size() as u64);
+        headers.tx_count.push(block.tx_count() as u32);
+
+        for tx in block.txs() {
+            self.add_tx(slot, &tx);
+        }
+    }
+
+    fn add_tx(&mut self, slot: u64, tx: &MultiEraTx) {
+        let tx_hash = tx.hash().to_vec();
+
+        for input in tx.consumes() {
+            let inputs = &mut self.inputs;
+            inputs.slot.push(slot);
+            inputs.tx_hash.push(tx_hash.clone());
+            inputs.spent_tx_hash.push(input.hash().to_vec());
+            inputs.spent_index.push(input.index());
+        }
+
+        for (index, output) in tx.produces() {
+            let value = output.value();
+            let asset_count = value
+                .assets()
+                .iter()
+                .map(|x| x.assets().len())
+                .sum::<usize>();
+
+            let outputs = &mut self.outputs;
+            outputs.slot.push(slot);
+            outputs.tx_hash.push(tx_hash.clone());
+            outputs.index.push(index as u64);
+            outputs
+                .address
+                .push(output.address().map(|x| x.to_vec()).unwrap_or_default());
+            outputs.lovelace.push(value.coin());
+            outputs.asset_count.push(asset_count as u32);
+            outputs.has_datum.push(output.datum().is_some());
+            outputs.has_script_ref.push(output.script_ref().is_some());
+        }
+
+        let metadata = tx.metadata();
+        let entries: Vec<_> = metadata.collect();
+
+        for (label, metadatum) in entries {
+            let metadata = &mut self.metadata;
+            metadata.slot.push(slot);
+            metadata.tx_hash.push(tx_hash.clone());
+            metadata.label.push(label);
+            metadata
+                .cbor
+                .push(minicbor::to_vec(metadatum).expect("encoding to a vec can't fail"));
+        }
+    }
+
+    /// Number of blocks in the batch
+    pub fn len(&self) -> usize {
+        self.headers.slot.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.slot.is_empty()
+    }
+
+    /// Hands over the accumulated columns, leaving the batch empty, so that
+    /// long streams can be written out in chunks
+    pub fn take(&mut self) -> ColumnarBatch {
+        std::mem::take(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_are_split_into_columns() {
+        let blocks = [
+            include_str!("../../test_data/byron1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/conway1.block"),
+        ];
+
+        let mut batch = ColumnarBatch::new();
+        let (mut inputs, mut outputs, mut metadata) = (0, 0, 0);
+
+        for block in blocks {
+            let cbor = hex::decode(block).unwrap();
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+
+            batch.add_block(&block);
+
+            for tx in block.txs() {
+                inputs += tx.consumes().len();
+                outputs += tx.produces().len();
+                metadata += tx.metadata().collect::<Vec<_>>().len();
+            }
+        }
+
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.headers.era, vec![1, 5, 6, 7]);
+
+        assert_eq!(batch.inputs.spent_index.len(), inputs);
+        assert_eq!(batch.inputs.tx_hash.len(), inputs);
+        assert_eq!(batch.outputs.lovelace.len(), outputs);
+        assert_eq!(batch.outputs.has_script_ref.len(), outputs);
+        assert_eq!(batch.metadata.cbor.len(), metadata);
+        assert!(batch.outputs.address.iter().all(|x| !x.is_empty()));
+
+        let taken = batch.take();
+        assert_eq!(taken.len(), 4);
+        assert!(batch.is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! Detection of inputs spent more than once in a set of transactions
+//!
+//! Mempools and tools batching transactions from many users need to know
+//! which transactions can't be included together. Only the inputs each tx
+//! consumes are considered: its regular inputs if it's valid, its collateral
+//! otherwise.
+
+use std::collections::HashMap;
+
+use crate::{MultiEraTx, OutputRef};
+
+/// An input consumed more than once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputConflict {
+    pub input: OutputRef,
+    /// Positions in the set of the txs consuming the input, in order. A tx
+    /// listing the input twice appears twice.
+    pub txs: Vec<usize>,
+}
+
+impl InputConflict {
+    /// Whether a single tx is involved, spending the input twice itself
+    pub fn is_within_tx(&self) -> bool {
+        self.txs.iter().all(|x| *x == self.txs[0])
+    }
+}
+
+/// Reports the inputs consumed more than once within or across `txs`, in
+/// order of first use
+pub fn detect_conflicts(txs: &[MultiEraTx<'_>]) -> Vec<InputConflict> {
+    let mut order = vec![];
+    let mut spenders: HashMap<OutputRef, Vec<usize>> = HashMap::new();
+
+    for (index, tx) in txs.iter().enumerate() {
+        // not `consumes`, which drops the duplicates within the tx
+        let consumed = match tx.is_valid() {
+            true => tx.inputs(),
+            false => tx.collateral(),
+        };
+
+        for input in consumed {
+            let entry = spenders.entry(input.output_ref()).or_insert_with(|| {
+                order.push(input.output_ref());
+                vec![]
+            });
+
+            entry.push(index);
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|input| {
+            let txs = spenders.remove(&input)?;
+            (txs.len() > 1).then_some(InputConflict { input, txs })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Era;
+
+    #[test]
+    fn conflicts_within_and_across_txs() {
+        let duplicated = hex::decode(include_str!("../../test_data/duplicateinput.tx")).unwrap();
+        let a = hex::decode(include_str!("../../test_data/babbage3.tx")).unwrap();
+        let b = hex::decode(include_str!("../../test_data/babbage4.tx")).unwrap();
+
+        let duplicated = MultiEraTx::decode_for_era(Era::Alonzo, &duplicated).unwrap();
+        let a = MultiEraTx::decode_for_era(Era::Babbage, &a).unwrap();
+        let b = MultiEraTx::decode_for_era(Era::Babbage, &b).unwrap();
+
+        assert!(detect_conflicts(&[a.clone(), b.clone()]).is_empty());
+
+        let conflicts = detect_conflicts(&[duplicated, b, a.clone()]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].txs, [0, 0]);
+        assert!(conflicts[0].is_within_tx());
+
+        let conflicts = detect_conflicts(&[a.clone(), a.clone()]);
+        assert_eq!(conflicts.len(), a.inputs().len());
+
+        for (conflict, input) in conflicts.iter().zip(a.inputs()) {
+            assert_eq!(conflict.input, input.output_ref());
+            assert_eq!(conflict.txs, [0, 1]);
+            assert!(!conflict.is_within_tx());
+        }
+    }
+}
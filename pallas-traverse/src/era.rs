@@ -1,7 +1,37 @@
 use std::fmt::{Display, Formatter};
 
+use serde::{Deserialize, Serialize};
+
 use crate::{Era, Feature};
 
+/// Major and minor protocol version, as announced in block headers and
+/// protocol parameter updates. Versions are ordered by major first.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
+pub struct ProtocolVersion {
+    pub major: u64,
+    pub minor: u64,
+}
+
+impl ProtocolVersion {
+    pub fn new(major: u64, minor: u64) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl From<(u64, u64)> for ProtocolVersion {
+    fn from((major, minor): (u64, u64)) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
 impl Era {
     #[allow(clippy::match_like_matches_macro)]
     pub fn has_feature(&self, feature: Feature) -> bool {
@@ -16,6 +46,35 @@ impl Era {
             Feature::CIP1694 => self.ge(&Era::Conway),
         }
     }
+
+    /// Era of the ledger rules for a protocol version. Major versions beyond
+    /// the latest known era return `None`, meaning a hard fork this library
+    /// doesn't support yet.
+    pub fn from_protocol_version(version: ProtocolVersion) -> Option<Era> {
+        match version.major {
+            0..=1 => Some(Era::Byron),
+            2 => Some(Era::Shelley),
+            3 => Some(Era::Allegra),
+            4 => Some(Era::Mary),
+            5..=6 => Some(Era::Alonzo),
+            7..=8 => Some(Era::Babbage),
+            9..=10 => Some(Era::Conway),
+            _ => None,
+        }
+    }
+
+    /// First protocol version of the era, the one of its hard fork
+    pub fn first_protocol_version(&self) -> ProtocolVersion {
+        match self {
+            Era::Byron => ProtocolVersion::new(0, 0),
+            Era::Shelley => ProtocolVersion::new(2, 0),
+            Era::Allegra => ProtocolVersion::new(3, 0),
+            Era::Mary => ProtocolVersion::new(4, 0),
+            Era::Alonzo => ProtocolVersion::new(5, 0),
+            Era::Babbage => ProtocolVersion::new(7, 0),
+            Era::Conway => ProtocolVersion::new(9, 0),
+        }
+    }
 }
 
 // for consistency, we use the same tag convention used by the node's cbor
@@ -52,6 +111,80 @@ impl From<Era> for u16 {
     }
 }
 
+/// Tag of the `[tag, block]` wrapper used by the node to tell apart the
+/// eras of serialized blocks
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EraTag {
+    EpochBoundary,
+    Byron,
+    Shelley,
+    Allegra,
+    Mary,
+    Alonzo,
+    Babbage,
+    Conway,
+}
+
+impl EraTag {
+    pub const ALL: [EraTag; 8] = [
+        EraTag::EpochBoundary,
+        EraTag::Byron,
+        EraTag::Shelley,
+        EraTag::Allegra,
+        EraTag::Mary,
+        EraTag::Alonzo,
+        EraTag::Babbage,
+        EraTag::Conway,
+    ];
+
+    pub fn era(&self) -> Era {
+        match self {
+            EraTag::EpochBoundary => Era::Byron,
+            EraTag::Byron => Era::Byron,
+            EraTag::Shelley => Era::Shelley,
+            EraTag::Allegra => Era::Allegra,
+            EraTag::Mary => Era::Mary,
+            EraTag::Alonzo => Era::Alonzo,
+            EraTag::Babbage => Era::Babbage,
+            EraTag::Conway => Era::Conway,
+        }
+    }
+}
+
+/// Fails with [`crate::Error::UnknownEra`], which keeps the offending value
+impl TryFrom<u16> for EraTag {
+    type Error = crate::Error;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EraTag::EpochBoundary),
+            1 => Ok(EraTag::Byron),
+            2 => Ok(EraTag::Shelley),
+            3 => Ok(EraTag::Allegra),
+            4 => Ok(EraTag::Mary),
+            5 => Ok(EraTag::Alonzo),
+            6 => Ok(EraTag::Babbage),
+            7 => Ok(EraTag::Conway),
+            x => Err(crate::Error::UnknownEra(x)),
+        }
+    }
+}
+
+impl From<EraTag> for u16 {
+    fn from(value: EraTag) -> Self {
+        match value {
+            EraTag::EpochBoundary => 0,
+            EraTag::Byron => 1,
+            EraTag::Shelley => 2,
+            EraTag::Allegra => 3,
+            EraTag::Mary => 4,
+            EraTag::Alonzo => 5,
+            EraTag::Babbage => 6,
+            EraTag::Conway => 7,
+        }
+    }
+}
+
 impl Display for Era {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -65,3 +198,33 @@ impl Display for Era {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn era_tags_roundtrip() {
+        for (value, tag) in EraTag::ALL.into_iter().enumerate() {
+            let value = value as u16;
+
+            assert_eq!(EraTag::try_from(value).unwrap(), tag);
+            assert_eq!(u16::from(tag), value);
+
+            // apart from epoch boundaries, era tags match the era conversions
+            if tag != EraTag::EpochBoundary {
+                assert_eq!(Era::try_from(value).unwrap(), tag.era());
+                assert_eq!(u16::from(tag.era()), value);
+            }
+        }
+
+        assert_eq!(EraTag::EpochBoundary.era(), Era::Byron);
+
+        for unknown in [8, 42, u16::MAX] {
+            assert!(matches!(
+                EraTag::try_from(unknown),
+                Err(crate::Error::UnknownEra(x)) if x == unknown
+            ));
+        }
+    }
+}
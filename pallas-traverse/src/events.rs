@@ -0,0 +1,240 @@
+//! Domain events derived from applying blocks to the chain
+//!
+//! Indexers usually follow the chain and turn each block into a flat list of
+//! changes (outputs created or consumed, certs, mints, metadata). This module
+//! provides a canonical set of such events so that each indexer doesn't need
+//! to come up with its own.
+
+use std::borrow::Cow;
+
+use pallas_codec::minicbor;
+use pallas_crypto::hash::Hash;
+use pallas_primitives::alonzo::MetadatumLabel;
+
+use crate::{Era, MultiEraBlock, MultiEraCert, MultiEraTx, OutputRef};
+
+/// Identifies the position of a tx within the chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxContext {
+    pub slot: u64,
+    pub block_hash: Hash<32>,
+    pub tx_hash: Hash<32>,
+    pub tx_index: usize,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ChainEvent {
+    /// A tx was included in a block; emitted before the rest of its events
+    TxApplied {
+        ctx: TxContext,
+        era: Era,
+        valid: bool,
+    },
+
+    /// An output became available. The output is kept as CBOR, use
+    /// [`crate::MultiEraOutput::decode`] with the given era to traverse it.
+    OutputCreated {
+        ctx: TxContext,
+        output_ref: OutputRef,
+        era: Era,
+        cbor: Vec<u8>,
+    },
+
+    OutputConsumed {
+        ctx: TxContext,
+        output_ref: OutputRef,
+    },
+
+    CertAttached {
+        ctx: TxContext,
+        cert_index: usize,
+        cert: MultiEraCert<'static>,
+    },
+
+    /// An asset was minted (positive quantity) or burned (negative quantity)
+    MintExecuted {
+        ctx: TxContext,
+        policy: Hash<28>,
+        asset_name: Vec<u8>,
+        quantity: i64,
+    },
+
+    /// Metadata under a label was attached to a tx, kept as CBOR
+    MetadataAttached {
+        ctx: TxContext,
+        label: MetadatumLabel,
+        cbor: Vec<u8>,
+    },
+
+    /// The chain was rolled back to the given point; every event emitted for
+    /// blocks after it should be reverted
+    RolledBack {
+        slot: u64,
+        block_hash: Option<Hash<32>>,
+    },
+}
+
+fn owned_cert(cert: MultiEraCert) -> MultiEraCert<'static> {
+    match cert {
+        MultiEraCert::NotApplicable => MultiEraCert::NotApplicable,
+        MultiEraCert::AlonzoCompatible(x) => {
+            MultiEraCert::AlonzoCompatible(Box::new(Cow::Owned(x.into_owned())))
+        }
+        MultiEraCert::Conway(x) => MultiEraCert::Conway(Box::new(Cow::Owned(x.into_owned()))),
+    }
+}
+
+fn apply_tx(events: &mut Vec<ChainEvent>, ctx: TxContext, tx: &MultiEraTx) {
+    let valid = tx.is_valid();
+
+    events.push(ChainEvent::TxApplied {
+        ctx: ctx.clone(),
+        era: tx.era(),
+        valid,
+    });
+
+    for input in tx.consumes() {
+        events.push(ChainEvent::OutputConsumed {
+            ctx: ctx.clone(),
+            output_ref: input.output_ref(),
+        });
+    }
+
+    for (index, output) in tx.produces() {
+        events.push(ChainEvent::OutputCreated {
+            ctx: ctx.clone(),
+            output_ref: OutputRef::new(ctx.tx_hash, index as u64),
+            era: output.era(),
+            cbor: output.encode(),
+        });
+    }
+
+    // phase-2 invalid txs only affect collateral, the rest of the tx is
+    // discarded by the ledger
+    if !valid {
+        return;
+    }
+
+    for (cert_index, cert) in tx.certs().into_iter().enumerate() {
+        events.push(ChainEvent::CertAttached {
+            ctx: ctx.clone(),
+            cert_index,
+            cert: owned_cert(cert),
+        });
+    }
+
+    for policy in tx.mints() {
+        for asset in policy.assets() {
+            if let Some(quantity) = asset.mint_coin() {
+                events.push(ChainEvent::MintExecuted {
+                    ctx: ctx.clone(),
+                    policy: *asset.policy(),
+                    asset_name: asset.name().to_vec(),
+                    quantity,
+                });
+            }
+        }
+    }
+
+    let meta = tx.metadata();
+    let labels: Vec<(MetadatumLabel, _)> = meta.collect();
+
+    for (label, value) in labels {
+        events.push(ChainEvent::MetadataAttached {
+            ctx: ctx.clone(),
+            label,
+            // to_vec is infallible
+            cbor: minicbor::to_vec(value).unwrap(),
+        });
+    }
+}
+
+impl ChainEvent {
+    /// Produces the events of applying the block, in tx order
+    pub fn from_block(block: &MultiEraBlock) -> Vec<ChainEvent> {
+        let slot = block.slot();
+        let block_hash = block.hash();
+
+        let mut events = vec![];
+
+        for (tx_index, tx) in block.txs().iter().enumerate() {
+            let ctx = TxContext {
+                slot,
+                block_hash,
+                tx_hash: tx.hash(),
+                tx_index,
+            };
+
+            apply_tx(&mut events, ctx, tx);
+        }
+
+        events
+    }
+
+    pub fn rolled_back(slot: u64, block_hash: Option<Hash<32>>) -> Self {
+        ChainEvent::RolledBack { slot, block_hash }
+    }
+
+    /// The tx context of the event, if it was produced by a tx
+    pub fn context(&self) -> Option<&TxContext> {
+        match self {
+            ChainEvent::TxApplied { ctx, .. }
+            | ChainEvent::OutputCreated { ctx, .. }
+            | ChainEvent::OutputConsumed { ctx, .. }
+            | ChainEvent::CertAttached { ctx, .. }
+            | ChainEvent::MintExecuted { ctx, .. }
+            | ChainEvent::MetadataAttached { ctx, .. } => Some(ctx),
+            ChainEvent::RolledBack { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiEraOutput;
+
+    #[test]
+    fn block_events_follow_txs() {
+        let cbor = hex::decode(include_str!("../../test_data/alonzo27.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        let events = ChainEvent::from_block(&block);
+
+        let applied = events
+            .iter()
+            .filter(|x| matches!(x, ChainEvent::TxApplied { .. }))
+            .count();
+        assert_eq!(applied, txs.len());
+
+        let created = events
+            .iter()
+            .filter(|x| matches!(x, ChainEvent::OutputCreated { .. }))
+            .count();
+        let produced: usize = txs.iter().map(|x| x.produces().len()).sum();
+        assert_eq!(created, produced);
+
+        assert!(events
+            .iter()
+            .any(|x| matches!(x, ChainEvent::MintExecuted { .. })));
+        assert!(events
+            .iter()
+            .any(|x| matches!(x, ChainEvent::MetadataAttached { .. })));
+
+        for event in events.iter() {
+            if let ChainEvent::OutputCreated { era, cbor, .. } = event {
+                assert!(MultiEraOutput::decode(*era, cbor).is_ok());
+            }
+        }
+
+        let indexes: Vec<_> = events
+            .iter()
+            .filter_map(|x| x.context().map(|c| c.tx_index))
+            .collect();
+        assert!(indexes.windows(2).all(|x| x[0] <= x[1]));
+
+        assert!(ChainEvent::rolled_back(0, None).context().is_none());
+    }
+}
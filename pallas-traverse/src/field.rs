@@ -0,0 +1,128 @@
+//! Optional tx fields that tell apart "not part of this era" from "absent"
+
+use pallas_primitives::alonzo::NetworkId;
+
+use crate::{Era, Feature, MultiEraTx};
+
+/// Value of a tx field whose existence depends on the era
+///
+/// Plain `Option` accessors such as [`MultiEraTx::ttl`] return `None` both
+/// for a Byron tx (which can't have a TTL) and for a Shelley tx that didn't
+/// set one. This type keeps both cases apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EraField<T> {
+    /// The field doesn't exist in the era of the tx
+    NotApplicable,
+    /// The field exists in the era, but the tx doesn't set it
+    Absent,
+    Present(T),
+}
+
+impl<T> EraField<T> {
+    fn for_era(applicable: bool, value: Option<T>) -> Self {
+        match (applicable, value) {
+            (false, _) => EraField::NotApplicable,
+            (true, None) => EraField::Absent,
+            (true, Some(x)) => EraField::Present(x),
+        }
+    }
+
+    pub fn is_applicable(&self) -> bool {
+        !matches!(self, EraField::NotApplicable)
+    }
+
+    pub fn is_present(&self) -> bool {
+        matches!(self, EraField::Present(_))
+    }
+
+    pub fn as_option(&self) -> Option<&T> {
+        match self {
+            EraField::Present(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> EraField<U> {
+        match self {
+            EraField::NotApplicable => EraField::NotApplicable,
+            EraField::Absent => EraField::Absent,
+            EraField::Present(x) => EraField::Present(f(x)),
+        }
+    }
+}
+
+impl<T> From<EraField<T>> for Option<T> {
+    fn from(value: EraField<T>) -> Self {
+        match value {
+            EraField::Present(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl<'b> MultiEraTx<'b> {
+    /// Explicit fee of the tx; Byron fees are implicit
+    pub fn fee_field(&self) -> EraField<u64> {
+        EraField::for_era(self.era() != Era::Byron, self.fee())
+    }
+
+    pub fn ttl_field(&self) -> EraField<u64> {
+        EraField::for_era(self.era() != Era::Byron, self.ttl())
+    }
+
+    pub fn validity_start_field(&self) -> EraField<u64> {
+        EraField::for_era(
+            self.era().has_feature(Feature::TimeLocks),
+            self.validity_start(),
+        )
+    }
+
+    pub fn network_id_field(&self) -> EraField<NetworkId> {
+        EraField::for_era(
+            self.era().has_feature(Feature::SmartContracts),
+            self.network_id(),
+        )
+    }
+
+    pub fn total_collateral_field(&self) -> EraField<u64> {
+        EraField::for_era(self.era() >= Era::Babbage, self.total_collateral())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiEraBlock;
+
+    fn first_tx_fields(block_str: &str) -> (EraField<u64>, EraField<u64>, EraField<u64>) {
+        let cbor = hex::decode(block_str).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().remove(0);
+
+        (tx.fee_field(), tx.ttl_field(), tx.validity_start_field())
+    }
+
+    #[test]
+    fn fields_follow_era() {
+        let (fee, ttl, start) = first_tx_fields(include_str!("../../test_data/byron2.block"));
+        assert_eq!(fee, EraField::NotApplicable);
+        assert_eq!(ttl, EraField::NotApplicable);
+        assert_eq!(start, EraField::NotApplicable);
+
+        let (fee, ttl, start) = first_tx_fields(include_str!("../../test_data/shelley1.block"));
+        assert!(fee.is_present());
+        assert!(ttl.is_present());
+        assert_eq!(start, EraField::NotApplicable);
+
+        let cbor = hex::decode(include_str!("../../test_data/alonzo1.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().remove(0);
+        assert!(tx.validity_start_field().is_applicable());
+        assert!(tx.network_id_field().is_applicable());
+        assert_eq!(tx.total_collateral_field(), EraField::NotApplicable);
+
+        let ttl: Option<u64> = tx.ttl_field().into();
+        assert_eq!(ttl, tx.ttl());
+        assert_eq!(tx.fee_field().map(|x| x > 0), EraField::Present(true));
+    }
+}
@@ -0,0 +1,433 @@
+//! Assembly of signed Praos blocks, for block producers of private testnets
+//!
+//! The leader check is out of scope: callers evaluate the VRF themselves and
+//! hand the resulting certificates over as a [`LeaderProof`]. The forger
+//! lays out the block body, computes its hash and size, and signs the header
+//! with the KES key of the pool at the period of the slot.
+
+use pallas_codec::{
+    minicbor::{self, Encoder},
+    utils::{KeepRaw, Nullable},
+};
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::{ed25519::SecretKey, kes::Sum6Kes},
+};
+use pallas_primitives::{alonzo, babbage, VrfCert};
+
+use crate::{Era, Error, MultiEraBlock, MultiEraTx};
+
+const SLOTS_PER_KES_PERIOD: u64 = 129_600;
+
+/// VRF certificates proving the pool leads the slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaderProof {
+    /// Separate nonce and leader certificates, up to Alonzo
+    TPraos {
+        nonce_vrf: VrfCert,
+        leader_vrf: VrfCert,
+    },
+    /// Single certificate, from Babbage onwards
+    Praos(VrfCert),
+}
+
+/// Bytes signed by the cold key of a pool in an operational certificate
+pub fn operational_cert_signable(
+    hot_vkey: &[u8],
+    sequence_number: u64,
+    kes_period: u64,
+) -> Vec<u8> {
+    let mut out = hot_vkey.to_vec();
+    out.extend_from_slice(&sequence_number.to_be_bytes());
+    out.extend_from_slice(&kes_period.to_be_bytes());
+    out
+}
+
+/// Issues a certificate delegating block signing from the cold key of a
+/// pool to a KES key, starting at `kes_period`
+pub fn issue_operational_cert(
+    cold: &SecretKey,
+    hot_vkey: [u8; 32],
+    sequence_number: u64,
+    kes_period: u64,
+) -> babbage::OperationalCert {
+    let signable = operational_cert_signable(&hot_vkey, sequence_number, kes_period);
+
+    babbage::OperationalCert {
+        operational_cert_hot_vkey: hot_vkey.to_vec().into(),
+        operational_cert_sequence_number: sequence_number,
+        operational_cert_kes_period: kes_period,
+        operational_cert_sigma: cold.sign(signable).as_ref().to_vec().into(),
+    }
+}
+
+/// Keys of the pool forging the blocks
+pub struct PoolKeys {
+    pub issuer_vkey: [u8; 32],
+    pub vrf_vkey: [u8; 32],
+    pub operational_cert: babbage::OperationalCert,
+    pub kes: Sum6Kes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgedBlock {
+    pub hash: Hash<32>,
+    /// The block wrapped with its era tag, as served by node-to-client
+    /// chain-sync
+    pub cbor: Vec<u8>,
+}
+
+impl ForgedBlock {
+    pub fn decode(&self) -> Result<MultiEraBlock<'_>, Error> {
+        MultiEraBlock::decode(&self.cbor)
+    }
+}
+
+fn cbor_array(items: &[&[u8]]) -> Vec<u8> {
+    let mut buf = vec![];
+    Encoder::new(&mut buf).array(items.len() as u64).unwrap();
+
+    for item in items {
+        buf.extend_from_slice(item);
+    }
+
+    buf
+}
+
+struct TxParts<'a> {
+    body: &'a [u8],
+    witness_set: &'a [u8],
+    auxiliary_data: Option<&'a [u8]>,
+    valid: bool,
+}
+
+fn raw_auxiliary_data<'a, T: Clone>(aux: &'a Nullable<KeepRaw<'_, T>>) -> Option<&'a [u8]> {
+    match aux {
+        Nullable::Some(x) => Some(x.raw_cbor()),
+        _ => None,
+    }
+}
+
+fn tx_parts<'a>(tx: &'a MultiEraTx<'_>) -> Option<TxParts<'a>> {
+    match tx {
+        MultiEraTx::AlonzoCompatible(x, _) => Some(TxParts {
+            body: x.transaction_body.raw_cbor(),
+            witness_set: x.transaction_witness_set.raw_cbor(),
+            auxiliary_data: raw_auxiliary_data(&x.auxiliary_data),
+            valid: x.success,
+        }),
+        MultiEraTx::Babbage(x) => Some(TxParts {
+            body: x.transaction_body.raw_cbor(),
+            witness_set: x.transaction_witness_set.raw_cbor(),
+            auxiliary_data: raw_auxiliary_data(&x.auxiliary_data),
+            valid: x.success,
+        }),
+        _ => None,
+    }
+}
+
+/// Forges Alonzo or Babbage blocks signed by a single pool
+pub struct BlockForger {
+    era: Era,
+    keys: PoolKeys,
+    protocol_version: (u64, u64),
+    slots_per_kes_period: u64,
+}
+
+impl BlockForger {
+    pub fn new(era: Era, keys: PoolKeys) -> Result<Self, Error> {
+        let protocol_version = match era {
+            Era::Alonzo => (6, 0),
+            Era::Babbage => (8, 0),
+            _ => return Err(Error::InvalidEra(era)),
+        };
+
+        Ok(Self {
+            era,
+            keys,
+            protocol_version,
+            slots_per_kes_period: SLOTS_PER_KES_PERIOD,
+        })
+    }
+
+    pub fn protocol_version(mut self, major: u64, minor: u64) -> Self {
+        self.protocol_version = (major, minor);
+        self
+    }
+
+    /// Length of a KES period, as in the `slotsPerKESPeriod` genesis param
+    pub fn slots_per_kes_period(mut self, slots: u64) -> Self {
+        self.slots_per_kes_period = slots.max(1);
+        self
+    }
+
+    /// KES period relative to the one the operational cert starts at
+    fn kes_period(&self, slot: u64) -> Result<u32, Error> {
+        let start = self.keys.operational_cert.operational_cert_kes_period;
+        let current = slot / self.slots_per_kes_period;
+
+        current
+            .checked_sub(start)
+            .and_then(|x| u32::try_from(x).ok())
+            .ok_or_else(|| {
+                Error::Forge(format!(
+                    "slot {slot} is before the operational cert KES period {start}"
+                ))
+            })
+    }
+
+    /// Assembles and signs a block with the given txs, which must all belong
+    /// to the era of the forger
+    pub fn forge(
+        &self,
+        number: u64,
+        slot: u64,
+        prev_hash: Option<Hash<32>>,
+        proof: &LeaderProof,
+        txs: &[MultiEraTx<'_>],
+    ) -> Result<ForgedBlock, Error> {
+        let txs = txs
+            .iter()
+            .map(|tx| match tx.era() == self.era {
+                true => tx_parts(tx).ok_or(Error::InvalidEra(tx.era())),
+                false => Err(Error::InvalidEra(tx.era())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let bodies = cbor_array(&txs.iter().map(|x| x.body).collect::<Vec<_>>());
+        let witness_sets = cbor_array(&txs.iter().map(|x| x.witness_set).collect::<Vec<_>>());
+
+        let mut auxiliary_data_set = vec![];
+        {
+            let with_aux: Vec<_> = txs
+                .iter()
+                .enumerate()
+                .filter_map(|(i, x)| Some((i, x.auxiliary_data?)))
+                .collect();
+
+            let mut e = Encoder::new(&mut auxiliary_data_set);
+            e.map(with_aux.len() as u64).unwrap();
+
+            for (i, aux) in with_aux {
+                e.u64(i as u64).unwrap();
+                e.writer_mut().extend_from_slice(aux);
+            }
+        }
+
+        let mut invalid_txs = vec![];
+        {
+            let indexes: Vec<_> = (0..txs.len()).filter(|i| !txs[*i].valid).collect();
+
+            let mut e = Encoder::new(&mut invalid_txs);
+            e.array(indexes.len() as u64).unwrap();
+
+            for i in indexes {
+                e.u64(i as u64).unwrap();
+            }
+        }
+
+        let parts = [bodies, witness_sets, auxiliary_data_set, invalid_txs];
+
+        let body_size = parts.iter().map(Vec::len).sum::<usize>() as u64;
+        let part_hashes: Vec<u8> = parts.iter().flat_map(|x| *Hasher::<256>::hash(x)).collect();
+        let body_hash = Hasher::<256>::hash(&part_hashes);
+
+        let header = self.header(number, slot, prev_hash, proof, body_size, body_hash)?;
+        let hash = Hasher::<256>::hash(&header);
+
+        let mut cbor = vec![];
+        let mut e = Encoder::new(&mut cbor);
+        e.array(2).unwrap().u16(self.era.into()).unwrap();
+        e.array(1 + parts.len() as u64).unwrap();
+        e.writer_mut().extend_from_slice(&header);
+
+        for part in parts {
+            e.writer_mut().extend_from_slice(&part);
+        }
+
+        Ok(ForgedBlock { hash, cbor })
+    }
+
+    fn header(
+        &self,
+        block_number: u64,
+        slot: u64,
+        prev_hash: Option<Hash<32>>,
+        proof: &LeaderProof,
+        block_body_size: u64,
+        block_body_hash: Hash<32>,
+    ) -> Result<Vec<u8>, Error> {
+        let issuer_vkey = self.keys.issuer_vkey.to_vec().into();
+        let vrf_vkey = self.keys.vrf_vkey.to_vec().into();
+        let opcert = self.keys.operational_cert.clone();
+
+        // the KES signature covers the CBOR of the header body
+        let header_body = match (self.era, proof) {
+            (
+                Era::Alonzo,
+                LeaderProof::TPraos {
+                    nonce_vrf,
+                    leader_vrf,
+                },
+            ) => minicbor::to_vec(alonzo::HeaderBody {
+                block_number,
+                slot,
+                prev_hash,
+                issuer_vkey,
+                vrf_vkey,
+                nonce_vrf: nonce_vrf.clone(),
+                leader_vrf: leader_vrf.clone(),
+                block_body_size,
+                block_body_hash,
+                operational_cert_hot_vkey: opcert.operational_cert_hot_vkey,
+                operational_cert_sequence_number: opcert.operational_cert_sequence_number,
+                operational_cert_kes_period: opcert.operational_cert_kes_period,
+                operational_cert_sigma: opcert.operational_cert_sigma,
+                protocol_major: self.protocol_version.0,
+                protocol_minor: self.protocol_version.1,
+            }),
+            (Era::Babbage, LeaderProof::Praos(vrf_result)) => {
+                minicbor::to_vec(babbage::HeaderBody {
+                    block_number,
+                    slot,
+                    prev_hash,
+                    issuer_vkey,
+                    vrf_vkey,
+                    vrf_result: vrf_result.clone(),
+                    block_body_size,
+                    block_body_hash,
+                    operational_cert: opcert,
+                    protocol_version: self.protocol_version,
+                })
+            }
+            _ => {
+                return Err(Error::Forge(format!(
+                    "leader proof doesn't match the {} header layout",
+                    self.era
+                )))
+            }
+        }
+        .unwrap();
+
+        let signature = self
+            .keys
+            .kes
+            .sign(self.kes_period(slot)?, &header_body)
+            .map_err(|x| Error::Forge(x.to_string()))?;
+
+        let mut header = vec![];
+        let mut e = Encoder::new(&mut header);
+        e.array(2).unwrap();
+        e.writer_mut().extend_from_slice(&header_body);
+        e.bytes(signature.as_ref()).unwrap();
+
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_crypto::key::{ed25519::PublicKey, kes::Sum6KesSignature};
+
+    use super::*;
+
+    fn verify_header(block: &MultiEraBlock, slots_per_kes_period: u64) {
+        let header = block.header();
+        let header = header.as_babbage().unwrap();
+        let body = &header.header_body;
+        let opcert = &body.operational_cert;
+
+        let issuer = PublicKey::try_from(body.issuer_vkey.as_slice()).unwrap();
+        let sigma = opcert.operational_cert_sigma.as_slice().try_into().unwrap();
+
+        assert!(issuer.verify(
+            operational_cert_signable(
+                &opcert.operational_cert_hot_vkey,
+                opcert.operational_cert_sequence_number,
+                opcert.operational_cert_kes_period,
+            ),
+            &sigma
+        ));
+
+        let hot_vkey = opcert
+            .operational_cert_hot_vkey
+            .as_slice()
+            .try_into()
+            .unwrap();
+        let period = body.slot / slots_per_kes_period - opcert.operational_cert_kes_period;
+        let signature = Sum6KesSignature::try_from(header.body_signature.as_slice()).unwrap();
+
+        assert!(signature.verify(&hot_vkey, period as u32, body.raw_cbor()));
+    }
+
+    #[test]
+    fn mainnet_header_signatures_verify() {
+        // a mainnet block, with KES periods of 129600 slots
+        let cbor = hex::decode(include_str!("../../test_data/babbage4.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        verify_header(&block, SLOTS_PER_KES_PERIOD);
+    }
+
+    #[test]
+    fn forged_blocks_decode_and_verify() {
+        let cold = SecretKey::from([1; 32]);
+        let kes = Sum6Kes::from_seed([2; 32]);
+
+        let keys = PoolKeys {
+            issuer_vkey: cold.public_key().into(),
+            vrf_vkey: [3; 32],
+            operational_cert: issue_operational_cert(&cold, kes.verification_key(), 0, 2),
+            kes,
+        };
+
+        let forger = BlockForger::new(Era::Babbage, keys)
+            .unwrap()
+            .slots_per_kes_period(100);
+
+        let tx_cbor = hex::decode(include_str!("../../test_data/babbage3.tx")).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Babbage, &tx_cbor).unwrap();
+
+        let proof = LeaderProof::Praos(VrfCert(vec![4; 32].into(), vec![5; 80].into()));
+        let prev_hash = Some(Hash::from([6; 32]));
+
+        let forged = forger
+            .forge(7, 250, prev_hash, &proof, std::slice::from_ref(&tx))
+            .unwrap();
+
+        let block = forged.decode().unwrap();
+
+        assert_eq!(block.era(), Era::Babbage);
+        assert_eq!(block.hash(), forged.hash);
+        assert_eq!(block.number(), 7);
+        assert_eq!(block.slot(), 250);
+        assert_eq!(block.header().previous_hash(), prev_hash);
+        assert_eq!(block.txs()[0].hash(), tx.hash());
+
+        verify_header(&block, 100);
+
+        // the opcert starts at period 2, slot 150 is in period 1
+        assert!(matches!(
+            forger.forge(7, 150, prev_hash, &proof, &[]),
+            Err(Error::Forge(_))
+        ));
+
+        let tpraos = LeaderProof::TPraos {
+            nonce_vrf: VrfCert(vec![4; 32].into(), vec![5; 80].into()),
+            leader_vrf: VrfCert(vec![4; 32].into(), vec![5; 80].into()),
+        };
+
+        assert!(matches!(
+            forger.forge(7, 250, prev_hash, &tpraos, &[]),
+            Err(Error::Forge(_))
+        ));
+
+        let alonzo_cbor = hex::decode(include_str!("../../test_data/alonzo1.tx")).unwrap();
+        let alonzo_tx = MultiEraTx::decode_for_era(Era::Alonzo, &alonzo_cbor).unwrap();
+
+        assert!(matches!(
+            forger.forge(7, 250, prev_hash, &proof, &[alonzo_tx]),
+            Err(Error::InvalidEra(Era::Alonzo))
+        ));
+    }
+}
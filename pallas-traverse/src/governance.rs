@@ -0,0 +1,336 @@
+//! Folding of Conway proposals and votes into a view of the governance state
+//!
+//! Tracks the actions proposed on chain, the last vote each voter cast on
+//! them and when they expire, which is enough for dashboards following
+//! governance. Ratification depends on thresholds and stake snapshots that
+//! can't be derived from blocks alone, so proposals stay active until they
+//! expire; callers learning that an action was enacted can drop it with
+//! [`GovernanceState::remove`].
+
+use std::collections::BTreeMap;
+
+use pallas_primitives::conway::{GovActionId, ProposalProcedure, Vote, Voter};
+
+use crate::{time::Epoch, wellknown::GenesisValues, MultiEraBlock, MultiEraTx};
+
+/// Number of epochs a governance action stays active on mainnet
+pub const MAINNET_GOV_ACTION_LIFETIME: u64 = 6;
+
+/// Body a voter belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VoterRole {
+    DRep,
+    StakePool,
+    Committee,
+}
+
+impl From<&Voter> for VoterRole {
+    fn from(voter: &Voter) -> Self {
+        match voter {
+            Voter::DRepKey(_) | Voter::DRepScript(_) => VoterRole::DRep,
+            Voter::StakePoolKey(_) => VoterRole::StakePool,
+            Voter::ConstitutionalCommitteeKey(_) | Voter::ConstitutionalCommitteeScript(_) => {
+                VoterRole::Committee
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub yes: u64,
+    pub no: u64,
+    pub abstain: u64,
+}
+
+impl Tally {
+    fn add(&mut self, vote: &Vote, weight: u64) {
+        match vote {
+            Vote::Yes => self.yes += weight,
+            Vote::No => self.no += weight,
+            Vote::Abstain => self.abstain += weight,
+        }
+    }
+}
+
+/// Votes on an action, split by the body of the voters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tallies {
+    pub dreps: Tally,
+    pub stake_pools: Tally,
+    pub committee: Tally,
+}
+
+impl Tallies {
+    pub fn get(&self, role: VoterRole) -> &Tally {
+        match role {
+            VoterRole::DRep => &self.dreps,
+            VoterRole::StakePool => &self.stake_pools,
+            VoterRole::Committee => &self.committee,
+        }
+    }
+
+    fn get_mut(&mut self, role: VoterRole) -> &mut Tally {
+        match role {
+            VoterRole::DRep => &mut self.dreps,
+            VoterRole::StakePool => &mut self.stake_pools,
+            VoterRole::Committee => &mut self.committee,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveProposal {
+    pub procedure: ProposalProcedure,
+    pub proposed_in: Epoch,
+    /// Last epoch at whose end the action can still be ratified
+    pub expires_after: Epoch,
+    /// Last vote of each voter
+    pub votes: BTreeMap<Voter, Vote>,
+}
+
+impl ActiveProposal {
+    /// Counts one per voter
+    pub fn tally(&self) -> Tallies {
+        self.weighted_tally(|_| 1)
+    }
+
+    /// Weighs each vote with the voting power of its voter, such as the
+    /// stake delegated to a DRep or a pool
+    pub fn weighted_tally(&self, power: impl Fn(&Voter) -> u64) -> Tallies {
+        let mut tallies = Tallies::default();
+
+        for (voter, vote) in self.votes.iter() {
+            tallies
+                .get_mut(VoterRole::from(voter))
+                .add(vote, power(voter));
+        }
+
+        tallies
+    }
+}
+
+/// Active governance actions folded from a stream of blocks
+#[derive(Debug, Clone)]
+pub struct GovernanceState {
+    genesis: GenesisValues,
+    lifetime: u64,
+    epoch: Option<Epoch>,
+    proposals: BTreeMap<GovActionId, ActiveProposal>,
+}
+
+impl GovernanceState {
+    pub fn new(genesis: GenesisValues, gov_action_lifetime: u64) -> Self {
+        Self {
+            genesis,
+            lifetime: gov_action_lifetime,
+            epoch: None,
+            proposals: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the proposals and votes of a block, returning the proposals that
+    /// expired when the block started a new epoch
+    pub fn add_block(&mut self, block: &MultiEraBlock) -> Vec<(GovActionId, ActiveProposal)> {
+        let (epoch, _) = block.epoch(&self.genesis);
+
+        let expired = match self.epoch {
+            Some(x) if x >= epoch => vec![],
+            _ => self.expire(epoch),
+        };
+
+        for tx in block.txs() {
+            self.add_tx(&tx, epoch);
+        }
+
+        expired
+    }
+
+    /// Adds the proposals and votes of a tx included in the given epoch.
+    /// Votes on actions proposed before the state started folding are
+    /// ignored, as are phase-2 invalid txs.
+    pub fn add_tx(&mut self, tx: &MultiEraTx<'_>, epoch: Epoch) {
+        if !tx.is_valid() {
+            return;
+        }
+
+        for (index, procedure) in tx.proposals().into_iter().enumerate() {
+            let id = GovActionId {
+                transaction_id: tx.hash(),
+                action_index: index as u32,
+            };
+
+            let proposal = ActiveProposal {
+                procedure: procedure.clone(),
+                proposed_in: epoch,
+                expires_after: epoch + self.lifetime,
+                votes: BTreeMap::new(),
+            };
+
+            self.proposals.insert(id, proposal);
+        }
+
+        for (voter, id, procedure) in tx.votes() {
+            if let Some(proposal) = self.proposals.get_mut(id) {
+                proposal.votes.insert(voter.clone(), procedure.vote.clone());
+            }
+        }
+    }
+
+    /// Moves to the given epoch, removing and returning the proposals that
+    /// can no longer be ratified
+    pub fn expire(&mut self, epoch: Epoch) -> Vec<(GovActionId, ActiveProposal)> {
+        self.epoch = Some(epoch);
+
+        let expired: Vec<_> = self
+            .proposals
+            .iter()
+            .filter(|(_, x)| x.expires_after < epoch)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|id| self.proposals.remove(&id).map(|x| (id, x)))
+            .collect()
+    }
+
+    /// Drops a proposal, e.g. after learning it was enacted
+    pub fn remove(&mut self, id: &GovActionId) -> Option<ActiveProposal> {
+        self.proposals.remove(id)
+    }
+
+    pub fn get(&self, id: &GovActionId) -> Option<&ActiveProposal> {
+        self.proposals.get(id)
+    }
+
+    /// Active proposals, ordered by id
+    pub fn proposals(&self) -> impl Iterator<Item = (&GovActionId, &ActiveProposal)> {
+        self.proposals.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.proposals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proposals.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+    use pallas_crypto::hash::Hash;
+    use pallas_primitives::{
+        conway::{Anchor, GovAction, Tx, VotingProcedure},
+        Fragment, NonEmptyKeyValuePairs, NonEmptySet, Nullable,
+    };
+
+    use super::*;
+    use crate::Era;
+
+    fn conway_tx(edit: impl FnOnce(&mut Tx)) -> Vec<u8> {
+        let cbor = hex::decode(include_str!("../../test_data/conway1.tx")).unwrap();
+        let mut tx: Tx = minicbor::decode(&cbor).unwrap();
+
+        edit(&mut tx);
+        tx.encode_fragment().unwrap()
+    }
+
+    fn proposal() -> ProposalProcedure {
+        ProposalProcedure {
+            deposit: 100_000_000_000,
+            reward_account: vec![0xe1; 29].into(),
+            gov_action: GovAction::Information,
+            anchor: Anchor {
+                url: "https://example.com/info.json".into(),
+                content_hash: Hash::new([1; 32]),
+            },
+        }
+    }
+
+    fn ballots(votes: Vec<(Voter, GovActionId, Vote)>) -> Vec<u8> {
+        conway_tx(|tx| {
+            let votes = votes
+                .into_iter()
+                .map(|(voter, id, vote)| {
+                    let procedure = VotingProcedure {
+                        vote,
+                        anchor: Nullable::Null,
+                    };
+
+                    (voter, NonEmptyKeyValuePairs::Def(vec![(id, procedure)]))
+                })
+                .collect();
+
+            tx.transaction_body.voting_procedures = NonEmptyKeyValuePairs::from_vec(votes);
+        })
+    }
+
+    #[test]
+    fn proposals_collect_votes_until_expiry() {
+        let genesis = GenesisValues::mainnet();
+        let mut state = GovernanceState::new(genesis, MAINNET_GOV_ACTION_LIFETIME);
+
+        let proposing = conway_tx(|tx| {
+            tx.transaction_body.proposal_procedures =
+                NonEmptySet::from_vec(vec![proposal(), proposal()]);
+        });
+
+        let proposing = MultiEraTx::decode_for_era(Era::Conway, &proposing).unwrap();
+        state.add_tx(&proposing, 500);
+
+        assert_eq!(state.len(), 2);
+
+        let id = GovActionId {
+            transaction_id: proposing.hash(),
+            action_index: 1,
+        };
+
+        let unknown = GovActionId {
+            transaction_id: Hash::new([0; 32]),
+            action_index: 0,
+        };
+
+        let drep = Voter::DRepKey(Hash::new([1; 28]));
+        let pool = Voter::StakePoolKey(Hash::new([2; 28]));
+        let member = Voter::ConstitutionalCommitteeKey(Hash::new([3; 28]));
+
+        let first = ballots(vec![
+            (drep.clone(), id.clone(), Vote::Yes),
+            (pool.clone(), id.clone(), Vote::No),
+            (member.clone(), unknown.clone(), Vote::Yes),
+        ]);
+
+        // the DRep changes its mind
+        let second = ballots(vec![
+            (drep.clone(), id.clone(), Vote::Abstain),
+            (member, id.clone(), Vote::Yes),
+        ]);
+
+        for cbor in [first, second] {
+            let tx = MultiEraTx::decode_for_era(Era::Conway, &cbor).unwrap();
+            state.add_tx(&tx, 501);
+        }
+
+        let proposal = state.get(&id).unwrap();
+        assert_eq!(proposal.expires_after, 506);
+        assert!(state.get(&unknown).is_none());
+
+        let tallies = proposal.tally();
+        assert_eq!(tallies.get(VoterRole::DRep).abstain, 1);
+        assert_eq!(tallies.stake_pools.no, 1);
+        assert_eq!(tallies.committee.yes, 1);
+
+        let weighted = proposal.weighted_tally(|x| if *x == drep { 5_000 } else { 1 });
+        assert_eq!(weighted.dreps.abstain, 5_000);
+
+        assert!(state.expire(506).is_empty());
+
+        let expired = state.expire(507);
+        assert_eq!(expired.len(), 2);
+        assert_eq!(expired[1].0, id);
+        assert!(state.is_empty());
+    }
+}
@@ -5,7 +5,9 @@ use pallas_codec::minicbor;
 use pallas_crypto::hash::Hash;
 use pallas_primitives::{alonzo, babbage, byron};
 
-use crate::{wellknown::GenesisValues, Era, Error, MultiEraHeader, OriginalHash};
+use crate::{
+    era::ProtocolVersion, wellknown::GenesisValues, Era, Error, MultiEraHeader, OriginalHash,
+};
 
 impl<'b> MultiEraHeader<'b> {
     pub fn decode(tag: u8, subtag: Option<u8>, cbor: &'b [u8]) -> Result<Self, Error> {
@@ -87,6 +89,30 @@ impl<'b> MultiEraHeader<'b> {
         }
     }
 
+    /// Protocol version announced by the block producer, `None` for epoch
+    /// boundary blocks
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        match self {
+            MultiEraHeader::ShelleyCompatible(x) => Some(ProtocolVersion::new(
+                x.header_body.protocol_major,
+                x.header_body.protocol_minor,
+            )),
+            MultiEraHeader::BabbageCompatible(x) => Some(x.header_body.protocol_version.into()),
+            MultiEraHeader::Byron(x) => {
+                let (major, minor, _) = x.extra_data.block_version;
+                Some(ProtocolVersion::new(major as u64, minor as u64))
+            }
+            MultiEraHeader::EpochBoundary(_) => None,
+        }
+    }
+
+    /// Era whose rules the announced protocol version maps to. When it's
+    /// ahead of the era of the block itself, the chain is about to (or just
+    /// did) hard fork and decoders for the next era should be ready.
+    pub fn protocol_era(&self) -> Option<Era> {
+        self.protocol_version().and_then(Era::from_protocol_version)
+    }
+
     pub fn header_body_cbor(&self) -> Option<&'b [u8]> {
         match self {
             MultiEraHeader::ShelleyCompatible(x) => Some(x.header_body.raw_cbor()),
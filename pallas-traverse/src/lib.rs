@@ -13,24 +13,42 @@ use pallas_primitives::{alonzo, babbage, byron, conway};
 
 mod support;
 
+pub mod aggregate;
+pub mod amount;
+pub mod asset_registry;
 pub mod assets;
 pub mod auxiliary;
+pub mod availability;
 pub mod block;
 pub mod cert;
+pub mod chain_index;
+#[cfg(feature = "columnar")]
+pub mod columnar;
+pub mod conflicts;
 pub mod era;
+pub mod events;
 pub mod fees;
+pub mod field;
+pub mod forge;
+pub mod governance;
 pub mod hashes;
 pub mod header;
 pub mod input;
+pub mod matcher;
+pub mod merge;
 pub mod meta;
+pub mod normalize;
 pub mod output;
 pub mod probe;
 pub mod redeemers;
+pub mod registry;
 pub mod signers;
 pub mod size;
+pub mod synth;
 pub mod time;
 pub mod tx;
 pub mod update;
+pub mod validity;
 pub mod value;
 pub mod withdrawals;
 pub mod witnesses;
@@ -216,6 +234,21 @@ pub enum Error {
 
     #[error("Invalid UTxO ref: {0}")]
     InvalidUtxoRef(String),
+
+    #[error("Era tag already in use: {0}")]
+    DuplicateEraTag(u16),
+
+    #[error("Unexpected era tag: expected {expected:?}, found {found:?}")]
+    UnexpectedEraTag {
+        expected: era::EraTag,
+        found: era::EraTag,
+    },
+
+    #[error("Can't forge block: {0}")]
+    Forge(String),
+
+    #[error("Byron witnesses sign over the protocol magic, use verify_byron_witnesses")]
+    ByronRequiresMagic,
 }
 
 impl Error {
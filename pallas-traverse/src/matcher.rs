@@ -0,0 +1,189 @@
+//! Predicate over txs that match a set of watched addresses, policies and
+//! metadata labels
+//!
+//! The matcher works in two stages: a cheap pre-filter that scans the raw
+//! CBOR of a tx looking for the bytes of any watched address or policy id,
+//! followed by an exact check over the decoded tx. The pre-filter never
+//! yields false negatives, so txs it rejects can be skipped without decoding.
+
+use std::collections::{HashMap, HashSet};
+
+use pallas_addresses::Address;
+use pallas_crypto::hash::Hash;
+use pallas_primitives::alonzo::MetadatumLabel;
+
+use crate::{Error, MultiEraTx};
+
+const FINGERPRINT_LEN: usize = 8;
+
+type Fingerprint = [u8; FINGERPRINT_LEN];
+
+fn fingerprint(bytes: &[u8]) -> Option<Fingerprint> {
+    bytes.get(..FINGERPRINT_LEN)?.try_into().ok()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TxMatcher {
+    addresses: HashSet<Vec<u8>>,
+    policies: HashSet<Hash<28>>,
+    labels: HashSet<MetadatumLabel>,
+    patterns: HashMap<Fingerprint, Vec<Vec<u8>>>,
+}
+
+impl TxMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn watch_bytes(&mut self, bytes: Vec<u8>) {
+        // every address and policy id is longer than the fingerprint
+        if let Some(key) = fingerprint(&bytes) {
+            let entry = self.patterns.entry(key).or_default();
+
+            if !entry.contains(&bytes) {
+                entry.push(bytes);
+            }
+        }
+    }
+
+    /// Watch outputs locked by the given address
+    pub fn address(mut self, address: &Address) -> Self {
+        let bytes = address.to_vec();
+        self.watch_bytes(bytes.clone());
+        self.addresses.insert(bytes);
+        self
+    }
+
+    /// Watch txs that mint, burn or output assets of the given policy
+    pub fn policy(mut self, policy: Hash<28>) -> Self {
+        self.watch_bytes(policy.to_vec());
+        self.policies.insert(policy);
+        self
+    }
+
+    /// Watch txs with metadata under the given label
+    pub fn label(mut self, label: MetadatumLabel) -> Self {
+        self.labels.insert(label);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.policies.is_empty() && self.labels.is_empty()
+    }
+
+    /// Cheap check over the raw CBOR of a tx
+    ///
+    /// Returns `false` only when the tx can't match. Metadata labels are too
+    /// short to be searched for, so any watched label makes this check pass.
+    pub fn may_match_raw(&self, cbor: &[u8]) -> bool {
+        if !self.labels.is_empty() {
+            return true;
+        }
+
+        cbor.windows(FINGERPRINT_LEN)
+            .enumerate()
+            .any(|(i, window)| {
+                let candidates = match fingerprint(window).and_then(|x| self.patterns.get(&x)) {
+                    Some(x) => x,
+                    None => return false,
+                };
+
+                candidates.iter().any(|x| cbor[i..].starts_with(x))
+            })
+    }
+
+    /// Exact check over a decoded tx
+    pub fn matches(&self, tx: &MultiEraTx) -> bool {
+        let outputs = tx.outputs().into_iter().chain(tx.collateral_return());
+
+        for output in outputs {
+            if let Ok(address) = output.address() {
+                if self.addresses.contains(&address.to_vec()) {
+                    return true;
+                }
+            }
+
+            if !self.policies.is_empty()
+                && output
+                    .value()
+                    .assets()
+                    .iter()
+                    .any(|x| self.policies.contains(x.policy()))
+            {
+                return true;
+            }
+        }
+
+        if !self.policies.is_empty()
+            && tx
+                .mints()
+                .iter()
+                .any(|x| self.policies.contains(x.policy()))
+        {
+            return true;
+        }
+
+        if !self.labels.is_empty() {
+            let meta = tx.metadata();
+
+            if self.labels.iter().any(|x| meta.find(*x).is_some()) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Runs the pre-filter and only decodes the tx if it might match
+    pub fn matches_raw(&self, cbor: &[u8]) -> Result<bool, Error> {
+        if !self.may_match_raw(cbor) {
+            return Ok(false);
+        }
+
+        let tx = MultiEraTx::decode(cbor)?;
+
+        Ok(self.matches(&tx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiEraBlock;
+
+    #[test]
+    fn matches_watched_items() {
+        let cbor = hex::decode(include_str!("../../test_data/alonzo27.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        let target = &txs[0];
+        let address = target.outputs()[0].address().unwrap();
+        let raw = target.encode();
+
+        let empty = TxMatcher::new();
+        assert!(empty.is_empty());
+        assert!(!empty.may_match_raw(&raw));
+        assert!(!empty.matches(target));
+
+        let by_address = TxMatcher::new().address(&address);
+        assert!(by_address.may_match_raw(&raw));
+        assert!(by_address.matches_raw(&raw).unwrap());
+
+        let unrelated = TxMatcher::new().policy(Hash::from([0xab; 28]));
+        assert!(!unrelated.may_match_raw(&raw));
+        assert!(!unrelated.matches(target));
+
+        let minted = txs.iter().find(|tx| !tx.mints().is_empty()).unwrap();
+        let policy = *minted.mints()[0].policy();
+        let by_policy = TxMatcher::new().policy(policy);
+        assert!(by_policy.may_match_raw(&minted.encode()));
+        assert!(by_policy.matches(minted));
+
+        let labeled = txs.iter().find(|tx| !tx.metadata().is_empty()).unwrap();
+        let meta = labeled.metadata();
+        let labels: Vec<(MetadatumLabel, _)> = meta.collect();
+        let by_label = TxMatcher::new().label(labels[0].0);
+        assert!(by_label.matches(labeled));
+    }
+}
@@ -0,0 +1,294 @@
+//! Merging of witness sets collected from different signers
+//!
+//! When several parties sign the same tx, each one returns a witness set
+//! that needs to be combined with the others. Merging keeps the entries of
+//! the first set and appends those of the second that aren't already there:
+//!
+//! - vkey and bootstrap witnesses are deduplicated by public key
+//! - scripts are deduplicated by script hash
+//! - datums are deduplicated by datum hash
+//! - redeemers are deduplicated by tag and index; on conflict the redeemer of
+//!   the first set wins
+
+use pallas_codec::utils::{MaybeIndefArray, NonEmptyKeyValuePairs, NonEmptySet};
+use pallas_primitives::{alonzo, babbage, conway};
+
+use crate::ComputeHash;
+
+pub trait MergeWitnesses {
+    fn merge(self, other: Self) -> Self;
+}
+
+fn merge_by<T, K: PartialEq>(
+    a: Option<Vec<T>>,
+    b: Option<Vec<T>>,
+    key: impl Fn(&T) -> K,
+) -> Option<Vec<T>> {
+    let mut out = a.unwrap_or_default();
+    let mut keys: Vec<K> = out.iter().map(&key).collect();
+
+    for item in b.unwrap_or_default() {
+        let k = key(&item);
+
+        if !keys.contains(&k) {
+            keys.push(k);
+            out.push(item);
+        }
+    }
+
+    match out.is_empty() {
+        true => None,
+        false => Some(out),
+    }
+}
+
+fn merge_sets<T, K: PartialEq>(
+    a: Option<NonEmptySet<T>>,
+    b: Option<NonEmptySet<T>>,
+    key: impl Fn(&T) -> K,
+) -> Option<NonEmptySet<T>> {
+    merge_by(a.map(|x| x.to_vec()), b.map(|x| x.to_vec()), key).and_then(NonEmptySet::from_vec)
+}
+
+impl MergeWitnesses for alonzo::WitnessSet {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            vkeywitness: merge_by(self.vkeywitness, other.vkeywitness, |x| x.vkey.clone()),
+            native_script: merge_by(self.native_script, other.native_script, |x| {
+                x.compute_hash()
+            }),
+            bootstrap_witness: merge_by(self.bootstrap_witness, other.bootstrap_witness, |x| {
+                x.public_key.clone()
+            }),
+            plutus_script: merge_by(self.plutus_script, other.plutus_script, |x| {
+                x.compute_hash()
+            }),
+            plutus_data: merge_by(self.plutus_data, other.plutus_data, |x| x.compute_hash()),
+            redeemer: merge_by(self.redeemer, other.redeemer, |x| (x.tag, x.index)),
+        }
+    }
+}
+
+impl MergeWitnesses for babbage::WitnessSet {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            vkeywitness: merge_by(self.vkeywitness, other.vkeywitness, |x| x.vkey.clone()),
+            native_script: merge_by(self.native_script, other.native_script, |x| {
+                x.compute_hash()
+            }),
+            bootstrap_witness: merge_by(self.bootstrap_witness, other.bootstrap_witness, |x| {
+                x.public_key.clone()
+            }),
+            plutus_v1_script: merge_by(self.plutus_v1_script, other.plutus_v1_script, |x| {
+                x.compute_hash()
+            }),
+            plutus_data: merge_by(self.plutus_data, other.plutus_data, |x| x.compute_hash()),
+            redeemer: merge_by(self.redeemer, other.redeemer, |x| (x.tag, x.index)),
+            plutus_v2_script: merge_by(self.plutus_v2_script, other.plutus_v2_script, |x| {
+                x.compute_hash()
+            }),
+        }
+    }
+}
+
+/// Keeps a lone side untouched, since re-encoding it would change the script
+/// data hash. Two lists stay a list, otherwise falls back to the map encoding
+/// preferred since Conway.
+fn merge_redeemers(
+    a: Option<conway::Redeemers>,
+    b: Option<conway::Redeemers>,
+) -> Option<conway::Redeemers> {
+    match (a, b) {
+        (a, None) => a,
+        (None, b) => b,
+        (Some(conway::Redeemers::List(a)), Some(conway::Redeemers::List(b))) => {
+            let indef = matches!(a, MaybeIndefArray::Indef(_));
+
+            merge_by(Some(a.to_vec()), Some(b.to_vec()), |x| (x.tag, x.index)).map(
+                |x| match indef {
+                    true => conway::Redeemers::List(MaybeIndefArray::Indef(x)),
+                    false => conway::Redeemers::List(MaybeIndefArray::Def(x)),
+                },
+            )
+        }
+        (a, b) => merge_by(
+            a.map(|x| x.to_pairs()),
+            b.map(|x| x.to_pairs()),
+            |(k, _)| k.clone(),
+        )
+        .and_then(NonEmptyKeyValuePairs::from_vec)
+        .map(conway::Redeemers::Map),
+    }
+}
+
+impl MergeWitnesses for conway::WitnessSet {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            vkeywitness: merge_sets(self.vkeywitness, other.vkeywitness, |x| x.vkey.clone()),
+            native_script: merge_sets(self.native_script, other.native_script, |x| {
+                x.compute_hash()
+            }),
+            bootstrap_witness: merge_sets(self.bootstrap_witness, other.bootstrap_witness, |x| {
+                x.public_key.clone()
+            }),
+            plutus_v1_script: merge_sets(self.plutus_v1_script, other.plutus_v1_script, |x| {
+                x.compute_hash()
+            }),
+            plutus_data: merge_sets(self.plutus_data, other.plutus_data, |x| x.compute_hash()),
+            redeemer: merge_redeemers(self.redeemer, other.redeemer),
+            plutus_v2_script: merge_sets(self.plutus_v2_script, other.plutus_v2_script, |x| {
+                x.compute_hash()
+            }),
+            plutus_v3_script: merge_sets(self.plutus_v3_script, other.plutus_v3_script, |x| {
+                x.compute_hash()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pallas_codec::utils::Bytes;
+    use pallas_primitives::{alonzo::PlutusData, BigInt, ExUnits};
+
+    fn vkey(key: u8, sig: u8) -> alonzo::VKeyWitness {
+        alonzo::VKeyWitness {
+            vkey: Bytes::from(vec![key; 32]),
+            signature: Bytes::from(vec![sig; 64]),
+        }
+    }
+
+    fn datum(x: i64) -> PlutusData {
+        PlutusData::BigInt(BigInt::Int(x.into()))
+    }
+
+    fn redeemer(index: u32, data: i64) -> conway::Redeemer {
+        conway::Redeemer {
+            tag: conway::RedeemerTag::Spend,
+            index,
+            data: datum(data),
+            ex_units: ExUnits { mem: 1, steps: 1 },
+        }
+    }
+
+    fn empty() -> conway::WitnessSet {
+        conway::WitnessSet {
+            vkeywitness: None,
+            native_script: None,
+            bootstrap_witness: None,
+            plutus_v1_script: None,
+            plutus_data: None,
+            redeemer: None,
+            plutus_v2_script: None,
+            plutus_v3_script: None,
+        }
+    }
+
+    #[test]
+    fn conway_merge_dedups() {
+        let a = conway::WitnessSet {
+            vkeywitness: NonEmptySet::from_vec(vec![vkey(1, 1), vkey(2, 2)]),
+            plutus_data: NonEmptySet::from_vec(vec![datum(1)]),
+            redeemer: Some(conway::Redeemers::List(MaybeIndefArray::Def(vec![
+                redeemer(0, 1),
+            ]))),
+            ..empty()
+        };
+
+        let b = conway::WitnessSet {
+            vkeywitness: NonEmptySet::from_vec(vec![vkey(2, 9), vkey(3, 3)]),
+            plutus_data: NonEmptySet::from_vec(vec![datum(1), datum(2)]),
+            redeemer: Some(conway::Redeemers::List(MaybeIndefArray::Def(vec![
+                redeemer(0, 7),
+                redeemer(1, 2),
+            ]))),
+            ..empty()
+        };
+
+        let merged = a.merge(b);
+
+        let vkeys = merged.vkeywitness.unwrap().to_vec();
+        assert_eq!(vkeys, vec![vkey(1, 1), vkey(2, 2), vkey(3, 3)]);
+
+        assert_eq!(merged.plutus_data.unwrap().to_vec().len(), 2);
+
+        match merged.redeemer.unwrap() {
+            conway::Redeemers::List(x) => {
+                assert_eq!(x.to_vec(), vec![redeemer(0, 1), redeemer(1, 2)]);
+            }
+            _ => panic!("expected list redeemers"),
+        }
+
+        assert_eq!(merged.native_script, None);
+    }
+
+    #[test]
+    fn conway_merge_mixed_redeemers_uses_map() {
+        let a = conway::WitnessSet {
+            redeemer: Some(conway::Redeemers::List(MaybeIndefArray::Def(vec![
+                redeemer(0, 1),
+            ]))),
+            ..empty()
+        };
+
+        let map = vec![(
+            conway::RedeemersKey {
+                tag: conway::RedeemerTag::Spend,
+                index: 1,
+            },
+            conway::RedeemersValue {
+                data: datum(2),
+                ex_units: ExUnits { mem: 1, steps: 1 },
+            },
+        )];
+
+        let b = conway::WitnessSet {
+            redeemer: NonEmptyKeyValuePairs::from_vec(map).map(conway::Redeemers::Map),
+            ..empty()
+        };
+
+        match a.merge(b).redeemer.unwrap() {
+            conway::Redeemers::Map(x) => assert_eq!(x.to_vec().len(), 2),
+            _ => panic!("expected map redeemers"),
+        }
+    }
+
+    #[test]
+    fn conway_merge_with_vkey_only_keeps_script_data() {
+        use pallas_codec::minicbor;
+        use pallas_crypto::hash::Hasher;
+
+        // the script data hash covers the redeemers and datums as encoded
+        fn script_data(x: &conway::WitnessSet) -> (Vec<u8>, pallas_crypto::hash::Hash<32>) {
+            let redeemers = minicbor::to_vec(x.redeemer.as_ref().unwrap()).unwrap();
+            let datums = minicbor::to_vec(x.plutus_data.as_ref().unwrap()).unwrap();
+            let hash = Hasher::<256>::hash(&[redeemers.clone(), datums].concat());
+
+            (redeemers, hash)
+        }
+
+        let scripted = conway::WitnessSet {
+            vkeywitness: NonEmptySet::from_vec(vec![vkey(1, 1)]),
+            plutus_data: NonEmptySet::from_vec(vec![datum(1)]),
+            redeemer: Some(conway::Redeemers::List(MaybeIndefArray::Indef(vec![
+                redeemer(0, 1),
+            ]))),
+            ..empty()
+        };
+
+        let vkey_only = conway::WitnessSet {
+            vkeywitness: NonEmptySet::from_vec(vec![vkey(2, 2)]),
+            ..empty()
+        };
+
+        let before = script_data(&scripted);
+
+        let merged = scripted.clone().merge(vkey_only.clone());
+        assert_eq!(script_data(&merged), before);
+        assert_eq!(merged.vkeywitness.unwrap().to_vec().len(), 2);
+
+        let merged = vkey_only.merge(scripted);
+        assert_eq!(script_data(&merged), before);
+    }
+}
@@ -0,0 +1,228 @@
+//! Re-encoding of blocks in a normalized CBOR form
+//!
+//! Blocks carry whatever encoding their producer chose: indefinite lengths,
+//! maps in any key order, integers wider than needed. Normalizing rewrites
+//! them with definite lengths, the shortest heads and map entries sorted by
+//! the bytes of their keys (the core deterministic encoding of RFC 8949),
+//! which makes blocks easy to diff and compress.
+//!
+//! Hashes are computed over the original bytes, so a normalized block and
+//! its txs will often hash differently than on chain. [`NormalizedBlock`]
+//! reports both hashes so that no one mistakes one for the other. Bytes
+//! embedded with tag 24 are left as they are.
+
+use pallas_codec::minicbor::{data::Type, Decoder, Encoder};
+use pallas_crypto::hash::Hash;
+
+use crate::{Era, Error, MultiEraBlock};
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn invalid(e: impl std::fmt::Display) -> Error {
+    Error::invalid_cbor(e)
+}
+
+// Whether all entries of a definite or indefinite array or map were read,
+// consuming the break of the latter
+fn at_end(d: &mut Decoder, len: Option<u64>, read: u64) -> Result<bool> {
+    match len {
+        Some(len) => Ok(read >= len),
+        None => match d.datatype().map_err(invalid)? {
+            Type::Break => {
+                d.set_position(d.position() + 1);
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+    }
+}
+
+fn normalize_item(d: &mut Decoder, out: &mut Vec<u8>) -> Result<()> {
+    let mut e = Encoder::new(&mut *out);
+
+    match d.datatype().map_err(invalid)? {
+        Type::Bool => e.bool(d.bool().map_err(invalid)?).map_err(invalid)?,
+        Type::Null => {
+            d.null().map_err(invalid)?;
+            e.null().map_err(invalid)?
+        }
+        Type::Undefined => {
+            d.undefined().map_err(invalid)?;
+            e.undefined().map_err(invalid)?
+        }
+        Type::U8 | Type::U16 | Type::U32 | Type::U64 => {
+            e.u64(d.u64().map_err(invalid)?).map_err(invalid)?
+        }
+        Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::Int => {
+            e.int(d.int().map_err(invalid)?).map_err(invalid)?
+        }
+        Type::F16 => e.f16(d.f16().map_err(invalid)?).map_err(invalid)?,
+        Type::F32 => e.f32(d.f32().map_err(invalid)?).map_err(invalid)?,
+        Type::F64 => e.f64(d.f64().map_err(invalid)?).map_err(invalid)?,
+        Type::Simple => e.simple(d.simple().map_err(invalid)?).map_err(invalid)?,
+        Type::Bytes | Type::BytesIndef => {
+            let mut bytes = vec![];
+
+            for chunk in d.bytes_iter().map_err(invalid)? {
+                bytes.extend_from_slice(chunk.map_err(invalid)?);
+            }
+
+            e.bytes(&bytes).map_err(invalid)?
+        }
+        Type::String | Type::StringIndef => {
+            let mut string = String::new();
+
+            for chunk in d.str_iter().map_err(invalid)? {
+                string.push_str(chunk.map_err(invalid)?);
+            }
+
+            e.str(&string).map_err(invalid)?
+        }
+        Type::Tag => {
+            e.tag(d.tag().map_err(invalid)?).map_err(invalid)?;
+            return normalize_item(d, out);
+        }
+        Type::Array | Type::ArrayIndef => {
+            let len = d.array().map_err(invalid)?;
+            let mut items = vec![];
+
+            while !at_end(d, len, items.len() as u64)? {
+                let mut item = vec![];
+                normalize_item(d, &mut item)?;
+                items.push(item);
+            }
+
+            e.array(items.len() as u64).map_err(invalid)?;
+            out.extend(items.concat());
+            return Ok(());
+        }
+        Type::Map | Type::MapIndef => {
+            let len = d.map().map_err(invalid)?;
+            let mut entries = vec![];
+
+            while !at_end(d, len, entries.len() as u64)? {
+                let (mut key, mut value) = (vec![], vec![]);
+                normalize_item(d, &mut key)?;
+                normalize_item(d, &mut value)?;
+                entries.push((key, value));
+            }
+
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            e.map(entries.len() as u64).map_err(invalid)?;
+
+            for (key, value) in entries {
+                out.extend(key);
+                out.extend(value);
+            }
+
+            return Ok(());
+        }
+        x => return Err(invalid(format!("unexpected {x} at {}", d.position()))),
+    };
+
+    Ok(())
+}
+
+/// Re-encodes any CBOR item in the normalized form
+pub fn normalize_cbor(cbor: &[u8]) -> Result<Vec<u8>> {
+    let mut d = Decoder::new(cbor);
+    let mut out = vec![];
+
+    normalize_item(&mut d, &mut out)?;
+
+    if d.position() != cbor.len() {
+        return Err(invalid("trailing bytes after the cbor item"));
+    }
+
+    Ok(out)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedBlock {
+    pub era: Era,
+    pub cbor: Vec<u8>,
+    /// Hash of the block as found on chain
+    pub original_hash: Hash<32>,
+    /// Hash of the normalized header, usually not a hash found on chain
+    pub normalized_hash: Hash<32>,
+    /// Original and normalized hash of each tx
+    pub tx_hashes: Vec<(Hash<32>, Hash<32>)>,
+}
+
+impl NormalizedBlock {
+    pub fn hash_changed(&self) -> bool {
+        self.original_hash != self.normalized_hash
+    }
+
+    /// Original and normalized hashes of the txs whose hash changed
+    pub fn changed_txs(&self) -> impl Iterator<Item = &(Hash<32>, Hash<32>)> {
+        self.tx_hashes.iter().filter(|(a, b)| a != b)
+    }
+}
+
+/// Decodes a block of any era, wrapped with its era tag, and re-encodes it
+/// in the normalized form, reporting how its hashes changed
+pub fn normalize_block(cbor: &[u8]) -> Result<NormalizedBlock> {
+    let original = MultiEraBlock::decode(cbor)?;
+    let normalized_cbor = normalize_cbor(cbor)?;
+    let normalized = MultiEraBlock::decode(&normalized_cbor)?;
+
+    let tx_hashes = original
+        .txs()
+        .iter()
+        .zip(normalized.txs().iter())
+        .map(|(a, b)| (a.hash(), b.hash()))
+        .collect();
+
+    Ok(NormalizedBlock {
+        era: original.era(),
+        original_hash: original.hash(),
+        normalized_hash: normalized.hash(),
+        tx_hashes,
+        cbor: normalized_cbor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_are_normalized() {
+        // [_ 1, {2: "b", 1: 0x190001}], with a non-minimal integer
+        let cbor = hex::decode("9f01a202616201190001ff").unwrap();
+
+        assert_eq!(
+            hex::encode(normalize_cbor(&cbor).unwrap()),
+            "8201a20101026162"
+        );
+    }
+
+    #[test]
+    fn blocks_of_every_era_normalize() {
+        let blocks = [
+            include_str!("../../test_data/byron1.block"),
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/conway1.block"),
+        ];
+
+        for block in blocks {
+            let cbor = hex::decode(block).unwrap();
+            let normalized = normalize_block(&cbor).unwrap();
+
+            let original = MultiEraBlock::decode(&cbor).unwrap();
+            assert_eq!(normalized.era, original.era());
+            assert_eq!(normalized.original_hash, original.hash());
+            assert_eq!(normalized.tx_hashes.len(), original.txs().len());
+
+            // normalizing is idempotent
+            let again = normalize_block(&normalized.cbor).unwrap();
+            assert_eq!(again.cbor, normalized.cbor);
+            assert!(!again.hash_changed());
+            assert_eq!(again.changed_txs().count(), 0);
+        }
+    }
+}
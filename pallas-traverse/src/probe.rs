@@ -1,8 +1,13 @@
 //! Lightweight inspection of block data without full CBOR decoding
 
-use pallas_codec::minicbor::{data::Token, decode::Tokenizer};
+use pallas_addresses::{Address, ShelleyDelegationPart, ShelleyPaymentPart};
+use pallas_codec::minicbor::{
+    data::{Token, Type},
+    decode::Tokenizer,
+    Decoder,
+};
 
-use crate::Era;
+use crate::{era::EraTag, Era, Error};
 
 #[derive(Debug)]
 pub enum Outcome {
@@ -22,21 +27,141 @@ pub fn block_era(cbor: &[u8]) -> Outcome {
     }
 
     match tokenizer.next() {
-        Some(Ok(Token::U8(variant))) => match variant {
-            0 => Outcome::EpochBoundary,
-            1 => Outcome::Matched(Era::Byron),
-            2 => Outcome::Matched(Era::Shelley),
-            3 => Outcome::Matched(Era::Allegra),
-            4 => Outcome::Matched(Era::Mary),
-            5 => Outcome::Matched(Era::Alonzo),
-            6 => Outcome::Matched(Era::Babbage),
-            7 => Outcome::Matched(Era::Conway),
-            _ => Outcome::Inconclusive,
+        Some(Ok(Token::U8(variant))) => match EraTag::try_from(variant as u16) {
+            Ok(EraTag::EpochBoundary) => Outcome::EpochBoundary,
+            Ok(tag) => Outcome::Matched(tag.era()),
+            Err(_) => Outcome::Inconclusive,
         },
         _ => Outcome::Inconclusive,
     }
 }
 
+/// Tag of the block wrapper, whether it matches a known era or not
+pub fn block_tag(cbor: &[u8]) -> Option<u16> {
+    let mut tokenizer = Tokenizer::new(cbor);
+
+    if !matches!(tokenizer.next(), Some(Ok(Token::Array(2)))) {
+        return None;
+    }
+
+    match tokenizer.next() {
+        Some(Ok(Token::U8(x))) => Some(x as u16),
+        Some(Ok(Token::U16(x))) => Some(x),
+        _ => None,
+    }
+}
+
+/// Credentials and lovelace of an output, as needed by balance indexers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputCredentials {
+    /// Payment part of the address, `None` for Byron addresses
+    pub payment: Option<ShelleyPaymentPart>,
+    pub delegation: ShelleyDelegationPart,
+    pub lovelace: u64,
+}
+
+impl OutputCredentials {
+    fn new(address: Option<&[u8]>, lovelace: Option<u64>) -> Result<Self, Error> {
+        let address = address.ok_or_else(|| Error::invalid_cbor("output without address"))?;
+        let lovelace = lovelace.ok_or_else(|| Error::invalid_cbor("output without value"))?;
+
+        let (payment, delegation) =
+            match Address::from_bytes(address).map_err(Error::invalid_cbor)? {
+                Address::Shelley(x) => (Some(x.payment().clone()), x.delegation().clone()),
+                Address::Byron(_) => (None, ShelleyDelegationPart::Null),
+                Address::Stake(_) => return Err(Error::invalid_cbor("stake address in output")),
+            };
+
+        Ok(Self {
+            payment,
+            delegation,
+            lovelace,
+        })
+    }
+}
+
+// Whether all entries of a definite or indefinite array or map were read,
+// consuming the break of the latter
+fn at_end(d: &mut Decoder, len: Option<u64>, read: u64) -> Result<bool, Error> {
+    match len {
+        Some(len) => Ok(read >= len),
+        None => match d.datatype().map_err(Error::invalid_cbor)? {
+            Type::Break => {
+                d.set_position(d.position() + 1);
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+    }
+}
+
+fn coin(d: &mut Decoder) -> Result<u64, Error> {
+    match d.datatype().map_err(Error::invalid_cbor)? {
+        Type::Array | Type::ArrayIndef => {
+            // [coin, multiasset], the assets are skipped
+            let len = d.array().map_err(Error::invalid_cbor)?;
+            let coin = d.u64().map_err(Error::invalid_cbor)?;
+            let mut read = 1;
+
+            while !at_end(d, len, read)? {
+                d.skip().map_err(Error::invalid_cbor)?;
+                read += 1;
+            }
+
+            Ok(coin)
+        }
+        _ => d.u64().map_err(Error::invalid_cbor),
+    }
+}
+
+/// Extracts the payment and stake credentials and the lovelace of an output
+/// of any era, decoding just the address and the coin.
+///
+/// Meant for indexers going through large amounts of outputs, the rest of
+/// the output (assets, datums, scripts) is left undecoded, and the stake
+/// credential of pointer addresses is left unresolved.
+pub fn output_credentials(cbor: &[u8]) -> Result<OutputCredentials, Error> {
+    let mut d = Decoder::new(cbor);
+
+    let mut address = None;
+    let mut lovelace = None;
+
+    match d.datatype().map_err(Error::invalid_cbor)? {
+        Type::Map | Type::MapIndef => {
+            let len = d.map().map_err(Error::invalid_cbor)?;
+            let mut read = 0;
+
+            while !at_end(&mut d, len, read)? {
+                read += 1;
+
+                match d.u8().map_err(Error::invalid_cbor)? {
+                    0 => address = Some(d.bytes().map_err(Error::invalid_cbor)?),
+                    1 => lovelace = Some(coin(&mut d)?),
+                    _ => d.skip().map_err(Error::invalid_cbor)?,
+                }
+            }
+        }
+        Type::Array | Type::ArrayIndef => {
+            d.array().map_err(Error::invalid_cbor)?;
+
+            match d.datatype().map_err(Error::invalid_cbor)? {
+                Type::Bytes => address = Some(d.bytes().map_err(Error::invalid_cbor)?),
+                _ => {
+                    // byron addresses are cbor arrays, only their bytes are kept
+                    let start = d.position();
+                    d.skip().map_err(Error::invalid_cbor)?;
+                    address = Some(&cbor[start..d.position()]);
+                }
+            }
+
+            lovelace = Some(coin(&mut d)?);
+        }
+        _ => return Err(Error::unknown_cbor(cbor)),
+    }
+
+    OutputCredentials::new(address, lovelace)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,4 +245,54 @@ mod tests {
 
         assert!(matches!(inference, Outcome::Matched(Era::Conway)));
     }
+
+    #[test]
+    fn output_credentials_match_full_decoding() {
+        let subjects = [
+            (Era::Byron, include_str!("../../test_data/byron1.tx")),
+            (Era::Shelley, include_str!("../../test_data/shelley1.tx")),
+            (Era::Mary, include_str!("../../test_data/mary1.tx")),
+            (Era::Alonzo, include_str!("../../test_data/alonzo1.tx")),
+            (Era::Babbage, include_str!("../../test_data/babbage13.tx")),
+            (Era::Conway, include_str!("../../test_data/conway1.tx")),
+        ];
+
+        for (era, tx_hex) in subjects {
+            let bytes = hex::decode(tx_hex).unwrap();
+            let tx = crate::MultiEraTx::decode_for_era(era, &bytes).unwrap();
+
+            for output in tx.outputs() {
+                let extracted = output_credentials(&output.encode()).unwrap();
+
+                assert_eq!(extracted.lovelace, output.value().coin());
+
+                match output.address().unwrap() {
+                    Address::Shelley(x) => {
+                        assert_eq!(extracted.payment.as_ref(), Some(x.payment()));
+                        assert_eq!(&extracted.delegation, x.delegation());
+                    }
+                    _ => {
+                        assert_eq!(extracted.payment, None);
+                        assert_eq!(extracted.delegation, ShelleyDelegationPart::Null);
+                    }
+                }
+            }
+        }
+
+        // indefinite map holding an indefinite [coin, multiasset] value
+        let address =
+            hex::decode("61c3b1f2e1a0a1a5d40a9f6a5a4e0d9c7d1f08c1ab0f2cd76a1b2a4e7c").unwrap();
+        let cbor = [
+            &[0xbf, 0x00, 0x58, 29][..],
+            &address,
+            &[0x01, 0x9f, 0x05, 0xa0, 0xff, 0xff],
+        ]
+        .concat();
+        let extracted = output_credentials(&cbor).unwrap();
+
+        assert_eq!(extracted.lovelace, 5);
+        assert_eq!(extracted.delegation, ShelleyDelegationPart::Null);
+
+        assert!(output_credentials(&[0x01]).is_err());
+    }
 }
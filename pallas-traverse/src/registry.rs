@@ -0,0 +1,128 @@
+//! Extension point for eras unknown to pallas. Partner chains reusing the
+//! Ouroboros block framing can register a decoder for their own era tag and
+//! have their blocks decoded alongside the Cardano ones.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{probe, Error, MultiEraBlock};
+
+/// Decoder for the blocks of a custom era
+pub trait EraDecoder: Send + Sync {
+    /// Tag of the era in the `[tag, block]` wrapper
+    fn tag(&self) -> u16;
+
+    /// Decodes the whole wrapped block. Eras sharing the structure of a
+    /// Cardano one can reuse its decoder through
+    /// `MultiEraBlock::decode_custom`.
+    fn decode<'b>(&self, cbor: &'b [u8]) -> Result<MultiEraBlock<'b>, Error>;
+}
+
+/// Set of custom era decoders, falling back to the built-in eras for any
+/// other tag
+#[derive(Default, Clone)]
+pub struct EraRegistry {
+    decoders: HashMap<u16, Arc<dyn EraDecoder>>,
+}
+
+impl EraRegistry {
+    /// Tags used by the Cardano eras, which can't be overridden
+    pub const RESERVED_TAGS: std::ops::RangeInclusive<u16> = 0..=7;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, decoder: impl EraDecoder + 'static) -> Result<(), Error> {
+        let tag = decoder.tag();
+
+        if Self::RESERVED_TAGS.contains(&tag) || self.decoders.contains_key(&tag) {
+            return Err(Error::DuplicateEraTag(tag));
+        }
+
+        self.decoders.insert(tag, Arc::new(decoder));
+
+        Ok(())
+    }
+
+    pub fn with(mut self, decoder: impl EraDecoder + 'static) -> Result<Self, Error> {
+        self.register(decoder)?;
+        Ok(self)
+    }
+
+    pub fn is_registered(&self, tag: u16) -> bool {
+        self.decoders.contains_key(&tag)
+    }
+
+    /// Decodes a block of any registered or built-in era
+    pub fn decode<'b>(&self, cbor: &'b [u8]) -> Result<MultiEraBlock<'b>, Error> {
+        let custom = probe::block_tag(cbor).and_then(|tag| self.decoders.get(&tag));
+
+        match custom {
+            Some(decoder) => decoder.decode(cbor),
+            None => MultiEraBlock::decode(cbor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{era::EraTag, Era};
+
+    /// Sidechain whose blocks are Conway blocks under tag 42
+    struct Sidechain;
+
+    impl EraDecoder for Sidechain {
+        fn tag(&self) -> u16 {
+            42
+        }
+
+        fn decode<'b>(&self, cbor: &'b [u8]) -> Result<MultiEraBlock<'b>, Error> {
+            MultiEraBlock::decode_custom(cbor, EraTag::Conway)
+        }
+    }
+
+    #[test]
+    fn custom_era_tag() {
+        let mut cbor = hex::decode(include_str!("../../test_data/conway1.block")).unwrap();
+
+        // [7, block] -> [42, block]
+        assert_eq!(&cbor[..2], &[0x82, 0x07]);
+        cbor.splice(1..2, [0x18, 42]);
+
+        assert!(MultiEraBlock::decode(&cbor).is_err());
+
+        let registry = EraRegistry::new().with(Sidechain).unwrap();
+        let block = registry.decode(&cbor).unwrap();
+        assert_eq!(block.era(), Era::Conway);
+        assert!(block.tx_count() > 0);
+
+        // built-in eras keep working
+        let alonzo = hex::decode(include_str!("../../test_data/alonzo1.block")).unwrap();
+        assert_eq!(registry.decode(&alonzo).unwrap().era(), Era::Alonzo);
+    }
+
+    #[test]
+    fn reserved_tags() {
+        struct Impostor;
+
+        impl EraDecoder for Impostor {
+            fn tag(&self) -> u16 {
+                7
+            }
+
+            fn decode<'b>(&self, cbor: &'b [u8]) -> Result<MultiEraBlock<'b>, Error> {
+                MultiEraBlock::decode(cbor)
+            }
+        }
+
+        let mut registry = EraRegistry::new();
+        assert!(matches!(
+            registry.register(Impostor),
+            Err(Error::DuplicateEraTag(7))
+        ));
+
+        registry.register(Sidechain).unwrap();
+        assert!(registry.register(Sidechain).is_err());
+    }
+}
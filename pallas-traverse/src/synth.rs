@@ -0,0 +1,805 @@
+//! Generator of synthetic chains to be used as test fixtures by followers,
+//! indexers and UTxO stores. Blocks are valid in shape: they decode, hashes
+//! link and transactions spend existing outputs with properly signed
+//! witnesses, but VRF and KES material are random bytes.
+
+use std::collections::BTreeMap;
+
+use pallas_addresses::{Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart};
+use pallas_codec::minicbor::{self, Encoder};
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::ed25519::SecretKey,
+};
+use pallas_primitives::{alonzo, babbage, VrfCert};
+
+use crate::{Era, Error, Feature, MultiEraBlock};
+
+const GENESIS_LOVELACE: u64 = 1_000_000_000_000;
+const MIN_LOVELACE: u64 = 1_000_000;
+const TTL_SLOTS: u64 = 7200;
+const KES_PERIOD_SLOTS: u64 = 129_600;
+const METADATA_LABEL: u64 = 674;
+
+// SplitMix64, enough to make fixtures reproducible from a seed
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next_u64() % n
+        }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut out = [0; N];
+
+        for chunk in out.chunks_mut(8) {
+            let x = self.next_u64().to_be_bytes();
+            chunk.copy_from_slice(&x[..chunk.len()]);
+        }
+
+        out
+    }
+
+    fn vec(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+/// Relative weights of the kinds of transactions in generated blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxMix {
+    /// Plain ADA transfers
+    pub payment: u32,
+    /// Transfers minting native assets, payments before Mary
+    pub multi_asset: u32,
+    /// Transfers with CIP-20 message metadata
+    pub metadata: u32,
+}
+
+impl Default for TxMix {
+    fn default() -> Self {
+        Self {
+            payment: 6,
+            multi_asset: 2,
+            metadata: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxKind {
+    Payment,
+    MultiAsset,
+    Metadata,
+}
+
+impl TxMix {
+    fn pick(&self, rng: &mut Rng) -> TxKind {
+        let total = self.payment as u64 + self.multi_asset as u64 + self.metadata as u64;
+        let x = rng.below(total);
+
+        if x < self.payment as u64 {
+            TxKind::Payment
+        } else if x < self.payment as u64 + self.multi_asset as u64 {
+            TxKind::MultiAsset
+        } else {
+            TxKind::Metadata
+        }
+    }
+}
+
+pub type AssetId = (Hash<28>, Vec<u8>);
+
+/// An output of the synthetic chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SynthUtxo {
+    pub tx_hash: Hash<32>,
+    pub index: u64,
+    pub address: Vec<u8>,
+    pub lovelace: u64,
+    pub assets: BTreeMap<AssetId, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SynthBlock {
+    pub era: Era,
+    pub number: u64,
+    pub slot: u64,
+    pub hash: Hash<32>,
+    pub prev_hash: Option<Hash<32>>,
+    pub tx_hashes: Vec<Hash<32>>,
+    /// The block wrapped with its era tag, as served by node-to-client
+    /// chain-sync
+    pub cbor: Vec<u8>,
+}
+
+impl SynthBlock {
+    pub fn decode(&self) -> Result<MultiEraBlock<'_>, Error> {
+        MultiEraBlock::decode(&self.cbor)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SynthChain {
+    /// Outputs available before the first block
+    pub genesis: Vec<SynthUtxo>,
+    pub blocks: Vec<SynthBlock>,
+    /// Outputs left unspent after the last block
+    pub utxos: Vec<SynthUtxo>,
+}
+
+/// Generates chains of blocks of a single era, reproducible from a seed
+#[derive(Debug, Clone)]
+pub struct ChainGenerator {
+    era: Era,
+    seed: u64,
+    network: Network,
+    first_slot: u64,
+    max_slot_gap: u64,
+    txs_per_block: (usize, usize),
+    mix: TxMix,
+    wallets: usize,
+    genesis_outputs: usize,
+}
+
+impl ChainGenerator {
+    pub fn new(era: Era) -> Self {
+        Self {
+            era,
+            seed: 0,
+            network: Network::Testnet,
+            first_slot: 0,
+            max_slot_gap: 40,
+            txs_per_block: (0, 10),
+            mix: TxMix::default(),
+            wallets: 8,
+            genesis_outputs: 16,
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn first_slot(mut self, slot: u64) -> Self {
+        self.first_slot = slot;
+        self
+    }
+
+    /// Maximum number of slots between consecutive blocks
+    pub fn max_slot_gap(mut self, slots: u64) -> Self {
+        self.max_slot_gap = slots.max(1);
+        self
+    }
+
+    /// Range of the number of transactions per block, both inclusive
+    pub fn txs_per_block(mut self, min: usize, max: usize) -> Self {
+        self.txs_per_block = (min, max.max(min));
+        self
+    }
+
+    pub fn tx_mix(mut self, mix: TxMix) -> Self {
+        self.mix = mix;
+        self
+    }
+
+    /// Number of keys owning the outputs of the chain
+    pub fn wallets(mut self, wallets: usize) -> Self {
+        self.wallets = wallets.max(1);
+        self
+    }
+
+    pub fn genesis_outputs(mut self, outputs: usize) -> Self {
+        self.genesis_outputs = outputs;
+        self
+    }
+
+    /// Generates a chain of `blocks` blocks. Byron is not supported.
+    pub fn generate(&self, blocks: usize) -> Result<SynthChain, Error> {
+        if self.era == Era::Byron {
+            return Err(Error::InvalidEra(self.era));
+        }
+
+        let mut state = State::new(self);
+        let genesis = state.utxos.iter().map(|(x, _)| x.clone()).collect();
+
+        let mut out = Vec::with_capacity(blocks);
+        let mut slot = self.first_slot;
+        let mut prev_hash = None;
+
+        for number in 0..blocks as u64 {
+            if number > 0 {
+                slot += 1 + state.rng.below(self.max_slot_gap);
+            }
+
+            let block = state.block(number, slot, prev_hash);
+            prev_hash = Some(block.hash);
+            out.push(block);
+        }
+
+        Ok(SynthChain {
+            genesis,
+            blocks: out,
+            utxos: state.utxos.into_iter().map(|(x, _)| x).collect(),
+        })
+    }
+}
+
+struct Tx {
+    body: Vec<u8>,
+    witness_set: Vec<u8>,
+    auxiliary_data: Option<Vec<u8>>,
+}
+
+struct State<'a> {
+    config: &'a ChainGenerator,
+    rng: Rng,
+    keys: Vec<SecretKey>,
+    addresses: Vec<Vec<u8>>,
+    // unspent outputs along with the index of their owner key
+    utxos: Vec<(SynthUtxo, usize)>,
+    policy_script: Vec<u8>,
+    policy_id: Hash<28>,
+}
+
+fn key_hash(key: &SecretKey) -> Hash<28> {
+    Hasher::<224>::hash(key.public_key().as_ref())
+}
+
+fn cbor_array(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut buf = vec![];
+    Encoder::new(&mut buf).array(items.len() as u64).unwrap();
+
+    for item in items {
+        buf.extend_from_slice(item);
+    }
+
+    buf
+}
+
+impl<'a> State<'a> {
+    fn new(config: &'a ChainGenerator) -> Self {
+        let mut rng = Rng(config.seed);
+
+        let keys: Vec<_> = (0..config.wallets)
+            .map(|_| SecretKey::from(rng.bytes::<{ SecretKey::SIZE }>()))
+            .collect();
+
+        let addresses = keys
+            .iter()
+            .map(|key| {
+                ShelleyAddress::new(
+                    config.network,
+                    ShelleyPaymentPart::key_hash(key_hash(key)),
+                    ShelleyDelegationPart::Null,
+                )
+                .to_vec()
+            })
+            .collect();
+
+        // the minting policy is a signature of the first key
+        let mut policy_script = vec![];
+        Encoder::new(&mut policy_script)
+            .array(2)
+            .unwrap()
+            .u8(0)
+            .unwrap()
+            .bytes(key_hash(&keys[0]).as_ref())
+            .unwrap();
+
+        let policy_id = Hasher::<224>::hash_tagged(&policy_script, 0);
+
+        let mut state = Self {
+            config,
+            rng,
+            keys,
+            addresses,
+            utxos: vec![],
+            policy_script,
+            policy_id,
+        };
+
+        for index in 0..config.genesis_outputs {
+            let owner = index % config.wallets;
+
+            let utxo = SynthUtxo {
+                tx_hash: Hash::new(state.rng.bytes()),
+                index: 0,
+                address: state.addresses[owner].clone(),
+                lovelace: GENESIS_LOVELACE,
+                assets: BTreeMap::new(),
+            };
+
+            state.utxos.push((utxo, owner));
+        }
+
+        state
+    }
+
+    fn has(&self, feature: Feature) -> bool {
+        self.config.era.has_feature(feature)
+    }
+
+    fn encode_value(
+        &self,
+        e: &mut Encoder<&mut Vec<u8>>,
+        lovelace: u64,
+        assets: &BTreeMap<AssetId, u64>,
+    ) {
+        if assets.is_empty() {
+            e.u64(lovelace).unwrap();
+            return;
+        }
+
+        let mut policies: BTreeMap<&Hash<28>, Vec<(&Vec<u8>, u64)>> = BTreeMap::new();
+
+        for ((policy, name), quantity) in assets {
+            policies.entry(policy).or_default().push((name, *quantity));
+        }
+
+        e.array(2).unwrap().u64(lovelace).unwrap();
+        e.map(policies.len() as u64).unwrap();
+
+        for (policy, names) in policies {
+            e.bytes(policy.as_ref()).unwrap();
+            e.map(names.len() as u64).unwrap();
+
+            for (name, quantity) in names {
+                e.bytes(name).unwrap().u64(quantity).unwrap();
+            }
+        }
+    }
+
+    fn encode_output(&self, e: &mut Encoder<&mut Vec<u8>>, output: &SynthUtxo) {
+        if self.has(Feature::CIP31) {
+            e.map(2)
+                .unwrap()
+                .u8(0)
+                .unwrap()
+                .bytes(&output.address)
+                .unwrap();
+            e.u8(1).unwrap();
+        } else {
+            e.array(2).unwrap().bytes(&output.address).unwrap();
+        }
+
+        self.encode_value(e, output.lovelace, &output.assets);
+    }
+
+    fn encode_body(
+        &self,
+        input: &SynthUtxo,
+        outputs: &[SynthUtxo],
+        fee: u64,
+        ttl: u64,
+        auxiliary_data_hash: Option<Hash<32>>,
+        mint: &BTreeMap<AssetId, u64>,
+    ) -> Vec<u8> {
+        let mut buf = vec![];
+        let mut e = Encoder::new(&mut buf);
+
+        let len = 4 + auxiliary_data_hash.is_some() as u64 + !mint.is_empty() as u64;
+        e.map(len).unwrap();
+
+        e.u8(0).unwrap().array(1).unwrap();
+        e.array(2).unwrap();
+        e.bytes(input.tx_hash.as_ref())
+            .unwrap()
+            .u64(input.index)
+            .unwrap();
+
+        e.u8(1).unwrap().array(outputs.len() as u64).unwrap();
+        for output in outputs {
+            self.encode_output(&mut e, output);
+        }
+
+        e.u8(2).unwrap().u64(fee).unwrap();
+        e.u8(3).unwrap().u64(ttl).unwrap();
+
+        if let Some(hash) = auxiliary_data_hash {
+            e.u8(7).unwrap().bytes(hash.as_ref()).unwrap();
+        }
+
+        if !mint.is_empty() {
+            e.u8(9).unwrap().map(1).unwrap();
+            e.bytes(self.policy_id.as_ref()).unwrap();
+            e.map(mint.len() as u64).unwrap();
+
+            for ((_, name), quantity) in mint {
+                e.bytes(name).unwrap().u64(*quantity).unwrap();
+            }
+        }
+
+        buf
+    }
+
+    fn encode_witness_set(&self, tx_hash: &Hash<32>, signers: &[usize], minting: bool) -> Vec<u8> {
+        let mut buf = vec![];
+        let mut e = Encoder::new(&mut buf);
+
+        e.map(1 + minting as u64).unwrap();
+        e.u8(0).unwrap().array(signers.len() as u64).unwrap();
+
+        for signer in signers {
+            let key = &self.keys[*signer];
+            let signature = key.sign(tx_hash);
+
+            e.array(2).unwrap();
+            e.bytes(key.public_key().as_ref()).unwrap();
+            e.bytes(signature.as_ref()).unwrap();
+        }
+
+        if minting {
+            e.u8(1).unwrap().array(1).unwrap();
+            buf.extend_from_slice(&self.policy_script);
+        }
+
+        buf
+    }
+
+    fn encode_metadata(&self, message: &str) -> Vec<u8> {
+        let mut buf = vec![];
+
+        Encoder::new(&mut buf)
+            .map(1)
+            .unwrap()
+            .u64(METADATA_LABEL)
+            .unwrap()
+            .map(1)
+            .unwrap()
+            .str("msg")
+            .unwrap()
+            .array(1)
+            .unwrap()
+            .str(message)
+            .unwrap();
+
+        buf
+    }
+
+    /// Builds a transaction spending a random output, `None` if none is
+    /// large enough
+    fn tx(&mut self, slot: u64) -> Option<Tx> {
+        let fee = 160_000 + self.rng.below(40_000);
+
+        let candidates: Vec<_> = (0..self.utxos.len())
+            .filter(|i| self.utxos[*i].0.lovelace >= 2 * MIN_LOVELACE + fee)
+            .collect();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let picked = candidates[self.rng.below(candidates.len() as u64) as usize];
+        let (input, owner) = self.utxos.swap_remove(picked);
+
+        let mut kind = self.config.mix.pick(&mut self.rng);
+
+        if kind == TxKind::MultiAsset && !self.has(Feature::MultiAssets) {
+            kind = TxKind::Payment;
+        }
+
+        let available = input.lovelace - fee - MIN_LOVELACE;
+        let amount = MIN_LOVELACE
+            + self
+                .rng
+                .below((available - MIN_LOVELACE).min(1_000_000_000));
+        let recipient = self.rng.below(self.keys.len() as u64) as usize;
+
+        let mut mint = BTreeMap::new();
+
+        if kind == TxKind::MultiAsset {
+            let name = format!("TOKEN{}", self.rng.below(4)).into_bytes();
+            mint.insert((self.policy_id, name), 1 + self.rng.below(1_000));
+        }
+
+        let outputs = vec![
+            SynthUtxo {
+                tx_hash: Hash::new([0; 32]),
+                index: 0,
+                address: self.addresses[recipient].clone(),
+                lovelace: amount,
+                assets: mint.clone(),
+            },
+            SynthUtxo {
+                tx_hash: Hash::new([0; 32]),
+                index: 1,
+                address: input.address.clone(),
+                lovelace: input.lovelace - amount - fee,
+                assets: input.assets.clone(),
+            },
+        ];
+
+        let auxiliary_data = (kind == TxKind::Metadata).then(|| {
+            let message = format!("synthetic tx {}", self.rng.next_u64());
+            self.encode_metadata(&message)
+        });
+
+        let auxiliary_data_hash = auxiliary_data.as_deref().map(Hasher::<256>::hash);
+
+        let body = self.encode_body(
+            &input,
+            &outputs,
+            fee,
+            slot + TTL_SLOTS,
+            auxiliary_data_hash,
+            &mint,
+        );
+
+        let tx_hash = Hasher::<256>::hash(&body);
+
+        let mut signers = vec![owner];
+        if !mint.is_empty() && owner != 0 {
+            signers.push(0);
+        }
+
+        let witness_set = self.encode_witness_set(&tx_hash, &signers, !mint.is_empty());
+
+        for mut output in outputs {
+            output.tx_hash = tx_hash;
+
+            let owner = if output.index == 0 { recipient } else { owner };
+            self.utxos.push((output, owner));
+        }
+
+        Some(Tx {
+            body,
+            witness_set,
+            auxiliary_data,
+        })
+    }
+
+    fn block(&mut self, number: u64, slot: u64, prev_hash: Option<Hash<32>>) -> SynthBlock {
+        let (min, max) = self.config.txs_per_block;
+        let count = min + self.rng.below((max - min + 1) as u64) as usize;
+
+        let txs: Vec<_> = (0..count).map_while(|_| self.tx(slot)).collect();
+        let tx_hashes = txs.iter().map(|x| Hasher::<256>::hash(&x.body)).collect();
+
+        let bodies = cbor_array(&txs.iter().map(|x| x.body.clone()).collect::<Vec<_>>());
+        let witness_sets = cbor_array(
+            &txs.iter()
+                .map(|x| x.witness_set.clone())
+                .collect::<Vec<_>>(),
+        );
+
+        let mut auxiliary_data_set = vec![];
+        {
+            let with_aux: Vec<_> = txs
+                .iter()
+                .enumerate()
+                .filter_map(|(i, x)| Some((i, x.auxiliary_data.as_ref()?)))
+                .collect();
+
+            let mut e = Encoder::new(&mut auxiliary_data_set);
+            e.map(with_aux.len() as u64).unwrap();
+
+            for (i, aux) in with_aux {
+                e.u64(i as u64).unwrap();
+                e.writer_mut().extend_from_slice(aux);
+            }
+        }
+
+        let mut parts = vec![bodies, witness_sets, auxiliary_data_set];
+
+        // blocks only carry the invalid transactions from alonzo onwards
+        if self.has(Feature::SmartContracts) {
+            parts.push(cbor_array(&[]));
+        }
+
+        let body_size = parts.iter().map(Vec::len).sum::<usize>() as u64;
+
+        let part_hashes: Vec<u8> = parts.iter().flat_map(|x| *Hasher::<256>::hash(x)).collect();
+
+        let body_hash = Hasher::<256>::hash(&part_hashes);
+
+        let header = self.header(number, slot, prev_hash, body_size, body_hash);
+        let hash = Hasher::<256>::hash(&header);
+
+        let mut cbor = vec![];
+        let mut e = Encoder::new(&mut cbor);
+        e.array(2).unwrap().u16(self.config.era.into()).unwrap();
+        e.array(1 + parts.len() as u64).unwrap();
+        e.writer_mut().extend_from_slice(&header);
+
+        for part in parts {
+            e.writer_mut().extend_from_slice(&part);
+        }
+
+        SynthBlock {
+            era: self.config.era,
+            number,
+            slot,
+            hash,
+            prev_hash,
+            tx_hashes,
+            cbor,
+        }
+    }
+
+    fn header(
+        &mut self,
+        number: u64,
+        slot: u64,
+        prev_hash: Option<Hash<32>>,
+        block_body_size: u64,
+        block_body_hash: Hash<32>,
+    ) -> Vec<u8> {
+        let issuer_vkey = self.rng.vec(32).into();
+        let vrf_vkey = self.rng.vec(32).into();
+        let hot_vkey = self.rng.vec(32).into();
+        let sigma = self.rng.vec(64).into();
+        let body_signature = self.rng.vec(448).into();
+
+        let protocol = match self.config.era {
+            Era::Shelley => (2, 0),
+            Era::Allegra => (3, 0),
+            Era::Mary => (4, 0),
+            Era::Alonzo => (6, 0),
+            Era::Babbage => (8, 0),
+            _ => (9, 0),
+        };
+
+        if self.has(Feature::CIP31) {
+            let header = babbage::Header {
+                header_body: babbage::HeaderBody {
+                    block_number: number,
+                    slot,
+                    prev_hash,
+                    issuer_vkey,
+                    vrf_vkey,
+                    vrf_result: VrfCert(self.rng.vec(32).into(), self.rng.vec(80).into()),
+                    block_body_size,
+                    block_body_hash,
+                    operational_cert: babbage::OperationalCert {
+                        operational_cert_hot_vkey: hot_vkey,
+                        operational_cert_sequence_number: 0,
+                        operational_cert_kes_period: slot / KES_PERIOD_SLOTS,
+                        operational_cert_sigma: sigma,
+                    },
+                    protocol_version: protocol,
+                },
+                body_signature,
+            };
+
+            minicbor::to_vec(header).unwrap()
+        } else {
+            let header = alonzo::Header {
+                header_body: alonzo::HeaderBody {
+                    block_number: number,
+                    slot,
+                    prev_hash,
+                    issuer_vkey,
+                    vrf_vkey,
+                    nonce_vrf: VrfCert(self.rng.vec(32).into(), self.rng.vec(80).into()),
+                    leader_vrf: VrfCert(self.rng.vec(32).into(), self.rng.vec(80).into()),
+                    block_body_size,
+                    block_body_hash,
+                    operational_cert_hot_vkey: hot_vkey,
+                    operational_cert_sequence_number: 0,
+                    operational_cert_kes_period: slot / KES_PERIOD_SLOTS,
+                    operational_cert_sigma: sigma,
+                    protocol_major: protocol.0,
+                    protocol_minor: protocol.1,
+                },
+                body_signature,
+            };
+
+            minicbor::to_vec(header).unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const ERAS: [Era; 6] = [
+        Era::Shelley,
+        Era::Allegra,
+        Era::Mary,
+        Era::Alonzo,
+        Era::Babbage,
+        Era::Conway,
+    ];
+
+    #[test]
+    fn chains_decode_and_link() {
+        for era in ERAS {
+            let chain = ChainGenerator::new(era)
+                .seed(42)
+                .first_slot(1_000)
+                .txs_per_block(1, 6)
+                .generate(20)
+                .unwrap();
+
+            let mut unspent: HashMap<_, _> = chain
+                .genesis
+                .iter()
+                .map(|x| ((x.tx_hash, x.index), x.lovelace))
+                .collect();
+
+            let mut prev = None;
+
+            for synth in &chain.blocks {
+                let block = synth.decode().unwrap();
+
+                assert_eq!(block.era(), era);
+                assert_eq!(block.hash(), synth.hash);
+                assert_eq!(block.slot(), synth.slot);
+                assert_eq!(block.header().previous_hash(), prev);
+                prev = Some(block.hash());
+
+                for (tx, hash) in block.txs().iter().zip(&synth.tx_hashes) {
+                    assert_eq!(tx.hash(), *hash);
+                    assert!(!tx.vkey_witnesses().is_empty());
+
+                    let spent: u64 = tx
+                        .inputs()
+                        .iter()
+                        .map(|x| unspent.remove(&(*x.hash(), x.index())).unwrap())
+                        .sum();
+
+                    let produced: u64 = tx.outputs().iter().map(|x| x.value().coin()).sum();
+                    assert_eq!(spent, produced + tx.fee().unwrap());
+
+                    for (index, output) in tx.outputs().iter().enumerate() {
+                        unspent.insert((tx.hash(), index as u64), output.value().coin());
+                    }
+                }
+
+                assert_eq!(block.txs().len(), synth.tx_hashes.len());
+            }
+
+            assert_eq!(unspent.len(), chain.utxos.len());
+        }
+    }
+
+    #[test]
+    fn tx_mix_and_determinism() {
+        let mix = TxMix {
+            payment: 0,
+            multi_asset: 1,
+            metadata: 1,
+        };
+
+        let generator = ChainGenerator::new(Era::Conway).seed(7).tx_mix(mix);
+        let chain = generator.generate(10).unwrap();
+
+        let txs: Vec<_> = chain.blocks.iter().map(|x| x.decode().unwrap()).collect();
+        let txs: Vec<_> = txs.iter().flat_map(|x| x.txs()).collect();
+
+        assert!(txs.iter().any(|x| !x.mints().is_empty()));
+        assert!(txs
+            .iter()
+            .any(|x| x.metadata().find(METADATA_LABEL).is_some()));
+        assert!(txs
+            .iter()
+            .all(|x| x.mints().is_empty() != x.metadata().as_alonzo().is_none()));
+
+        let again = generator.generate(10).unwrap();
+        assert_eq!(chain.blocks, again.blocks);
+
+        assert!(ChainGenerator::new(Era::Byron).generate(1).is_err());
+    }
+}
@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
 use crate::{wellknown::GenesisValues, MultiEraBlock};
 
 pub type Epoch = u64;
@@ -6,16 +10,6 @@ pub type Slot = u64;
 
 pub type SubSlot = u64;
 
-#[inline]
-fn compute_linear_timestamp(
-    known_slot: u64,
-    known_time: u64,
-    slot_length: u64,
-    query_slot: u64,
-) -> u64 {
-    known_time + (query_slot - known_slot) * slot_length
-}
-
 #[inline]
 fn compute_era_epoch(era_slot: Slot, era_slot_length: u64, era_epoch_length: u64) -> (Epoch, Slot) {
     assert!(
@@ -50,21 +44,7 @@ impl GenesisValues {
     }
 
     pub fn slot_to_wallclock(&self, slot: u64) -> u64 {
-        if slot < self.shelley_known_slot {
-            compute_linear_timestamp(
-                self.byron_known_slot,
-                self.byron_known_time,
-                self.byron_slot_length as u64,
-                slot,
-            )
-        } else {
-            compute_linear_timestamp(
-                self.shelley_known_slot,
-                self.shelley_known_time,
-                self.shelley_slot_length as u64,
-                slot,
-            )
-        }
+        EpochSchedule::from(self).slot_to_time(slot)
     }
 
     pub fn absolute_slot_to_relative(&self, slot: u64) -> (u64, u64) {
@@ -88,32 +68,152 @@ impl GenesisValues {
     }
 
     pub fn relative_slot_to_absolute(&self, epoch: Epoch, slot: Slot) -> Slot {
-        let shelley_start_epoch = self.shelley_start_epoch();
+        EpochSchedule::from(self).epoch_first_slot(epoch) + slot
+    }
+}
 
-        if epoch < shelley_start_epoch {
-            compute_absolute_slot_within_era(
-                epoch,
-                slot,
-                self.byron_epoch_length,
-                self.byron_slot_length,
-            )
-        } else {
-            let byron_slots = compute_absolute_slot_within_era(
-                shelley_start_epoch,
-                0,
-                self.byron_epoch_length,
-                self.byron_slot_length,
-            );
+/// Where an era starts and how long its slots and epochs are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EraSummary {
+    pub start_slot: Slot,
+    pub start_epoch: Epoch,
+    /// Unix timestamp of the first slot
+    pub start_time: u64,
+    /// Length of a slot in seconds
+    pub slot_length: u64,
+    /// Length of an epoch in slots
+    pub epoch_length: u64,
+}
 
-            let shelley_slots = compute_absolute_slot_within_era(
-                epoch - shelley_start_epoch,
-                slot,
-                self.shelley_epoch_length,
-                self.shelley_slot_length,
-            );
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("an epoch schedule needs at least one era")]
+    NoEras,
 
-            byron_slots + shelley_slots
+    #[error("era {0} doesn't start after the previous one")]
+    Unordered(usize),
+
+    #[error("era {0} has slots or epochs of length zero")]
+    ZeroLength(usize),
+}
+
+/// Summaries of the eras of a chain, the single source for converting
+/// between slots, epochs and wall-clock time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochSchedule {
+    eras: Vec<EraSummary>,
+}
+
+impl EpochSchedule {
+    /// Builds a schedule from the summaries of its eras, oldest first
+    pub fn new(eras: Vec<EraSummary>) -> Result<Self, ScheduleError> {
+        if eras.is_empty() {
+            return Err(ScheduleError::NoEras);
+        }
+
+        for (index, era) in eras.iter().enumerate() {
+            if era.slot_length == 0 || era.epoch_length == 0 {
+                return Err(ScheduleError::ZeroLength(index));
+            }
         }
+
+        for (index, pair) in eras.windows(2).enumerate() {
+            let ordered = pair[1].start_slot > pair[0].start_slot
+                && pair[1].start_epoch > pair[0].start_epoch
+                && pair[1].start_time > pair[0].start_time;
+
+            if !ordered {
+                return Err(ScheduleError::Unordered(index + 1));
+            }
+        }
+
+        Ok(Self { eras })
+    }
+
+    pub fn eras(&self) -> &[EraSummary] {
+        &self.eras
+    }
+
+    fn era_by(&self, starts_before: impl Fn(&EraSummary) -> bool) -> &EraSummary {
+        self.eras
+            .iter()
+            .rev()
+            .find(|x| starts_before(x))
+            .unwrap_or(&self.eras[0])
+    }
+
+    /// Epoch of a slot and the position of the slot within it
+    pub fn slot_to_epoch(&self, slot: Slot) -> (Epoch, SubSlot) {
+        let era = self.era_by(|x| x.start_slot <= slot);
+        let era_slot = slot.saturating_sub(era.start_slot);
+
+        (
+            era.start_epoch + era_slot / era.epoch_length,
+            era_slot % era.epoch_length,
+        )
+    }
+
+    pub fn epoch_first_slot(&self, epoch: Epoch) -> Slot {
+        let era = self.era_by(|x| x.start_epoch <= epoch);
+        era.start_slot + epoch.saturating_sub(era.start_epoch) * era.epoch_length
+    }
+
+    /// Number of slots of an epoch
+    pub fn epoch_length(&self, epoch: Epoch) -> u64 {
+        self.era_by(|x| x.start_epoch <= epoch).epoch_length
+    }
+
+    /// Unix timestamp at which a slot starts
+    pub fn slot_to_time(&self, slot: Slot) -> u64 {
+        let era = self.era_by(|x| x.start_slot <= slot);
+        era.start_time + slot.saturating_sub(era.start_slot) * era.slot_length
+    }
+
+    /// Slot in progress at a unix timestamp, `None` before the chain started
+    pub fn time_to_slot(&self, time: u64) -> Option<Slot> {
+        if time < self.eras[0].start_time {
+            return None;
+        }
+
+        let era = self.era_by(|x| x.start_time <= time);
+        Some(era.start_slot + (time - era.start_time) / era.slot_length)
+    }
+
+    /// Slot in progress once `duration` elapsed since the start of `slot`
+    pub fn slot_after(&self, slot: Slot, duration: Duration) -> Slot {
+        let time = self.slot_to_time(slot) + duration.as_secs();
+        self.time_to_slot(time).unwrap_or(slot)
+    }
+}
+
+impl From<&GenesisValues> for EpochSchedule {
+    fn from(genesis: &GenesisValues) -> Self {
+        let byron_slot_length = genesis.byron_slot_length as u64;
+        let shelley_slot_length = genesis.shelley_slot_length as u64;
+
+        let byron = EraSummary {
+            start_slot: genesis.byron_known_slot,
+            start_epoch: 0,
+            start_time: genesis.byron_known_time,
+            slot_length: byron_slot_length,
+            epoch_length: genesis.byron_epoch_length as u64 / byron_slot_length,
+        };
+
+        let shelley = EraSummary {
+            start_slot: genesis.shelley_known_slot,
+            start_epoch: genesis.shelley_start_epoch(),
+            start_time: genesis.shelley_known_time,
+            slot_length: shelley_slot_length,
+            epoch_length: genesis.shelley_epoch_length as u64 / shelley_slot_length,
+        };
+
+        // networks starting right in shelley have no byron epochs
+        let eras = match shelley.start_epoch {
+            0 => vec![shelley],
+            _ => vec![byron, shelley],
+        };
+
+        Self { eras }
     }
 }
 
@@ -253,6 +353,45 @@ mod tests {
         assert_slot_matches_timestamp(&genesis, 38580791, 1694263991, 93, 46391);
     }
 
+    #[test]
+    fn schedule_conversions() {
+        let schedule = EpochSchedule::from(&GenesisValues::mainnet());
+
+        assert_eq!(schedule.eras().len(), 2);
+        assert_eq!(schedule.slot_to_epoch(2160007), (100, 7));
+        assert_eq!(schedule.slot_to_epoch(51580240), (316, 431440));
+        assert_eq!(schedule.epoch_first_slot(208), 4492800);
+        assert_eq!(schedule.epoch_first_slot(100), 2160000);
+        assert_eq!(schedule.epoch_length(100), 21600);
+        assert_eq!(schedule.epoch_length(208), 432000);
+
+        assert_eq!(schedule.time_to_slot(1549403231), Some(2160007));
+        assert_eq!(schedule.time_to_slot(1549403250), Some(2160007));
+        assert_eq!(schedule.time_to_slot(1643146531), Some(51580240));
+        assert_eq!(schedule.time_to_slot(1506203090), None);
+
+        // the last ten byron slots take 200 seconds, shelley slots one
+        assert_eq!(
+            schedule.slot_after(4492790, Duration::from_secs(400)),
+            4493000
+        );
+        assert_eq!(
+            schedule.slot_after(51580240, Duration::from_secs(3600)),
+            51583840
+        );
+
+        let preview = EpochSchedule::from(&GenesisValues::preview());
+        assert_eq!(preview.eras().len(), 1);
+        assert_eq!(preview.slot_to_epoch(27556036), (318, 80836));
+
+        let era = schedule.eras()[1];
+        assert_eq!(
+            EpochSchedule::new(vec![era, era]),
+            Err(ScheduleError::Unordered(1))
+        );
+        assert_eq!(EpochSchedule::new(vec![]), Err(ScheduleError::NoEras));
+    }
+
     #[test]
     fn known_slot_matches() {
         // TODO: expand this test to include more test blocks
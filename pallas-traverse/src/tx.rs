@@ -287,6 +287,39 @@ impl<'b> MultiEraTx<'b> {
         }
     }
 
+    /// Governance actions proposed by the tx, empty before Conway
+    pub fn proposals(&self) -> Vec<&conway::ProposalProcedure> {
+        match self {
+            MultiEraTx::Conway(x) => x
+                .transaction_body
+                .proposal_procedures
+                .iter()
+                .flat_map(|x| x.iter())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Votes cast by the tx on governance actions, empty before Conway
+    pub fn votes(
+        &self,
+    ) -> Vec<(
+        &conway::Voter,
+        &conway::GovActionId,
+        &conway::VotingProcedure,
+    )> {
+        match self {
+            MultiEraTx::Conway(x) => x
+                .transaction_body
+                .voting_procedures
+                .iter()
+                .flat_map(|x| x.iter())
+                .flat_map(|(voter, votes)| votes.iter().map(move |(id, vote)| (voter, id, vote)))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     pub fn update(&self) -> Option<MultiEraUpdate> {
         match self {
             MultiEraTx::AlonzoCompatible(x, _) => x
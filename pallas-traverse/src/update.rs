@@ -50,7 +50,7 @@ pub type ExUnits = alonzo::ExUnits;
 pub type AlonzoCostModels = alonzo::CostModels;
 pub type BabbageCostModels = babbage::CostModels;
 pub type ConwayCostModels = conway::CostModels;
-pub type ProtocolVersion = alonzo::ProtocolVersion;
+pub use crate::era::ProtocolVersion;
 pub type PoolVotingThresholds = conway::PoolVotingThresholds;
 pub type DRepVotingThresholds = conway::DRepVotingThresholds;
 
@@ -223,7 +223,39 @@ impl<'b> MultiEraUpdate<'b> {
 
     param_boilerplate!(extra_entropy: Nonce, [AlonzoCompatible]);
 
-    param_boilerplate!(protocol_version: ProtocolVersion, [AlonzoCompatible Babbage]);
+    pub fn first_proposed_protocol_version(&self) -> Option<ProtocolVersion> {
+        let update = match self {
+            MultiEraUpdate::AlonzoCompatible(x) => x
+                .proposed_protocol_parameter_updates
+                .first()
+                .and_then(|x| x.1.protocol_version),
+            MultiEraUpdate::Babbage(x) => x
+                .proposed_protocol_parameter_updates
+                .first()
+                .and_then(|x| x.1.protocol_version),
+            _ => None,
+        };
+
+        update.map(ProtocolVersion::from)
+    }
+
+    pub fn all_proposed_protocol_version(&self) -> Vec<ProtocolVersion> {
+        let updates: Vec<_> = match self {
+            MultiEraUpdate::AlonzoCompatible(x) => x
+                .proposed_protocol_parameter_updates
+                .iter()
+                .filter_map(|x| x.1.protocol_version)
+                .collect(),
+            MultiEraUpdate::Babbage(x) => x
+                .proposed_protocol_parameter_updates
+                .iter()
+                .filter_map(|x| x.1.protocol_version)
+                .collect(),
+            _ => vec![],
+        };
+
+        updates.into_iter().map(ProtocolVersion::from).collect()
+    }
 
     param_boilerplate!(min_pool_cost: u64, [AlonzoCompatible Babbage]);
 
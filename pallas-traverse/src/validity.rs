@@ -0,0 +1,176 @@
+//! Slot-based validity checks for txs
+//!
+//! The bounds of the validity interval don't behave the same:
+//!
+//! - `validity_interval_start` is inclusive: the tx is valid from that slot on
+//! - since Allegra, `ttl` (a.k.a. `invalid_hereafter`) is exclusive: the tx is
+//!   no longer valid at that slot
+//! - in Shelley, `ttl` was inclusive: the tx was still valid at that slot
+//!
+//! Byron txs have no validity interval and are valid at any slot.
+
+use crate::{Era, MultiEraBlock, MultiEraTx};
+
+/// Upper end of the validity interval of a tx
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidityEnd {
+    /// The tx has no ttl and never expires
+    Unbounded,
+    /// Last slot at which the tx can be included in a block
+    LastSlot(u64),
+    /// The ttl is zero, so there's no slot at which the tx is valid
+    AlreadyExpired,
+}
+
+impl ValidityEnd {
+    /// Returns true if the tx can't be included in a block at the slot
+    pub fn is_expired_at(&self, slot: u64) -> bool {
+        match self {
+            ValidityEnd::Unbounded => false,
+            ValidityEnd::LastSlot(last) => slot > *last,
+            ValidityEnd::AlreadyExpired => true,
+        }
+    }
+}
+
+impl<'b> MultiEraTx<'b> {
+    /// Returns true if the validity interval of the tx has not started yet
+    pub fn is_premature_at(&self, slot: u64) -> bool {
+        match self.validity_start() {
+            Some(start) => slot < start,
+            None => false,
+        }
+    }
+
+    /// Returns true if the validity interval of the tx has already ended
+    ///
+    /// Once a tx is expired it stays that way, which makes this the check to
+    /// evict txs from a mempool.
+    pub fn is_expired_at(&self, slot: u64) -> bool {
+        match (self.era(), self.ttl()) {
+            (Era::Shelley, Some(ttl)) => slot > ttl,
+            (_, Some(ttl)) => slot >= ttl,
+            (_, None) => false,
+        }
+    }
+
+    /// Returns true if the tx can be included in a block at the given slot
+    pub fn is_valid_at(&self, slot: u64) -> bool {
+        !self.is_premature_at(slot) && !self.is_expired_at(slot)
+    }
+
+    /// Last slot at which the tx can be included in a block
+    pub fn last_valid_slot(&self) -> ValidityEnd {
+        match (self.era(), self.ttl()) {
+            (Era::Shelley, Some(ttl)) => ValidityEnd::LastSlot(ttl),
+            (_, Some(0)) => ValidityEnd::AlreadyExpired,
+            (_, Some(ttl)) => ValidityEnd::LastSlot(ttl - 1),
+            (_, None) => ValidityEnd::Unbounded,
+        }
+    }
+}
+
+impl<'b> MultiEraBlock<'b> {
+    /// Txs whose validity interval doesn't contain the slot of the block
+    ///
+    /// A well-formed block never has any, so a non-empty result means that
+    /// the block would be rejected by the ledger.
+    pub fn txs_outside_validity(&self) -> Vec<MultiEraTx<'_>> {
+        let slot = self.slot();
+
+        self.txs()
+            .into_iter()
+            .filter(|tx| !tx.is_valid_at(slot))
+            .collect()
+    }
+
+    /// Returns true if every tx of the block is valid at the slot of the block
+    pub fn txs_within_validity(&self) -> bool {
+        let slot = self.slot();
+        self.txs().iter().all(|tx| tx.is_valid_at(slot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+    use pallas_primitives::alonzo;
+
+    use super::ValidityEnd;
+    use crate::{Era, MultiEraBlock, MultiEraTx};
+
+    fn decode(block_str: &str) -> Vec<u8> {
+        hex::decode(block_str).unwrap()
+    }
+
+    #[test]
+    fn boundaries_follow_era() {
+        let cbor = decode(include_str!("../../test_data/shelley1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().remove(0);
+        let ttl = tx.ttl().unwrap();
+
+        assert!(tx.is_valid_at(ttl));
+        assert!(!tx.is_valid_at(ttl + 1));
+        assert_eq!(tx.last_valid_slot(), ValidityEnd::LastSlot(ttl));
+
+        let cbor = decode(include_str!("../../test_data/alonzo1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        for tx in block.txs() {
+            if let Some(ttl) = tx.ttl() {
+                assert!(tx.is_valid_at(ttl - 1));
+                assert!(tx.is_expired_at(ttl));
+                assert_eq!(tx.last_valid_slot(), ValidityEnd::LastSlot(ttl - 1));
+            }
+
+            if let Some(start) = tx.validity_start() {
+                assert_eq!(tx.is_premature_at(start.saturating_sub(1)), start > 0);
+                assert!(!tx.is_premature_at(start));
+            }
+        }
+
+        let cbor = decode(include_str!("../../test_data/byron2.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().remove(0);
+        assert!(tx.is_valid_at(0));
+        assert!(tx.is_valid_at(u64::MAX));
+        assert_eq!(tx.last_valid_slot(), ValidityEnd::Unbounded);
+    }
+
+    #[test]
+    fn zero_ttl_is_already_expired() {
+        let cbor = decode(include_str!("../../test_data/alonzo1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        let mut tx: alonzo::Tx = minicbor::decode(&block.txs()[0].encode()).unwrap();
+        tx.transaction_body.ttl = Some(0);
+        let cbor = minicbor::to_vec(&tx).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Alonzo, &cbor).unwrap();
+
+        let end = tx.last_valid_slot();
+        assert_eq!(end, ValidityEnd::AlreadyExpired);
+        assert!(tx.is_expired_at(0));
+        assert!(end.is_expired_at(0));
+        assert!(end.is_expired_at(u64::MAX));
+        assert!(!ValidityEnd::Unbounded.is_expired_at(u64::MAX));
+        assert!(!ValidityEnd::LastSlot(0).is_expired_at(0));
+        assert!(ValidityEnd::LastSlot(0).is_expired_at(1));
+    }
+
+    #[test]
+    fn block_txs_are_within_validity() {
+        for block_str in [
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/allegra1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+        ] {
+            let cbor = decode(block_str);
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+
+            assert!(block.txs_within_validity());
+            assert!(block.txs_outside_validity().is_empty());
+        }
+    }
+}
@@ -1,10 +1,51 @@
-use pallas_codec::utils::KeepRaw;
+use pallas_codec::{minicbor, utils::KeepRaw};
+use pallas_crypto::key::ed25519::{PublicKey, Signature};
 use pallas_primitives::{
     alonzo::{self, BootstrapWitness, NativeScript, VKeyWitness},
-    conway, PlutusData, PlutusScript,
+    byron, conway, PlutusData, PlutusScript,
 };
 
-use crate::{MultiEraRedeemer, MultiEraTx};
+use crate::{Error, MultiEraRedeemer, MultiEraTx};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WitnessKind {
+    VKey,
+    Bootstrap,
+    /// Byron witness of a regular key
+    ByronKey,
+    /// Byron witness of an AVVM redeem key
+    ByronRedeem,
+}
+
+/// Outcome of checking the signature of one witness
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessCheck {
+    pub kind: WitnessKind,
+    /// Position of the witness among those of its kind
+    pub index: usize,
+    /// Public key as found in the witness, 64 bytes for Byron extended keys
+    pub public_key: Vec<u8>,
+    pub valid: bool,
+}
+
+/// Outcome of checking the witnesses of a Byron tx
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByronWitnessReport {
+    pub checks: Vec<WitnessCheck>,
+    /// Position of the witnesses without a signature to check, such as
+    /// script witnesses, which are left unverified
+    pub skipped: Vec<usize>,
+}
+
+fn verify(public_key: &[u8], signature: &[u8], message: &[u8]) -> bool {
+    let public_key = public_key.get(..PublicKey::SIZE).map(PublicKey::try_from);
+    let signature = Signature::try_from(signature);
+
+    match (public_key, signature) {
+        (Some(Ok(public_key)), Ok(signature)) => public_key.verify(message, &signature),
+        _ => false,
+    }
+}
 
 impl<'b> MultiEraTx<'b> {
     pub fn vkey_witnesses(&self) -> &[VKeyWitness] {
@@ -216,4 +257,180 @@ impl<'b> MultiEraTx<'b> {
                 .unwrap_or(&[]),
         }
     }
+
+    /// Checks the signature of every vkey and bootstrap witness against the
+    /// hash of the original body bytes. Byron witnesses also sign over the
+    /// protocol magic, so Byron txs fail with [`Error::ByronRequiresMagic`]
+    /// and are checked with [`Self::verify_byron_witnesses`] instead.
+    pub fn verify_witnesses(&self) -> Result<Vec<WitnessCheck>, Error> {
+        if let Self::Byron(_) = self {
+            return Err(Error::ByronRequiresMagic);
+        }
+
+        let hash = self.hash();
+
+        let vkeys = self
+            .vkey_witnesses()
+            .iter()
+            .enumerate()
+            .map(|(index, x)| WitnessCheck {
+                kind: WitnessKind::VKey,
+                index,
+                public_key: x.vkey.to_vec(),
+                valid: verify(&x.vkey, &x.signature, hash.as_ref()),
+            });
+
+        let bootstraps = self
+            .bootstrap_witnesses()
+            .iter()
+            .enumerate()
+            .map(|(index, x)| WitnessCheck {
+                kind: WitnessKind::Bootstrap,
+                index,
+                public_key: x.public_key.to_vec(),
+                valid: verify(&x.public_key, &x.signature, hash.as_ref()),
+            });
+
+        Ok(vkeys.chain(bootstraps).collect())
+    }
+
+    /// Checks the key and redeem witnesses of a Byron tx, which sign over
+    /// the protocol magic of the network and the tx hash. Txs of later eras
+    /// fail with [`Error::InvalidEra`].
+    pub fn verify_byron_witnesses(&self, protocol_magic: u32) -> Result<ByronWitnessReport, Error> {
+        let tx = match self {
+            Self::Byron(x) => x,
+            _ => return Err(Error::InvalidEra(self.era())),
+        };
+
+        let hash = self.hash();
+        let mut checks = vec![];
+        let mut skipped = vec![];
+
+        for (index, witness) in tx.witness.iter().enumerate() {
+            let (kind, tag, (public_key, signature)) = match witness {
+                byron::Twit::PkWitness(x) => (WitnessKind::ByronKey, 1u8, &x.0),
+                byron::Twit::RedeemWitness(x) => (WitnessKind::ByronRedeem, 2u8, &x.0),
+                _ => {
+                    skipped.push(index);
+                    continue;
+                }
+            };
+
+            let mut message = vec![];
+
+            minicbor::Encoder::new(&mut message)
+                .u8(tag)
+                .and_then(|e| e.u32(protocol_magic))
+                .and_then(|e| e.bytes(hash.as_ref()))
+                .expect("encoding to a vec can't fail");
+
+            checks.push(WitnessCheck {
+                kind,
+                index,
+                public_key: public_key.to_vec(),
+                valid: verify(public_key, signature, &message),
+            });
+        }
+
+        Ok(ByronWitnessReport { checks, skipped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::utils::{CborWrap, MaybeIndefArray};
+
+    use super::*;
+    use crate::{wellknown::GenesisValues, Era, MultiEraBlock};
+
+    fn all_valid(checks: &[WitnessCheck]) -> bool {
+        !checks.is_empty() && checks.iter().all(|x| x.valid)
+    }
+
+    #[test]
+    fn vkey_and_bootstrap_signatures_verify() {
+        let mut cbor = hex::decode(include_str!("../../test_data/babbage13.tx")).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Babbage, &cbor).unwrap();
+
+        let checks = tx.verify_witnesses().unwrap();
+        assert!(all_valid(&checks));
+        assert!(checks.iter().all(|x| x.kind == WitnessKind::VKey));
+
+        // flip a bit of the first signature
+        let signature = tx.vkey_witnesses()[0].signature.to_vec();
+        let at = cbor
+            .windows(signature.len())
+            .position(|x| x == signature)
+            .unwrap();
+
+        cbor[at] ^= 1;
+
+        let tx = MultiEraTx::decode_for_era(Era::Babbage, &cbor).unwrap();
+        let checks = tx.verify_witnesses().unwrap();
+        assert!(!checks[0].valid);
+        assert!(checks[1..].iter().all(|x| x.valid));
+
+        let cbor = hex::decode(include_str!("../../test_data/alonzo15.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let checks = block.txs()[2].verify_witnesses().unwrap();
+
+        assert!(all_valid(&checks));
+        assert!(checks.iter().all(|x| x.kind == WitnessKind::Bootstrap));
+    }
+
+    #[test]
+    fn byron_signatures_verify_under_their_magic() {
+        let magic = GenesisValues::mainnet().magic as u32;
+
+        let cbor = hex::decode(include_str!("../../test_data/byron1.tx")).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Byron, &cbor).unwrap();
+
+        assert!(matches!(
+            tx.verify_witnesses(),
+            Err(Error::ByronRequiresMagic)
+        ));
+
+        let report = tx.verify_byron_witnesses(magic).unwrap();
+        assert!(all_valid(&report.checks));
+        assert!(report.skipped.is_empty());
+        assert!(!tx.verify_byron_witnesses(magic + 1).unwrap().checks[0].valid);
+
+        let cbor = hex::decode(include_str!("../../test_data/byron2.tx")).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Byron, &cbor).unwrap();
+        let report = tx.verify_byron_witnesses(magic).unwrap();
+
+        assert!(all_valid(&report.checks));
+        assert_eq!(report.checks[0].kind, WitnessKind::ByronRedeem);
+
+        let cbor = hex::decode(include_str!("../../test_data/babbage13.tx")).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Babbage, &cbor).unwrap();
+
+        assert!(matches!(
+            tx.verify_byron_witnesses(magic),
+            Err(Error::InvalidEra(Era::Babbage))
+        ));
+    }
+
+    #[test]
+    fn byron_script_witnesses_are_reported_as_skipped() {
+        let cbor = hex::decode(include_str!("../../test_data/byron1.tx")).unwrap();
+        let mut tx: byron::TxPayload = minicbor::decode(&cbor).unwrap();
+
+        let script = ((0, vec![].into()), (0, vec![].into()));
+        let mut witness = tx.witness.to_vec();
+        witness.push(byron::Twit::ScriptWitness(CborWrap(script)));
+        witness.push(byron::Twit::Other(7, vec![0xa0].into()));
+        tx.witness = MaybeIndefArray::Def(witness);
+
+        let cbor = minicbor::to_vec(&tx).unwrap();
+        let tx = MultiEraTx::decode_for_era(Era::Byron, &cbor).unwrap();
+        let report = tx
+            .verify_byron_witnesses(GenesisValues::mainnet().magic as u32)
+            .unwrap();
+
+        assert_eq!(report.checks.len(), 1);
+        assert!(report.checks[0].valid);
+        assert_eq!(report.skipped, vec![1, 2]);
+    }
 }
@@ -0,0 +1,98 @@
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::conway::Anchor;
+
+use crate::TxBuilderError;
+
+/// Maximum length in bytes of an anchor url, as enforced by the ledger
+pub const MAX_ANCHOR_URL_LENGTH: usize = 128;
+
+/// Builds an anchor pointing to an off-chain document, hashing the document
+/// contents served under the url
+pub fn anchor(url: &str, contents: &[u8]) -> Result<Anchor, TxBuilderError> {
+    anchor_hash(url, Hasher::<256>::hash(contents))
+}
+
+/// Builds an anchor from its url and the already known hash of the document
+pub fn anchor_hash(url: &str, content_hash: Hash<32>) -> Result<Anchor, TxBuilderError> {
+    if url.len() > MAX_ANCHOR_URL_LENGTH {
+        return Err(TxBuilderError::AnchorUrlTooLong);
+    }
+
+    if !url.is_ascii() {
+        return Err(TxBuilderError::MalformedAnchorUrl);
+    }
+
+    Ok(Anchor {
+        url: url.to_string(),
+        content_hash,
+    })
+}
+
+/// Checks that the document contents match the hash of the anchor
+pub fn verify_anchor(anchor: &Anchor, contents: &[u8]) -> bool {
+    Hasher::<256>::hash(contents) == anchor.content_hash
+}
+
+/// Retrieves the document pointed to by the anchor using the provided fetch
+/// function, returning its contents only if they match the anchored hash
+///
+/// The fetch function receives the anchor url and is free to use any
+/// transport (http, ipfs gateway, local cache, etc).
+pub fn fetch_and_verify_anchor<F, E>(anchor: &Anchor, fetch: F) -> Result<Vec<u8>, TxBuilderError>
+where
+    F: FnOnce(&str) -> Result<Vec<u8>, E>,
+    E: std::fmt::Display,
+{
+    let contents = fetch(&anchor.url).map_err(|e| TxBuilderError::AnchorFetch(e.to_string()))?;
+
+    if !verify_anchor(anchor, &contents) {
+        return Err(TxBuilderError::AnchorHashMismatch);
+    }
+
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchor_validation_and_verification() {
+        let doc = br#"{"body":{"title":"hello"}}"#;
+
+        let anchor = anchor("https://example.com/doc.json", doc).unwrap();
+
+        assert!(verify_anchor(&anchor, doc));
+        assert!(!verify_anchor(&anchor, b"{}"));
+
+        assert_eq!(
+            fetch_and_verify_anchor(&anchor, |url| {
+                assert_eq!(url, "https://example.com/doc.json");
+                Ok::<_, String>(doc.to_vec())
+            }),
+            Ok(doc.to_vec())
+        );
+
+        assert_eq!(
+            fetch_and_verify_anchor(&anchor, |_| Ok::<_, String>(b"{}".to_vec())),
+            Err(TxBuilderError::AnchorHashMismatch)
+        );
+
+        assert_eq!(
+            fetch_and_verify_anchor(&anchor, |_| Err("not found")),
+            Err(TxBuilderError::AnchorFetch("not found".into()))
+        );
+
+        let too_long = format!("https://example.com/{}", "x".repeat(MAX_ANCHOR_URL_LENGTH));
+
+        assert_eq!(
+            super::anchor(&too_long, doc),
+            Err(TxBuilderError::AnchorUrlTooLong)
+        );
+
+        assert_eq!(
+            super::anchor("https://exámple.com", doc),
+            Err(TxBuilderError::MalformedAnchorUrl)
+        );
+    }
+}
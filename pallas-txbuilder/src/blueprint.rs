@@ -0,0 +1,239 @@
+//! Loader for CIP-57 contract blueprints (the `plutus.json` produced by Aiken
+//! and other compilers), bridging compiled validators and transactions.
+
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{PlutusData, PlutusScript};
+use pallas_traverse::ComputeHash;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+use crate::{uplc, ScriptKind, TxBuilderError};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Blueprint {
+    pub preamble: Preamble,
+    pub validators: Vec<Validator>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definitions: Option<serde_json::Map<String, Json>>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Preamble {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plutus_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<Compiler>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct Compiler {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Validator {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datum: Option<Argument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redeemer: Option<Argument>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Argument>,
+    pub compiled_code: String,
+    pub hash: String,
+}
+
+/// A datum, redeemer or parameter of a validator, along with the schema of
+/// the plutus data it expects
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Argument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<Json>,
+    pub schema: Json,
+}
+
+impl Blueprint {
+    pub fn from_json(json: &str) -> Result<Self, TxBuilderError> {
+        serde_json::from_str(json).map_err(|e| TxBuilderError::MalformedBlueprint(e.to_string()))
+    }
+
+    /// Language of the validators, blueprints without a plutus version
+    /// predate Plutus V3 and default to V2
+    pub fn script_kind(&self) -> Result<ScriptKind, TxBuilderError> {
+        match self.preamble.plutus_version.as_deref() {
+            Some("v1") => Ok(ScriptKind::PlutusV1),
+            Some("v2") | None => Ok(ScriptKind::PlutusV2),
+            Some("v3") => Ok(ScriptKind::PlutusV3),
+            Some(x) => Err(TxBuilderError::MalformedBlueprint(format!(
+                "unknown plutus version {x}"
+            ))),
+        }
+    }
+
+    pub fn validator(&self, title: &str) -> Option<&Validator> {
+        self.validators.iter().find(|x| x.title == title)
+    }
+
+    /// Follows a `$ref` schema to its definition, other schemas are returned
+    /// as they are
+    pub fn resolve_schema<'a>(&'a self, schema: &'a Json) -> Option<&'a Json> {
+        match schema.get("$ref").and_then(Json::as_str) {
+            Some(path) => {
+                let name = path.strip_prefix("#/definitions/")?;
+                // names are escaped as JSON pointers
+                let name = name.replace("~1", "/").replace("~0", "~");
+                self.definitions.as_ref()?.get(&name)
+            }
+            None => Some(schema),
+        }
+    }
+}
+
+fn script_hash(kind: ScriptKind, script: PlutusScript<0>) -> Result<Hash<28>, TxBuilderError> {
+    let PlutusScript(bytes) = script;
+
+    match kind {
+        ScriptKind::PlutusV1 => Ok(PlutusScript::<1>(bytes).compute_hash()),
+        ScriptKind::PlutusV2 => Ok(PlutusScript::<2>(bytes).compute_hash()),
+        ScriptKind::PlutusV3 => Ok(PlutusScript::<3>(bytes).compute_hash()),
+        // blueprints only describe plutus validators
+        ScriptKind::Native => Err(TxBuilderError::MalformedScript),
+    }
+}
+
+impl Validator {
+    /// The compiled validator, as included in transactions
+    pub fn script<const VERSION: usize>(&self) -> Result<PlutusScript<VERSION>, TxBuilderError> {
+        PlutusScript::from_hex_envelope(&self.compiled_code)
+            .map_err(|_| TxBuilderError::MalformedScript)
+    }
+
+    /// Checks the compiled code against the hash stated in the blueprint
+    pub fn verify_hash(&self, kind: ScriptKind) -> Result<Hash<28>, TxBuilderError> {
+        // the language is given by `kind`, the version of the type is irrelevant
+        let hash = script_hash(kind, self.script()?)?;
+
+        if hex::encode(hash) != self.hash {
+            return Err(TxBuilderError::ScriptHashMismatch);
+        }
+
+        Ok(hash)
+    }
+
+    /// Specializes a parameterized validator with one plutus data value per
+    /// parameter, returning a validator with no parameters left
+    pub fn apply_parameters(
+        &self,
+        kind: ScriptKind,
+        params: &[PlutusData],
+    ) -> Result<Validator, TxBuilderError> {
+        if params.len() != self.parameters.len() {
+            return Err(TxBuilderError::ParameterCountMismatch(
+                self.parameters.len(),
+                params.len(),
+            ));
+        }
+
+        let script = self.script::<0>()?;
+        let flat = script
+            .unwrap_inner()
+            .ok_or(TxBuilderError::MalformedScript)?;
+
+        let applied = PlutusScript::<0>::from_any_wrapping(&uplc::apply_data(flat, params)?);
+        let compiled_code = hex::encode(applied.as_ref());
+
+        Ok(Validator {
+            parameters: vec![],
+            compiled_code,
+            hash: hex::encode(script_hash(kind, applied)?),
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_primitives::Fragment;
+
+    use super::*;
+
+    const BLUEPRINT: &str = r##"{
+        "preamble": {
+            "title": "acme/vesting",
+            "version": "0.0.0",
+            "plutusVersion": "v3",
+            "compiler": { "name": "Aiken", "version": "v1.1.0" },
+            "license": "Apache-2.0"
+        },
+        "validators": [
+            {
+                "title": "vesting.vesting.spend",
+                "datum": { "title": "datum", "schema": { "$ref": "#/definitions/vesting~1Datum" } },
+                "redeemer": { "title": "_redeemer", "schema": { "$ref": "#/definitions/Data" } },
+                "parameters": [{ "title": "owner", "schema": { "$ref": "#/definitions/Int" } }],
+                "compiledCode": "46010100200101",
+                "hash": "00"
+            }
+        ],
+        "definitions": {
+            "Data": { "title": "Data", "description": "Any Plutus data." },
+            "Int": { "dataType": "integer" },
+            "vesting/Datum": { "title": "Datum", "anyOf": [] }
+        }
+    }"##;
+
+    #[test]
+    fn load_and_apply_parameters() {
+        let blueprint = Blueprint::from_json(BLUEPRINT).unwrap();
+
+        assert_eq!(blueprint.script_kind().unwrap(), ScriptKind::PlutusV3);
+
+        let validator = blueprint.validator("vesting.vesting.spend").unwrap();
+
+        let datum = blueprint
+            .resolve_schema(&validator.datum.as_ref().unwrap().schema)
+            .unwrap();
+        assert_eq!(datum["title"], "Datum");
+
+        assert_eq!(
+            validator.verify_hash(ScriptKind::PlutusV3),
+            Err(TxBuilderError::ScriptHashMismatch)
+        );
+
+        let owner = PlutusData::decode_fragment(&[0x18, 0x2a]).unwrap();
+
+        assert_eq!(
+            validator.apply_parameters(ScriptKind::PlutusV3, &[]),
+            Err(TxBuilderError::ParameterCountMismatch(1, 0))
+        );
+
+        let applied = validator
+            .apply_parameters(ScriptKind::PlutusV3, &[owner])
+            .unwrap();
+
+        assert!(applied.parameters.is_empty());
+        assert_eq!(applied.compiled_code, "4c010100320014c102182a0001");
+        assert!(applied.verify_hash(ScriptKind::PlutusV3).is_ok());
+        assert_eq!(
+            applied.verify_hash(ScriptKind::Native),
+            Err(TxBuilderError::MalformedScript)
+        );
+    }
+}
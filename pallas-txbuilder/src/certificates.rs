@@ -0,0 +1,474 @@
+use std::net::{IpAddr, SocketAddr};
+
+use pallas_addresses::{Address as PallasAddress, StakePayload};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::{
+    conway::{Anchor, Certificate, DRep, PoolMetadata, Relay},
+    Nullable, RationalNumber, Set, StakeCredential,
+};
+
+use crate::TxBuilderError;
+
+const MAX_URL_LENGTH: usize = 64;
+
+/// Converts a ratio expressed as a floating point number into the equivalent
+/// rational, using the shortest decimal representation of the number.
+pub(crate) fn unit_interval(value: f64) -> Result<RationalNumber, TxBuilderError> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(TxBuilderError::InvalidPoolMargin);
+    }
+
+    let repr = value.to_string();
+
+    let decimals = repr.split_once('.').map(|(_, x)| x.len()).unwrap_or(0) as u32;
+
+    let denominator = 10u64
+        .checked_pow(decimals)
+        .ok_or(TxBuilderError::InvalidPoolMargin)?;
+
+    let numerator = repr
+        .replace('.', "")
+        .parse::<u64>()
+        .map_err(|_| TxBuilderError::InvalidPoolMargin)?;
+
+    let gcd = gcd(numerator, denominator);
+
+    Ok(RationalNumber {
+        numerator: numerator / gcd,
+        denominator: denominator / gcd,
+    })
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Parses a relay from its string representation
+///
+/// - `1.2.3.4:3001` or `[2001:db8::1]:3001` are single host addresses
+/// - `relay.example.com:3001` is a single host name
+/// - `relay.example.com` (no port) is a multi host name, resolved through DNS
+///   SRV records
+pub fn parse_relay(relay: &str) -> Result<Relay, TxBuilderError> {
+    if let Ok(addr) = relay.parse::<SocketAddr>() {
+        let port = Nullable::Some(addr.port() as u32);
+
+        return match addr.ip() {
+            IpAddr::V4(ip) => Ok(Relay::SingleHostAddr(
+                port,
+                Nullable::Some(ip.octets().to_vec().into()),
+                Nullable::Null,
+            )),
+            IpAddr::V6(ip) => {
+                // the ledger serializes IPv6 addresses as four little-endian
+                // 32 bits words
+                let bytes = ip
+                    .octets()
+                    .chunks(4)
+                    .flat_map(|x| x.iter().rev().copied().collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+
+                Ok(Relay::SingleHostAddr(
+                    port,
+                    Nullable::Null,
+                    Nullable::Some(bytes.into()),
+                ))
+            }
+        };
+    }
+
+    let invalid = || TxBuilderError::InvalidRelay(relay.to_string());
+
+    match relay.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port.parse::<u16>().map_err(|_| invalid())?;
+
+            if host.is_empty() || host.len() > MAX_URL_LENGTH {
+                return Err(invalid());
+            }
+
+            Ok(Relay::SingleHostName(
+                Nullable::Some(port as u32),
+                host.to_string(),
+            ))
+        }
+        None => {
+            if relay.is_empty() || relay.len() > MAX_URL_LENGTH {
+                return Err(invalid());
+            }
+
+            Ok(Relay::MultiHostName(relay.to_string()))
+        }
+    }
+}
+
+/// Builder for a stake pool registration certificate
+#[derive(Debug, Clone)]
+pub struct PoolRegistration {
+    operator: Hash<28>,
+    vrf_keyhash: Hash<32>,
+    pledge: u64,
+    cost: u64,
+    margin: f64,
+    reward_account: PallasAddress,
+    owners: Vec<Hash<28>>,
+    relays: Vec<String>,
+    metadata: Option<(String, Hash<32>)>,
+}
+
+impl PoolRegistration {
+    pub fn new(
+        operator: Hash<28>,
+        vrf_keyhash: Hash<32>,
+        pledge: u64,
+        cost: u64,
+        margin: f64,
+        reward_account: PallasAddress,
+    ) -> Self {
+        Self {
+            operator,
+            vrf_keyhash,
+            pledge,
+            cost,
+            margin,
+            reward_account,
+            owners: vec![],
+            relays: vec![],
+            metadata: None,
+        }
+    }
+
+    pub fn owner(mut self, owner: Hash<28>) -> Self {
+        self.owners.push(owner);
+        self
+    }
+
+    pub fn relay(mut self, relay: &str) -> Self {
+        self.relays.push(relay.to_string());
+        self
+    }
+
+    /// Sets the pool metadata url, hashing the metadata file contents that
+    /// are served under that url
+    pub fn metadata(mut self, url: &str, contents: &[u8]) -> Self {
+        self.metadata = Some((url.to_string(), Hasher::<256>::hash(contents)));
+        self
+    }
+
+    /// Sets the pool metadata url and the already known hash of its contents
+    pub fn metadata_hash(mut self, url: &str, hash: Hash<32>) -> Self {
+        self.metadata = Some((url.to_string(), hash));
+        self
+    }
+
+    pub fn build(self) -> Result<Certificate, TxBuilderError> {
+        let reward_account = match self.reward_account {
+            PallasAddress::Stake(x) => x.to_vec(),
+            _ => return Err(TxBuilderError::UnexpectedAddressKind),
+        };
+
+        let relays = self
+            .relays
+            .iter()
+            .map(|x| parse_relay(x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pool_metadata = match self.metadata {
+            Some((url, hash)) => {
+                if url.len() > MAX_URL_LENGTH {
+                    return Err(TxBuilderError::MetadataUrlTooLong);
+                }
+
+                Nullable::Some(PoolMetadata { url, hash })
+            }
+            None => Nullable::Null,
+        };
+
+        let mut pool_owners = self.owners;
+        pool_owners.sort();
+        pool_owners.dedup();
+
+        Ok(Certificate::PoolRegistration {
+            operator: self.operator,
+            vrf_keyhash: self.vrf_keyhash,
+            pledge: self.pledge,
+            cost: self.cost,
+            margin: unit_interval(self.margin)?,
+            reward_account: reward_account.into(),
+            pool_owners: Set::from(pool_owners),
+            relays,
+            pool_metadata,
+        })
+    }
+}
+
+/// Extracts the stake credential of a stake (reward) address
+pub fn stake_credential(address: &PallasAddress) -> Result<StakeCredential, TxBuilderError> {
+    match address {
+        PallasAddress::Stake(x) => Ok(match x.payload() {
+            StakePayload::Stake(x) => StakeCredential::AddrKeyhash(*x),
+            StakePayload::Script(x) => StakeCredential::ScriptHash(*x),
+        }),
+        _ => Err(TxBuilderError::UnexpectedAddressKind),
+    }
+}
+
+/// Delegates the voting power of a stake address to a DRep
+pub fn vote_delegation(
+    stake_address: &PallasAddress,
+    drep: DRep,
+) -> Result<Certificate, TxBuilderError> {
+    Ok(Certificate::VoteDeleg(
+        stake_credential(stake_address)?,
+        drep,
+    ))
+}
+
+/// Delegates both the stake to a pool and the voting power to a DRep
+pub fn stake_vote_delegation(
+    stake_address: &PallasAddress,
+    pool: Hash<28>,
+    drep: DRep,
+) -> Result<Certificate, TxBuilderError> {
+    Ok(Certificate::StakeVoteDeleg(
+        stake_credential(stake_address)?,
+        pool,
+        drep,
+    ))
+}
+
+/// Registers a stake address and delegates its stake to a pool and its
+/// voting power to a DRep, all in a single certificate
+pub fn stake_vote_registration_delegation(
+    stake_address: &PallasAddress,
+    pool: Hash<28>,
+    drep: DRep,
+    deposit: u64,
+) -> Result<Certificate, TxBuilderError> {
+    Ok(Certificate::StakeVoteRegDeleg(
+        stake_credential(stake_address)?,
+        pool,
+        drep,
+        deposit,
+    ))
+}
+
+/// Registers a DRep, optionally anchoring its metadata document
+pub fn drep_registration(
+    credential: StakeCredential,
+    deposit: u64,
+    anchor: Option<Anchor>,
+) -> Certificate {
+    Certificate::RegDRepCert(credential, deposit, anchor.into())
+}
+
+/// Retires a DRep, refunding the deposit paid on registration
+pub fn drep_deregistration(credential: StakeCredential, refund: u64) -> Certificate {
+    Certificate::UnRegDRepCert(credential, refund)
+}
+
+/// Updates the metadata anchor of a registered DRep
+pub fn drep_update(credential: StakeCredential, anchor: Option<Anchor>) -> Certificate {
+    Certificate::UpdateDRepCert(credential, anchor.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn margin_to_unit_interval() {
+        let cases = [
+            (0.0, (0, 1)),
+            (1.0, (1, 1)),
+            (0.015, (3, 200)),
+            (0.1, (1, 10)),
+            (0.25, (1, 4)),
+        ];
+
+        for (margin, (numerator, denominator)) in cases {
+            let ratio = unit_interval(margin).unwrap();
+            assert_eq!(
+                (ratio.numerator, ratio.denominator),
+                (numerator, denominator)
+            );
+        }
+
+        assert!(unit_interval(1.5).is_err());
+        assert!(unit_interval(-0.1).is_err());
+    }
+
+    #[test]
+    fn relays_from_strings() {
+        assert_eq!(
+            parse_relay("1.2.3.4:3001").unwrap(),
+            Relay::SingleHostAddr(
+                Nullable::Some(3001),
+                Nullable::Some(vec![1, 2, 3, 4].into()),
+                Nullable::Null
+            )
+        );
+
+        assert_eq!(
+            parse_relay("[2001:db8::1]:3001").unwrap(),
+            Relay::SingleHostAddr(
+                Nullable::Some(3001),
+                Nullable::Null,
+                Nullable::Some(
+                    vec![0xb8, 0x0d, 0x01, 0x20, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0].into()
+                )
+            )
+        );
+
+        assert_eq!(
+            parse_relay("relay.example.com:3001").unwrap(),
+            Relay::SingleHostName(Nullable::Some(3001), "relay.example.com".into())
+        );
+
+        assert_eq!(
+            parse_relay("relay.example.com").unwrap(),
+            Relay::MultiHostName("relay.example.com".into())
+        );
+
+        assert!(parse_relay("relay.example.com:port").is_err());
+    }
+
+    #[test]
+    fn pool_registration_build() {
+        let reward_account =
+            PallasAddress::from_str("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw")
+                .unwrap();
+
+        let cert = PoolRegistration::new(
+            Hash::new([1; 28]),
+            Hash::new([2; 32]),
+            1_000_000,
+            340_000_000,
+            0.015,
+            reward_account.clone(),
+        )
+        .owner(Hash::new([4; 28]))
+        .owner(Hash::new([3; 28]))
+        .relay("relay.example.com:3001")
+        .metadata("https://example.com/pool.json", b"{}")
+        .build()
+        .unwrap();
+
+        match cert {
+            Certificate::PoolRegistration {
+                margin,
+                reward_account: account,
+                pool_owners,
+                relays,
+                pool_metadata,
+                ..
+            } => {
+                assert_eq!((margin.numerator, margin.denominator), (3, 200));
+                assert_eq!(account.to_vec(), reward_account.to_vec());
+                assert_eq!(
+                    pool_owners.to_vec(),
+                    vec![Hash::new([3; 28]), Hash::new([4; 28])]
+                );
+                assert_eq!(relays.len(), 1);
+
+                match pool_metadata {
+                    Nullable::Some(x) => assert_eq!(x.hash, Hasher::<256>::hash(b"{}")),
+                    _ => panic!("expected pool metadata"),
+                }
+            }
+            _ => panic!("expected pool registration"),
+        }
+
+        let too_long = PoolRegistration::new(
+            Hash::new([1; 28]),
+            Hash::new([2; 32]),
+            0,
+            0,
+            0.0,
+            reward_account,
+        )
+        .metadata(&format!("https://example.com/{}", "x".repeat(64)), b"{}")
+        .build();
+
+        assert_eq!(too_long, Err(TxBuilderError::MetadataUrlTooLong));
+    }
+
+    #[test]
+    fn remove_certificate_by_index() {
+        use pallas_primitives::Fragment;
+
+        use crate::StagingTransaction;
+
+        let first =
+            Certificate::StakeRegistration(StakeCredential::AddrKeyhash(Hash::new([1; 28])));
+        let second =
+            Certificate::StakeDeregistration(StakeCredential::AddrKeyhash(Hash::new([2; 28])));
+
+        let tx = StagingTransaction::new()
+            .add_certificate(first)
+            .add_certificate(second.clone());
+
+        // out of range indexes are ignored
+        let tx = tx.remove_certificate(2).remove_certificate(0);
+
+        let expected = second.encode_fragment().unwrap();
+        assert_eq!(tx.certificates.unwrap(), vec![expected.into()]);
+    }
+
+    #[test]
+    fn drep_certificates_roundtrip() {
+        use pallas_primitives::Fragment;
+
+        let stake_address =
+            PallasAddress::from_str("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw")
+                .unwrap();
+
+        let credential = stake_credential(&stake_address).unwrap();
+        let drep = StakeCredential::AddrKeyhash(Hash::new([9; 28]));
+
+        let certs = vec![
+            vote_delegation(&stake_address, DRep::Key(Hash::new([9; 28]))).unwrap(),
+            stake_vote_delegation(&stake_address, Hash::new([1; 28]), DRep::Abstain).unwrap(),
+            stake_vote_registration_delegation(
+                &stake_address,
+                Hash::new([1; 28]),
+                DRep::NoConfidence,
+                2_000_000,
+            )
+            .unwrap(),
+            drep_registration(
+                drep.clone(),
+                500_000_000,
+                Some(crate::anchor("https://example.com/drep.json", b"{}").unwrap()),
+            ),
+            drep_update(drep.clone(), None),
+            drep_deregistration(drep, 500_000_000),
+        ];
+
+        for cert in certs {
+            let bytes = cert.encode_fragment().unwrap();
+            assert_eq!(Certificate::decode_fragment(&bytes).unwrap(), cert);
+        }
+
+        assert!(matches!(
+            vote_delegation(&stake_address, DRep::Abstain).unwrap(),
+            Certificate::VoteDeleg(x, DRep::Abstain) if x == credential
+        ));
+
+        let address = PallasAddress::from_str(
+            "addr1g9ekml92qyvzrjmawxkh64r2w5xr6mg9ngfmxh2khsmdrcudevsft64mf887333adamant",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vote_delegation(&address, DRep::Abstain),
+            Err(TxBuilderError::UnexpectedAddressKind)
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use pallas_codec::minicbor::{self, data::Type, Decoder, Encoder};
+use pallas_crypto::key::ed25519;
+use pallas_primitives::{
+    conway::{TransactionUnspentOutput, Value},
+    Fragment,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::TxBuilderError;
+
+const COSE_SIGN1_TAG: u64 = 18;
+const COSE_ALG_LABEL: i64 = 1;
+const COSE_ALG_EDDSA: i64 = -8;
+const COSE_KEY_X_LABEL: i64 = -2;
+
+fn decode_hex_fragment<T>(hex: &str, err: TxBuilderError) -> Result<T, TxBuilderError>
+where
+    T: for<'b> Fragment<'b>,
+{
+    let bytes = hex::decode(hex).map_err(|_| TxBuilderError::InvalidHex)?;
+    T::decode_fragment(&bytes).map_err(|_| err)
+}
+
+/// Decodes a hex-encoded cbor `Value`, as returned by `getBalance()`
+pub fn decode_value(hex: &str) -> Result<Value, TxBuilderError> {
+    decode_hex_fragment(hex, TxBuilderError::MalformedValue)
+}
+
+/// Hex-encoded cbor of a `Value`, as expected by dApp connector APIs
+pub fn encode_value(value: &Value) -> String {
+    hex::encode(value.encode_fragment().unwrap())
+}
+
+/// Decodes a hex-encoded cbor `TransactionUnspentOutput`, as returned by
+/// `getUtxos()` and `getCollateral()`
+pub fn decode_utxo(hex: &str) -> Result<TransactionUnspentOutput, TxBuilderError> {
+    decode_hex_fragment(hex, TxBuilderError::MalformedUtxo)
+}
+
+/// Hex-encoded cbor of a `TransactionUnspentOutput`
+pub fn encode_utxo(utxo: &TransactionUnspentOutput) -> String {
+    hex::encode(utxo.encode_fragment().unwrap())
+}
+
+/// Result of `signData()`, a COSE_Sign1 structure and the COSE_Key of the
+/// signer, both hex-encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSignature {
+    pub signature: String,
+    pub key: String,
+}
+
+/// Contents of a data signature that has been verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedData {
+    /// The address the wallet claims to have signed with, as raw bytes
+    pub address: Vec<u8>,
+    /// The signed payload (its blake2b-224 hash if `hashed` is set)
+    pub payload: Vec<u8>,
+    pub hashed: bool,
+    pub public_key: ed25519::PublicKey,
+}
+
+struct CoseSign1<'b> {
+    protected: &'b [u8],
+    hashed: bool,
+    payload: &'b [u8],
+    signature: &'b [u8],
+}
+
+fn malformed<T>(_: T) -> TxBuilderError {
+    TxBuilderError::MalformedDataSignature
+}
+
+/// Calls `entry` for each key/value pair of a header map, which may be of
+/// definite or indefinite length
+fn decode_map<'b>(
+    d: &mut Decoder<'b>,
+    mut entry: impl FnMut(&mut Decoder<'b>) -> Result<(), minicbor::decode::Error>,
+) -> Result<(), minicbor::decode::Error> {
+    match d.map()? {
+        Some(len) => {
+            for _ in 0..len {
+                entry(d)?;
+            }
+        }
+        None => {
+            while d.datatype()? != Type::Break {
+                entry(d)?;
+            }
+
+            d.set_position(d.position() + 1);
+        }
+    }
+
+    Ok(())
+}
+
+fn skip_entry(d: &mut Decoder) -> Result<(), minicbor::decode::Error> {
+    d.skip()?;
+    d.skip()
+}
+
+fn decode_cose_sign1(bytes: &[u8]) -> Result<CoseSign1<'_>, minicbor::decode::Error> {
+    let mut d = Decoder::new(bytes);
+
+    if d.datatype()? == Type::Tag && d.tag()?.as_u64() != COSE_SIGN1_TAG {
+        return Err(minicbor::decode::Error::message("unexpected COSE tag"));
+    }
+
+    d.array()?;
+
+    let protected = d.bytes()?;
+
+    let mut hashed = false;
+
+    decode_map(&mut d, |d| match d.datatype()? {
+        Type::String if d.probe().str()? == "hashed" => {
+            d.str()?;
+            hashed = d.bool()?;
+            Ok(())
+        }
+        _ => skip_entry(d),
+    })?;
+
+    let payload = d.bytes()?;
+    let signature = d.bytes()?;
+
+    Ok(CoseSign1 {
+        protected,
+        hashed,
+        payload,
+        signature,
+    })
+}
+
+// returns the algorithm and the address found in the protected header
+fn decode_protected_header(
+    bytes: &[u8],
+) -> Result<(Option<i64>, Option<Vec<u8>>), minicbor::decode::Error> {
+    let mut d = Decoder::new(bytes);
+
+    let (mut alg, mut address) = (None, None);
+
+    decode_map(&mut d, |d| match d.datatype()? {
+        Type::String if d.probe().str()? == "address" => {
+            d.str()?;
+            address = Some(d.bytes()?.to_vec());
+            Ok(())
+        }
+        Type::U8 | Type::I8 if d.probe().i64()? == COSE_ALG_LABEL => {
+            d.i64()?;
+            alg = Some(d.i64()?);
+            Ok(())
+        }
+        _ => skip_entry(d),
+    })?;
+
+    Ok((alg, address))
+}
+
+fn decode_cose_key(bytes: &[u8]) -> Result<Option<Vec<u8>>, minicbor::decode::Error> {
+    let mut d = Decoder::new(bytes);
+
+    let mut x = None;
+
+    decode_map(&mut d, |d| match d.datatype()? {
+        Type::I8 if d.probe().i64()? == COSE_KEY_X_LABEL => {
+            d.i64()?;
+            x = Some(d.bytes()?.to_vec());
+            Ok(())
+        }
+        _ => skip_entry(d),
+    })?;
+
+    Ok(x)
+}
+
+/// The `Sig_structure` of a COSE_Sign1, the bytes actually signed
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut buf = vec![];
+    let mut e = Encoder::new(&mut buf);
+
+    e.array(4).unwrap();
+    e.str("Signature1").unwrap();
+    e.bytes(protected).unwrap();
+    e.bytes(&[]).unwrap();
+    e.bytes(payload).unwrap();
+
+    buf
+}
+
+impl DataSignature {
+    /// Checks the signature against the key, returning the signed contents
+    ///
+    /// It is up to the caller to check that the returned address and public
+    /// key belong together, and that the payload is the expected one.
+    pub fn verify(&self) -> Result<SignedData, TxBuilderError> {
+        let signature = hex::decode(&self.signature).map_err(|_| TxBuilderError::InvalidHex)?;
+        let key = hex::decode(&self.key).map_err(|_| TxBuilderError::InvalidHex)?;
+
+        let cose = decode_cose_sign1(&signature).map_err(malformed)?;
+
+        let (alg, address) = decode_protected_header(cose.protected).map_err(malformed)?;
+
+        if alg != Some(COSE_ALG_EDDSA) {
+            return Err(TxBuilderError::MalformedDataSignature);
+        }
+
+        let address = address.ok_or(TxBuilderError::MalformedDataSignature)?;
+
+        let public_key = decode_cose_key(&key)
+            .map_err(malformed)?
+            .ok_or(TxBuilderError::MalformedDataSignature)?;
+
+        let public_key: [u8; ed25519::PublicKey::SIZE] = public_key
+            .try_into()
+            .map_err(|_| TxBuilderError::MalformedKey)?;
+
+        let public_key = ed25519::PublicKey::from(public_key);
+
+        let ed_signature = ed25519::Signature::try_from(cose.signature)
+            .map_err(|_| TxBuilderError::InvalidSignature)?;
+
+        if !public_key.verify(sig_structure(cose.protected, cose.payload), &ed_signature) {
+            return Err(TxBuilderError::InvalidSignature);
+        }
+
+        Ok(SignedData {
+            address,
+            payload: cose.payload.to_vec(),
+            hashed: cose.hashed,
+            public_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_crypto::{hash::Hash, key::ed25519::SecretKey};
+
+    use super::*;
+    use crate::{Input, Output};
+
+    #[test]
+    fn value_and_utxo_roundtrip() {
+        let value = decode_value("1a000f4240").unwrap();
+        assert_eq!(value, Value::Coin(1_000_000));
+        assert_eq!(encode_value(&value), "1a000f4240");
+
+        assert_eq!(decode_value("zz"), Err(TxBuilderError::InvalidHex));
+        assert_eq!(decode_value("80"), Err(TxBuilderError::MalformedValue));
+
+        let address = pallas_addresses::Address::from_bech32(
+            "addr_test1vr80076l3x5uw6n94nwhgmv7ssgy6muzf47ugn6z0l92rhg2mgtu0",
+        )
+        .unwrap();
+
+        let utxo = Output::new(address, 5_000_000)
+            .build_utxo(&Input::new(Hash::new([1; 32]), 2))
+            .unwrap();
+
+        let hex = encode_utxo(&utxo);
+        assert_eq!(decode_utxo(&hex).unwrap(), utxo);
+        assert_eq!(decode_utxo("8180"), Err(TxBuilderError::MalformedUtxo));
+    }
+
+    fn sign(key: &SecretKey, address: &[u8], payload: &[u8]) -> DataSignature {
+        // { "hashed": false }
+        let unprotected = hex::decode("a166686173686564f4").unwrap();
+        sign_with_header(key, address, payload, &unprotected)
+    }
+
+    fn sign_with_header(
+        key: &SecretKey,
+        address: &[u8],
+        payload: &[u8],
+        unprotected: &[u8],
+    ) -> DataSignature {
+        let mut protected = vec![];
+        let mut e = Encoder::new(&mut protected);
+        e.map(2).unwrap();
+        e.i64(COSE_ALG_LABEL).unwrap().i64(COSE_ALG_EDDSA).unwrap();
+        e.str("address").unwrap().bytes(address).unwrap();
+
+        let signature = key.sign(sig_structure(&protected, payload));
+
+        let mut cose = vec![];
+        let mut e = Encoder::new(&mut cose);
+        e.array(4).unwrap();
+        e.bytes(&protected).unwrap();
+        e.writer_mut().extend_from_slice(unprotected);
+        e.bytes(payload).unwrap();
+        e.bytes(signature.as_ref()).unwrap();
+
+        let mut cose_key = vec![];
+        let mut e = Encoder::new(&mut cose_key);
+        e.map(4).unwrap();
+        e.i64(1).unwrap().i64(1).unwrap();
+        e.i64(3).unwrap().i64(COSE_ALG_EDDSA).unwrap();
+        e.i64(-1).unwrap().i64(6).unwrap();
+        e.i64(COSE_KEY_X_LABEL)
+            .unwrap()
+            .bytes(key.public_key().as_ref())
+            .unwrap();
+
+        DataSignature {
+            signature: hex::encode(cose),
+            key: hex::encode(cose_key),
+        }
+    }
+
+    #[test]
+    fn data_signature_verification() {
+        let key = SecretKey::from([7; SecretKey::SIZE]);
+        let address = [0xe1; 29];
+
+        let signed = sign(&key, &address, b"hello").verify().unwrap();
+
+        assert_eq!(signed.address, address.to_vec());
+        assert_eq!(signed.payload, b"hello".to_vec());
+        assert!(!signed.hashed);
+        assert_eq!(signed.public_key, key.public_key());
+
+        let mut forged = sign(&key, &address, b"hello");
+        forged.key = sign(&SecretKey::from([8; SecretKey::SIZE]), &address, b"hello").key;
+
+        assert_eq!(forged.verify(), Err(TxBuilderError::InvalidSignature));
+    }
+
+    #[test]
+    fn unprotected_header_variants() {
+        let key = SecretKey::from([7; SecretKey::SIZE]);
+        let address = [0xe1; 29];
+
+        // { "xyz": 1, "hashed": true }, the unknown key must not throw the
+        // decoder out of sync
+        let unprotected = hex::decode("a26378797a0166686173686564f5").unwrap();
+        let signed = sign_with_header(&key, &address, b"hello", &unprotected)
+            .verify()
+            .unwrap();
+
+        assert!(signed.hashed);
+        assert_eq!(signed.payload, b"hello".to_vec());
+
+        // the same header as an indefinite map
+        let unprotected = hex::decode("bf6378797a0166686173686564f5ff").unwrap();
+        let signed = sign_with_header(&key, &address, b"hello", &unprotected)
+            .verify()
+            .unwrap();
+
+        assert!(signed.hashed);
+        assert_eq!(signed.payload, b"hello".to_vec());
+    }
+}
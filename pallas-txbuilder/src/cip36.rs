@@ -0,0 +1,209 @@
+use pallas_addresses::Address as PallasAddress;
+use pallas_codec::{minicbor, utils::Int};
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::ed25519,
+};
+use pallas_primitives::{Fragment, KeyValuePairs, Metadatum};
+use pallas_wallet::PrivateKey;
+
+use crate::{transaction::model::StagingTransaction, TxBuilderError};
+
+/// Metadata label of the CIP-36 registration payload
+pub const REGISTRATION_LABEL: u64 = 61284;
+
+/// Metadata label of the CIP-36 registration witness
+pub const WITNESS_LABEL: u64 = 61285;
+
+/// Voting purpose used by Catalyst
+pub const CATALYST_VOTING_PURPOSE: u64 = 0;
+
+/// A CIP-36 (Catalyst) vote key registration
+///
+/// Voting power of the stake key is delegated to one or more voting keys in
+/// proportion to their weight. Rewards are paid to the payment address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cip36Registration {
+    pub delegations: Vec<(ed25519::PublicKey, u32)>,
+    pub stake_key: ed25519::PublicKey,
+    pub payment_address: PallasAddress,
+    pub nonce: u64,
+    pub voting_purpose: u64,
+}
+
+impl Cip36Registration {
+    pub fn new(stake_key: ed25519::PublicKey, payment_address: PallasAddress, nonce: u64) -> Self {
+        Self {
+            delegations: vec![],
+            stake_key,
+            payment_address,
+            nonce,
+            voting_purpose: CATALYST_VOTING_PURPOSE,
+        }
+    }
+
+    pub fn delegation(mut self, voting_key: ed25519::PublicKey, weight: u32) -> Self {
+        self.delegations.push((voting_key, weight));
+        self
+    }
+
+    pub fn voting_purpose(mut self, voting_purpose: u64) -> Self {
+        self.voting_purpose = voting_purpose;
+        self
+    }
+
+    /// The metadatum to be placed under the [`REGISTRATION_LABEL`] label
+    pub fn payload(&self) -> Metadatum {
+        let delegations = self
+            .delegations
+            .iter()
+            .map(|(key, weight)| {
+                Metadatum::Array(vec![
+                    Metadatum::Bytes(key.as_ref().to_vec().into()),
+                    Metadatum::Int(Int::from(*weight as i64)),
+                ])
+            })
+            .collect();
+
+        Metadatum::Map(KeyValuePairs::Def(vec![
+            (uint(1), Metadatum::Array(delegations)),
+            (
+                uint(2),
+                Metadatum::Bytes(self.stake_key.as_ref().to_vec().into()),
+            ),
+            (
+                uint(3),
+                Metadatum::Bytes(self.payment_address.to_vec().into()),
+            ),
+            (uint(4), uint(self.nonce)),
+            (uint(5), uint(self.voting_purpose)),
+        ]))
+    }
+
+    /// The hash that needs to be signed by the stake key, computed over the
+    /// metadata map `{ 61284: payload }`
+    pub fn payload_hash(&self) -> Hash<32> {
+        let metadata = Metadatum::Map(KeyValuePairs::Def(vec![(
+            uint(REGISTRATION_LABEL),
+            self.payload(),
+        )]));
+
+        Hasher::<256>::hash(&minicbor::to_vec(metadata).unwrap())
+    }
+
+    /// The metadatum to be placed under the [`WITNESS_LABEL`] label
+    pub fn witness(signature: &ed25519::Signature) -> Metadatum {
+        Metadatum::Map(KeyValuePairs::Def(vec![(
+            uint(1),
+            Metadatum::Bytes(signature.as_ref().to_vec().into()),
+        )]))
+    }
+}
+
+fn uint(x: u64) -> Metadatum {
+    Metadatum::Int(Int(minicbor::data::Int::from(x)))
+}
+
+impl StagingTransaction {
+    /// Add a CIP-36 registration to the transaction metadata, signing it with
+    /// the private key of the registered stake key.
+    pub fn cip36_registration(
+        self,
+        registration: &Cip36Registration,
+        stake_key: &PrivateKey,
+    ) -> Result<Self, TxBuilderError> {
+        if stake_key.public_key() != registration.stake_key {
+            return Err(TxBuilderError::MalformedKey);
+        }
+
+        let signature = stake_key.sign(registration.payload_hash());
+
+        self.add_cip36_registration(registration, signature)
+    }
+
+    /// Add a CIP-36 registration to the transaction metadata, using a
+    /// signature produced externally by the owner of the stake key.
+    pub fn add_cip36_registration(
+        self,
+        registration: &Cip36Registration,
+        signature: ed25519::Signature,
+    ) -> Result<Self, TxBuilderError> {
+        if !registration
+            .stake_key
+            .verify(registration.payload_hash(), &signature)
+        {
+            return Err(TxBuilderError::InvalidSignature);
+        }
+
+        let payload = registration
+            .payload()
+            .encode_fragment()
+            .map_err(|_| TxBuilderError::MalformedMetadatum)?;
+
+        let witness = Cip36Registration::witness(&signature)
+            .encode_fragment()
+            .map_err(|_| TxBuilderError::MalformedMetadatum)?;
+
+        Ok(self
+            .add_metadata(REGISTRATION_LABEL, payload)
+            .add_metadata(WITNESS_LABEL, witness))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use pallas_crypto::key::ed25519::SecretKey;
+    use pallas_primitives::conway;
+
+    use super::*;
+    use crate::{BuildConway, Input, Output};
+
+    #[test]
+    fn registration_roundtrip() {
+        let stake_key = PrivateKey::from(SecretKey::from([1; SecretKey::SIZE]));
+        let voting_key = SecretKey::from([2; SecretKey::SIZE]).public_key();
+
+        let address = PallasAddress::from_str(
+            "addr1g9ekml92qyvzrjmawxkh64r2w5xr6mg9ngfmxh2khsmdrcudevsft64mf887333adamant",
+        )
+        .unwrap();
+
+        let registration = Cip36Registration::new(stake_key.public_key(), address.clone(), 1337)
+            .delegation(voting_key, 1);
+
+        let built = StagingTransaction::new()
+            .input(Input::new(Hash::new([0; 32]), 0))
+            .output(Output::new(address, 1_000_000))
+            .fee(200_000)
+            .cip36_registration(&registration, &stake_key)
+            .unwrap()
+            .build_conway_raw()
+            .unwrap();
+
+        let tx = conway::Tx::decode_fragment(&built.tx_bytes.0).unwrap();
+
+        let metadata = match Option::from(tx.auxiliary_data).unwrap() {
+            conway::AuxiliaryData::Shelley(x) => x.to_vec(),
+            _ => panic!("expected shelley metadata"),
+        };
+
+        assert_eq!(metadata[0], (REGISTRATION_LABEL, registration.payload()));
+        assert_eq!(metadata[1].0, WITNESS_LABEL);
+
+        let signature = match &metadata[1].1 {
+            Metadatum::Map(x) => match &x.clone().to_vec()[0].1 {
+                Metadatum::Bytes(x) => ed25519::Signature::try_from(x.as_slice()).unwrap(),
+                _ => panic!("expected signature bytes"),
+            },
+            _ => panic!("expected witness map"),
+        };
+
+        assert!(stake_key
+            .public_key()
+            .verify(registration.payload_hash(), &signature));
+
+        assert!(tx.transaction_body.auxiliary_data_hash.is_some());
+    }
+}
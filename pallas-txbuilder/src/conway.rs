@@ -1,24 +1,26 @@
-use std::ops::Deref;
+use std::{collections::BTreeMap, ops::Deref};
 
 use pallas_codec::utils::CborWrap;
-use pallas_crypto::hash::Hash;
+use pallas_crypto::hash::{Hash, Hasher};
 use pallas_primitives::{
     conway::{
-        DatumOption, ExUnits as PallasExUnits, NativeScript, NetworkId, NonZeroInt, PlutusData,
-        PlutusScript, PostAlonzoTransactionOutput, PseudoScript as PallasScript,
-        PseudoTransactionOutput, Redeemer, RedeemerTag, TransactionBody, TransactionInput, Tx,
-        Value, WitnessSet,
+        AuxiliaryData, Certificate, DatumOption, ExUnits as PallasExUnits, GovActionId, Metadatum,
+        NativeScript, NetworkId, NonZeroInt, PlutusData, PlutusScript, PostAlonzoTransactionOutput,
+        ProposalProcedure, PseudoScript as PallasScript, PseudoTransactionOutput, PseudoTx,
+        Redeemer, RedeemerTag, TransactionBody, TransactionInput, TransactionUnspentOutput, Value,
+        Voter, VotingProcedure, WitnessSet,
     },
-    Fragment, NonEmptyKeyValuePairs, NonEmptySet, PositiveCoin,
+    AnyCbor, Fragment, KeyValuePairs, NonEmptyKeyValuePairs, NonEmptySet, Nullable, PositiveCoin,
 };
 use pallas_traverse::ComputeHash;
 
 use crate::{
+    governance::Ballot,
     scriptdata,
     transaction::{
         model::{
-            BuilderEra, BuiltTransaction, DatumKind, ExUnits, Output, RedeemerPurpose, ScriptKind,
-            StagingTransaction,
+            BuilderEra, BuiltTransaction, DatumKind, ExUnits, Input, Output, RedeemerPurpose,
+            ScriptKind, StagingTransaction,
         },
         Bytes, Bytes32, TransactionStatus,
     },
@@ -38,17 +40,19 @@ impl BuildConway for StagingTransaction {
             .inputs
             .unwrap_or_default()
             .iter()
-            .map(|x| TransactionInput {
-                transaction_id: x.tx_hash.0.into(),
-                index: x.txo_index,
-            })
+            .map(Input::build_raw)
             .collect::<Vec<_>>();
 
         inputs.sort_unstable_by_key(|x| (x.transaction_id, x.index));
 
-        let outputs = self
-            .outputs
-            .unwrap_or_default()
+        let mut outputs = self.outputs.unwrap_or_default();
+
+        if let Some(ordering) = self.output_ordering {
+            let change = self.change_address.as_ref();
+            outputs = ordering.apply(outputs, |x| change.is_some_and(|c| c.0 == x.address.0));
+        }
+
+        let outputs = outputs
             .iter()
             .map(Output::build_babbage_raw)
             .collect::<Result<Vec<_>, _>>()?;
@@ -76,10 +80,7 @@ impl BuildConway for StagingTransaction {
             self.collateral_inputs
                 .unwrap_or_default()
                 .iter()
-                .map(|x| TransactionInput {
-                    transaction_id: x.tx_hash.0.into(),
-                    index: x.txo_index,
-                })
+                .map(Input::build_raw)
                 .collect(),
         );
 
@@ -110,10 +111,7 @@ impl BuildConway for StagingTransaction {
             self.reference_inputs
                 .unwrap_or_default()
                 .iter()
-                .map(|x| TransactionInput {
-                    transaction_id: x.tx_hash.0.into(),
-                    index: x.txo_index,
-                })
+                .map(Input::build_raw)
                 .collect(),
         );
 
@@ -217,46 +215,143 @@ impl BuildConway for StagingTransaction {
 
         let witness_set_redeemers = pallas_primitives::conway::Redeemers::List(
             pallas_codec::utils::MaybeIndefArray::Def(redeemers.clone()),
+        )
+        .with_encoding(self.redeemers_encoding.unwrap_or_default());
+
+        let script_data_hash =
+            self.language_views
+                .filter(|x| !x.is_empty())
+                .map(|language_views| {
+                    let dta = scriptdata::ScriptData {
+                        redeemers: witness_set_redeemers.clone(),
+                        datums: if !plutus_data.is_empty() {
+                            Some(plutus_data.clone())
+                        } else {
+                            None
+                        },
+                        language_views,
+                    };
+
+                    dbg!(&dta);
+                    dta.hash()
+                });
+
+        let certificates = self
+            .certificates
+            .unwrap_or_default()
+            .iter()
+            .map(|x| {
+                Certificate::decode_fragment(x.as_ref())
+                    .map_err(|_| TxBuilderError::MalformedCertificate)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let proposals = self
+            .proposals
+            .unwrap_or_default()
+            .iter()
+            .map(|x| {
+                ProposalProcedure::decode_fragment(x.as_ref())
+                    .map_err(|_| TxBuilderError::MalformedProposal)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut withdrawals = self
+            .withdrawals
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(account, amount)| (account.0, amount))
+            .collect::<Vec<_>>();
+
+        withdrawals.sort_unstable();
+
+        let withdrawals = NonEmptyKeyValuePairs::from_vec(
+            withdrawals
+                .into_iter()
+                .map(|(account, amount)| (account.into(), amount))
+                .collect(),
         );
 
-        let script_data_hash = self.language_view.map(|language_view| {
-            let dta = scriptdata::ScriptData {
-                redeemers: witness_set_redeemers.clone(),
-                datums: if !plutus_data.is_empty() {
-                    Some(plutus_data.clone())
-                } else {
-                    None
-                },
-                language_view,
-            };
+        let mut votes = BTreeMap::<Voter, BTreeMap<GovActionId, VotingProcedure>>::new();
 
-            dbg!(&dta);
-            dta.hash()
-        });
-
-        let mut pallas_tx = Tx {
-            transaction_body: TransactionBody {
-                inputs: pallas_primitives::Set::from(inputs),
-                outputs,
-                ttl: self.invalid_from_slot,
-                validity_interval_start: self.valid_from_slot,
-                fee: self.fee.unwrap_or_default(),
-                certificates: None,        // TODO
-                withdrawals: None,         // TODO
-                auxiliary_data_hash: None, // TODO (accept user input)
-                mint,
-                script_data_hash,
-                collateral,
-                required_signers,
-                network_id,
-                collateral_return,
-                reference_inputs,
-                total_collateral: None,    // TODO
-                voting_procedures: None,   // TODO
-                proposal_procedures: None, // TODO
-                treasury_value: None,      // TODO
-                donation: None,            // TODO
-            },
+        for vote in self.votes.unwrap_or_default() {
+            let ballot = Ballot::decode_fragment(vote.as_ref())
+                .map_err(|_| TxBuilderError::MalformedVote)?;
+
+            votes
+                .entry(ballot.voter)
+                .or_default()
+                .insert(ballot.action, ballot.procedure);
+        }
+
+        let voting_procedures = NonEmptyKeyValuePairs::from_vec(
+            votes
+                .into_iter()
+                .filter_map(|(voter, x)| {
+                    NonEmptyKeyValuePairs::from_vec(x.into_iter().collect()).map(|x| (voter, x))
+                })
+                .collect(),
+        );
+
+        let mut metadata = self
+            .metadata
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(label, x)| {
+                Metadatum::decode_fragment(x.as_ref())
+                    .map(|x| (label, x))
+                    .map_err(|_| TxBuilderError::MalformedMetadatum)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        metadata.sort_unstable_by_key(|(label, _)| *label);
+
+        let auxiliary_data = if metadata.is_empty() {
+            None
+        } else {
+            Some(AuxiliaryData::Shelley(KeyValuePairs::Def(metadata)))
+        };
+
+        let mut transaction_body = TransactionBody {
+            inputs: pallas_primitives::Set::from(inputs),
+            outputs,
+            ttl: self.invalid_from_slot,
+            validity_interval_start: self.valid_from_slot,
+            fee: self.fee.unwrap_or_default(),
+            certificates: NonEmptySet::from_vec(certificates),
+            withdrawals,
+            auxiliary_data_hash: None, // TODO (accept user input)
+            mint,
+            script_data_hash,
+            collateral,
+            required_signers,
+            network_id,
+            collateral_return,
+            reference_inputs,
+            total_collateral: None, // TODO
+            voting_procedures,
+            proposal_procedures: NonEmptySet::from_vec(proposals),
+            treasury_value: None, // TODO
+            donation: None,       // TODO
+        };
+
+        let auxiliary_data: Nullable<AuxiliaryData> = auxiliary_data.into();
+
+        // TODO: pallas auxiliary_data_hash should be Hash<32> not Bytes
+        transaction_body.auxiliary_data_hash = auxiliary_data
+            .clone()
+            .map(|ad| ad.compute_hash().to_vec().into())
+            .into();
+
+        let mut body_bytes = transaction_body.encode_fragment().unwrap();
+
+        if let Some(order) = self.body_key_order {
+            body_bytes = order.apply(&body_bytes).unwrap();
+        }
+
+        let pallas_tx = PseudoTx {
+            // keeps the bytes of the body as they are hashed
+            transaction_body: AnyCbor::decode_fragment(&body_bytes).unwrap(),
             transaction_witness_set: WitnessSet {
                 vkeywitness: None,
                 native_script: NonEmptySet::from_vec(native_script),
@@ -271,22 +366,15 @@ impl BuildConway for StagingTransaction {
                     Some(witness_set_redeemers)
                 },
             },
-            success: true,               // TODO
-            auxiliary_data: None.into(), // TODO
+            success: true, // TODO
+            auxiliary_data,
         };
 
-        // TODO: pallas auxiliary_data_hash should be Hash<32> not Bytes
-        pallas_tx.transaction_body.auxiliary_data_hash = pallas_tx
-            .auxiliary_data
-            .clone()
-            .map(|ad| ad.compute_hash().to_vec().into())
-            .into();
-
         Ok(BuiltTransaction {
             version: self.version,
             era: BuilderEra::Conway,
             status: TransactionStatus::Built,
-            tx_hash: Bytes32(*pallas_tx.transaction_body.compute_hash()),
+            tx_hash: Bytes32(*Hasher::<256>::hash(&body_bytes)),
             tx_bytes: Bytes(pallas_tx.encode_fragment().unwrap()),
             signatures: None,
         })
@@ -297,7 +385,30 @@ impl BuildConway for StagingTransaction {
     // }
 }
 
+impl Input {
+    pub fn build_raw(&self) -> TransactionInput {
+        TransactionInput {
+            transaction_id: self.tx_hash.0.into(),
+            index: self.txo_index,
+        }
+    }
+}
+
+impl From<&TransactionInput> for Input {
+    fn from(value: &TransactionInput) -> Self {
+        Input::new(value.transaction_id, value.index)
+    }
+}
+
 impl Output {
+    /// Builds the unspent output located at `input`
+    pub fn build_utxo(&self, input: &Input) -> Result<TransactionUnspentOutput, TxBuilderError> {
+        Ok(TransactionUnspentOutput::new(
+            input.build_raw(),
+            self.build_babbage_raw()?,
+        ))
+    }
+
     pub fn build_babbage_raw(
         &self,
     ) -> Result<PseudoTransactionOutput<PostAlonzoTransactionOutput>, TxBuilderError> {
@@ -376,3 +487,261 @@ impl Output {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pallas_addresses::Address;
+    use pallas_crypto::key::ed25519::SecretKey;
+    use pallas_primitives::{conway::MintedTx, BodyKeyOrder};
+
+    use super::*;
+
+    #[test]
+    fn body_key_order_survives_signing() {
+        let address =
+            Address::from_bech32("addr_test1vr80076l3x5uw6n94nwhgmv7ssgy6muzf47ugn6z0l92rhg2mgtu0")
+                .unwrap();
+
+        let tx = StagingTransaction::new()
+            .input(Input::new(Hash::new([1; 32]), 0))
+            .collateral_input(Input::new(Hash::new([1; 32]), 1))
+            .output(Output::new(address, 1_000_000))
+            .fee(200_000)
+            .invalid_from_slot(100)
+            .disclosed_signer(Hash::new([2; 28]))
+            .body_key_order(BodyKeyOrder::Conway)
+            .build_conway_raw()
+            .unwrap();
+
+        let key = SecretKey::from([7; SecretKey::SIZE]);
+        let signed = tx.sign(key.into()).unwrap();
+
+        let minted = MintedTx::decode_fragment(&signed.tx_bytes.0).unwrap();
+        let body = minted.transaction_body.raw_cbor();
+
+        // collateral (13) goes right after the inputs
+        assert_eq!(body_keys(body), vec![0, 13, 1, 2, 3, 14]);
+
+        assert_eq!(*Hasher::<256>::hash(body), signed.tx_hash.0);
+        assert_eq!(
+            minted
+                .transaction_witness_set
+                .vkeywitness
+                .as_ref()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn bootstrap_witnesses_verify() {
+        use pallas_addresses::byron::{AddressPayload, ByronAddress};
+        use pallas_traverse::{witnesses::WitnessKind, MultiEraTx};
+        use pallas_wallet::hd::Bip32PrivateKey;
+
+        let mnemonic = ["abandon"; 11].join(" ") + " about";
+        let key = Bip32PrivateKey::from_bip39_mnenomic(mnemonic, String::new()).unwrap();
+        let other = key.derive(0);
+
+        let xpub = key.to_public().to_ed25519_extended_pubkey();
+        let address = ByronAddress::from_decoded(AddressPayload::new_pubkey(xpub, vec![].into()));
+
+        let build = || {
+            StagingTransaction::new()
+                .input(Input::new(Hash::new([1; 32]), 0))
+                .output(Output::new(Address::Byron(address.clone()), 1_000_000))
+                .fee(200_000)
+                .build_conway_raw()
+                .unwrap()
+        };
+
+        assert!(matches!(
+            build().sign_bootstrap(other, &address),
+            Err(TxBuilderError::BootstrapKeyMismatch)
+        ));
+
+        // a signature made elsewhere with the same key yields the same witness
+        let unsigned = build();
+        let signature = key
+            .to_ed25519_private_key()
+            .sign(unsigned.tx_hash.0)
+            .as_ref()
+            .try_into()
+            .unwrap();
+
+        let external = unsigned
+            .add_bootstrap_signature(xpub, signature, &address)
+            .unwrap();
+
+        let signed = build().sign_bootstrap(key, &address).unwrap();
+        assert_eq!(signed.tx_bytes, external.tx_bytes);
+
+        let tx = MultiEraTx::decode(&signed.tx_bytes.0).unwrap();
+        let checks = tx.verify_witnesses().unwrap();
+
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].kind, WitnessKind::Bootstrap);
+        assert_eq!(checks[0].public_key, xpub.public_key().as_ref());
+        assert!(checks[0].valid);
+    }
+
+    #[test]
+    fn bootstrap_signatures_can_be_removed() {
+        use pallas_addresses::byron::{AddressPayload, ByronAddress};
+        use pallas_wallet::hd::Bip32PrivateKey;
+
+        let mnemonic = ["abandon"; 11].join(" ") + " about";
+        let key = Bip32PrivateKey::from_bip39_mnenomic(mnemonic, String::new()).unwrap();
+
+        let xpub = key.to_public().to_ed25519_extended_pubkey();
+        let address = ByronAddress::from_decoded(AddressPayload::new_pubkey(xpub, vec![].into()));
+
+        let unsigned = StagingTransaction::new()
+            .input(Input::new(Hash::new([1; 32]), 0))
+            .output(Output::new(Address::Byron(address.clone()), 1_000_000))
+            .fee(200_000)
+            .build_conway_raw()
+            .unwrap();
+
+        let unsigned_bytes = unsigned.tx_bytes.clone();
+
+        let signed = unsigned.sign_bootstrap(key, &address).unwrap();
+        let pub_key = xpub.public_key();
+
+        let signatures = signed.signatures.as_ref().unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert!(signatures.contains_key(&Bytes32(pub_key.as_ref().try_into().unwrap())));
+
+        let minted = MintedTx::decode_fragment(&signed.tx_bytes.0).unwrap();
+        assert!(minted.transaction_witness_set.bootstrap_witness.is_some());
+
+        let removed = signed.remove_signature(pub_key).unwrap();
+        assert!(removed.signatures.as_ref().unwrap().is_empty());
+
+        let minted = MintedTx::decode_fragment(&removed.tx_bytes.0).unwrap();
+        assert!(minted.transaction_witness_set.bootstrap_witness.is_none());
+        assert_eq!(removed.tx_bytes, unsigned_bytes);
+    }
+
+    #[test]
+    fn ttl_from_wall_clock_time() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        use pallas_traverse::{time::EpochSchedule, wellknown::GenesisValues};
+
+        let schedule = EpochSchedule::from(&GenesisValues::mainnet());
+
+        let address =
+            Address::from_bech32("addr_test1vr80076l3x5uw6n94nwhgmv7ssgy6muzf47ugn6z0l92rhg2mgtu0")
+                .unwrap();
+
+        let staging = || {
+            StagingTransaction::new()
+                .input(Input::new(Hash::new([1; 32]), 0))
+                .output(Output::new(address.clone(), 1_000_000))
+                .fee(200_000)
+        };
+
+        // slot 51580240 started at 1643146531
+        let time = UNIX_EPOCH + Duration::from_secs(1643146531);
+
+        let built = staging()
+            .valid_until(&schedule, time)
+            .unwrap()
+            .build_conway_raw()
+            .unwrap();
+
+        let tx = pallas_primitives::conway::Tx::decode_fragment(&built.tx_bytes.0).unwrap();
+        assert_eq!(tx.transaction_body.ttl, Some(51580240));
+
+        assert_eq!(
+            staging().valid_until(&schedule, UNIX_EPOCH).unwrap_err(),
+            TxBuilderError::TimeBeforeChainStart
+        );
+
+        let now = || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+
+        // the clock is read by the builder somewhere in between
+        let before = now();
+
+        let ttl = staging()
+            .valid_for(&schedule, Duration::from_secs(3600))
+            .unwrap()
+            .invalid_from_slot
+            .unwrap();
+
+        let after = now();
+
+        assert!(ttl >= schedule.time_to_slot(before + 3600).unwrap());
+        assert!(ttl <= schedule.time_to_slot(after + 3600).unwrap());
+
+        assert_eq!(
+            staging().valid_for(&schedule, Duration::MAX).unwrap_err(),
+            TxBuilderError::TimeOutOfRange
+        );
+    }
+
+    #[test]
+    fn redeemers_in_map_encoding() {
+        use pallas_primitives::conway::{Redeemers, RedeemersEncoding};
+
+        let address =
+            Address::from_bech32("addr_test1vr80076l3x5uw6n94nwhgmv7ssgy6muzf47ugn6z0l92rhg2mgtu0")
+                .unwrap();
+
+        let input = || Input::new(Hash::new([1; 32]), 0);
+        let unit = PlutusData::Array(pallas_codec::utils::MaybeIndefArray::Def(vec![]));
+
+        let staging = || {
+            StagingTransaction::new()
+                .input(input())
+                .output(Output::new(address.clone(), 1_000_000))
+                .fee(200_000)
+                .add_spend_redeemer(
+                    input(),
+                    unit.encode_fragment().unwrap(),
+                    Some(ExUnits { mem: 1, steps: 2 }),
+                )
+        };
+
+        let redeemers = |tx: BuiltTransaction| {
+            pallas_primitives::conway::Tx::decode_fragment(&tx.tx_bytes.0)
+                .unwrap()
+                .transaction_witness_set
+                .redeemer
+                .unwrap()
+        };
+
+        let list = redeemers(staging().build_conway_raw().unwrap());
+
+        let map = redeemers(
+            staging()
+                .redeemers_encoding(RedeemersEncoding::Map)
+                .build_conway_raw()
+                .unwrap(),
+        );
+
+        assert!(matches!(list, Redeemers::List(_)));
+        assert!(matches!(map, Redeemers::Map(_)));
+        assert_eq!(list.to_pairs(), map.to_pairs());
+    }
+
+    fn body_keys(body: &[u8]) -> Vec<u64> {
+        let mut d = pallas_codec::minicbor::Decoder::new(body);
+        let len = d.map().unwrap().unwrap();
+
+        (0..len)
+            .map(|_| {
+                let key = d.u64().unwrap();
+                d.skip().unwrap();
+                key
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,249 @@
+use pallas_addresses::Address as PallasAddress;
+use pallas_codec::minicbor::{self, Decode, Encode};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{
+    conway::{
+        Anchor, GovAction, GovActionId, ProposalProcedure, ProtocolParamUpdate, Vote, Voter,
+        VotingProcedure,
+    },
+    KeyValuePairs, Nullable, ProtocolVersion,
+};
+
+use crate::TxBuilderError;
+
+fn reward_account(address: &PallasAddress) -> Result<Vec<u8>, TxBuilderError> {
+    match address {
+        PallasAddress::Stake(x) => Ok(x.to_vec()),
+        _ => Err(TxBuilderError::UnexpectedAddressKind),
+    }
+}
+
+/// Builder for a governance action proposal
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    deposit: u64,
+    return_account: PallasAddress,
+    anchor: Anchor,
+}
+
+impl Proposal {
+    /// Starts a proposal locking `deposit` lovelace, which is returned to
+    /// `return_account` once the action is enacted or expires
+    pub fn new(deposit: u64, return_account: PallasAddress, anchor: Anchor) -> Self {
+        Self {
+            deposit,
+            return_account,
+            anchor,
+        }
+    }
+
+    fn build(self, gov_action: GovAction) -> Result<ProposalProcedure, TxBuilderError> {
+        Ok(ProposalProcedure {
+            deposit: self.deposit,
+            reward_account: reward_account(&self.return_account)?.into(),
+            gov_action,
+            anchor: self.anchor,
+        })
+    }
+
+    /// Proposes withdrawing funds from the treasury into the given reward
+    /// accounts, guarded by the constitution guardrail script if any
+    pub fn treasury_withdrawals(
+        self,
+        withdrawals: Vec<(PallasAddress, u64)>,
+        guardrail_script: Option<Hash<28>>,
+    ) -> Result<ProposalProcedure, TxBuilderError> {
+        let mut withdrawals = withdrawals
+            .iter()
+            .map(|(account, amount)| Ok((reward_account(account)?, *amount)))
+            .collect::<Result<Vec<_>, TxBuilderError>>()?;
+
+        withdrawals.sort_unstable();
+
+        let withdrawals = withdrawals
+            .into_iter()
+            .map(|(account, amount)| (account.into(), amount))
+            .collect();
+
+        self.build(GovAction::TreasuryWithdrawals(
+            KeyValuePairs::Def(withdrawals),
+            guardrail_script.into(),
+        ))
+    }
+
+    /// Proposes changing the protocol parameters set in `update`, following
+    /// the previously enacted parameter change action if any
+    pub fn parameter_change(
+        self,
+        previous_action: Option<GovActionId>,
+        update: ProtocolParamUpdate,
+        guardrail_script: Option<Hash<28>>,
+    ) -> Result<ProposalProcedure, TxBuilderError> {
+        self.build(GovAction::ParameterChange(
+            previous_action.into(),
+            Box::new(update),
+            guardrail_script.into(),
+        ))
+    }
+
+    /// Proposes initiating a hard fork into the given protocol version,
+    /// following the previously enacted hard fork action if any
+    pub fn hard_fork_initiation(
+        self,
+        previous_action: Option<GovActionId>,
+        protocol_version: ProtocolVersion,
+    ) -> Result<ProposalProcedure, TxBuilderError> {
+        self.build(GovAction::HardForkInitiation(
+            previous_action.into(),
+            protocol_version,
+        ))
+    }
+}
+
+/// A single vote cast by a DRep, committee member or stake pool on a
+/// governance action
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Ballot {
+    #[n(0)]
+    pub voter: Voter,
+    #[n(1)]
+    pub action: GovActionId,
+    #[n(2)]
+    pub procedure: VotingProcedure,
+}
+
+impl Ballot {
+    pub fn new(voter: Voter, action: GovActionId, vote: Vote) -> Self {
+        Self {
+            voter,
+            action,
+            procedure: VotingProcedure {
+                vote,
+                anchor: Nullable::Null,
+            },
+        }
+    }
+
+    /// Attaches an anchor to the document explaining the rationale of the vote
+    pub fn rationale(mut self, anchor: Anchor) -> Self {
+        self.procedure.anchor = Nullable::Some(anchor);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use pallas_primitives::{conway, Fragment, StakeCredential};
+
+    use super::*;
+    use crate::{anchor, BuildConway, Input, Output, StagingTransaction};
+
+    fn stake_address() -> PallasAddress {
+        PallasAddress::from_str("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw")
+            .unwrap()
+    }
+
+    #[test]
+    fn proposals_and_votes_roundtrip() {
+        let address = PallasAddress::from_str(
+            "addr1g9ekml92qyvzrjmawxkh64r2w5xr6mg9ngfmxh2khsmdrcudevsft64mf887333adamant",
+        )
+        .unwrap();
+
+        let withdrawal = Proposal::new(
+            100_000_000_000,
+            stake_address(),
+            anchor("https://example.com/withdrawal.json", b"{}").unwrap(),
+        )
+        .treasury_withdrawals(vec![(stake_address(), 5_000_000)], None)
+        .unwrap();
+
+        let hard_fork = Proposal::new(
+            100_000_000_000,
+            stake_address(),
+            anchor("https://example.com/hard-fork.json", b"{}").unwrap(),
+        )
+        .hard_fork_initiation(None, (10, 0))
+        .unwrap();
+
+        let action = GovActionId {
+            transaction_id: Hash::new([7; 32]),
+            action_index: 0,
+        };
+
+        let drep = Voter::drep(StakeCredential::AddrKeyhash(Hash::new([1; 28])));
+        let pool = Voter::stake_pool(Hash::new([2; 28]));
+
+        let built = StagingTransaction::new()
+            .input(Input::new(Hash::new([0; 32]), 0))
+            .output(Output::new(address, 1_000_000))
+            .fee(200_000)
+            .add_proposal(withdrawal.clone())
+            .add_proposal(hard_fork.clone())
+            .add_vote(Ballot::new(pool.clone(), action.clone(), Vote::Yes))
+            .add_vote(Ballot::new(drep.clone(), action.clone(), Vote::No))
+            .add_vote(
+                Ballot::new(drep.clone(), action.clone(), Vote::Abstain)
+                    .rationale(anchor("https://example.com/vote.json", b"{}").unwrap()),
+            )
+            .build_conway_raw()
+            .unwrap();
+
+        let tx = conway::Tx::decode_fragment(&built.tx_bytes.0).unwrap();
+        let body = tx.transaction_body;
+
+        assert_eq!(
+            body.proposal_procedures.unwrap().to_vec(),
+            vec![withdrawal, hard_fork]
+        );
+
+        let votes = body.voting_procedures.unwrap().to_vec();
+
+        // the later ballot of the DRep overrides its previous one, and voters
+        // are sorted
+        assert_eq!(votes.len(), 2);
+        assert_eq!(votes[0].0, drep);
+        assert_eq!(votes[1].0, pool);
+
+        let (_, drep_vote) = votes[0].1.clone().to_vec().remove(0);
+        assert_eq!(drep_vote.vote, Vote::Abstain);
+        assert!(matches!(drep_vote.anchor, Nullable::Some(_)));
+    }
+
+    #[test]
+    fn remove_proposal_by_index() {
+        let proposal = |url| {
+            Proposal::new(0, stake_address(), anchor(url, b"{}").unwrap())
+                .hard_fork_initiation(None, (10, 0))
+                .unwrap()
+        };
+
+        let tx = StagingTransaction::new()
+            .add_proposal(proposal("https://example.com/first.json"))
+            .add_proposal(proposal("https://example.com/second.json"));
+
+        // out of range indexes are ignored
+        let tx = tx.remove_proposal(2).remove_proposal(0);
+
+        let expected = proposal("https://example.com/second.json")
+            .encode_fragment()
+            .unwrap();
+
+        assert_eq!(tx.proposals.unwrap(), vec![expected.into()]);
+    }
+
+    #[test]
+    fn reward_account_must_be_stake_address() {
+        let address = PallasAddress::from_str(
+            "addr1g9ekml92qyvzrjmawxkh64r2w5xr6mg9ngfmxh2khsmdrcudevsft64mf887333adamant",
+        )
+        .unwrap();
+
+        let proposal = Proposal::new(0, address, anchor("https://example.com", b"").unwrap())
+            .hard_fork_initiation(None, (10, 0));
+
+        assert_eq!(proposal, Err(TxBuilderError::UnexpectedAddressKind));
+    }
+}
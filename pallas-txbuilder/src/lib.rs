@@ -1,11 +1,34 @@
+mod anchor;
+mod blueprint;
+mod certificates;
+mod cip30;
+mod cip36;
 mod conway;
+mod governance;
+mod ordering;
 mod scriptdata;
 mod transaction;
+mod uplc;
+mod withdrawals;
 
+pub use anchor::{
+    anchor, anchor_hash, fetch_and_verify_anchor, verify_anchor, MAX_ANCHOR_URL_LENGTH,
+};
+pub use blueprint::{Argument, Blueprint, Compiler, Preamble, Validator};
+pub use certificates::{
+    drep_deregistration, drep_registration, drep_update, parse_relay, stake_credential,
+    stake_vote_delegation, stake_vote_registration_delegation, vote_delegation, PoolRegistration,
+};
+pub use cip30::{decode_utxo, decode_value, encode_utxo, encode_value, DataSignature, SignedData};
+pub use cip36::{Cip36Registration, CATALYST_VOTING_PURPOSE, REGISTRATION_LABEL, WITNESS_LABEL};
 pub use conway::BuildConway;
+pub use governance::{Ballot, Proposal};
+pub use ordering::{weighted_shuffle, OutputOrdering};
 pub use transaction::model::{
     BuiltTransaction, ExUnits, Input, Output, ScriptKind, StagingTransaction,
 };
+pub use uplc::{apply_data, apply_params_to_script, program_version};
+pub use withdrawals::{reward_account, RewardsResolver, Withdrawals};
 
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum TxBuilderError {
@@ -22,19 +45,109 @@ pub enum TxBuilderError {
     /// transaction
     #[error("Input/policy pointed to by redeemer not found in tx")]
     RedeemerTargetMissing,
+    /// Provided bytes could not be decoded into a metadatum
+    #[error("Could not decode metadatum bytes")]
+    MalformedMetadatum,
+    /// Provided bytes could not be decoded into a certificate
+    #[error("Could not decode certificate bytes")]
+    MalformedCertificate,
+    /// Provided bytes could not be decoded into a governance proposal
+    #[error("Could not decode proposal bytes")]
+    MalformedProposal,
+    /// Provided bytes could not be decoded into a vote
+    #[error("Could not decode vote bytes")]
+    MalformedVote,
+    /// Pool margin must be a number between 0 and 1
+    #[error("Invalid pool margin")]
+    InvalidPoolMargin,
+    /// Relay could not be parsed from the provided string
+    #[error("Invalid relay: {0}")]
+    InvalidRelay(String),
+    /// Metadata URL is too long, it must be 64 bytes or less
+    #[error("Metadata URL must be 64 bytes or less")]
+    MetadataUrlTooLong,
+    /// Anchor URL is too long, it must be 128 bytes or less
+    #[error("Anchor URL must be 128 bytes or less")]
+    AnchorUrlTooLong,
+    /// Anchor URL contains non-ASCII characters
+    #[error("Anchor URL must be ASCII")]
+    MalformedAnchorUrl,
+    /// The anchored document could not be retrieved
+    #[error("Could not fetch anchored document: {0}")]
+    AnchorFetch(String),
+    /// The anchored document does not match the anchor hash
+    #[error("Anchored document does not match its hash")]
+    AnchorHashMismatch,
+    /// Provided address is not of the expected kind
+    #[error("Unexpected address kind")]
+    UnexpectedAddressKind,
     /// Provided network ID is invalid (must be 0 or 1)
     #[error("Invalid network ID")]
     InvalidNetworkId,
+    /// Provided address belongs to another network
+    #[error("Address network does not match")]
+    NetworkMismatch,
+    /// A reward account is withdrawn from more than once
+    #[error("Duplicate withdrawal from the same reward account")]
+    DuplicateWithdrawal,
+    /// Withdrawals map must have at least one entry
+    #[error("No withdrawals")]
+    NoWithdrawals,
+    /// Reward account is not registered
+    #[error("Reward account {0} is not registered")]
+    UnregisteredRewardAccount(String),
+    /// Withdrawals must take the whole reward balance of the account
+    #[error("Withdrawal of {0} does not match the reward balance of {1}")]
+    WithdrawalAmountMismatch(u64, u64),
     /// Transaction bytes in built transaction object could not be decoded
     #[error("Corrupted transaction bytes in built transaction")]
     CorruptedTxBytes,
     /// Public key generated from private key was of unexpected length
     #[error("Public key for private key is malformed")]
     MalformedKey,
+    /// Provided signature does not match the signed payload
+    #[error("Invalid signature")]
+    InvalidSignature,
     /// Asset name is too long, it must be 32 bytes or less
     #[error("Asset name must be 32 bytes or less")]
     AssetNameTooLong,
+    /// Provided address could not be decoded
+    #[error("Malformed address")]
+    MalformedAddress,
+    /// Provided key is not the one the Byron address was derived from
+    #[error("Key does not match the bootstrap address")]
+    BootstrapKeyMismatch,
+    /// Provided string is not valid hex
+    #[error("Invalid hex string")]
+    InvalidHex,
+    /// Provided bytes could not be decoded into a value
+    #[error("Could not decode value bytes")]
+    MalformedValue,
+    /// Provided bytes could not be decoded into an unspent output
+    #[error("Could not decode unspent output bytes")]
+    MalformedUtxo,
+    /// Provided bytes could not be decoded into a COSE data signature
+    #[error("Could not decode data signature")]
+    MalformedDataSignature,
+    /// Contract blueprint could not be parsed
+    #[error("Malformed blueprint: {0}")]
+    MalformedBlueprint(String),
+    /// Script does not match the hash it was provided with
+    #[error("Script does not match its hash")]
+    ScriptHashMismatch,
+    /// Number of parameters applied to a validator doesn't match the expected
+    #[error("Expected {0} parameters, got {1}")]
+    ParameterCountMismatch(usize, usize),
+    /// Number of weights doesn't match the number of items to shuffle
+    #[error("Expected {0} weights, got {1}")]
+    WeightCountMismatch(usize, usize),
     /// Unsupported era
     #[error("Unsupported era")]
     UnsupportedEra,
+    /// Provided time is before the start of the chain
+    #[error("Time is before the start of the chain")]
+    TimeBeforeChainStart,
+    /// Provided time can't be represented by the system clock
+    #[error("Time is out of range")]
+    TimeOutOfRange,
 }
@@ -0,0 +1,175 @@
+//! Seedable ordering of transaction outputs, so that the position of each
+//! output (the change in particular) doesn't reveal how the transaction was
+//! assembled. Inputs need no such treatment, the ledger keeps them sorted.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::TxBuilderError;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub struct OutputOrdering {
+    pub seed: u64,
+    pub change_weight: u32,
+}
+
+impl OutputOrdering {
+    /// Shuffles outputs deterministically from the given seed
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            change_weight: 1,
+        }
+    }
+
+    /// Shuffles outputs from a seed picked at random
+    pub fn random() -> Self {
+        Self::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    /// Weight of change outputs relative to the rest (which weigh 1). Higher
+    /// weights move change towards the front, 0 always places it last.
+    pub fn change_weight(mut self, weight: u32) -> Self {
+        self.change_weight = weight;
+        self
+    }
+
+    pub fn apply<T, F>(&self, items: Vec<T>, is_change: F) -> Vec<T>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let weighted = items.into_iter().map(|x| {
+            let weight = if is_change(&x) { self.change_weight } else { 1 };
+            (x, weight)
+        });
+
+        shuffle(weighted, self.seed)
+    }
+}
+
+/// SplitMix64, good enough to shuffle and stable across platforms
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in (0, 1]
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Orders items at random, each one taking the next position with a
+/// probability proportional to its weight (Efraimidis-Spirakis). Items of
+/// weight 0 are placed last, in their original order.
+pub fn weighted_shuffle<T>(
+    items: Vec<T>,
+    weights: &[u32],
+    seed: u64,
+) -> Result<Vec<T>, TxBuilderError> {
+    if items.len() != weights.len() {
+        return Err(TxBuilderError::WeightCountMismatch(
+            items.len(),
+            weights.len(),
+        ));
+    }
+
+    Ok(shuffle(
+        items.into_iter().zip(weights.iter().copied()),
+        seed,
+    ))
+}
+
+fn shuffle<T>(weighted: impl Iterator<Item = (T, u32)>, seed: u64) -> Vec<T> {
+    let mut rng = SplitMix64(seed);
+
+    let mut keyed = weighted
+        .map(|(item, weight)| {
+            let u = rng.next_f64();
+
+            let key = match weight {
+                0 => f64::NEG_INFINITY,
+                w => u.ln() / w as f64,
+            };
+
+            (key, item)
+        })
+        .collect::<Vec<_>>();
+
+    keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_ordering_is_deterministic() {
+        let items = (0..10).collect::<Vec<_>>();
+
+        let a = OutputOrdering::seeded(42).apply(items.clone(), |_| false);
+        let b = OutputOrdering::seeded(42).apply(items.clone(), |_| false);
+        let c = OutputOrdering::seeded(43).apply(items.clone(), |_| false);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, items);
+
+        let mut sorted = a.clone();
+        sorted.sort();
+        assert_eq!(sorted, items);
+    }
+
+    #[test]
+    fn change_weight_biases_placement() {
+        let items = (0..5).collect::<Vec<_>>();
+        let is_change = |x: &i32| *x == 0;
+
+        for seed in 0..50 {
+            let last = OutputOrdering::seeded(seed)
+                .change_weight(0)
+                .apply(items.clone(), is_change);
+
+            assert_eq!(last.last(), Some(&0));
+        }
+
+        let first = (0..1000)
+            .filter(|seed| {
+                OutputOrdering::seeded(*seed)
+                    .change_weight(100)
+                    .apply(items.clone(), is_change)[0]
+                    == 0
+            })
+            .count();
+
+        assert!(first > 900);
+    }
+
+    #[test]
+    fn weights_must_match_items() {
+        let shuffled = weighted_shuffle(vec!['a', 'b', 'c'], &[1, 0, 1], 7).unwrap();
+        assert_eq!(shuffled.last(), Some(&'b'));
+
+        assert!(matches!(
+            weighted_shuffle(vec!['a', 'b', 'c'], &[1, 1], 7),
+            Err(TxBuilderError::WeightCountMismatch(3, 2))
+        ));
+
+        assert!(matches!(
+            weighted_shuffle(vec!['a'], &[1, 1], 7),
+            Err(TxBuilderError::WeightCountMismatch(1, 2))
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 use pallas_codec::minicbor::{self, Encode};
-use pallas_primitives::conway::{CostModel, PlutusData, Redeemers};
+use pallas_primitives::conway::{CostModel, CostModels, Language, PlutusData, Redeemers};
 use serde::{Deserialize, Serialize};
 
 pub type PlutusVersion = u8;
@@ -7,8 +7,30 @@ pub type PlutusVersion = u8;
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct LanguageView(pub PlutusVersion, pub CostModel);
 
-impl<C> Encode<C> for LanguageView {
-    fn encode<W: minicbor::encode::Write>(
+impl LanguageView {
+    pub fn new(language: Language, cost_model: CostModel) -> Self {
+        match language {
+            Language::PlutusV1 => LanguageView(0, cost_model),
+            Language::PlutusV2 => LanguageView(1, cost_model),
+            Language::PlutusV3 => LanguageView(2, cost_model),
+        }
+    }
+
+    /// The view of the given language using the cost model found in the
+    /// protocol parameters, if any
+    pub fn from_cost_models(language: Language, cost_models: &CostModels) -> Option<Self> {
+        cost_models
+            .for_language(&language)
+            .map(|x| Self::new(language, x.clone()))
+    }
+
+    // PlutusV1 keys are (incorrectly, for historical reasons) double-encoded
+    // as bytes, which makes them sort last in canonical cbor order
+    fn sort_key(&self) -> (bool, PlutusVersion) {
+        (self.0 == 0, self.0)
+    }
+
+    fn encode_entry<C, W: minicbor::encode::Write>(
         &self,
         e: &mut minicbor::Encoder<W>,
         ctx: &mut C,
@@ -24,13 +46,11 @@ impl<C> Encode<C> for LanguageView {
                 }
                 sub.end().unwrap();
 
-                e.map(1)?;
                 e.bytes(&minicbor::to_vec(0).unwrap())?;
                 e.bytes(&inner)?;
                 Ok(())
             }
             _ => {
-                e.map(1)?;
                 e.encode(self.0)?;
                 e.encode(&self.1)?;
                 Ok(())
@@ -39,11 +59,84 @@ impl<C> Encode<C> for LanguageView {
     }
 }
 
+impl<C> Encode<C> for LanguageView {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.map(1)?;
+        self.encode_entry(e, ctx)
+    }
+}
+
+/// The language views of all the Plutus languages used by a transaction
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Default)]
+pub struct LanguageViews(pub Vec<LanguageView>);
+
+// staging txs saved before multiple views were supported hold a single one
+impl<'de> Deserialize<'de> for LanguageViews {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Shape {
+            Many(Vec<LanguageView>),
+            Single(LanguageView),
+        }
+
+        match Shape::deserialize(deserializer)? {
+            Shape::Many(x) => Ok(LanguageViews(x)),
+            Shape::Single(x) => Ok(x.into()),
+        }
+    }
+}
+
+impl LanguageViews {
+    /// Sets the view of a language, replacing any previous view of it
+    pub fn set(&mut self, view: LanguageView) {
+        self.0.retain(|x| x.0 != view.0);
+        self.0.push(view);
+    }
+
+    pub fn remove(&mut self, version: PlutusVersion) {
+        self.0.retain(|x| x.0 != version);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<LanguageView> for LanguageViews {
+    fn from(value: LanguageView) -> Self {
+        LanguageViews(vec![value])
+    }
+}
+
+impl<C> Encode<C> for LanguageViews {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        let mut views = self.0.iter().collect::<Vec<_>>();
+        views.sort_by_key(|x| x.sort_key());
+
+        e.map(views.len() as u64)?;
+
+        for view in views {
+            view.encode_entry(e, ctx)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScriptData {
     pub redeemers: Redeemers,
     pub datums: Option<Vec<PlutusData>>,
-    pub language_view: LanguageView,
+    pub language_views: LanguageViews,
 }
 
 impl ScriptData {
@@ -56,7 +149,7 @@ impl ScriptData {
             minicbor::encode(datums, &mut buf).unwrap(); // infallible
         }
 
-        minicbor::encode(&self.language_view, &mut buf).unwrap(); // infallible
+        minicbor::encode(&self.language_views, &mut buf).unwrap(); // infallible
 
         pallas_crypto::hash::Hasher::<256>::hash(&buf)
     }
@@ -129,7 +222,7 @@ mod tests {
         let script_data = ScriptData {
             redeemers: witness.redeemer.unwrap(),
             datums: witness.plutus_data.map(|x| x.iter().cloned().collect()),
-            language_view: language_view.clone(),
+            language_views: language_view.clone().into(),
         };
 
         let obtained = script_data.hash();
@@ -145,4 +238,32 @@ mod tests {
             assert_script_data_hash_matches(bytes, language_view);
         }
     }
+
+    #[test]
+    fn language_views_canonical_order() {
+        let views = LanguageViews(vec![
+            LanguageView::new(Language::PlutusV1, vec![1]),
+            LanguageView::new(Language::PlutusV3, vec![3]),
+            LanguageView::new(Language::PlutusV2, vec![2]),
+        ]);
+
+        assert_eq!(
+            hex::encode(minicbor::to_vec(&views).unwrap()),
+            // {1: [2], 2: [3], h'00': h'9f01ff'}
+            "a30181020281034100439f01ff"
+        );
+
+        let cost_models = conway::CostModels {
+            plutus_v1: None,
+            plutus_v2: None,
+            plutus_v3: Some(vec![3]),
+        };
+
+        assert_eq!(
+            LanguageView::from_cost_models(Language::PlutusV3, &cost_models),
+            Some(LanguageView(2, vec![3]))
+        );
+
+        assert!(LanguageView::from_cost_models(Language::PlutusV1, &cost_models).is_none());
+    }
 }
@@ -52,3 +52,8 @@ pub type PolicyId = ScriptHash;
 pub type DatumHash = Bytes32;
 pub type DatumBytes = Bytes;
 pub type AssetName = Bytes;
+pub type MetadatumLabel = u64;
+pub type MetadatumBytes = Bytes;
+pub type CertificateBytes = Bytes;
+pub type ProposalBytes = Bytes;
+pub type VoteBytes = Bytes;
@@ -1,20 +1,30 @@
-use pallas_addresses::Address as PallasAddress;
+use pallas_addresses::{Address as PallasAddress, ByronAddress};
 use pallas_crypto::{
     hash::{Hash, Hasher},
     key::ed25519,
 };
-use pallas_primitives::{babbage, conway, Fragment, NonEmptySet};
-use pallas_wallet::PrivateKey;
+use pallas_primitives::{
+    babbage,
+    conway::{self, RedeemersEncoding},
+    BodyKeyOrder, Fragment, NonEmptySet,
+};
+use pallas_traverse::time::EpochSchedule;
+use pallas_wallet::{hd::Bip32PrivateKey, PrivateKey};
 
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{scriptdata, TxBuilderError};
+use crate::{governance::Ballot, scriptdata, OutputOrdering, TxBuilderError};
 
 use super::{
-    AssetName, Bytes, Bytes32, Bytes64, DatumBytes, DatumHash, Hash28, PolicyId, PubKeyHash,
-    PublicKey, ScriptBytes, ScriptHash, Signature, TransactionStatus, TxHash,
+    AssetName, Bytes, Bytes32, Bytes64, CertificateBytes, DatumBytes, DatumHash, Hash28,
+    MetadatumBytes, MetadatumLabel, PolicyId, ProposalBytes, PubKeyHash, PublicKey, ScriptBytes,
+    ScriptHash, Signature, TransactionStatus, TxHash, VoteBytes,
 };
 
 // TODO: Don't make wrapper types public
@@ -39,11 +49,18 @@ pub struct StagingTransaction {
     pub script_data_hash: Option<Bytes32>,
     pub signature_amount_override: Option<u8>,
     pub change_address: Option<Address>,
-    pub language_view: Option<scriptdata::LanguageView>,
-    // pub certificates: TODO
-    // pub withdrawals: TODO
+    #[serde(alias = "language_view")]
+    pub language_views: Option<scriptdata::LanguageViews>,
+    pub metadata: Option<HashMap<MetadatumLabel, MetadatumBytes>>,
+    pub certificates: Option<Vec<CertificateBytes>>,
+    pub proposals: Option<Vec<ProposalBytes>>,
+    pub votes: Option<Vec<VoteBytes>>,
+    pub output_ordering: Option<OutputOrdering>,
+    pub body_key_order: Option<BodyKeyOrder>,
+    pub withdrawals: Option<HashMap<Bytes, u64>>,
+    pub redeemers_encoding: Option<RedeemersEncoding>,
     // pub updates: TODO
-    // pub auxiliary_data: TODO
+    // pub auxiliary_scripts: TODO
     // pub phase_2_valid: TODO
 }
 
@@ -179,6 +196,38 @@ impl StagingTransaction {
         self
     }
 
+    /// Sets the TTL so that the tx is no longer valid once `time` is
+    /// reached. The tx becomes invalid at the start of the slot in progress
+    /// at `time`, so it is never accepted past it.
+    pub fn valid_until(
+        self,
+        schedule: &EpochSchedule,
+        time: SystemTime,
+    ) -> Result<Self, TxBuilderError> {
+        let time = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| TxBuilderError::TimeBeforeChainStart)?;
+
+        let slot = schedule
+            .time_to_slot(time.as_secs())
+            .ok_or(TxBuilderError::TimeBeforeChainStart)?;
+
+        Ok(self.invalid_from_slot(slot))
+    }
+
+    /// Sets the TTL so that the tx is valid for `duration` from now
+    pub fn valid_for(
+        self,
+        schedule: &EpochSchedule,
+        duration: Duration,
+    ) -> Result<Self, TxBuilderError> {
+        let time = SystemTime::now()
+            .checked_add(duration)
+            .ok_or(TxBuilderError::TimeOutOfRange)?;
+
+        self.valid_until(schedule, time)
+    }
+
     pub fn network_id(mut self, id: u8) -> Self {
         self.network_id = Some(id);
         self
@@ -286,14 +335,124 @@ impl StagingTransaction {
         self
     }
 
+    pub fn add_certificate(mut self, certificate: conway::Certificate) -> Self {
+        let mut certs = self.certificates.unwrap_or_default();
+        certs.push(certificate.encode_fragment().unwrap().into());
+        self.certificates = Some(certs);
+        self
+    }
+
+    pub fn remove_certificate(mut self, index: usize) -> Self {
+        let mut certs = self.certificates.unwrap_or_default();
+        if index < certs.len() {
+            certs.remove(index);
+        }
+        self.certificates = Some(certs);
+        self
+    }
+
+    pub fn add_proposal(mut self, proposal: conway::ProposalProcedure) -> Self {
+        let mut proposals = self.proposals.unwrap_or_default();
+        proposals.push(proposal.encode_fragment().unwrap().into());
+        self.proposals = Some(proposals);
+        self
+    }
+
+    pub fn remove_proposal(mut self, index: usize) -> Self {
+        let mut proposals = self.proposals.unwrap_or_default();
+        if index < proposals.len() {
+            proposals.remove(index);
+        }
+        self.proposals = Some(proposals);
+        self
+    }
+
+    /// Add a vote to the transaction, replacing any previous vote of the same
+    /// voter on the same governance action
+    pub fn add_vote(self, ballot: Ballot) -> Self {
+        let mut tx = self.remove_vote(&ballot.voter, &ballot.action);
+
+        let mut votes = tx.votes.unwrap_or_default();
+        votes.push(ballot.encode_fragment().unwrap().into());
+        tx.votes = Some(votes);
+        tx
+    }
+
+    pub fn remove_vote(mut self, voter: &conway::Voter, action: &conway::GovActionId) -> Self {
+        let mut votes = self.votes.unwrap_or_default();
+        votes.retain(|x| match Ballot::decode_fragment(x.as_ref()) {
+            Ok(ballot) => (&ballot.voter, &ballot.action) != (voter, action),
+            Err(_) => true,
+        });
+        self.votes = Some(votes);
+        self
+    }
+
+    /// Adds withdrawals to the transaction, replacing any previous amount
+    /// withdrawn from the same reward account
+    pub fn add_withdrawals(mut self, withdrawals: conway::Withdrawals) -> Self {
+        let mut current = self.withdrawals.unwrap_or_default();
+
+        for (account, amount) in withdrawals {
+            current.insert(account.to_vec().into(), amount);
+        }
+
+        self.withdrawals = Some(current);
+        self
+    }
+
+    pub fn remove_withdrawal(mut self, reward_account: Vec<u8>) -> Self {
+        let mut withdrawals = self.withdrawals.unwrap_or_default();
+
+        withdrawals.remove(&reward_account.into());
+        self.withdrawals = Some(withdrawals);
+        self
+    }
+
+    pub fn add_metadata(mut self, label: u64, metadatum: Vec<u8>) -> Self {
+        let mut metadata = self.metadata.unwrap_or_default();
+
+        metadata.insert(label, metadatum.into());
+        self.metadata = Some(metadata);
+        self
+    }
+
+    pub fn remove_metadata(mut self, label: u64) -> Self {
+        let mut metadata = self.metadata.unwrap_or_default();
+
+        metadata.remove(&label);
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Sets the cost model of a Plutus language used by the transaction,
+    /// required to compute the script data hash. Every language used by the
+    /// transaction scripts needs its own view.
     pub fn language_view(mut self, plutus_version: ScriptKind, cost_model: Vec<i64>) -> Self {
-        self.language_view = match plutus_version {
-            ScriptKind::PlutusV1 => Some(scriptdata::LanguageView(0, cost_model)),
-            ScriptKind::PlutusV2 => Some(scriptdata::LanguageView(1, cost_model)),
-            ScriptKind::PlutusV3 => Some(scriptdata::LanguageView(2, cost_model)),
-            ScriptKind::Native => None,
+        let view = match plutus_version {
+            ScriptKind::PlutusV1 => scriptdata::LanguageView(0, cost_model),
+            ScriptKind::PlutusV2 => scriptdata::LanguageView(1, cost_model),
+            ScriptKind::PlutusV3 => scriptdata::LanguageView(2, cost_model),
+            ScriptKind::Native => return self,
         };
 
+        let mut views = self.language_views.unwrap_or_default();
+        views.set(view);
+        self.language_views = Some(views);
+        self
+    }
+
+    pub fn remove_language_view(mut self, plutus_version: ScriptKind) -> Self {
+        let mut views = self.language_views.unwrap_or_default();
+
+        match plutus_version {
+            ScriptKind::PlutusV1 => views.remove(0),
+            ScriptKind::PlutusV2 => views.remove(1),
+            ScriptKind::PlutusV3 => views.remove(2),
+            ScriptKind::Native => (),
+        };
+
+        self.language_views = Some(views);
         self
     }
 
@@ -372,6 +531,42 @@ impl StagingTransaction {
         self.change_address = None;
         self
     }
+
+    /// Shuffles the outputs when building instead of keeping the order they
+    /// were added in. Outputs paying to the change address count as change.
+    pub fn output_ordering(mut self, ordering: OutputOrdering) -> Self {
+        self.output_ordering = Some(ordering);
+        self
+    }
+
+    pub fn clear_output_ordering(mut self) -> Self {
+        self.output_ordering = None;
+        self
+    }
+
+    /// Encodes the body keys in the given order instead of ascending, e.g.
+    /// `BodyKeyOrder::Conway` to match the bodies built by the node
+    pub fn body_key_order(mut self, order: BodyKeyOrder) -> Self {
+        self.body_key_order = Some(order);
+        self
+    }
+
+    pub fn clear_body_key_order(mut self) -> Self {
+        self.body_key_order = None;
+        self
+    }
+
+    /// Encodes the redeemers in the given shape instead of the legacy list,
+    /// e.g. `RedeemersEncoding::Map` as emitted by recent nodes
+    pub fn redeemers_encoding(mut self, encoding: RedeemersEncoding) -> Self {
+        self.redeemers_encoding = Some(encoding);
+        self
+    }
+
+    pub fn clear_redeemers_encoding(mut self) -> Self {
+        self.redeemers_encoding = None;
+        self
+    }
 }
 
 // TODO: Don't want our wrapper types in fields public
@@ -612,6 +807,28 @@ pub struct BuiltTransaction {
     pub signatures: Option<HashMap<PublicKey, Signature>>,
 }
 
+/// Rewrites the witness set of a conway transaction, keeping the original
+/// bytes of the body (and so its hash and key order) and auxiliary data
+fn update_witness_set(
+    tx_bytes: &[u8],
+    f: impl FnOnce(&mut conway::WitnessSet),
+) -> Result<Vec<u8>, TxBuilderError> {
+    let tx = conway::MintedTx::decode_fragment(tx_bytes)
+        .map_err(|_| TxBuilderError::CorruptedTxBytes)?;
+
+    let mut witness_set: conway::WitnessSet = tx.transaction_witness_set.unwrap().into();
+    f(&mut witness_set);
+
+    let tx = conway::PseudoTx {
+        transaction_body: tx.transaction_body,
+        transaction_witness_set: witness_set,
+        success: tx.success,
+        auxiliary_data: tx.auxiliary_data,
+    };
+
+    Ok(tx.encode_fragment().unwrap())
+}
+
 impl BuiltTransaction {
     pub fn sign(mut self, private_key: PrivateKey) -> Result<Self, TxBuilderError> {
         let pubkey: [u8; 32] = private_key
@@ -634,25 +851,96 @@ impl BuiltTransaction {
 
                 self.signatures = Some(new_sigs);
 
-                // TODO: chance for serialisation round trip issues?
-                let mut tx = conway::Tx::decode_fragment(&self.tx_bytes.0)
-                    .map_err(|_| TxBuilderError::CorruptedTxBytes)?;
+                self.tx_bytes = update_witness_set(&self.tx_bytes.0, |witness_set| {
+                    let mut vkey_witnesses = witness_set
+                        .vkeywitness
+                        .take()
+                        .map(|x| x.to_vec())
+                        .unwrap_or_default();
+
+                    vkey_witnesses.push(babbage::VKeyWitness {
+                        vkey: Vec::from(pubkey.as_ref()).into(),
+                        signature: Vec::from(signature.as_ref()).into(),
+                    });
+
+                    witness_set.vkeywitness = Some(NonEmptySet::from_vec(vkey_witnesses).unwrap());
+                })?
+                .into();
+            }
+            _ => return Err(TxBuilderError::UnsupportedEra),
+        }
 
-                let mut vkey_witnesses = tx
-                    .transaction_witness_set
-                    .vkeywitness
-                    .map(|x| x.to_vec())
-                    .unwrap_or_default();
+        Ok(self)
+    }
 
-                vkey_witnesses.push(babbage::VKeyWitness {
-                    vkey: Vec::from(pubkey.as_ref()).into(),
-                    signature: Vec::from(signature.as_ref()).into(),
-                });
+    /// Sign the transaction with the HD key that owns the given legacy Byron
+    /// address, adding a bootstrap witness to the witness set.
+    pub fn sign_bootstrap(
+        self,
+        private_key: Bip32PrivateKey,
+        address: &ByronAddress,
+    ) -> Result<Self, TxBuilderError> {
+        let xpub = private_key.to_public().to_ed25519_extended_pubkey();
 
-                tx.transaction_witness_set.vkeywitness =
-                    Some(NonEmptySet::from_vec(vkey_witnesses).unwrap());
+        let signature: [u8; ed25519::Signature::SIZE] = private_key
+            .to_ed25519_private_key()
+            .sign(self.tx_hash.0)
+            .as_ref()
+            .try_into()
+            .unwrap();
 
-                self.tx_bytes = tx.encode_fragment().unwrap().into();
+        self.add_bootstrap_signature(xpub, signature, address)
+    }
+
+    /// Add a bootstrap witness for the given legacy Byron address, using a
+    /// signature produced externally by the owner of the extended key.
+    pub fn add_bootstrap_signature(
+        mut self,
+        xpub: ed25519::PublicKeyExtended,
+        signature: [u8; 64],
+        address: &ByronAddress,
+    ) -> Result<Self, TxBuilderError> {
+        let payload = address
+            .decode()
+            .map_err(|_| TxBuilderError::MalformedAddress)?;
+
+        if !payload.is_derived_from(&xpub) {
+            return Err(TxBuilderError::BootstrapKeyMismatch);
+        }
+
+        match self.era {
+            BuilderEra::Conway => {
+                let mut new_sigs = self.signatures.unwrap_or_default();
+
+                new_sigs.insert(
+                    Bytes32(
+                        xpub.public_key()
+                            .as_ref()
+                            .try_into()
+                            .map_err(|_| TxBuilderError::MalformedKey)?,
+                    ),
+                    Bytes64(signature),
+                );
+
+                self.signatures = Some(new_sigs);
+
+                self.tx_bytes = update_witness_set(&self.tx_bytes.0, |witness_set| {
+                    let mut bootstrap_witnesses = witness_set
+                        .bootstrap_witness
+                        .take()
+                        .map(|x| x.to_vec())
+                        .unwrap_or_default();
+
+                    bootstrap_witnesses.push(babbage::BootstrapWitness {
+                        public_key: Vec::from(xpub.public_key().as_ref()).into(),
+                        signature: Vec::from(signature.as_ref()).into(),
+                        chain_code: Vec::from(xpub.chain_code().as_ref()).into(),
+                        attributes: payload.attributes_cbor().into(),
+                    });
+
+                    witness_set.bootstrap_witness = NonEmptySet::from_vec(bootstrap_witnesses);
+                })?
+                .into();
             }
             _ => return Err(TxBuilderError::UnsupportedEra),
         }
@@ -660,11 +948,16 @@ impl BuiltTransaction {
         Ok(self)
     }
 
+    /// Add a witness for the given public key. Extended public keys
+    /// ([`ed25519::PublicKeyExtended`]) are accepted too, in which case only
+    /// the public key part is used for the witness.
     pub fn add_signature(
         mut self,
-        pub_key: ed25519::PublicKey,
+        pub_key: impl Into<ed25519::PublicKey>,
         signature: [u8; 64],
     ) -> Result<Self, TxBuilderError> {
+        let pub_key: ed25519::PublicKey = pub_key.into();
+
         match self.era {
             BuilderEra::Conway => {
                 let mut new_sigs = self.signatures.unwrap_or_default();
@@ -681,20 +974,21 @@ impl BuiltTransaction {
 
                 self.signatures = Some(new_sigs);
 
-                // TODO: chance for serialisation round trip issues?
-                let mut tx = babbage::Tx::decode_fragment(&self.tx_bytes.0)
-                    .map_err(|_| TxBuilderError::CorruptedTxBytes)?;
-
-                let mut vkey_witnesses = tx.transaction_witness_set.vkeywitness.unwrap_or_default();
-
-                vkey_witnesses.push(babbage::VKeyWitness {
-                    vkey: Vec::from(pub_key.as_ref()).into(),
-                    signature: Vec::from(signature.as_ref()).into(),
-                });
-
-                tx.transaction_witness_set.vkeywitness = Some(vkey_witnesses);
-
-                self.tx_bytes = tx.encode_fragment().unwrap().into();
+                self.tx_bytes = update_witness_set(&self.tx_bytes.0, |witness_set| {
+                    let mut vkey_witnesses = witness_set
+                        .vkeywitness
+                        .take()
+                        .map(|x| x.to_vec())
+                        .unwrap_or_default();
+
+                    vkey_witnesses.push(babbage::VKeyWitness {
+                        vkey: Vec::from(pub_key.as_ref()).into(),
+                        signature: Vec::from(signature.as_ref()).into(),
+                    });
+
+                    witness_set.vkeywitness = NonEmptySet::from_vec(vkey_witnesses);
+                })?
+                .into();
             }
             _ => return Err(TxBuilderError::UnsupportedEra),
         }
@@ -702,7 +996,13 @@ impl BuiltTransaction {
         Ok(self)
     }
 
-    pub fn remove_signature(mut self, pub_key: ed25519::PublicKey) -> Result<Self, TxBuilderError> {
+    /// Remove the vkey and bootstrap witnesses made with the given public key
+    pub fn remove_signature(
+        mut self,
+        pub_key: impl Into<ed25519::PublicKey>,
+    ) -> Result<Self, TxBuilderError> {
+        let pub_key: ed25519::PublicKey = pub_key.into();
+
         match self.era {
             BuilderEra::Conway => {
                 let mut new_sigs = self.signatures.unwrap_or_default();
@@ -718,17 +1018,28 @@ impl BuiltTransaction {
 
                 self.signatures = Some(new_sigs);
 
-                // TODO: chance for serialisation round trip issues?
-                let mut tx = babbage::Tx::decode_fragment(&self.tx_bytes.0)
-                    .map_err(|_| TxBuilderError::CorruptedTxBytes)?;
+                self.tx_bytes = update_witness_set(&self.tx_bytes.0, |witness_set| {
+                    let mut vkey_witnesses = witness_set
+                        .vkeywitness
+                        .take()
+                        .map(|x| x.to_vec())
+                        .unwrap_or_default();
+
+                    vkey_witnesses.retain(|x| *x.vkey != pk.0.to_vec());
 
-                let mut vkey_witnesses = tx.transaction_witness_set.vkeywitness.unwrap_or_default();
+                    witness_set.vkeywitness = NonEmptySet::from_vec(vkey_witnesses);
 
-                vkey_witnesses.retain(|x| *x.vkey != pk.0.to_vec());
+                    let mut bootstrap_witnesses = witness_set
+                        .bootstrap_witness
+                        .take()
+                        .map(|x| x.to_vec())
+                        .unwrap_or_default();
 
-                tx.transaction_witness_set.vkeywitness = Some(vkey_witnesses);
+                    bootstrap_witnesses.retain(|x| *x.public_key != pk.0.to_vec());
 
-                self.tx_bytes = tx.encode_fragment().unwrap().into();
+                    witness_set.bootstrap_witness = NonEmptySet::from_vec(bootstrap_witnesses);
+                })?
+                .into();
             }
             _ => return Err(TxBuilderError::UnsupportedEra),
         }
@@ -479,7 +479,15 @@ mod tests {
             signature_amount_override: Some(5),
             change_address: Some(Address(PallasAddress::from_str("addr1g9ekml92qyvzrjmawxkh64r2w5xr6mg9ngfmxh2khsmdrcudevsft64mf887333adamant").unwrap())),
             script_data_hash: Some(Bytes32([0; 32])),
-            language_view: Some(crate::scriptdata::LanguageView(1, vec![1, 2, 3])),
+            language_views: Some(crate::scriptdata::LanguageViews(vec![crate::scriptdata::LanguageView(1, vec![1, 2, 3]), crate::scriptdata::LanguageView(2, vec![4, 5])])),
+            metadata: Some(vec![(674, Bytes(vec![0xa0]))].into_iter().collect::<HashMap<_, _>>()),
+            certificates: Some(vec![Bytes(vec![0x82, 0x00, 0x82, 0x00, 0x41, 0x00])]),
+            proposals: Some(vec![Bytes(vec![0x84, 0x00, 0x40, 0x81, 0x06, 0x82, 0x60, 0x40])]),
+            votes: Some(vec![Bytes(vec![0x83, 0x82, 0x04, 0x41, 0x00, 0x82, 0x40, 0x00, 0x82, 0x01, 0xf6])]),
+            output_ordering: Some(crate::OutputOrdering::seeded(7).change_weight(2)),
+            body_key_order: Some(pallas_primitives::BodyKeyOrder::Conway),
+            withdrawals: Some(vec![(Bytes(vec![0xe1; 29]), 1337)].into_iter().collect::<HashMap<_, _>>()),
+            redeemers_encoding: Some(pallas_primitives::conway::RedeemersEncoding::Map),
         };
 
         let serialised_tx = serde_json::to_string(&tx).unwrap();
@@ -490,6 +498,23 @@ mod tests {
         assert_eq!(tx, deserialised_tx)
     }
 
+    #[test]
+    fn staging_json_with_a_single_language_view() {
+        // staging txs serialised before multiple language views were supported
+        let json = serde_json::to_string(&StagingTransaction::new())
+            .unwrap()
+            .replace(r#""language_views":null"#, r#""language_view":[1,[1,2,3]]"#);
+
+        assert!(json.contains("language_view\""));
+
+        let tx: StagingTransaction = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            tx.language_views,
+            Some(crate::scriptdata::LanguageView(1, vec![1, 2, 3]).into())
+        );
+    }
+
     #[test]
     fn built_json_roundtrip() {
         let tx = BuiltTransaction {
@@ -0,0 +1,389 @@
+//! Minimal support for flat-encoded UPLC programs, enough to apply data
+//! arguments to a compiled validator without decoding it into terms.
+
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{Fragment, PlutusData, PlutusScript};
+use pallas_traverse::ComputeHash;
+
+use crate::TxBuilderError;
+
+const TERM_TAG_BITS: usize = 4;
+const TYPE_TAG_BITS: usize = 4;
+const BUILTIN_TAG_BITS: usize = 7;
+
+const APPLY_TAG: u64 = 3;
+const CONSTANT_TAG: u64 = 4;
+const DATA_TYPE_TAG: u64 = 8;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    // constr and case terms, only available from version 1.1.0
+    sop: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bit(&mut self) -> Result<bool, TxBuilderError> {
+        let byte = self
+            .bytes
+            .get(self.pos / 8)
+            .ok_or(TxBuilderError::MalformedScript)?;
+
+        let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+
+        Ok(bit)
+    }
+
+    fn bits(&mut self, n: usize) -> Result<u64, TxBuilderError> {
+        let mut x = 0;
+
+        for _ in 0..n {
+            x = (x << 1) | self.bit()? as u64;
+        }
+
+        Ok(x)
+    }
+
+    fn skip_natural(&mut self) -> Result<(), TxBuilderError> {
+        while self.bits(8)? & 0x80 != 0 {}
+        Ok(())
+    }
+
+    fn natural(&mut self) -> Result<u64, TxBuilderError> {
+        let mut x = 0;
+
+        for shift in (0..64).step_by(7) {
+            let chunk = self.bits(8)?;
+            x |= (chunk & 0x7f) << shift;
+
+            if chunk & 0x80 == 0 {
+                return Ok(x);
+            }
+        }
+
+        Err(TxBuilderError::MalformedScript)
+    }
+
+    fn filler(&mut self) -> Result<(), TxBuilderError> {
+        while !self.bit()? {}
+        Ok(())
+    }
+
+    fn skip_bytestring(&mut self) -> Result<(), TxBuilderError> {
+        self.filler()?;
+
+        loop {
+            let len = self.bits(8)? as usize;
+
+            if len == 0 {
+                return Ok(());
+            }
+
+            self.pos += len * 8;
+        }
+    }
+
+    // list of items, each one preceded by a 1 bit and closed by a 0 bit
+    fn list<F>(&mut self, mut item: F) -> Result<(), TxBuilderError>
+    where
+        F: FnMut(&mut Self) -> Result<(), TxBuilderError>,
+    {
+        while self.bit()? {
+            item(self)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConstantType {
+    Integer,
+    ByteString,
+    String,
+    Unit,
+    Bool,
+    Data,
+    List(Box<ConstantType>),
+    Pair(Box<ConstantType>, Box<ConstantType>),
+}
+
+fn parse_type(tags: &mut impl Iterator<Item = u64>) -> Result<ConstantType, TxBuilderError> {
+    let ty = match tags.next().ok_or(TxBuilderError::MalformedScript)? {
+        0 => ConstantType::Integer,
+        1 => ConstantType::ByteString,
+        2 => ConstantType::String,
+        3 => ConstantType::Unit,
+        4 => ConstantType::Bool,
+        8 => ConstantType::Data,
+        // type application, either `list a` or `pair a b`
+        7 => match tags.next() {
+            Some(5) => ConstantType::List(Box::new(parse_type(tags)?)),
+            Some(7) => match tags.next() {
+                Some(6) => {
+                    ConstantType::Pair(Box::new(parse_type(tags)?), Box::new(parse_type(tags)?))
+                }
+                _ => return Err(TxBuilderError::MalformedScript),
+            },
+            _ => return Err(TxBuilderError::MalformedScript),
+        },
+        _ => return Err(TxBuilderError::MalformedScript),
+    };
+
+    Ok(ty)
+}
+
+fn skip_constant(r: &mut Reader, ty: &ConstantType) -> Result<(), TxBuilderError> {
+    match ty {
+        ConstantType::Integer => r.skip_natural(),
+        ConstantType::ByteString | ConstantType::String | ConstantType::Data => r.skip_bytestring(),
+        ConstantType::Unit => Ok(()),
+        ConstantType::Bool => r.bit().map(|_| ()),
+        ConstantType::List(x) => r.list(|r| skip_constant(r, x)),
+        ConstantType::Pair(a, b) => {
+            skip_constant(r, a)?;
+            skip_constant(r, b)
+        }
+    }
+}
+
+fn skip_term(r: &mut Reader) -> Result<(), TxBuilderError> {
+    match r.bits(TERM_TAG_BITS)? {
+        // var
+        0 => r.skip_natural(),
+        // delay, lambda, force
+        1 | 2 | 5 => skip_term(r),
+        // apply
+        3 => {
+            skip_term(r)?;
+            skip_term(r)
+        }
+        // constant
+        4 => {
+            let mut tags = vec![];
+            r.list(|r| {
+                tags.push(r.bits(TYPE_TAG_BITS)?);
+                Ok(())
+            })?;
+
+            let mut tags = tags.into_iter();
+            let ty = parse_type(&mut tags)?;
+
+            if tags.next().is_some() {
+                return Err(TxBuilderError::MalformedScript);
+            }
+
+            skip_constant(r, &ty)
+        }
+        // error
+        6 => Ok(()),
+        // builtin
+        7 => r.bits(BUILTIN_TAG_BITS).map(|_| ()),
+        // constr
+        8 if r.sop => {
+            r.skip_natural()?;
+            r.list(skip_term)
+        }
+        // case
+        9 if r.sop => {
+            skip_term(r)?;
+            r.list(skip_term)
+        }
+        _ => Err(TxBuilderError::MalformedScript),
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl Writer {
+    fn bit(&mut self, bit: bool) {
+        if self.pos.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> (self.pos % 8);
+        }
+
+        self.pos += 1;
+    }
+
+    fn bits(&mut self, n: usize, x: u64) {
+        for i in (0..n).rev() {
+            self.bit(x >> i & 1 == 1);
+        }
+    }
+
+    fn copy(&mut self, from: &Reader, start: usize) {
+        for pos in start..from.pos {
+            self.bit(from.bytes[pos / 8] & (0x80 >> (pos % 8)) != 0);
+        }
+    }
+
+    fn filler(&mut self) {
+        while self.pos % 8 != 7 {
+            self.bit(false);
+        }
+
+        self.bit(true);
+    }
+
+    fn bytestring(&mut self, bytes: &[u8]) {
+        self.filler();
+
+        for chunk in bytes.chunks(255) {
+            self.bytes.push(chunk.len() as u8);
+            self.bytes.extend_from_slice(chunk);
+            self.pos += (chunk.len() + 1) * 8;
+        }
+
+        self.bytes.push(0);
+        self.pos += 8;
+    }
+}
+
+/// Version of a flat-encoded program, as (major, minor, patch)
+pub fn program_version(flat: &[u8]) -> Result<(u64, u64, u64), TxBuilderError> {
+    let mut r = Reader {
+        bytes: flat,
+        pos: 0,
+        sop: false,
+    };
+
+    Ok((r.natural()?, r.natural()?, r.natural()?))
+}
+
+/// Applies data arguments to the term of a flat-encoded program, in order
+pub fn apply_data(flat: &[u8], args: &[PlutusData]) -> Result<Vec<u8>, TxBuilderError> {
+    let sop = match program_version(flat)? {
+        (1, 0, 0) => false,
+        (1, 1, 0) => true,
+        _ => return Err(TxBuilderError::MalformedScript),
+    };
+
+    let mut r = Reader {
+        bytes: flat,
+        pos: 0,
+        sop,
+    };
+
+    for _ in 0..3 {
+        r.skip_natural()?;
+    }
+
+    let mut w = Writer::default();
+    w.copy(&r, 0);
+
+    for _ in args {
+        w.bits(TERM_TAG_BITS, APPLY_TAG);
+    }
+
+    let start = r.pos;
+    skip_term(&mut r)?;
+    w.copy(&r, start);
+
+    // the program must end with its padding
+    r.filler()?;
+    if r.pos != flat.len() * 8 {
+        return Err(TxBuilderError::MalformedScript);
+    }
+
+    for arg in args {
+        w.bits(TERM_TAG_BITS, CONSTANT_TAG);
+        w.bit(true);
+        w.bits(TYPE_TAG_BITS, DATA_TYPE_TAG);
+        w.bit(false);
+
+        let cbor = arg
+            .encode_fragment()
+            .map_err(|_| TxBuilderError::MalformedDatum)?;
+
+        w.bytestring(&cbor);
+    }
+
+    w.filler();
+
+    Ok(w.bytes)
+}
+
+/// Specializes a compiled script by applying data arguments to it, returning
+/// the resulting script and its hash
+pub fn apply_params_to_script<const VERSION: usize>(
+    script: &PlutusScript<VERSION>,
+    params: &[PlutusData],
+) -> Result<(PlutusScript<VERSION>, Hash<28>), TxBuilderError> {
+    let flat = script
+        .unwrap_inner()
+        .ok_or(TxBuilderError::MalformedScript)?;
+
+    let applied = PlutusScript::from_any_wrapping(&apply_data(flat, params)?);
+    let hash = applied.compute_hash();
+
+    Ok((applied, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (program 1.1.0 (lam x x))
+    const IDENTITY: &str = "010100200101";
+
+    #[test]
+    fn apply_data_to_program() {
+        let flat = hex::decode(IDENTITY).unwrap();
+
+        let arg = PlutusData::decode_fragment(&[0x18, 0x2a]).unwrap();
+        let applied = apply_data(&flat, &[arg]).unwrap();
+
+        // (program 1.1.0 [(lam x x) (con data (I 42))])
+        assert_eq!(hex::encode(&applied), "010100320014c102182a0001");
+
+        // the applied program is a well-formed program itself
+        assert!(apply_data(&applied, &[]).is_ok());
+        assert_eq!(apply_data(&applied, &[]).unwrap(), applied);
+    }
+
+    #[test]
+    fn malformed_programs() {
+        let flat = hex::decode(IDENTITY).unwrap();
+
+        assert!(apply_data(&flat[..4], &[]).is_err());
+        assert!(apply_data(&[flat.clone(), vec![0]].concat(), &[]).is_err());
+    }
+
+    #[test]
+    fn skips_constants_and_builtins() {
+        // (program 1.0.0 (lam x [(force (builtin ifThenElse)) (con bool True)
+        //   (con (list integer) [1, -1]) (con (pair bytestring unit) (#ab, ()))]))
+        let flat = hex::decode("010000233357349452f582050125ef68cd01ab0001").unwrap();
+
+        assert_eq!(program_version(&flat).unwrap(), (1, 0, 0));
+        assert_eq!(apply_data(&flat, &[]).unwrap(), flat);
+    }
+
+    #[test]
+    fn constr_requires_version_1_1_0() {
+        // (program 1.x.0 (constr 0))
+        assert!(apply_data(&hex::decode("0101008001").unwrap(), &[]).is_ok());
+        assert!(apply_data(&hex::decode("0100008001").unwrap(), &[]).is_err());
+        assert!(apply_data(&hex::decode("0200008001").unwrap(), &[]).is_err());
+    }
+
+    #[test]
+    fn specialized_script_and_hash() {
+        let script = PlutusScript::<3>::from_hex_envelope(IDENTITY).unwrap();
+        let arg = PlutusData::decode_fragment(&[0x18, 0x2a]).unwrap();
+
+        let (applied, hash) = apply_params_to_script(&script, &[arg]).unwrap();
+
+        assert_eq!(hex::encode(applied.as_ref()), "4c010100320014c102182a0001");
+        assert_eq!(hash, applied.compute_hash());
+        assert_ne!(hash, script.compute_hash());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use pallas_addresses::{Address as PallasAddress, Network};
+use pallas_primitives::{conway, NonEmptyKeyValuePairs, StakeCredential};
+
+use crate::{stake_credential, TxBuilderError};
+
+/// Encodes the reward account of a stake credential: a header byte holding
+/// the credential kind and the network tag, followed by the credential hash
+pub fn reward_account(
+    network: Network,
+    credential: &StakeCredential,
+) -> Result<Vec<u8>, TxBuilderError> {
+    let network = network.value();
+
+    if network > 0b1111 {
+        return Err(TxBuilderError::InvalidNetworkId);
+    }
+
+    let (kind, hash) = match credential {
+        StakeCredential::AddrKeyhash(x) => (0b1110, x),
+        StakeCredential::ScriptHash(x) => (0b1111, x),
+    };
+
+    Ok([&[kind << 4 | network], hash.as_ref()].concat())
+}
+
+/// Source of the reward balances of stake credentials, such as a node
+/// queried through LocalStateQuery
+///
+/// Balances are returned in the same order as the credentials, with `None`
+/// for the ones that aren't registered.
+pub trait RewardsResolver {
+    fn rewards(&self, credentials: &[StakeCredential]) -> Vec<Option<u64>>;
+}
+
+impl<F> RewardsResolver for F
+where
+    F: Fn(&[StakeCredential]) -> Vec<Option<u64>>,
+{
+    fn rewards(&self, credentials: &[StakeCredential]) -> Vec<Option<u64>> {
+        self(credentials)
+    }
+}
+
+impl RewardsResolver for HashMap<StakeCredential, u64> {
+    fn rewards(&self, credentials: &[StakeCredential]) -> Vec<Option<u64>> {
+        credentials.iter().map(|x| self.get(x).copied()).collect()
+    }
+}
+
+/// Builder for the withdrawals of a transaction, all from reward accounts of
+/// the same network
+#[derive(Debug, Clone)]
+pub struct Withdrawals {
+    network: Network,
+    entries: Vec<(StakeCredential, u64)>,
+}
+
+impl Withdrawals {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            entries: vec![],
+        }
+    }
+
+    pub fn withdraw(mut self, credential: StakeCredential, amount: u64) -> Self {
+        self.entries.push((credential, amount));
+        self
+    }
+
+    /// Withdraws from a stake address, which must belong to the network of
+    /// the builder
+    pub fn withdraw_from(
+        self,
+        stake_address: &PallasAddress,
+        amount: u64,
+    ) -> Result<Self, TxBuilderError> {
+        if stake_address.network() != Some(self.network) {
+            return Err(TxBuilderError::NetworkMismatch);
+        }
+
+        Ok(self.withdraw(stake_credential(stake_address)?, amount))
+    }
+
+    /// Builds the withdrawals map, sorted by reward account
+    pub fn build(self) -> Result<conway::Withdrawals, TxBuilderError> {
+        let mut withdrawals = self
+            .entries
+            .iter()
+            .map(|(credential, amount)| Ok((reward_account(self.network, credential)?, *amount)))
+            .collect::<Result<Vec<_>, TxBuilderError>>()?;
+
+        withdrawals.sort_unstable();
+
+        if withdrawals.windows(2).any(|x| x[0].0 == x[1].0) {
+            return Err(TxBuilderError::DuplicateWithdrawal);
+        }
+
+        let withdrawals = withdrawals
+            .into_iter()
+            .map(|(account, amount)| (account.into(), amount))
+            .collect();
+
+        NonEmptyKeyValuePairs::from_vec(withdrawals).ok_or(TxBuilderError::NoWithdrawals)
+    }
+
+    /// Builds the withdrawals map after checking that every amount is the
+    /// whole reward balance of its account, as the ledger requires
+    pub fn build_checked(
+        self,
+        resolver: &impl RewardsResolver,
+    ) -> Result<conway::Withdrawals, TxBuilderError> {
+        let credentials: Vec<_> = self.entries.iter().map(|(x, _)| x.clone()).collect();
+        let balances = resolver.rewards(&credentials);
+
+        for (index, (credential, amount)) in self.entries.iter().enumerate() {
+            let account = reward_account(self.network, credential)?;
+
+            match balances.get(index).copied().flatten() {
+                Some(balance) if balance == *amount => (),
+                Some(balance) => {
+                    return Err(TxBuilderError::WithdrawalAmountMismatch(*amount, balance))
+                }
+                None => {
+                    return Err(TxBuilderError::UnregisteredRewardAccount(hex::encode(
+                        account,
+                    )))
+                }
+            }
+        }
+
+        self.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use pallas_crypto::hash::Hash;
+    use pallas_primitives::Fragment;
+
+    use super::*;
+    use crate::{BuildConway, Input, Output, StagingTransaction};
+
+    fn stake_address() -> PallasAddress {
+        PallasAddress::from_str("stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw")
+            .unwrap()
+    }
+
+    #[test]
+    fn reward_account_layout() {
+        let credential = stake_credential(&stake_address()).unwrap();
+
+        assert_eq!(
+            reward_account(Network::Mainnet, &credential).unwrap(),
+            stake_address().to_vec()
+        );
+
+        let script = StakeCredential::ScriptHash(Hash::new([1; 28]));
+        let account = reward_account(Network::Testnet, &script).unwrap();
+
+        assert_eq!(account[0], 0xf0);
+        assert_eq!(&account[1..], &[1; 28]);
+
+        assert_eq!(
+            reward_account(Network::Other(16), &script),
+            Err(TxBuilderError::InvalidNetworkId)
+        );
+    }
+
+    #[test]
+    fn withdrawals_build_and_check() {
+        let credential = stake_credential(&stake_address()).unwrap();
+        let script = StakeCredential::ScriptHash(Hash::new([1; 28]));
+
+        let withdrawals = || {
+            Withdrawals::new(Network::Mainnet)
+                .withdraw(script.clone(), 10)
+                .withdraw_from(&stake_address(), 5_000_000)
+                .unwrap()
+        };
+
+        let built = withdrawals().build().unwrap().to_vec();
+
+        assert_eq!(built.len(), 2);
+        assert_eq!(built[0].0.to_vec(), stake_address().to_vec());
+        assert_eq!(built[1].1, 10);
+
+        assert_eq!(
+            Withdrawals::new(Network::Testnet)
+                .withdraw_from(&stake_address(), 1)
+                .unwrap_err(),
+            TxBuilderError::NetworkMismatch
+        );
+
+        assert_eq!(
+            withdrawals().withdraw(script.clone(), 20).build(),
+            Err(TxBuilderError::DuplicateWithdrawal)
+        );
+
+        assert_eq!(
+            Withdrawals::new(Network::Mainnet).build(),
+            Err(TxBuilderError::NoWithdrawals)
+        );
+
+        let mut rewards = HashMap::from([(credential, 5_000_000), (script.clone(), 10)]);
+        assert_eq!(withdrawals().build_checked(&rewards), withdrawals().build());
+
+        rewards.insert(script.clone(), 11);
+        assert_eq!(
+            withdrawals().build_checked(&rewards),
+            Err(TxBuilderError::WithdrawalAmountMismatch(10, 11))
+        );
+
+        let unregistered = |x: &[StakeCredential]| vec![None; x.len()];
+        assert!(matches!(
+            withdrawals().build_checked(&unregistered),
+            Err(TxBuilderError::UnregisteredRewardAccount(_))
+        ));
+
+        let address = PallasAddress::from_str(
+            "addr1g9ekml92qyvzrjmawxkh64r2w5xr6mg9ngfmxh2khsmdrcudevsft64mf887333adamant",
+        )
+        .unwrap();
+
+        let built = StagingTransaction::new()
+            .input(Input::new(Hash::new([0; 32]), 0))
+            .output(Output::new(address, 1_000_000))
+            .fee(200_000)
+            .add_withdrawals(withdrawals().build().unwrap())
+            .build_conway_raw()
+            .unwrap();
+
+        let tx = conway::Tx::decode_fragment(&built.tx_bytes.0).unwrap();
+
+        assert_eq!(
+            tx.transaction_body.withdrawals,
+            Some(withdrawals().build().unwrap())
+        );
+    }
+}
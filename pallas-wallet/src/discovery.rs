@@ -0,0 +1,184 @@
+use pallas_crypto::hash::{Hash, Hasher};
+
+use crate::{hd::Bip32PublicKey, Error};
+
+/// Number of consecutive unused addresses after which the scan of a chain
+/// stops, as recommended by BIP-44
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Chain (role) of the addresses derived from an account key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Addresses handed out to receive funds
+    External = 0,
+    /// Change addresses
+    Internal = 1,
+}
+
+/// A payment key derived from an account key
+#[derive(Debug, PartialEq, Eq)]
+pub struct DerivedKey {
+    pub role: Role,
+    pub index: u32,
+    pub key: Bip32PublicKey,
+}
+
+impl DerivedKey {
+    /// Hash of the public key, as used in the payment part of addresses
+    pub fn key_hash(&self) -> Hash<28> {
+        Hasher::<224>::hash(self.key.to_ed25519_pubkey().as_ref())
+    }
+}
+
+/// Source of truth used to tell whether a derived key has been used on-chain
+/// (e.g. an indexer or a local UTxO set)
+pub trait UsageOracle {
+    type Error;
+
+    fn is_used(&mut self, key: &DerivedKey) -> Result<bool, Self::Error>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScanError<E> {
+    #[error("key derivation failed: {0}")]
+    Derivation(Error),
+    #[error("usage oracle failed")]
+    Oracle(E),
+}
+
+/// Discovers the used keys of an account by walking its external and
+/// internal chains until `gap_limit` consecutive unused keys are found
+pub struct AccountScanner {
+    account: Bip32PublicKey,
+    gap_limit: u32,
+}
+
+impl AccountScanner {
+    pub fn new(account: Bip32PublicKey) -> Self {
+        Self {
+            account,
+            gap_limit: DEFAULT_GAP_LIMIT,
+        }
+    }
+
+    pub fn gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
+
+    /// Returns the used keys of both chains, ordered by role and index
+    pub fn scan<O: UsageOracle>(
+        &self,
+        oracle: &mut O,
+    ) -> Result<Vec<DerivedKey>, ScanError<O::Error>> {
+        let mut used = self.scan_role(Role::External, oracle)?;
+        used.extend(self.scan_role(Role::Internal, oracle)?);
+
+        Ok(used)
+    }
+
+    pub fn scan_role<O: UsageOracle>(
+        &self,
+        role: Role,
+        oracle: &mut O,
+    ) -> Result<Vec<DerivedKey>, ScanError<O::Error>> {
+        let chain = self
+            .account
+            .derive(role as u32)
+            .map_err(ScanError::Derivation)?;
+
+        let mut used = vec![];
+        let mut gap = 0;
+        let mut index = 0;
+
+        while gap < self.gap_limit {
+            let key = DerivedKey {
+                role,
+                index,
+                key: chain.derive(index).map_err(ScanError::Derivation)?,
+            };
+
+            if oracle.is_used(&key).map_err(ScanError::Oracle)? {
+                used.push(key);
+                gap = 0;
+            } else {
+                gap += 1;
+            }
+
+            index += 1;
+        }
+
+        Ok(used)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bip39::rand_core::OsRng;
+
+    use super::*;
+    use crate::hd::Bip32PrivateKey;
+
+    struct SetOracle(HashSet<Hash<28>>, u32);
+
+    impl UsageOracle for SetOracle {
+        type Error = ();
+
+        fn is_used(&mut self, key: &DerivedKey) -> Result<bool, ()> {
+            self.1 += 1;
+            Ok(self.0.contains(&key.key_hash()))
+        }
+    }
+
+    #[test]
+    fn gap_limit_discovery() {
+        let account = Bip32PrivateKey::generate(OsRng)
+            .derive(0x80000000 | 1852)
+            .derive(0x80000000 | 1815)
+            .derive(0x80000000)
+            .to_public();
+
+        let key_hash = |role: Role, index: u32| {
+            DerivedKey {
+                role,
+                index,
+                key: account.derive(role as u32).unwrap().derive(index).unwrap(),
+            }
+            .key_hash()
+        };
+
+        // the key at index 43 is beyond the gap after index 22
+        let used = [
+            key_hash(Role::External, 0),
+            key_hash(Role::External, 3),
+            key_hash(Role::External, 22),
+            key_hash(Role::External, 43),
+            key_hash(Role::Internal, 1),
+        ];
+
+        let mut oracle = SetOracle(used.into_iter().collect(), 0);
+
+        let found = AccountScanner::new(account)
+            .scan(&mut oracle)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.role, x.index))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            found,
+            vec![
+                (Role::External, 0),
+                (Role::External, 3),
+                (Role::External, 22),
+                (Role::Internal, 1),
+            ]
+        );
+
+        // 23 external keys up to the last used one plus the gap, and 2
+        // internal keys plus the gap
+        assert_eq!(oracle.1, 23 + 20 + 2 + 20);
+    }
+}
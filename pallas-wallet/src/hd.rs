@@ -1,12 +1,33 @@
 use bech32::{FromBase32, ToBase32};
 use bip39::rand_core::{CryptoRng, RngCore};
 use bip39::{Language, Mnemonic};
-use cryptoxide::{hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+use cryptoxide::{
+    hmac::Hmac,
+    mac::Mac,
+    pbkdf2::pbkdf2,
+    sha2::{Sha256, Sha512},
+};
 use ed25519_bip32::{self, XPrv, XPub, XPRV_SIZE};
 use pallas_crypto::key::ed25519::{self, SecretKeyExtended};
 
 use crate::{Error, PrivateKey};
 
+/// Scheme used to derive the root key of a wallet from its mnemonic
+///
+/// Wallets restored with the wrong scheme produce a different root key, and
+/// therefore different (usually empty) addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MasterKeyScheme {
+    /// Used by most software wallets (Icarus, Yoroi, Daedalus Shelley, etc)
+    #[default]
+    Icarus,
+    /// Used by Ledger hardware wallets
+    Ledger,
+    /// Used by Trezor hardware wallets, which differs from Icarus for 24
+    /// word mnemonics only
+    Trezor,
+}
+
 /// Ed25519-BIP32 HD Private Key
 #[derive(Debug, PartialEq, Eq)]
 pub struct Bip32PrivateKey(ed25519_bip32::XPrv);
@@ -54,17 +75,75 @@ impl Bip32PrivateKey {
     }
 
     pub fn from_bip39_mnenomic(mnemonic: String, password: String) -> Result<Self, Error> {
+        Self::from_bip39_mnenomic_with_scheme(mnemonic, password, MasterKeyScheme::Icarus)
+    }
+
+    pub fn from_bip39_mnenomic_with_scheme(
+        mnemonic: String,
+        password: String,
+        scheme: MasterKeyScheme,
+    ) -> Result<Self, Error> {
         let bip39 = Mnemonic::parse(mnemonic).map_err(Error::Mnemonic)?;
-        let entropy = bip39.to_entropy();
 
+        match scheme {
+            MasterKeyScheme::Icarus => Ok(Self::icarus(&bip39.to_entropy(), &password)),
+            MasterKeyScheme::Trezor => {
+                let mut entropy = bip39.to_entropy();
+
+                // trezor includes the checksum in the entropy of 24 word
+                // mnemonics, where the checksum is exactly one byte long
+                if bip39.word_count() == 24 {
+                    entropy.push(bip39.checksum());
+                }
+
+                Ok(Self::icarus(&entropy, &password))
+            }
+            MasterKeyScheme::Ledger => Self::ledger(&bip39.to_seed(password)),
+        }
+    }
+
+    fn icarus(entropy: &[u8], password: &str) -> Self {
         let mut pbkdf2_result = [0; XPRV_SIZE];
 
         const ITER: u32 = 4096; // TODO: BIP39 says 2048, CML uses 4096?
 
         let mut mac = Hmac::new(Sha512::new(), password.as_bytes());
-        pbkdf2(&mut mac, &entropy, ITER, &mut pbkdf2_result);
+        pbkdf2(&mut mac, entropy, ITER, &mut pbkdf2_result);
 
-        Ok(Self(XPrv::normalize_bytes_force3rd(pbkdf2_result)))
+        Self(XPrv::normalize_bytes_force3rd(pbkdf2_result))
+    }
+
+    // SLIP-0010 style derivation from the bip39 seed, retrying until the
+    // third highest bit of the scalar is cleared
+    fn ledger(seed: &[u8]) -> Result<Self, Error> {
+        const KEY: &[u8] = b"ed25519 seed";
+
+        let mut chain_code = [0u8; 32];
+        let mut mac = Hmac::new(Sha256::new(), KEY);
+        mac.input(&[1]);
+        mac.input(seed);
+        mac.raw_result(&mut chain_code);
+
+        let mut i = [0u8; 64];
+        let mut mac = Hmac::new(Sha512::new(), KEY);
+        mac.input(seed);
+        mac.raw_result(&mut i);
+
+        while i[31] & 0b0010_0000 != 0 {
+            let mut mac = Hmac::new(Sha512::new(), KEY);
+            mac.input(&i);
+            mac.raw_result(&mut i);
+        }
+
+        i[0] &= 0b1111_1000;
+        i[31] &= 0b0111_1111;
+        i[31] |= 0b0100_0000;
+
+        let mut bytes = [0u8; XPRV_SIZE];
+        bytes[..64].copy_from_slice(&i);
+        bytes[64..].copy_from_slice(&chain_code);
+
+        Self::from_bytes(bytes)
     }
 
     pub fn derive(&self, index: u32) -> Self {
@@ -133,6 +212,10 @@ impl Bip32PublicKey {
         self.0.public_key().into()
     }
 
+    pub fn to_ed25519_extended_pubkey(&self) -> ed25519::PublicKeyExtended {
+        ed25519::PublicKeyExtended::new(self.to_ed25519_pubkey(), self.chain_code())
+    }
+
     pub fn chain_code(&self) -> [u8; 32] {
         *self.0.chain_code()
     }
@@ -163,7 +246,7 @@ impl Bip32PublicKey {
 mod test {
     use bip39::rand_core::OsRng;
 
-    use super::{Bip32PrivateKey, Bip32PublicKey};
+    use super::{Bip32PrivateKey, Bip32PublicKey, MasterKeyScheme};
 
     #[test]
     fn mnemonic_roundtrip() {
@@ -175,6 +258,60 @@ mod test {
         assert_eq!(xprv, xprv_from_mne)
     }
 
+    #[test]
+    fn master_key_schemes() {
+        let cases = [
+            (
+                MasterKeyScheme::Icarus,
+                "eight country switch draw meat scout mystery blade tip drift useless good keep usage title",
+                "",
+                "c065afd2832cd8b087c4d9ab7011f481ee1e0721e78ea5dd609f3ab3f156d245d176bd8fd4ec60b4731c3918a2a72a0226c0cd119ec35b47e4d55884667f552a23f7fdcd4a10c6cd2c7393ac61d877873e248f417634aa3d812af327ffe9d620",
+            ),
+            (
+                MasterKeyScheme::Icarus,
+                "eight country switch draw meat scout mystery blade tip drift useless good keep usage title",
+                "foo",
+                "70531039904019351e1afb361cd1b312a4d0565d4ff9f8062d38acf4b15cce41d7b5738d9c893feea55512a3004acb0d222c35d3e3d5cde943a15a9824cbac59443cf67e589614076ba01e354b1a432e0e6db3b59e37fc56b5fb0222970a010e",
+            ),
+            (
+                MasterKeyScheme::Ledger,
+                "recall grace sport punch exhibit mad harbor stand obey short width stem awkward used stairs wool ugly trap season stove worth toward congress jaguar",
+                "",
+                "a08cf85b564ecf3b947d8d4321fb96d70ee7bb760877e371899b14e2ccf88658104b884682b57efd97decbb318a45c05a527b9cc5c2f64f7352935a049ceea60680d52308194ccef2a18e6812b452a5815fbd7f5babc083856919aaf668fe7e4",
+            ),
+        ];
+
+        for (scheme, mnemonic, password, expected) in cases {
+            let xprv = Bip32PrivateKey::from_bip39_mnenomic_with_scheme(
+                mnemonic.into(),
+                password.into(),
+                scheme,
+            )
+            .unwrap();
+
+            assert_eq!(hex::encode(xprv.as_bytes()), expected);
+        }
+
+        // trezor only differs from icarus on 24 word mnemonics
+        let short = "eight country switch draw meat scout mystery blade tip drift useless good keep usage title";
+        let long = "recall grace sport punch exhibit mad harbor stand obey short width stem awkward used stairs wool ugly trap season stove worth toward congress jaguar";
+
+        let derive = |mnemonic: &str, scheme| {
+            Bip32PrivateKey::from_bip39_mnenomic_with_scheme(mnemonic.into(), "".into(), scheme)
+                .unwrap()
+        };
+
+        assert_eq!(
+            derive(short, MasterKeyScheme::Trezor),
+            derive(short, MasterKeyScheme::Icarus)
+        );
+
+        assert_ne!(
+            derive(long, MasterKeyScheme::Trezor),
+            derive(long, MasterKeyScheme::Icarus)
+        );
+    }
+
     #[test]
     fn bech32_roundtrip() {
         let xprv = Bip32PrivateKey::generate(OsRng);
@@ -0,0 +1,411 @@
+//! Checkpoints of the state of a chain follower
+//!
+//! Long-running indexers keep three pieces of state: the cursor they resume
+//! from, the window of volatile blocks that may still be rolled back, and the
+//! UTxO set built so far. A [`Checkpoint`] bundles them in a single file
+//! where every section carries its own Blake2b-256 hash, so a truncated or
+//! corrupted checkpoint is detected on load instead of silently resuming
+//! from a bad state.
+//!
+//! The file layout is `"PCKP" | version: u8 | cbor`, where the CBOR part is
+//! an array of three `[hash, bytes]` sections: cursor, volatile and utxos.
+//! The cursor section holds the points as written by [`encode_points`], so
+//! it can be shared with tools that read cursor files, and the volatile
+//! section stores the immutable tip in that same format. Sections are
+//! encoded deterministically, so the same state always yields the same
+//! [`Checkpoint::digest`].
+
+use std::{collections::HashMap, path::Path};
+
+use pallas_codec::{fs::atomic_write, minicbor, utils::Bytes};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_network::miniprotocols::{
+    chainsync::{decode_points, encode_points, PointsFileError, RollbackBuffer},
+    Point,
+};
+use pallas_traverse::{Era, OutputRef};
+use thiserror::Error;
+
+/// Magic bytes at the start of every checkpoint file
+pub const CHECKPOINT_MAGIC: &[u8; 4] = b"PCKP";
+
+/// Version of the checkpoint layout written by [`Checkpoint::encode`]
+pub const CHECKPOINT_FORMAT_VERSION: u8 = 1;
+
+const SECTIONS: [&str; 3] = ["cursor", "volatile", "utxos"];
+
+/// Failure reading or writing a checkpoint
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    /// The file doesn't start with [`CHECKPOINT_MAGIC`]
+    #[error("not a checkpoint file")]
+    BadMagic,
+
+    /// The file was written with a different layout version
+    #[error("unsupported checkpoint version {0}")]
+    UnsupportedVersion(u8),
+
+    /// The sections can't be decoded
+    #[error("malformed checkpoint: {0}")]
+    Malformed(String),
+
+    /// A section doesn't match the hash stored next to it
+    #[error("hash mismatch in checkpoint section {0}")]
+    SectionHashMismatch(&'static str),
+
+    /// The state doesn't match the digest it was checked against
+    #[error("checkpoint digest doesn't match the expected one")]
+    DigestMismatch,
+
+    /// An output is tagged with an era that doesn't exist
+    #[error("unknown era tag {0} in checkpoint")]
+    UnknownEra(u16),
+
+    /// The cursor section is not a valid points file
+    #[error("invalid checkpoint cursor")]
+    Cursor(#[from] PointsFileError),
+
+    /// The file can't be read or written
+    #[error("checkpoint I/O error")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<minicbor::decode::Error> for CheckpointError {
+    fn from(value: minicbor::decode::Error) -> Self {
+        CheckpointError::Malformed(value.to_string())
+    }
+}
+
+/// Block that can still be rolled back, kept as raw CBOR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolatileBlock {
+    /// Slot of the block
+    pub slot: u64,
+    /// Hash of the block header
+    pub hash: Hash<32>,
+    /// The block as received
+    pub cbor: Vec<u8>,
+}
+
+/// State of a chain follower at a given point
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Points to intersect from when resuming, most recent first
+    pub cursor: Vec<Point>,
+    /// Last point handed over as immutable, see
+    /// [`RollbackBuffer::immutable_tip`]
+    pub immutable_tip: Option<Point>,
+    /// Blocks after the immutable tip, oldest first
+    pub volatile: Vec<VolatileBlock>,
+    /// Unspent outputs as era and CBOR, the same shape used by UTxO stores
+    pub utxos: HashMap<OutputRef, (Era, Vec<u8>)>,
+}
+
+type VolatileSection = (Bytes, Vec<(u64, Hash<32>, Bytes)>);
+
+type UtxoEntry = (Hash<32>, u64, u16, Bytes);
+
+fn to_cbor<T: minicbor::Encode<()>>(value: &T) -> Vec<u8> {
+    minicbor::to_vec(value).expect("encoding to a vec can't fail")
+}
+
+impl Checkpoint {
+    fn sections(&self) -> Result<[Vec<u8>; 3], CheckpointError> {
+        let blocks = self
+            .volatile
+            .iter()
+            .map(|x| (x.slot, x.hash, Bytes::from(x.cbor.clone())))
+            .collect();
+
+        let tip = encode_points(self.immutable_tip.as_slice())?;
+        let volatile: VolatileSection = (tip.into(), blocks);
+
+        let mut utxos: Vec<UtxoEntry> = self
+            .utxos
+            .iter()
+            .map(|(k, (era, cbor))| (*k.hash(), k.index(), u16::from(*era), cbor.clone().into()))
+            .collect();
+
+        utxos.sort_by_key(|x| (x.0, x.1));
+
+        Ok([
+            encode_points(&self.cursor)?,
+            to_cbor(&volatile),
+            to_cbor(&utxos),
+        ])
+    }
+
+    /// Hash that identifies the whole state, computed over the hashes of
+    /// each section
+    pub fn digest(&self) -> Result<Hash<32>, CheckpointError> {
+        let mut hasher = Hasher::<256>::new();
+
+        for section in self.sections()? {
+            hasher.input(Hasher::<256>::hash(&section).as_ref());
+        }
+
+        Ok(hasher.finalize())
+    }
+
+    /// Serializes the checkpoint in the layout described at the module level
+    pub fn encode(&self) -> Result<Vec<u8>, CheckpointError> {
+        let sections: Vec<_> = self
+            .sections()?
+            .into_iter()
+            .map(|x| (Hasher::<256>::hash(&x), Bytes::from(x)))
+            .collect();
+
+        let mut out = CHECKPOINT_MAGIC.to_vec();
+        out.push(CHECKPOINT_FORMAT_VERSION);
+        out.extend(to_cbor(&sections));
+
+        Ok(out)
+    }
+
+    /// Parses a checkpoint, checking the hash of every section
+    pub fn decode(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        if bytes.len() < 5 || &bytes[..4] != CHECKPOINT_MAGIC {
+            return Err(CheckpointError::BadMagic);
+        }
+
+        if bytes[4] != CHECKPOINT_FORMAT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(bytes[4]));
+        }
+
+        let sections: Vec<(Hash<32>, Bytes)> = minicbor::decode(&bytes[5..])?;
+
+        if sections.len() != SECTIONS.len() {
+            return Err(CheckpointError::Malformed(format!(
+                "expected {} sections, found {}",
+                SECTIONS.len(),
+                sections.len()
+            )));
+        }
+
+        for ((hash, bytes), name) in sections.iter().zip(SECTIONS) {
+            if Hasher::<256>::hash(bytes) != *hash {
+                return Err(CheckpointError::SectionHashMismatch(name));
+            }
+        }
+
+        let cursor = decode_points(&sections[0].1)?;
+
+        let (tip, blocks): VolatileSection = minicbor::decode(&sections[1].1)?;
+
+        let mut tip = decode_points(&tip)?;
+
+        if tip.len() > 1 {
+            return Err(CheckpointError::Malformed(format!(
+                "expected at most one immutable tip, found {}",
+                tip.len()
+            )));
+        }
+
+        let immutable_tip = tip.pop();
+
+        let volatile = blocks
+            .into_iter()
+            .map(|(slot, hash, cbor)| VolatileBlock {
+                slot,
+                hash,
+                cbor: cbor.into(),
+            })
+            .collect();
+
+        let utxos: Vec<UtxoEntry> = minicbor::decode(&sections[2].1)?;
+
+        let utxos = utxos
+            .into_iter()
+            .map(|(hash, index, era, cbor)| {
+                let era = Era::try_from(era).map_err(|_| CheckpointError::UnknownEra(era))?;
+                Ok((OutputRef::new(hash, index), (era, cbor.into())))
+            })
+            .collect::<Result<_, CheckpointError>>()?;
+
+        Ok(Self {
+            cursor,
+            immutable_tip,
+            volatile,
+            utxos,
+        })
+    }
+
+    /// Rebuilds the rollback buffer of the follower from the immutable tip
+    /// and the volatile blocks, so that rolling back to any of them, the
+    /// immutable tip included, keeps working after a restart
+    pub fn rollback_buffer(&self, k: usize) -> RollbackBuffer {
+        let points = self
+            .volatile
+            .iter()
+            .map(|x| Point::Specific(x.slot, x.hash.to_vec()));
+
+        RollbackBuffer::restore(k, self.immutable_tip.clone(), points)
+    }
+
+    /// Writes the checkpoint through [`atomic_write`]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        atomic_write(path, &self.encode()?)?;
+
+        Ok(())
+    }
+
+    /// Reads a checkpoint written by [`Checkpoint::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let bytes = std::fs::read(path)?;
+        Self::decode(&bytes)
+    }
+
+    /// Loads a checkpoint and checks it against a digest recorded elsewhere,
+    /// e.g. in the database the indexer writes to
+    pub fn load_verified(
+        path: impl AsRef<Path>,
+        digest: &Hash<32>,
+    ) -> Result<Self, CheckpointError> {
+        let checkpoint = Self::load(path)?;
+
+        if checkpoint.digest()? != *digest {
+            return Err(CheckpointError::DigestMismatch);
+        }
+
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_network::miniprotocols::chainsync::RollbackEffect;
+
+    use super::*;
+
+    fn sample() -> Checkpoint {
+        let hash = |x: u8| Hash::from([x; 32]);
+
+        Checkpoint {
+            cursor: vec![
+                Point::Specific(20, hash(2).to_vec()),
+                Point::Specific(10, hash(1).to_vec()),
+                Point::Origin,
+            ],
+            immutable_tip: Some(Point::Specific(10, hash(1).to_vec())),
+            volatile: vec![VolatileBlock {
+                slot: 20,
+                hash: hash(2),
+                cbor: vec![0x82, 0x01, 0x02],
+            }],
+            utxos: HashMap::from([
+                (OutputRef::new(hash(3), 0), (Era::Babbage, vec![0xa0])),
+                (
+                    OutputRef::new(hash(3), 1),
+                    (Era::Conway, vec![0xa1, 0x00, 0x00]),
+                ),
+                (OutputRef::new(hash(4), 0), (Era::Byron, vec![0x80])),
+            ]),
+        }
+    }
+
+    #[test]
+    fn checkpoint_roundtrip() {
+        let bytes = sample().encode().unwrap();
+
+        assert_eq!(&bytes[..4], CHECKPOINT_MAGIC);
+        assert_eq!(Checkpoint::decode(&bytes).unwrap(), sample());
+
+        // encoding doesn't depend on the iteration order of the utxo map
+        assert_eq!(sample().encode().unwrap(), bytes);
+        assert_eq!(sample().digest().unwrap(), sample().digest().unwrap());
+        assert_ne!(
+            sample().digest().unwrap(),
+            Checkpoint::default().digest().unwrap()
+        );
+    }
+
+    #[test]
+    fn cursor_is_a_points_file() {
+        let [cursor, ..] = sample().sections().unwrap();
+
+        assert_eq!(decode_points(&cursor).unwrap(), sample().cursor);
+        assert_eq!(cursor, encode_points(&sample().cursor).unwrap());
+    }
+
+    #[test]
+    fn corrupted_checkpoints_are_rejected() {
+        let bytes = sample().encode().unwrap();
+
+        let mut flipped = bytes.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 0xff;
+
+        assert!(matches!(
+            Checkpoint::decode(&flipped),
+            Err(CheckpointError::SectionHashMismatch("utxos"))
+        ));
+
+        assert!(matches!(
+            Checkpoint::decode(&bytes[..bytes.len() - 10]),
+            Err(CheckpointError::Malformed(_))
+        ));
+
+        assert!(matches!(
+            Checkpoint::decode(b"nope"),
+            Err(CheckpointError::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn roll_back_to_immutable_tip_after_load() {
+        let path =
+            std::env::temp_dir().join(format!("pallas-checkpoint-tip-{}", std::process::id()));
+        let block = |x: u8| VolatileBlock {
+            slot: x as u64,
+            hash: Hash::from([x; 32]),
+            cbor: vec![x],
+        };
+        let point = |x: u8| Point::Specific(x as u64, vec![x; 32]);
+
+        let mut buffer = RollbackBuffer::with_security_param(2);
+        let mut volatile = vec![];
+
+        for x in 1..=5 {
+            let immutable = buffer.roll_forward(point(x));
+            volatile.push(block(x));
+            volatile.drain(..immutable.len());
+        }
+
+        let checkpoint = Checkpoint {
+            cursor: vec![point(5)],
+            immutable_tip: buffer.immutable_tip().cloned(),
+            volatile,
+            ..Default::default()
+        };
+
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut restored = loaded.rollback_buffer(2);
+        assert_eq!(restored, buffer);
+
+        assert!(matches!(
+            restored.roll_back(&point(3)),
+            RollbackEffect::Handled
+        ));
+        assert_eq!(restored.size(), 0);
+    }
+
+    #[test]
+    fn checkpoint_save_and_verified_load() {
+        let path = std::env::temp_dir().join(format!("pallas-checkpoint-{}", std::process::id()));
+
+        sample().save(&path).unwrap();
+
+        let loaded = Checkpoint::load_verified(&path, &sample().digest().unwrap()).unwrap();
+        assert_eq!(loaded, sample());
+
+        assert!(matches!(
+            Checkpoint::load_verified(&path, &Hash::from([0; 32])),
+            Err(CheckpointError::DigestMismatch)
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -12,6 +12,58 @@
 #[doc(inline)]
 pub use pallas_network as network;
 
+/// Failures across the Pallas subsystems, so that applications can handle
+/// every category through a single type
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Invalid CBOR data or a value that can't be encoded
+    #[error(transparent)]
+    Codec(#[from] pallas_codec::Error),
+
+    /// Invalid address or address encoding
+    #[error(transparent)]
+    Address(#[from] pallas_addresses::Error),
+
+    /// Block or transaction that can't be interpreted in any era
+    #[error(transparent)]
+    Traverse(#[from] pallas_traverse::Error),
+
+    /// Failure connecting to or talking with a peer
+    #[error(transparent)]
+    Network(#[from] pallas_network::facades::Error),
+
+    /// Failure querying the ledger state of a node
+    #[error(transparent)]
+    StateQuery(#[from] pallas_network::miniprotocols::localstate::ClientError),
+
+    /// Checkpoint that can't be read or written
+    #[error(transparent)]
+    Checkpoint(#[from] checkpoint::CheckpointError),
+
+    /// Transaction that can't be built
+    #[error(transparent)]
+    TxBuilder(#[from] pallas_txbuilder::TxBuilderError),
+
+    /// Failure reading the storage of the Haskell node
+    #[cfg(feature = "pallas-hardano")]
+    #[error(transparent)]
+    Storage(#[from] pallas_hardano::storage::immutable::Error),
+
+    /// Failure handling wallet keys
+    #[cfg(feature = "pallas-wallet")]
+    #[error(transparent)]
+    Wallet(#[from] pallas_wallet::Error),
+}
+
+impl From<pallas_codec::minicbor::decode::Error> for Error {
+    fn from(error: pallas_codec::minicbor::decode::Error) -> Self {
+        Error::Codec(error.into())
+    }
+}
+
+/// Result with the workspace-wide [`Error`]
+pub type Result<T> = std::result::Result<T, Error>;
+
 pub mod ledger {
     //! Ledger primitives and cbor codecs for different Cardano eras
 
@@ -75,6 +127,11 @@ pub mod storage {
     pub use pallas_hardano::storage as hardano;
 }
 
+pub mod checkpoint;
+
+#[cfg(feature = "pallas-applying")]
+pub mod resolve;
+
 #[doc(inline)]
 #[cfg(feature = "pallas-applying")]
 // WARNING: this is deprecated but since deprecation notices don't work for re-exports
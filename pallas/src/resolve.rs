@@ -0,0 +1,48 @@
+//! Input resolution backed by a node, through LocalStateQuery
+
+use pallas_applying::resolve::UtxoSnapshot;
+use pallas_codec::utils::{AnyCbor, KeyValuePairs};
+use pallas_network::miniprotocols::localstate::{self, queries_v16};
+use pallas_primitives::conway::TransactionInput;
+
+use crate::Result;
+
+/// Queries the node for the outputs at `inputs`, keeping them as a snapshot
+/// that can be used wherever an
+/// [`InputResolver`](pallas_applying::resolve::InputResolver) is expected
+///
+/// The client must have already acquired the ledger state to query. Inputs
+/// already spent are left out of the snapshot.
+pub async fn snapshot_from_node(
+    client: &mut localstate::Client,
+    era: u16,
+    inputs: &[TransactionInput],
+) -> Result<UtxoSnapshot> {
+    let txins = inputs
+        .iter()
+        .map(|x| queries_v16::UTxO {
+            transaction_id: x.transaction_id,
+            index: x.index.into(),
+        })
+        .collect();
+
+    let query = queries_v16::BlockQuery::GetUTxOByTxIn(AnyCbor::from_encode(txins));
+    let query = queries_v16::Request::LedgerQuery(queries_v16::LedgerQuery::BlockQuery(era, query));
+
+    // same shape as `UTxOByTxIn`, keeping the outputs as sent by the node
+    let (found,): (KeyValuePairs<queries_v16::UTxO, AnyCbor>,) = client.query(query).await?;
+
+    let snapshot = found
+        .iter()
+        .map(|(input, output)| {
+            let input = TransactionInput {
+                transaction_id: input.transaction_id,
+                index: (&input.index).into(),
+            };
+
+            (input, output.to_vec())
+        })
+        .collect();
+
+    Ok(snapshot)
+}